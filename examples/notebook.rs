@@ -0,0 +1,86 @@
+//! A minimal notebook: the cells of a file, separated by `%%cell` lines,
+//! are evaluated one after the other against the same scope, so a cell
+//! sees what the cells before it bound. Each cell shows what it printed and
+//! its value, and a cell that fails shows its diagnostic without stopping
+//! the ones after it.
+//!
+//! ```text
+//! cargo run --example notebook -- cells.txt
+//! ```
+
+use miette::{NamedSource, NarratableReportHandler, Report};
+use rinha::{
+    ast,
+    interpreter::{Interpreter, Scope},
+    parser,
+};
+use std::{env, fs, io, process};
+
+/// Separates the cells, on a line of its own.
+const SEPARATOR: &str = "%%cell";
+
+/// How much all the cells can print together.
+const MAX_OUTPUT_BYTES: u64 = 1024 * 1024;
+
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: notebook <FILE>");
+        process::exit(2);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("could not read `{path}`: {error}");
+            process::exit(2);
+        }
+    };
+
+    let mut interpreter = Interpreter::with_output(io::stdout()).max_output_bytes(MAX_OUTPUT_BYTES);
+    let mut scope = Scope::new();
+    for (index, cell) in cells(&text).into_iter().enumerate() {
+        // Every cell gets its own filename, so the diagnostics point into it
+        // and its functions aren't mistaken for the ones of other cells.
+        let name = format!("{path}#{}", index + 1);
+        println!("[{}]", index + 1);
+        match evaluate(&mut interpreter, &mut scope, &name, cell) {
+            Ok(value) => println!("=> {value}"),
+            Err(report) => {
+                let mut rendered = String::new();
+                let _ =
+                    NarratableReportHandler::new().render_report(&mut rendered, report.as_ref());
+                print!("{rendered}");
+            }
+        }
+    }
+}
+
+/// The cells of the notebook, without the separators.
+fn cells(text: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    for line in text.lines() {
+        match line.trim() == SEPARATOR {
+            true => cells.push(String::new()),
+            false => {
+                let cell = cells.last_mut().unwrap();
+                cell.push_str(line);
+                cell.push('\n');
+            }
+        }
+    }
+    cells.retain(|cell| !cell.trim().is_empty());
+    cells
+}
+
+/// Parses the cell and evaluates it in `scope`, keeping its bindings there.
+fn evaluate(
+    interpreter: &mut Interpreter,
+    scope: &mut Scope,
+    name: &str,
+    cell: String,
+) -> Result<String, Report> {
+    let file: ast::File = parser::parse_or_report(name, &cell)?;
+    match interpreter.interpret(file.expression, scope) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(Report::new(error).with_source_code(NamedSource::new(name, cell))),
+    }
+}
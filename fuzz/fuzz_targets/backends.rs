@@ -0,0 +1,24 @@
+//! Runs the random programs of `rinha::fuzz` on the tree-walking
+//! interpreter and on the bytecode, failing when one of them panics or
+//! when they disagree on what the program prints or how it ends.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rinha::{
+    format,
+    fuzz::{self, Finding, Program},
+};
+
+fuzz_target!(|program: Program| {
+    let source = format::to_source(&program.0.expression);
+    match fuzz::check(&program.0) {
+        Ok(()) => {}
+        Err(Finding::Panicked(backend, error)) => {
+            panic!("{backend:?} panicked on\n{source}\n{error:?}")
+        }
+        Err(Finding::Diverged { tree, vm }) => {
+            panic!("the engines disagree on\n{source}\ntree: {tree:?}\nvm: {vm:?}")
+        }
+    }
+});
@@ -1,4 +1,5 @@
-use std::{fmt::Debug, rc::Rc};
+use crate::runtime::symbol::Symbol;
+use std::{fmt::Debug, rc::Rc, sync::Arc};
 
 /// File definition, it contains all the statements,
 /// the module name, and a base location for it as anchor
@@ -8,6 +9,41 @@ pub struct File {
     pub name: String,
     pub expression: Term,
     pub location: Location,
+
+    /// Who wrote the AST. The ASTs written by other tools don't have it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+/// Facts about the tool that wrote an AST, so a reader that can't make
+/// sense of it can tell why.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Metadata {
+    /// The version of the `rinha` crate whose parser wrote the AST.
+    pub parser_version: String,
+}
+
+impl Metadata {
+    /// The metadata of the ASTs written by this build.
+    pub fn current() -> Self {
+        Self {
+            parser_version: env!("CARGO_PKG_VERSION").into(),
+        }
+    }
+
+    /// Whether the AST comes from a later version of the parser than this
+    /// one, which may write fields this build doesn't know. Versions that
+    /// aren't numbers separated by dots are never newer.
+    pub fn is_newer(&self) -> bool {
+        let version = crate::semantics::numeric_version;
+        match (
+            version(&self.parser_version),
+            version(env!("CARGO_PKG_VERSION")),
+        ) {
+            (Some(theirs), Some(ours)) => theirs > ours,
+            _ => false,
+        }
+    }
 }
 
 impl<T: Element> Element for Rc<T> {
@@ -22,22 +58,41 @@ impl<T: Element> Element for Box<T> {
     }
 }
 
+/// The byte offsets are `u64`, so the serialized ASTs are the same on every
+/// platform. The filename is interned, the locations are copied into the
+/// frames and the errors of every call without copying it.
 #[derive(Default, Hash, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Location {
-    pub start: usize,
-    pub end: usize,
-    pub filename: String,
+    pub start: u64,
+    pub end: u64,
+    pub filename: Symbol,
 }
 
 impl Location {
     /// Creates a new instance of [`Location`].
     pub fn new(start: usize, end: usize, filename: &str) -> Self {
         Self {
-            start,
-            end,
-            filename: filename.into(),
+            start: start as u64,
+            end: end as u64,
+            filename: Symbol::intern(filename),
         }
     }
+
+    /// The line and the column where the location starts, both counting
+    /// from one.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let start = usize::try_from(self.start).unwrap_or(usize::MAX);
+        let before = &source[..start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        (line, column)
+    }
 }
 
 impl Debug for Location {
@@ -48,7 +103,7 @@ impl Debug for Location {
 
 impl From<Location> for miette::SourceSpan {
     fn from(value: Location) -> Self {
-        Self::from(value.start..value.end)
+        Self::from(value.start as usize..value.end as usize)
     }
 }
 
@@ -95,7 +150,7 @@ pub struct Let {
 /// Int is a integer value like `0`, `1`, `2`, etc.
 #[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Str {
-    pub value: String,
+    pub value: Symbol,
 
     /// The location of the source in the source code.
     pub location: Location,
@@ -135,7 +190,50 @@ impl Element for Int {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Float is a fractional number like `1.5`, outside of the specification,
+/// see [`crate::interpreter::Interpreter::extensions`].
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Float {
+    /// The value of the number. In JSON it's a number, or a string like
+    /// `"1.5"` or `"NaN"` for the values JSON numbers can't hold.
+    #[serde(with = "float_value")]
+    pub value: f64,
+
+    /// The location of the number in the source code.
+    pub location: Location,
+}
+
+impl Element for Float {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+/// The values of [`Float`] in JSON, the finite ones as numbers and the
+/// others as strings. Both are read back, so are the numbers in strings.
+mod float_value {
+    pub fn serialize<S: serde::Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        match value.is_finite() {
+            true => serializer.serialize_f64(*value),
+            false => serializer.serialize_str(&format!("{value:?}")),
+        }
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Number(f64),
+            Text(String),
+        }
+        match serde::Deserialize::deserialize(deserializer)? {
+            Value::Number(value) => Ok(value),
+            Value::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum BinaryOp {
     Add, // Add
     Sub, // Subtract
@@ -182,7 +280,8 @@ impl Element for Call {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Function {
     pub parameters: Vec<crate::parser::Var>,
-    pub value: Box<Term>,
+    /// The body, shared with the closures made by evaluating the function.
+    pub value: Arc<Term>,
     pub location: Location,
 }
 
@@ -241,11 +340,194 @@ impl Element for Tuple {
     }
 }
 
+/// Record is a value with named fields like `{ x: 1, y: 2 }`, outside of
+/// the specification, see [`crate::interpreter::Interpreter::extensions`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Record {
+    /// The fields in the order they're written, which is the order they're
+    /// evaluated in.
+    pub fields: Vec<Field>,
+    pub location: Location,
+}
+
+impl Element for Record {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+/// A field of a [`Record`], with the term of its value.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Field {
+    pub name: crate::parser::Var,
+    pub value: Term,
+}
+
+/// Access is the read of a field of a record like `point.x`, outside of
+/// the specification like the records.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Access {
+    pub value: Box<Term>,
+    pub field: crate::parser::Var,
+    pub location: Location,
+}
+
+impl Element for Access {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+/// Match is the choice of the first arm whose pattern fits the value, like
+/// `match (p) { (0, y) => y, (x, _) if x > 0 => x, _ => 0 }`. The backends
+/// that don't run it run the `let` and `if` it's made of, see
+/// [`crate::optimize::lower_match`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Match {
+    pub value: Box<Term>,
+    /// The arms in the order they're tried.
+    pub arms: Vec<Arm>,
+    pub location: Location,
+}
+
+impl Element for Match {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Arm {
+    pub pattern: Pattern,
+    /// Evaluated with the variables of the pattern bound, the arm is only
+    /// taken when it's true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<Term>,
+    pub body: Term,
+}
+
+/// The shape of the values an [`Arm`] takes.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum Pattern {
+    /// `_`, any value, binding nothing.
+    Wildcard(Wildcard),
+    /// Any value, bound to the variable.
+    Var(crate::parser::Var),
+    /// The values equal to the literal, compared like `==` does.
+    Int(Int),
+    Str(Str),
+    Bool(Bool),
+    /// The tuples whose elements fit the patterns.
+    Tuple(TuplePattern),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Wildcard {
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TuplePattern {
+    pub first: Box<Pattern>,
+    pub second: Box<Pattern>,
+    pub location: Location,
+}
+
+impl Element for Pattern {
+    fn location(&self) -> &Location {
+        match self {
+            Pattern::Wildcard(wildcard) => &wildcard.location,
+            Pattern::Var(var) => &var.location,
+            Pattern::Int(int) => &int.location,
+            Pattern::Str(str) => &str.location,
+            Pattern::Bool(bool) => &bool.location,
+            Pattern::Tuple(tuple) => &tuple.location,
+        }
+    }
+}
+
+/// The element of a tuple a [`Part`] is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+/// A pattern without tuples inside of it, and where the part of the value
+/// it takes is, see [`Pattern::parts`].
+#[derive(Debug, Clone)]
+pub struct Part<'p> {
+    /// The elements to take from the value, outermost first, each with the
+    /// location of the tuple pattern taking it, where it fails when the
+    /// value isn't a tuple.
+    pub path: Vec<(Side, &'p Location)>,
+    pub pattern: &'p Pattern,
+}
+
+impl Part<'_> {
+    /// Whether the part is compared to a literal, instead of being bound.
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self.pattern,
+            Pattern::Int(_) | Pattern::Str(_) | Pattern::Bool(_)
+        )
+    }
+}
+
+impl Pattern {
+    /// The patterns inside of this one that aren't tuples, in the order
+    /// they're written. Every engine matches a value in two steps: the
+    /// parts of the literals are compared first, in this order, the arm
+    /// failing at the first that differs, then the other parts are taken,
+    /// binding the variables, so a tuple pattern fails on the values that
+    /// aren't tuples unless a literal failed before.
+    ///
+    /// ```
+    /// use rinha::{ast::{Pattern, Side}, parser};
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "match (p) { (x, (1, _)) => x }").unwrap();
+    /// let rinha::ast::Term::Match(match_) = &file.expression else { unreachable!() };
+    /// let parts = match_.arms[0].pattern.parts();
+    /// let paths: Vec<Vec<Side>> = parts
+    ///     .iter()
+    ///     .map(|part| part.path.iter().map(|(side, _)| *side).collect())
+    ///     .collect();
+    /// assert_eq!(paths, [vec![Side::First], vec![Side::Second, Side::First], vec![Side::Second, Side::Second]]);
+    /// assert!(matches!(parts[1].pattern, Pattern::Int(_)));
+    /// ```
+    pub fn parts(&self) -> Vec<Part<'_>> {
+        let mut parts = Vec::new();
+        self.collect_parts(&mut Vec::new(), &mut parts);
+        parts
+    }
+
+    fn collect_parts<'p>(
+        &'p self,
+        path: &mut Vec<(Side, &'p Location)>,
+        parts: &mut Vec<Part<'p>>,
+    ) {
+        let Pattern::Tuple(tuple) = self else {
+            parts.push(Part {
+                path: path.clone(),
+                pattern: self,
+            });
+            return;
+        };
+        for (side, element) in [(Side::First, &tuple.first), (Side::Second, &tuple.second)] {
+            path.push((side, &tuple.location));
+            element.collect_parts(path, parts);
+            path.pop();
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "kind")]
 pub enum Term {
     Error(Error),
     Int(Int),
+    Float(Float),
     Str(Str),
     Call(Call),
     Binary(Binary),
@@ -257,6 +539,9 @@ pub enum Term {
     Second(Second),
     Bool(Bool),
     Tuple(Tuple),
+    Record(Record),
+    Access(Access),
+    Match(Match),
     Var(crate::parser::Var),
 }
 
@@ -265,6 +550,7 @@ impl Element for Term {
         match self {
             Term::Error(arg0) => &arg0.location,
             Term::Int(arg0) => &arg0.location,
+            Term::Float(arg0) => &arg0.location,
             Term::Str(arg0) => &arg0.location,
             Term::Function(arg0) => &arg0.location,
             Term::Call(arg0) => arg0.location(),
@@ -277,6 +563,9 @@ impl Element for Term {
             Term::If(arg0) => &arg0.location,
             Term::Bool(arg0) => &arg0.location,
             Term::Tuple(arg0) => arg0.location(),
+            Term::Record(arg0) => &arg0.location,
+            Term::Access(arg0) => &arg0.location,
+            Term::Match(arg0) => &arg0.location,
         }
     }
 }
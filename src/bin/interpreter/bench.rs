@@ -0,0 +1,180 @@
+use miette::{IntoDiagnostic, WrapErr};
+use rinha::interpreter::{Interpreter, Scope, Stats};
+use std::{fmt::Write as _, fs, io, time::Instant};
+
+/// Programs printing more than this are refused without `--quiet`, the
+/// printing would dominate the timings.
+const MAX_PRINTED_BYTES: u64 = 64 * 1024;
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// The program to benchmark, a JSON AST or a `.rinha` source file, `-`
+    /// for the standard input
+    main: String,
+
+    /// How many timed runs per configuration, after one warm up run
+    #[arg(long, default_value_t = 5, value_parser = at_least_one)]
+    reps: u32,
+
+    /// Also writes the results as CSV into this path
+    #[arg(long)]
+    csv: Option<String>,
+
+    /// Benchmarks programs even when they print a lot
+    #[arg(long)]
+    quiet: bool,
+}
+
+fn at_least_one(value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(0) => Err("must be at least 1, every configuration needs a timed run".into()),
+        Ok(reps) => Ok(reps),
+        Err(error) => Err(format!("{error}, expected a positive number")),
+    }
+}
+
+/// One point of the configuration matrix.
+struct Config {
+    engine: &'static str,
+    memoize: bool,
+}
+
+/// The timings and counters of one configuration.
+struct Measurement {
+    config: Config,
+    median_ms: f64,
+    stddev_ms: f64,
+    stats: Stats,
+}
+
+/// Every configuration the interpreter supports.
+fn configurations() -> Vec<Config> {
+    [true, false]
+        .into_iter()
+        .map(|memoize| Config {
+            engine: "tree",
+            memoize,
+        })
+        .collect()
+}
+
+pub fn run(args: BenchArgs) -> miette::Result<()> {
+    let file = crate::read_program(&args.main);
+
+    let mut measurements = vec![];
+    for config in configurations() {
+        let run = || {
+            let mut interpreter = Interpreter::with_output(io::sink()).memoize(config.memoize);
+            let time = Instant::now();
+            let result = interpreter.interpret(file.expression.clone(), &mut Scope::new());
+            result.map(|_| {
+                (
+                    time.elapsed().as_secs_f64() * 1000.0,
+                    interpreter.stats().clone(),
+                )
+            })
+        };
+
+        let (_, warm_up) = run()?;
+        if warm_up.printed_bytes > MAX_PRINTED_BYTES && !args.quiet {
+            miette::bail!(
+                help = "pass `--quiet` to benchmark it anyway",
+                "the program prints {} bytes, more than the {MAX_PRINTED_BYTES} bytes limit",
+                warm_up.printed_bytes,
+            );
+        }
+
+        let mut times = vec![];
+        let mut stats = warm_up;
+        for _ in 0..args.reps {
+            let (time, run_stats) = run()?;
+            times.push(time);
+            stats = run_stats;
+        }
+
+        measurements.push(Measurement {
+            config,
+            median_ms: median(&mut times),
+            stddev_ms: stddev(&times),
+            stats,
+        });
+    }
+
+    print!("{}", table(&measurements));
+    if let Some(path) = args.csv {
+        fs::write(&path, csv(&measurements))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("could not write `{path}`, asked for by --csv"))?;
+    }
+
+    Ok(())
+}
+
+fn median(times: &mut [f64]) -> f64 {
+    if times.is_empty() {
+        return 0.0;
+    }
+    times.sort_by(f64::total_cmp);
+    let middle = times.len() / 2;
+    if times.len() % 2 == 1 {
+        times[middle]
+    } else {
+        (times[middle - 1] + times[middle]) / 2.0
+    }
+}
+
+/// The sample standard deviation, zero when there aren't enough samples.
+fn stddev(times: &[f64]) -> f64 {
+    if times.len() < 2 {
+        return 0.0;
+    }
+    let mean = times.iter().sum::<f64>() / times.len() as f64;
+    let variance = times.iter().map(|time| (time - mean).powi(2)).sum::<f64>();
+    (variance / (times.len() - 1) as f64).sqrt()
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn table(measurements: &[Measurement]) -> String {
+    let mut table = format!(
+        "{:<8} {:<5} {:>12} {:>12} {:>12} {:>12}\n",
+        "engine", "memo", "median ms", "stddev ms", "calls", "memo hits"
+    );
+    for measurement in measurements {
+        let _ = writeln!(
+            table,
+            "{:<8} {:<5} {:>12.3} {:>12.3} {:>12} {:>12}",
+            measurement.config.engine,
+            on_off(measurement.config.memoize),
+            measurement.median_ms,
+            measurement.stddev_ms,
+            measurement.stats.calls,
+            measurement.stats.memo_hits,
+        );
+    }
+    table
+}
+
+fn csv(measurements: &[Measurement]) -> String {
+    let mut csv = String::from("engine,memo,median_ms,stddev_ms,calls,memo_hits,printed_bytes\n");
+    for measurement in measurements {
+        let _ = writeln!(
+            csv,
+            "{},{},{:.6},{:.6},{},{},{}",
+            measurement.config.engine,
+            on_off(measurement.config.memoize),
+            measurement.median_ms,
+            measurement.stddev_ms,
+            measurement.stats.calls,
+            measurement.stats.memo_hits,
+            measurement.stats.printed_bytes,
+        );
+    }
+    csv
+}
@@ -0,0 +1,206 @@
+use rinha::{
+    ast::{self, Element, Term},
+    format,
+    input::Format,
+    interpreter::{Debugger, ErrorKind, Frame, IntFormat, Interpreter, Scope},
+    names::FunctionNames,
+};
+use std::{
+    fs,
+    io::{self, BufRead},
+    ops::ControlFlow,
+};
+
+/// The longest text of a term shown where the debugger stops.
+const SHOWN_TERM: usize = 60;
+
+const HELP: &str = "\
+  step, s              stops at the next term
+  continue, c          runs up to the next breakpoint
+  break, b LINE|NAME   stops at the line, or at the `let` of the name
+  scope, v             shows the variables in scope
+  stack, bt            shows the calls in progress, the innermost first
+  quit, q              stops the program
+  help, h              shows the commands";
+
+#[derive(clap::Args, Debug)]
+pub struct DebugArgs {
+    /// The program to debug, a JSON AST or a `.rinha` source file
+    main: String,
+
+    /// Stops at this line, or at the `let` binding this name. Without any,
+    /// it stops at the first term
+    #[arg(long = "break", value_name = "LINE|NAME", value_parser = parse_breakpoint)]
+    breakpoints: Vec<Breakpoint>,
+}
+
+/// Where the debugger stops without being told to step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Breakpoint {
+    /// The first term of a line, each time the run gets to it from another
+    /// line or another call. The lines count from 1.
+    Line(usize),
+    /// The `let` binding the name.
+    Name(String),
+}
+
+fn parse_breakpoint(text: &str) -> Result<Breakpoint, String> {
+    let text = text.trim();
+    if let Ok(line) = text.parse() {
+        return Ok(Breakpoint::Line(line));
+    }
+    match text
+        .chars()
+        .all(|char| char.is_alphanumeric() || char == '_')
+        && !text.is_empty()
+    {
+        true => Ok(Breakpoint::Name(text.into())),
+        false => Err(format!("`{text}` is neither a line nor a name")),
+    }
+}
+
+/// The debugger reading its commands from the standard input, and writing
+/// into stderr, so the output of the program stays its own.
+struct Stepper {
+    filename: String,
+    /// Where each line of the source starts, empty without the source.
+    lines: Vec<usize>,
+    breakpoints: Vec<Breakpoint>,
+    stepping: bool,
+    /// The line of the term seen last, and how many calls were in progress.
+    last: (Option<usize>, usize),
+    /// Set once the standard input ends, the run goes on without stopping.
+    detached: bool,
+}
+
+impl Stepper {
+    fn line(&self, location: &ast::Location) -> Option<usize> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let start = location.start as usize;
+        Some(self.lines.partition_point(|line| *line <= start))
+    }
+
+    fn position(&self, location: &ast::Location) -> String {
+        match self.line(location) {
+            Some(line) => {
+                let column = location.start as usize - self.lines[line - 1] + 1;
+                format!("{}:{line}:{column}", self.filename)
+            }
+            None => format!("{}@{}", self.filename, location.start),
+        }
+    }
+
+    fn hits(&self, term: &Term, at: (Option<usize>, usize)) -> bool {
+        self.breakpoints.iter().any(|breakpoint| match breakpoint {
+            Breakpoint::Line(line) => at.0 == Some(*line) && self.last != at,
+            Breakpoint::Name(name) => matches!(term, Term::Let(let_) if let_.name.text == *name),
+        })
+    }
+
+    /// Reads the commands until one of them resumes the run.
+    fn prompt(&mut self, scope: &Scope, stack: &[Frame]) -> ControlFlow<()> {
+        let stdin = io::stdin();
+        loop {
+            eprint!("(rinha) ");
+            let mut command = String::new();
+            if matches!(stdin.lock().read_line(&mut command), Ok(0) | Err(_)) {
+                eprintln!();
+                self.detached = true;
+                return ControlFlow::Continue(());
+            }
+            let mut words = command.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("step" | "s"), _) => {
+                    self.stepping = true;
+                    return ControlFlow::Continue(());
+                }
+                (Some("continue" | "c"), _) => {
+                    self.stepping = false;
+                    return ControlFlow::Continue(());
+                }
+                (Some("break" | "b"), Some(at)) => match parse_breakpoint(at) {
+                    Ok(breakpoint) => self.breakpoints.push(breakpoint),
+                    Err(error) => eprintln!("{error}"),
+                },
+                (Some("scope" | "v"), _) => {
+                    let mut vars: Vec<_> = scope.iter().collect();
+                    vars.sort_by(|a, b| a.0.cmp(b.0));
+                    for (name, value) in vars {
+                        eprintln!("  {name} = {}", value.display(IntFormat::Plain));
+                    }
+                }
+                (Some("stack" | "bt"), _) => {
+                    for frame in stack.iter().rev() {
+                        eprintln!("  {} at {}", frame.function, self.position(&frame.call));
+                    }
+                }
+                (Some("quit" | "q"), _) => return ControlFlow::Break(()),
+                (Some("help" | "h"), _) => eprintln!("{HELP}"),
+                (None, _) => {}
+                (Some(command), _) => {
+                    eprintln!("unknown command `{}`, `help` lists them", command.trim())
+                }
+            }
+        }
+    }
+}
+
+impl Debugger for Stepper {
+    fn before(&mut self, term: &Term, scope: &Scope, stack: &[Frame]) -> ControlFlow<()> {
+        let at = (self.line(term.location()), stack.len());
+        let stops = !self.detached && (self.stepping || self.hits(term, at));
+        self.last = at;
+        if !stops {
+            return ControlFlow::Continue(());
+        }
+        let source = format::to_source(term);
+        let mut shown: String = source.lines().next().unwrap_or_default().into();
+        if shown.chars().count() > SHOWN_TERM {
+            shown = shown.chars().take(SHOWN_TERM).collect::<String>() + "...";
+        }
+        eprintln!("stopped at {}: {shown}", self.position(term.location()));
+        self.prompt(scope, stack)
+    }
+}
+
+/// Runs the program, stopping where it's told to and reading the commands
+/// from the standard input. The memo is off, so every call runs its body.
+/// Quitting ends the run without an error.
+pub fn run(args: DebugArgs) -> miette::Result<()> {
+    let text = crate::read_source(&args.main);
+    let file = crate::read_program(&args.main);
+    let source = match Format::of(None, &args.main, &text) {
+        Format::Source => Some(text),
+        Format::Json => fs::read_to_string(file.location.filename.as_str()).ok(),
+    };
+    let lines = match &source {
+        Some(source) => std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(at, _)| at + 1))
+            .collect(),
+        None => {
+            eprintln!("warning: the source of the program is missing, the lines can't be told");
+            Vec::new()
+        }
+    };
+    let stepper = Stepper {
+        filename: file.location.filename.to_string(),
+        lines,
+        stepping: args.breakpoints.is_empty(),
+        breakpoints: args.breakpoints,
+        last: (None, 0),
+        detached: false,
+    };
+    let names = FunctionNames::new(&file.expression, source.as_deref());
+    let mut interpreter = Interpreter::new()
+        .memoize(false)
+        .function_names(names)
+        .debugger(stepper);
+    match interpreter.interpret(file.expression, &mut Scope::new()) {
+        // Only `quit` interrupts the run.
+        Err(error) if error.kind == ErrorKind::Interrupted => Ok(()),
+        Err(error) => Err(error.into()),
+        Ok(_) => Ok(()),
+    }
+}
@@ -0,0 +1,117 @@
+use rinha::{ast, Backend, RinhaError, RunConfig};
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(clap::Args, Debug)]
+pub struct DifftestArgs {
+    /// The program to run, a JSON AST or a `.rinha` source file, `-` for
+    /// the standard input
+    main: String,
+
+    /// The two engines compared, separated by a comma
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Backend::Tree, Backend::Vm]
+    )]
+    backends: Vec<Backend>,
+
+    /// Provides the natives outside of the specification to the program
+    #[arg(long)]
+    extensions: bool,
+}
+
+/// What a run printed and how it ended, the value or the error.
+struct Run {
+    printed: String,
+    ended: String,
+}
+
+/// Runs the program on both engines, returning whether they printed the
+/// same and ended the same.
+pub fn run(args: DifftestArgs) -> miette::Result<bool> {
+    let [left, right] = args.backends[..] else {
+        miette::bail!(
+            "`--backends` takes two engines, like `tree,vm`, it was given {}",
+            args.backends.len()
+        );
+    };
+    let file = crate::read_program(&args.main);
+    let runs = [left, right].map(|backend| run_on(backend, file.clone(), &args));
+    let names = [name(left), name(right)];
+    let labels = names.clone().map(|name| format!("{name}:"));
+    let width = labels[0].len().max(labels[1].len());
+
+    let mut left_lines = runs[0].printed.split_inclusive('\n');
+    let mut right_lines = runs[1].printed.split_inclusive('\n');
+    let mut lines = 0;
+    loop {
+        let pair = [left_lines.next(), right_lines.next()];
+        match pair {
+            [None, None] => break,
+            [left_line, right_line] if left_line != right_line => {
+                println!("the outputs diverge at line {}:", lines + 1);
+                for (label, line) in labels.iter().zip(pair) {
+                    match line {
+                        Some(line) => println!("  {label:width$} {line:?}"),
+                        None => println!("  {label:width$} nothing"),
+                    }
+                }
+                return Ok(false);
+            }
+            _ => lines += 1,
+        }
+    }
+    if runs[0].ended != runs[1].ended {
+        println!("the runs end differently, after {}:", output(lines));
+        for (label, run) in labels.iter().zip(&runs) {
+            println!("  {label:width$} {}", run.ended);
+        }
+        return Ok(false);
+    }
+    println!(
+        "{} and {} agree on {}, ending with {}",
+        names[0],
+        names[1],
+        output(lines),
+        runs[0].ended
+    );
+    Ok(true)
+}
+
+/// Runs the program on `backend`, with the settings that are the same for
+/// both engines.
+fn run_on(backend: Backend, file: ast::File, args: &DifftestArgs) -> Run {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        filename: args.main.clone(),
+        backend,
+        output: Some(output.clone()),
+        extensions: args.extensions,
+        ..RunConfig::default()
+    };
+    let ended = match rinha::run_ast(file, &config) {
+        Ok(outcome) => format!("value {}", outcome.value),
+        Err(RinhaError::Runtime(error)) => format!(
+            "error: {} at bytes {}..{}",
+            error.kind, error.location.start, error.location.end
+        ),
+        Err(error) => format!("error: {error}"),
+    };
+    let printed = String::from_utf8_lossy(&output.borrow()).into_owned();
+    Run { printed, ended }
+}
+
+/// The name of `backend` in `--backends`.
+fn name(backend: Backend) -> String {
+    let value = clap::ValueEnum::to_possible_value(&backend);
+    value.map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// How much was printed, for the report.
+fn output(lines: usize) -> String {
+    match lines {
+        1 => "1 line of output".into(),
+        lines => format!("{lines} lines of output"),
+    }
+}
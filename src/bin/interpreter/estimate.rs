@@ -0,0 +1,202 @@
+use rinha::interpreter::{Checkpoint, ErrorKind, Interpreter, Scope, Stats};
+use std::{cell::RefCell, fmt::Write as _, io, rc::Rc, time::Instant};
+
+/// Below this exponent the new calls grow far slower than the calls that
+/// run, most of them repeat a call already made.
+const REPEATING: f64 = 0.5;
+
+#[derive(clap::Args, Debug)]
+pub struct EstimateArgs {
+    /// The program to estimate, a JSON AST or a `.rinha` source file, `-`
+    /// for the standard input
+    main: String,
+
+    /// How many calls the sample run makes at most
+    #[arg(long, default_value_t = 1_000_000, value_name = "CALLS")]
+    fuel: u64,
+
+    /// How many times the counters are sampled during the run
+    #[arg(long, default_value_t = 20, value_name = "COUNT")]
+    checkpoints: u64,
+
+    /// How long the program may run, in milliseconds
+    #[arg(long, default_value_t = 60_000, value_name = "MS")]
+    time_limit_ms: u64,
+
+    /// How many bytes the program may print
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes: Option<u64>,
+
+    /// Samples the run without the memo
+    #[arg(long)]
+    no_memo: bool,
+}
+
+/// What the sample run suggests about the whole one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Within,
+    Over,
+    Unknown,
+}
+
+impl Verdict {
+    fn describe(self) -> &'static str {
+        match self {
+            Verdict::Within => "likely within the limits",
+            Verdict::Over => "likely over the limits",
+            Verdict::Unknown => "unknown, the sample is too short to tell",
+        }
+    }
+}
+
+/// Runs the program with a budget of calls, and estimates from the
+/// counters sampled along the way whether the whole run fits the limits.
+pub fn run(args: EstimateArgs) -> miette::Result<()> {
+    let file = crate::read_program(&args.main);
+
+    let samples = Rc::new(RefCell::new(Vec::new()));
+    let sampled = samples.clone();
+    let mut interpreter = Interpreter::with_output(io::sink())
+        .memoize(!args.no_memo)
+        .fuel(args.fuel)
+        .on_checkpoint(args.fuel / args.checkpoints.max(1), move |checkpoint| {
+            sampled.borrow_mut().push(*checkpoint)
+        });
+    let time = Instant::now();
+    let finished = match interpreter.interpret(file.expression, &mut Scope::new()) {
+        Ok(_) => true,
+        Err(error) if error.kind == ErrorKind::OutOfFuel(args.fuel) => false,
+        Err(error) => return Err(error.into()),
+    };
+    let elapsed_ms = time.elapsed().as_secs_f64() * 1000.0;
+
+    let samples = samples.take();
+    let stats = interpreter.stats();
+    let exponent = growth_exponent(&samples);
+    let over_output = args
+        .max_output_bytes
+        .is_some_and(|limit| stats.printed_bytes > limit);
+    let over_time = elapsed_ms > args.time_limit_ms as f64;
+    let repeating = !finished && exponent.is_some_and(|exponent| exponent < REPEATING);
+    let verdict = match finished {
+        _ if over_output || over_time || repeating => Verdict::Over,
+        true => Verdict::Within,
+        false => Verdict::Unknown,
+    };
+
+    let sample = Sample {
+        finished,
+        elapsed_ms,
+        stats,
+        samples: &samples,
+        exponent,
+        repeating,
+    };
+    print!("{}", report(&args, &sample, verdict));
+    Ok(())
+}
+
+/// Fits `distinct = a * executed ^ exponent` to the samples, by least
+/// squares on their logarithms. The executed calls are the ones the memo
+/// didn't serve. It's near 1 when the calls keep doing new work, and near
+/// 0 when they repeat the work done, like a recursion that branches
+/// without the memo. `None` without two samples to fit.
+fn growth_exponent(samples: &[Checkpoint]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|sample| (sample.calls - sample.memo_hits, sample.distinct_calls))
+        .filter(|&(executed, distinct)| executed > 0 && distinct > 0)
+        .map(|(executed, distinct)| ((executed as f64).ln(), (distinct as f64).ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| (covariance / variance).max(0.0))
+}
+
+/// What the sample run measured.
+struct Sample<'a> {
+    finished: bool,
+    elapsed_ms: f64,
+    stats: &'a Stats,
+    samples: &'a [Checkpoint],
+    exponent: Option<f64>,
+    /// Whether most calls repeated earlier ones, see [`REPEATING`].
+    repeating: bool,
+}
+
+fn report(args: &EstimateArgs, sample: &Sample, verdict: Verdict) -> String {
+    let Sample {
+        finished,
+        elapsed_ms,
+        stats,
+        samples,
+        exponent,
+        repeating,
+    } = *sample;
+    let mut report = String::from("estimate, a heuristic from a sample run:\n");
+    let ending = match finished {
+        true => "finished",
+        false => "ran out of fuel",
+    };
+    let _ = writeln!(
+        report,
+        "  sample:       {} calls in {elapsed_ms:.3} ms, {ending}",
+        stats.calls
+    );
+    let _ = match exponent {
+        Some(exponent) => writeln!(report, "  new calls:    grow as executed^{exponent:.2}"),
+        None => writeln!(report, "  new calls:    too few samples to fit"),
+    };
+    let hit_rate = |sample: &Checkpoint| match sample.calls {
+        0 => 0.0,
+        calls => sample.memo_hits as f64 / calls as f64 * 100.0,
+    };
+    if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+        let _ = writeln!(
+            report,
+            "  memo hits:    {:.1}% then {:.1}%",
+            hit_rate(first),
+            hit_rate(last)
+        );
+    }
+    let per_call = match stats.calls {
+        0 => stats.printed_bytes as f64,
+        calls => stats.printed_bytes as f64 / calls as f64,
+    };
+    let _ = writeln!(
+        report,
+        "  output:       {} bytes, {per_call:.2} per call",
+        stats.printed_bytes
+    );
+    let _ = writeln!(
+        report,
+        "  limits:       {} ms{}",
+        args.time_limit_ms,
+        match args.max_output_bytes {
+            Some(bytes) => format!(", {bytes} bytes"),
+            None => String::new(),
+        }
+    );
+    let _ = writeln!(report, "  verdict:      {}", verdict.describe());
+    if repeating {
+        let memo = match args.no_memo {
+            true => "the memo would serve them",
+            false => "the memo doesn't serve them",
+        };
+        let _ = writeln!(
+            report,
+            "  most calls repeat the work of earlier ones, {memo}"
+        );
+    }
+    report
+}
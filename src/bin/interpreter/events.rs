@@ -0,0 +1,69 @@
+use crate::teardown::Ending;
+use rinha::{cache, interpreter::Progress, RunConfig};
+use std::io::Write;
+
+/// The version of the events, bumped when a field changes its meaning or
+/// goes away. New fields and new events don't bump it.
+const SCHEMA: u32 = 1;
+
+/// How the lifecycle of a run is reported into stderr, for the tools that
+/// supervise the interpreter.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Writes one event as a line of its own, with the version of the schema.
+fn emit(event: &str, mut fields: serde_json::Value) {
+    fields["event"] = event.into();
+    fields["schema"] = SCHEMA.into();
+    // Locked, so the lines of the events never mix with other output.
+    let _ = writeln!(std::io::stderr().lock(), "{fields}");
+}
+
+/// The program is about to run, `hash` tells the versions of a file apart.
+pub fn start(file: &str, text: &str) {
+    let hash = format!("{:016x}", cache::fnv1a(text.as_bytes()));
+    emit("start", serde_json::json!({ "file": file, "hash": hash }));
+}
+
+/// The limits enforced on the run, `null` when one is off.
+pub fn limits(config: &RunConfig) {
+    let overflow = clap::ValueEnum::to_possible_value(&config.overflow);
+    emit(
+        "limits",
+        serde_json::json!({
+            "overflow": overflow.map(|overflow| overflow.get_name().to_string()),
+            "warn_print_depth": config.print_thresholds.depth,
+            "warn_print_bytes": config.print_thresholds.bytes,
+            "max_output_bytes": config.max_output_bytes,
+            "max_depth": config.max_depth,
+            "max_steps": config.max_steps,
+            "timeout_ms": config.timeout.map(|timeout| timeout.as_millis() as u64),
+        }),
+    );
+}
+
+pub fn progress(progress: &Progress) {
+    emit(
+        "progress",
+        serde_json::json!({
+            "ops": progress.calls,
+            "elapsed_ms": progress.elapsed.as_millis() as u64,
+        }),
+    );
+}
+
+pub fn done(ending: Ending) {
+    let status = match ending {
+        Ending::Completed => "ok",
+        Ending::Failed(_) => "error",
+        Ending::Interrupted => "interrupted",
+        Ending::Panicked => "panicked",
+    };
+    emit(
+        "done",
+        serde_json::json!({ "status": status, "exit_code": ending.exit_code() }),
+    );
+}
@@ -0,0 +1,1409 @@
+use clap::{CommandFactory, Parser};
+use events::EventFormat;
+use rinha::{
+    ast::{self, Element},
+    cache::{self, Cache},
+    check, format,
+    input::{self, Format},
+    interpreter::{
+        CallCounts, ErrorKind, Frame, ImpureRecursion, IntFormat, Overflow, Primitive,
+        PrintThresholds, PrintWarning, Progress, SequentialPair, Stats,
+    },
+    memo,
+    optimize::{self, OptLevel},
+    parser,
+    runtime::symbol::Symbol,
+    wasm, Backend, RinhaError, RunConfig,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    io::{self, IsTerminal, Write},
+    process,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use teardown::{Ending, Teardown};
+
+mod bench;
+mod debug;
+mod difftest;
+mod estimate;
+mod events;
+mod pipeline;
+mod snapshot;
+mod teardown;
+
+/// The default of `--warn-print-depth`, far deeper than any list a program
+/// means to print.
+const WARN_PRINT_DEPTH: u64 = 10_000;
+
+/// The default of `--warn-print-bytes`.
+const WARN_PRINT_BYTES: u64 = 1024 * 1024;
+
+/// The calls of the backtraces of the runtime errors shown before and after
+/// the ones left out, see [`backtrace`].
+const BACKTRACE_INNERMOST: usize = 16;
+const BACKTRACE_OUTERMOST: usize = 4;
+
+/// The exit code of runs stopped by `--max-output-bytes`, `--max-depth`,
+/// `--max-steps` or `--timeout`.
+const LIMIT_EXIT_CODE: i32 = 3;
+
+/// The native stack of the thread that runs the interpreter. The calls in
+/// tail position don't grow it, the other ones do, so it's as deep as the
+/// recursion of the programs that isn't a loop: a call nested in another
+/// takes about a kilobyte of it in release builds and six in debug ones,
+/// so a gigabyte holds a million of them. It's only reserved, the pages
+/// are used as the recursion gets there.
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
+
+/// The part of [`STACK_SIZE`] the calls leave to what runs past the last
+/// one checked, the arguments, the natives and the prints. The recursion
+/// that gets there fails with the depth limit instead of overflowing.
+const STACK_RESERVE: usize = 64 * 1024 * 1024;
+
+/// Interpreter for the `rinha` language.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+
+    /// Running is the default, so `interpreter file.json` works.
+    #[command(flatten)]
+    run: RunArgs,
+
+    /// When to use colors in the diagnostics
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: Color,
+
+    /// How the errors are reported
+    #[arg(long, global = true, value_enum, default_value_t)]
+    error_format: ErrorFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+enum Color {
+    /// Colors when the terminal supports them
+    #[default]
+    Auto,
+    /// Always colors, even when piped
+    Always,
+    /// Plain text
+    Never,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+enum ErrorFormat {
+    /// Graphical diagnostics pointing at the source code
+    #[default]
+    Human,
+    /// One JSON object per error
+    Json,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+    /// Runs a program.
+    Run(Box<RunArgs>),
+
+    /// Runs a program under every interpreter configuration and reports
+    /// the timings.
+    Bench(bench::BenchArgs),
+
+    /// Reports every problem found in a program, without running it.
+    Check(CheckArgs),
+
+    /// Compiles a program into a module of another target.
+    Compile(CompileArgs),
+
+    /// Runs a program step by step, stopping at breakpoints to show the
+    /// variables in scope and the calls in progress.
+    Debug(debug::DebugArgs),
+
+    /// Runs a program on two backends and reports the first place where
+    /// what they printed or how they ended differs.
+    Difftest(difftest::DifftestArgs),
+
+    /// Runs a program for a limited number of calls, and estimates whether
+    /// the whole run fits the time and output limits.
+    Estimate(estimate::EstimateArgs),
+
+    /// Prints a program as `.rinha` source, indented and with the
+    /// parentheses its meaning needs, to read the JSON ASTs of other tools.
+    Fmt(FmtArgs),
+
+    /// Parses, checks and runs a program, summarizing each stage.
+    Pipeline(pipeline::PipelineArgs),
+
+    /// Lists the changes to what the programs do, with the flags that
+    /// restore the old behavior.
+    Semantics(SemanticsArgs),
+
+    /// Runs every program of a directory and compares what they print with
+    /// their `.expected` files, exiting with 1 when one differs.
+    Test(snapshot::TestArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    /// The program to run, a JSON AST or a `.rinha` source file, `-` or
+    /// nothing for the standard input
+    #[arg(default_value = input::STDIN, hide_default_value = true)]
+    main: String,
+
+    /// What the program is written as, told by the `.rinha` extension or by
+    /// the text otherwise
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    from: Option<Format>,
+
+    /// Prints the AST of the program as JSON, like the `rinha` parser, and
+    /// exits without running it
+    #[arg(long)]
+    emit_ast: bool,
+
+    /// Prints the value of the program after running it
+    #[arg(long)]
+    print_result: bool,
+
+    /// Silences the notes of the interpreter
+    #[arg(long)]
+    quiet: bool,
+
+    /// Behaves exactly like the specification, only `print` writes
+    /// anything
+    #[arg(long)]
+    spec: bool,
+
+    /// How the integers are printed, ignored with `--spec`
+    #[arg(long, value_enum, default_value_t)]
+    int_format: IntFormat,
+
+    /// What the arithmetic does when the results don't fit in 32 bits,
+    /// `widen` by default and `wrap32` with `--spec`
+    #[arg(long, value_enum)]
+    overflow: Option<Overflow>,
+
+    /// Provides what's outside of the specification: the floats, like
+    /// `1.5`, the records, like `{ x: 1, y: 2 }`, and the natives, like
+    /// `par_pair(f, a, b)` that calls `f(a)` and `f(b)` on two threads
+    #[arg(long, conflicts_with = "spec")]
+    extensions: bool,
+
+    /// The engine that runs the program: the tree-walking interpreter, the
+    /// bytecode machine that doesn't memoize and provides no natives, or the
+    /// bytecode machine running the functions on integers as machine code
+    #[arg(long, value_enum, default_value_t)]
+    backend: Backend,
+
+    /// How much the program is rewritten before running it: `1` folds the
+    /// operators on constants and the `if` on constant conditions, `2` also
+    /// drops the unused `let`
+    #[arg(long, value_enum, value_name = "LEVEL", default_value_t)]
+    opt_level: OptLevel,
+
+    /// Binds `name` to the value for the program, as if it started with
+    /// `let name = value;`. The integers and the booleans bind those, the
+    /// other values bind strings
+    #[arg(long = "arg", value_name = "NAME=VALUE")]
+    args: Vec<input::Arg>,
+
+    /// Checks the program like `check` before running it: the variables
+    /// used where they aren't bound and the calls with the wrong number of
+    /// arguments are all reported, and the program doesn't run, exiting
+    /// with 1
+    #[arg(long)]
+    check: bool,
+
+    /// Reports how many calls ran each function and how many were served
+    /// by the memo, into stderr
+    #[arg(long)]
+    coverage: bool,
+
+    /// Reports how long the program took to run, into stderr
+    #[arg(long)]
+    time: bool,
+
+    /// Reports the counters of the run into stderr: the terms evaluated,
+    /// the calls, the memo hits and misses and the deepest the calls went
+    #[arg(long)]
+    stats: bool,
+
+    /// Warns the first time a printed value nests tuples deeper than
+    /// this, off by default with `--spec`
+    #[arg(long, value_name = "DEPTH")]
+    warn_print_depth: Option<u64>,
+
+    /// Warns the first time a single print writes more bytes than this,
+    /// off by default with `--spec`
+    #[arg(long, value_name = "BYTES")]
+    warn_print_bytes: Option<u64>,
+
+    /// Reports the lifecycle of the run into stderr, one event per line:
+    /// `start`, `limits`, `progress` and `done`
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    events: Option<EventFormat>,
+
+    /// How often the `progress` events are reported, in milliseconds, at
+    /// least one
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 1000,
+        requires = "events",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    events_interval: u64,
+
+    /// Runs the program again without the memo, printing nothing, and
+    /// compares both runs into stderr. The second run takes its own time,
+    /// but it's kept apart: the value, the summary and the other reports
+    /// are the ones of the first
+    #[arg(long, conflicts_with = "no_memo")]
+    ab_memo: bool,
+
+    /// Runs every call, without memoizing any
+    #[arg(long)]
+    no_memo: bool,
+
+    /// Keeps at most this many memo entries, dropping the ones used the
+    /// longest ago to make room for the new ones. `--no-memo` keeps none
+    #[arg(
+        long,
+        value_name = "ENTRIES",
+        default_value_t = memo::DEFAULT_CAPACITY,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    memo_capacity: usize,
+
+    /// Evaluates the values of the `let` and the arguments of the calls
+    /// once their variable is used, the ones never used aren't evaluated.
+    /// It doesn't memoize, and runs on the tree-walking interpreter
+    #[arg(long, conflicts_with_all = ["spec", "backend"])]
+    lazy: bool,
+
+    /// Also writes everything printed into this file. A regular file only
+    /// gets its name once the run ends on its own, the devices and the
+    /// pipes are written in place
+    #[arg(long, value_name = "FILE")]
+    tee: Option<String>,
+
+    /// Stops the program once it printed this many bytes, exiting with 3.
+    /// The output and the `--tee` files get the bytes up to the limit
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes: Option<u64>,
+
+    /// Stops the program at the call that would make more than this many
+    /// calls in progress at once, exiting with 3. The calls in tail
+    /// position don't add up
+    #[arg(long, value_name = "CALLS")]
+    max_depth: Option<u64>,
+
+    /// Stops the program after it evaluated this many terms, or ran this
+    /// many instructions of the bytecode, exiting with 3
+    #[arg(long, value_name = "STEPS")]
+    max_steps: Option<u64>,
+
+    /// Stops the program once it ran for this long, like `5s` or `500ms`,
+    /// exiting with 3. The summary still gets the counters of the run
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Writes how the run ended and its counters into this file, as JSON
+    #[arg(long, value_name = "FILE")]
+    summary: Option<String>,
+
+    /// Keeps the parsed `.rinha` programs in this directory, for the next
+    /// runs
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<String>,
+
+    /// Runs only the term at this path, like `expression.value.value` for
+    /// the body of the first function
+    #[arg(long, value_name = "PATH")]
+    entry: Option<String>,
+
+    /// Binds a variable used by the `--entry` term but bound outside of
+    /// it, the value is a `rinha` expression
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_definition, requires = "entry")]
+    define: Vec<(String, String)>,
+
+    /// Reports the term that produced a unit value, instead of carrying
+    /// it around. On by default in debug builds
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_value_t = cfg!(debug_assertions),
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    debug_assert_none: bool,
+
+    /// Panics at the print after this many, to test how the panics are
+    /// reported
+    #[cfg(feature = "debug-invariants")]
+    #[arg(long, value_name = "PRINTS", hide = true)]
+    panic_after_prints_for_tests: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    /// The program to check, a JSON AST or a `.rinha` source file, `-`
+    /// for the standard input
+    main: String,
+
+    /// The maximum number of findings shown, the rest are summarized
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+    max_findings: u32,
+
+    /// Also infers the types of the program, reporting the values used
+    /// with another type, and prints the types of the variables bound at
+    /// the top. It's stricter than the interpreter, the branches of an
+    /// `if` must have the same type
+    #[arg(long)]
+    types: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct FmtArgs {
+    /// The program to print, a JSON AST or a `.rinha` source file, `-` for
+    /// the standard input
+    main: String,
+
+    /// What the program is written as, told by the `.rinha` extension or by
+    /// the text otherwise
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    from: Option<Format>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompileArgs {
+    /// The program to compile, a JSON AST or a `.rinha` source file, `-`
+    /// for the standard input
+    main: String,
+
+    /// What the program is written as, told by the `.rinha` extension or by
+    /// the text otherwise
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    from: Option<Format>,
+
+    /// What the program is compiled into
+    #[arg(long, value_enum)]
+    target: Target,
+
+    /// Where the module is written, the program with the extension of the
+    /// target by default
+    #[arg(long, short)]
+    output: Option<String>,
+
+    /// What the arithmetic does when the results don't fit in 32 bits, only
+    /// `trap` and `wrap32` can be compiled
+    #[arg(long, value_enum, default_value_t = Overflow::Trap)]
+    overflow: Overflow,
+
+    /// How much the program is rewritten before compiling it, like for
+    /// `run`
+    #[arg(long, value_enum, value_name = "LEVEL", default_value_t)]
+    opt_level: OptLevel,
+
+    /// Binds `name` to the value in the module, like for `run`
+    #[arg(long = "arg", value_name = "NAME=VALUE")]
+    args: Vec<input::Arg>,
+
+    /// Writes the module in the text format of the target
+    #[arg(long)]
+    text: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Target {
+    /// A WebAssembly module for WASI, its `_start` runs the program
+    Wasm32,
+}
+
+#[derive(clap::Args, Debug)]
+struct SemanticsArgs {
+    /// Only the changes introduced after this version, like `0.0.5`
+    #[arg(long, value_name = "VERSION", value_parser = parse_version)]
+    since: Option<Version>,
+
+    /// Prints the changes as a JSON array, like `semantics.json`
+    #[arg(long)]
+    json: bool,
+}
+
+/// A version made of numbers separated by dots.
+#[derive(Clone, Debug)]
+struct Version(Vec<u64>);
+
+fn main() {
+    let interpreter = thread::Builder::new()
+        .name("main".into())
+        .stack_size(STACK_SIZE)
+        .spawn(|| interpreter(Some(STACK_SIZE - STACK_RESERVE)));
+    match interpreter {
+        Ok(interpreter) => {
+            // The panic was reported, and the teardown closed the files.
+            if interpreter.join().is_err() {
+                process::exit(Ending::Panicked.exit_code());
+            }
+        }
+        // Without room for the stack, it runs in the stack it has, whose
+        // size isn't known.
+        Err(_) => self::interpreter(None),
+    }
+}
+
+/// Runs the command line, the calls of the program taking at most
+/// `max_stack` bytes of the native stack.
+fn interpreter(max_stack: Option<usize>) {
+    // Without anything to read, the usage is more helpful than waiting.
+    if std::env::args_os().len() == 1 && io::stdin().is_terminal() {
+        let _ = Cli::command().bin_name(env!("CARGO_BIN_NAME")).print_help();
+        process::exit(2);
+    }
+    let cli = Cli::parse();
+    install_report_handler(cli.color, cli.error_format);
+    let command = match (cli.command, cli.run) {
+        (Some(Subcommand::Run(args)), _) => *args,
+        (None, args) => args,
+        (Some(Subcommand::Bench(args)), _) => {
+            if let Err(error) = bench::run(args) {
+                eprintln!("{error:?}");
+                process::exit(1);
+            }
+            return;
+        }
+        (Some(Subcommand::Check(args)), _) => check(args, cli.error_format),
+        (Some(Subcommand::Compile(args)), _) => compile(args),
+        (Some(Subcommand::Debug(args)), _) => {
+            if let Err(error) = debug::run(args) {
+                eprintln!("{error:?}");
+                process::exit(1);
+            }
+            return;
+        }
+        (Some(Subcommand::Difftest(args)), _) => match difftest::run(args) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(error) => {
+                eprintln!("{error:?}");
+                process::exit(2);
+            }
+        },
+        (Some(Subcommand::Estimate(args)), _) => {
+            if let Err(error) = estimate::run(args) {
+                eprintln!("{error:?}");
+                process::exit(1);
+            }
+            return;
+        }
+        (Some(Subcommand::Fmt(args)), _) => return fmt(args),
+        (Some(Subcommand::Pipeline(args)), _) => {
+            process::exit(pipeline::run(args, cli.error_format))
+        }
+        (Some(Subcommand::Semantics(args)), _) => return semantics(args),
+        (Some(Subcommand::Test(args)), _) => match snapshot::run(args) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(error) => {
+                eprintln!("{error:?}");
+                process::exit(2);
+            }
+        },
+    };
+
+    let text = read_source(&command.main);
+    if command.emit_ast {
+        return emit_ast(&command.main, &text, command.from);
+    }
+    let int_format = match command.spec {
+        true => IntFormat::Plain,
+        false => command.int_format,
+    };
+    let overflow = command.overflow.unwrap_or(match command.spec {
+        true => Overflow::Wrap32,
+        false => Overflow::Widen,
+    });
+
+    let print_thresholds = PrintThresholds {
+        depth: command
+            .warn_print_depth
+            .or((!command.spec).then_some(WARN_PRINT_DEPTH)),
+        bytes: command
+            .warn_print_bytes
+            .or((!command.spec).then_some(WARN_PRINT_BYTES)),
+    };
+    let is_source = Format::of(command.from, &command.main, &text) == Format::Source;
+    let source = Rc::new(is_source.then(|| text.clone()));
+    let on_print_warning = {
+        let source = source.clone();
+        move |warning: &PrintWarning| eprintln!("{}", print_warning(warning, source.as_deref()))
+    };
+    let on_sequential_pair = {
+        let source = source.clone();
+        move |pair: &SequentialPair| {
+            eprintln!(
+                "warning: the par_pair at {} calls `{}` on one thread after the other, \
+                 something it can call prints",
+                position(&pair.call, source.as_deref()),
+                pair.function
+            );
+        }
+    };
+    let on_impure_recursion = {
+        let source = source.clone();
+        move |recursion: &ImpureRecursion| {
+            eprintln!(
+                "hint: `{}` calls itself and prints at {}, so its calls aren't memoized and \
+                 it may take exponential time. Remove the print, or see the calls with \
+                 --coverage instead",
+                recursion.function,
+                position(&recursion.print, source.as_deref())
+            );
+        }
+    };
+
+    // The cache is only written when asked for, and an unusable one fails
+    // before anything runs.
+    if let Some(dir) = &command.cache_dir {
+        if let Err(error) = fs::create_dir_all(dir) {
+            eprintln!(
+                "{:?}",
+                miette::miette!("could not create `{dir}`, asked for by --cache-dir: {error}")
+            );
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+
+    // Every way out of the run from here goes through the teardown.
+    let mut teardown = Teardown::default();
+    if let Some(path) = &command.summary {
+        teardown.summary(path);
+    }
+    if command.events.is_some() {
+        teardown.events();
+    }
+    let limits = [
+        ("max_output_bytes", command.max_output_bytes),
+        ("max_depth", command.max_depth),
+        ("max_steps", command.max_steps),
+        (
+            "timeout_ms",
+            command.timeout.map(|timeout| timeout.as_millis() as u64),
+        ),
+    ];
+    for (name, limit) in limits {
+        if let Some(limit) = limit {
+            teardown.limit(name, limit);
+        }
+    }
+    teardown.semantic_flags(semantic_flags(&command, overflow, int_format));
+    if command.check && !check_before_run(&command, &text, cli.error_format) {
+        teardown.exit(Ending::Failed(1), None);
+    }
+    let config = RunConfig {
+        filename: command.main.clone(),
+        backend: command.backend,
+        memoize: !command.no_memo,
+        memo_capacity: command.memo_capacity,
+        assert_none: command.debug_assert_none,
+        tees: command.tee.iter().map(|path| teardown.tee(path)).collect(),
+        max_output_bytes: command.max_output_bytes,
+        max_depth: command.max_depth,
+        max_stack,
+        max_steps: command.max_steps,
+        timeout: command.timeout,
+        int_format,
+        overflow,
+        extensions: command.extensions,
+        opt_level: command.opt_level,
+        args: command.args.clone(),
+        lazy: command.lazy,
+        on_sequential_pair: Some(Rc::new(on_sequential_pair)),
+        on_impure_recursion: match command.spec {
+            true => None,
+            false => Some(Rc::new(on_impure_recursion)),
+        },
+        interrupt: Some(interrupt_on_ctrl_c()),
+        print_thresholds,
+        on_print_warning: Some(Rc::new(on_print_warning)),
+        progress_interval: Duration::from_millis(command.events_interval),
+        on_progress: command
+            .events
+            .map(|_| Rc::new(events::progress) as Rc<dyn Fn(&Progress)>),
+        #[cfg(feature = "debug-invariants")]
+        panic_after_prints: command.panic_after_prints_for_tests,
+        ..RunConfig::default()
+    };
+    if command.events.is_some() {
+        events::start(&command.main, &text);
+        events::limits(&config);
+    }
+    let run = |config: &RunConfig| {
+        if let Some(path) = &command.entry {
+            rinha::run_ast(select_entry(&command, path, &text), config)
+        } else if is_source {
+            match &command.cache_dir {
+                Some(dir) => parse_cached(&Cache::new(dir), &command.main, &text)
+                    .map_err(RinhaError::from)
+                    .and_then(|file| rinha::run_parsed(file, &text, config)),
+                None => rinha::run_source(&text, config),
+            }
+        } else {
+            rinha::run_ast(read_json(&command.main, &text), config)
+        }
+    };
+
+    let time = Instant::now();
+    let outcome = match run(&config) {
+        Ok(outcome) => outcome,
+        Err(error) => fail(
+            teardown,
+            error,
+            &command.main,
+            text,
+            command.from,
+            cli.error_format,
+        ),
+    };
+    let elapsed = time.elapsed();
+
+    if command.ab_memo {
+        let memoized = (elapsed, &outcome.stats);
+        let unmemoized = RunConfig {
+            memoize: false,
+            output: Some(Rc::new(RefCell::new(io::sink()))),
+            tees: vec![],
+            ..config.clone()
+        };
+        let time = Instant::now();
+        match run(&unmemoized) {
+            Ok(second) => eprint!(
+                "{}",
+                ab_memo_report(memoized, (time.elapsed(), &second.stats))
+            ),
+            Err(error) => fail(
+                teardown,
+                error,
+                &command.main,
+                text,
+                command.from,
+                cli.error_format,
+            ),
+        }
+    }
+
+    if command.coverage {
+        let source = is_source.then_some(text.as_str());
+        eprint!("{}", coverage_report(&outcome.stats, source));
+    }
+    if command.time {
+        eprintln!("time: {:.3} ms", elapsed.as_secs_f64() * 1000.0);
+    }
+    if command.stats {
+        let memoized = config.memoize && config.backend == Backend::Tree && !config.lazy;
+        eprint!("{}", stats_report(&outcome.stats, memoized));
+    }
+
+    if command.print_result {
+        let value = outcome.value.display(int_format);
+        if let Err(error) = writeln!(io::stdout(), "{value}") {
+            let ending = match error.kind() {
+                io::ErrorKind::BrokenPipe => Ending::Failed(teardown::BROKEN_PIPE_EXIT_CODE),
+                _ => {
+                    eprintln!(
+                        "{:?}",
+                        miette::miette!("could not write the value: {error}")
+                    );
+                    Ending::Failed(1)
+                }
+            };
+            teardown.exit(ending, Some(&outcome.stats));
+        }
+    } else if !command.quiet && !command.spec {
+        note_silent_value(&outcome, int_format);
+    }
+    teardown.exit(Ending::Completed, Some(&outcome.stats));
+}
+
+/// Prints the AST of the program as JSON, exiting with the input error
+/// code when it can't be loaded.
+fn emit_ast(path: &str, text: &str, from: Option<Format>) {
+    match load(path, text, from) {
+        Ok(file) => println!("{}", serde_json::to_string(&file).unwrap()),
+        Err(error) => {
+            eprintln!("{error:?}");
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+}
+
+/// Prints the program as `.rinha` source, exiting with the input error
+/// code when it can't be loaded.
+fn fmt(args: FmtArgs) {
+    let text = read_source(&args.main);
+    match load(&args.main, &text, args.from) {
+        Ok(file) => println!("{}", format::to_source(&file.expression)),
+        Err(error) => {
+            eprintln!("{error:?}");
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+}
+
+/// The flags in effect that change what the program does, with the value
+/// they have whether given or not. They're the flags named by
+/// `semantics.json` to restore the old behaviors.
+fn semantic_flags(command: &RunArgs, overflow: Overflow, int_format: IntFormat) -> Vec<String> {
+    use clap::ValueEnum;
+
+    let name = |value: Option<clap::builder::PossibleValue>| {
+        value
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    };
+    let mut flags = vec![
+        format!("--overflow={}", name(overflow.to_possible_value())),
+        format!("--int-format={}", name(int_format.to_possible_value())),
+        format!("--debug-assert-none={}", command.debug_assert_none),
+    ];
+    if command.spec {
+        flags.push("--spec".into());
+    }
+    if command.extensions {
+        flags.push("--extensions".into());
+    }
+    if command.lazy {
+        flags.push("--lazy".into());
+    }
+    flags
+}
+
+/// Prints the changes of `semantics.json`, the oldest first.
+fn semantics(args: SemanticsArgs) {
+    let changes: Vec<_> = rinha::semantics::changes()
+        .into_iter()
+        .filter(|change| match &args.since {
+            Some(since) => change.is_since(&since.0),
+            None => true,
+        })
+        .collect();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+        return;
+    }
+    for change in changes {
+        println!(
+            "{} ({}, in {})",
+            change.id, change.version, change.construct
+        );
+        println!("  {}", change.description);
+        match change.restore.is_empty() {
+            true => println!("  can't be restored"),
+            false => println!("  restored by {}", change.restore.join(" ")),
+        }
+    }
+}
+
+/// Parses `--since`, numbers separated by dots.
+fn parse_version(version: &str) -> Result<Version, String> {
+    rinha::semantics::numeric_version(version)
+        .map(Version)
+        .ok_or_else(|| "expected numbers separated by dots, like `0.0.5`".into())
+}
+
+/// Parses `--timeout`, a number followed by `ms`, `s` or `m`.
+fn parse_timeout(timeout: &str) -> Result<Duration, String> {
+    let unit = timeout.trim_start_matches(|c: char| c.is_ascii_digit());
+    let amount = timeout[..timeout.len() - unit.len()].parse::<u64>();
+    match (amount, unit) {
+        (Ok(amount), "ms") => Ok(Duration::from_millis(amount)),
+        (Ok(amount), "s") => Ok(Duration::from_secs(amount)),
+        (Ok(amount), "m") => Ok(Duration::from_secs(amount.saturating_mul(60))),
+        _ => Err("expected a number followed by `ms`, `s` or `m`, like `5s`".into()),
+    }
+}
+
+/// Reports the error and ends the run with the exit code of its kind. The
+/// runtime errors are followed by the calls they went through, for humans.
+fn fail(
+    teardown: Teardown,
+    error: RinhaError,
+    path: &str,
+    text: String,
+    from: Option<Format>,
+    error_format: ErrorFormat,
+) -> ! {
+    let ending = match &error {
+        RinhaError::Parse(_) => Ending::Failed(input::InputError::EXIT_CODE),
+        RinhaError::Runtime(error) if error.kind == ErrorKind::Interrupted => Ending::Interrupted,
+        // Nobody reads the output anymore, like `| head -1`, so there's
+        // nothing to report.
+        RinhaError::Runtime(error) if error.kind == ErrorKind::BrokenPipe => {
+            let stats = error.stats.clone();
+            teardown.exit(
+                Ending::Failed(teardown::BROKEN_PIPE_EXIT_CODE),
+                stats.as_deref(),
+            )
+        }
+        RinhaError::Runtime(error)
+            if matches!(
+                error.kind,
+                ErrorKind::OutputLimit(_)
+                    | ErrorKind::DepthLimit(_)
+                    | ErrorKind::StepLimit(_)
+                    | ErrorKind::Timeout(_)
+            ) =>
+        {
+            Ending::Failed(LIMIT_EXIT_CODE)
+        }
+        RinhaError::Runtime(_) => Ending::Failed(1),
+        RinhaError::Internal(_) => Ending::Panicked,
+    };
+    let trace = match (&error, error_format) {
+        (RinhaError::Runtime(error), ErrorFormat::Human) if !error.trace.is_empty() => {
+            let source = match Format::of(from, path, &text) {
+                Format::Source => Some(text.clone()),
+                Format::Json => fs::read_to_string(error.location.filename.as_str()).ok(),
+            };
+            Some(backtrace(&error.trace, source.as_deref()))
+        }
+        _ => None,
+    };
+    // The runs stopped halfway still tell how far they got.
+    let stats = match &error {
+        RinhaError::Runtime(error) => error.stats.clone(),
+        _ => None,
+    };
+    // What the program printed comes before the report.
+    let _ = io::stdout().flush();
+    eprintln!("{:?}", report(error, path, text, from));
+    if let Some(trace) = trace {
+        eprintln!("{trace}");
+    }
+    teardown.exit(ending, stats.as_deref())
+}
+
+/// The calls a runtime error went through, the innermost first, with the
+/// same call repeated in a row shown once. Only the innermost and the
+/// outermost calls of the deep traces are shown.
+fn backtrace(trace: &[Frame], source: Option<&str>) -> String {
+    let mut runs: Vec<(&Frame, usize)> = Vec::new();
+    for frame in trace {
+        match runs.last_mut() {
+            Some((last, count)) if *last == frame => *count += 1,
+            _ => runs.push((frame, 1)),
+        }
+    }
+    let line = |(frame, count): &(&Frame, usize)| {
+        let mut line = format!("  {} at {}", frame.function, position(&frame.call, source));
+        if *count > 1 {
+            line.push_str(&format!(", {count} times in a row"));
+        }
+        line
+    };
+
+    let mut lines = vec!["stack, innermost first:".to_string()];
+    if runs.len() <= BACKTRACE_INNERMOST + BACKTRACE_OUTERMOST {
+        lines.extend(runs.iter().map(line));
+    } else {
+        let hidden = &runs[BACKTRACE_INNERMOST..runs.len() - BACKTRACE_OUTERMOST];
+        let calls: usize = hidden.iter().map(|(_, count)| count).sum();
+        lines.extend(runs[..BACKTRACE_INNERMOST].iter().map(line));
+        lines.push(format!("  ... {calls} more calls"));
+        lines.extend(runs[runs.len() - BACKTRACE_OUTERMOST..].iter().map(line));
+    }
+    lines.join("\n")
+}
+
+/// Compares the run with the memo and the run without it. The calls are
+/// the bodies that actually ran, the memo serves the rest.
+fn ab_memo_report(memoized: (Duration, &Stats), unmemoized: (Duration, &Stats)) -> String {
+    let millis = |time: Duration| time.as_secs_f64() * 1000.0;
+    let (on_time, on) = memoized;
+    let (off_time, off) = unmemoized;
+    let speedup = off_time.as_secs_f64() / on_time.as_secs_f64().max(f64::MIN_POSITIVE);
+    format!(
+        "ab-memo:\n  memo on:  {:>12.3} ms {:>12} calls\n  memo off: {:>12.3} ms {:>12} calls\n  speedup:  {speedup:>12.2}x\n",
+        millis(on_time),
+        on.calls - on.memo_hits,
+        millis(off_time),
+        off.calls - off.memo_hits,
+    )
+}
+
+/// The counters of the run, the memo misses are the calls whose bodies ran
+/// while memoizing.
+fn stats_report(stats: &Stats, memoized: bool) -> String {
+    let memo = match memoized {
+        true => format!(
+            "{} hits, {} misses",
+            stats.memo_hits,
+            stats.calls - stats.memo_hits
+        ),
+        false => "off".into(),
+    };
+    format!(
+        "stats:\n  terms:     {:>12}\n  calls:     {:>12}\n  memo:      {memo}\n  max depth: {:>12}\n",
+        stats.terms, stats.calls, stats.max_depth,
+    )
+}
+
+/// The flag raised by Ctrl-C, the run stops at the next call so the
+/// teardown can close the files.
+fn interrupt_on_ctrl_c() -> Arc<AtomicBool> {
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let raised = interrupt.clone();
+    if let Err(error) = ctrlc::set_handler(move || raised.store(true, Ordering::Relaxed)) {
+        eprintln!("warning: Ctrl-C will stop the run abruptly: {error}");
+    }
+    interrupt
+}
+
+/// Parses the program, unless it's in the cache already. The cache files
+/// that can't be used are reported and parsed again.
+fn parse_cached(
+    cache: &Cache,
+    filename: &str,
+    text: &str,
+) -> Result<ast::File, parser::ParseError> {
+    // The locations hold the filename, so it's part of the key.
+    let key = format!(
+        "ast-{:016x}",
+        cache::fnv1a(format!("{filename}\0{text}").as_bytes())
+    );
+    let cached = cache.load(&key).and_then(|payload| {
+        let parsed = payload.map(|payload| serde_json::from_slice(&payload));
+        parsed.transpose().map_err(|error| {
+            let error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+            cache::CacheError::Io(error)
+        })
+    });
+    match cached {
+        Ok(Some(file)) => return Ok(file),
+        Ok(None) => {}
+        Err(error) => eprintln!("warning: ignoring the cached AST of `{filename}`: {error}"),
+    }
+
+    let file = parser::parse_or_report(filename, text)?;
+    let stored = serde_json::to_vec(&file).map_err(std::io::Error::from);
+    if let Err(error) = stored.and_then(|payload| cache.store(&key, &payload)) {
+        let path = cache.path(&key);
+        eprintln!(
+            "warning: could not cache the AST of `{filename}` into `{}`, asked for by \
+             --cache-dir: {error}",
+            path.display()
+        );
+    }
+    Ok(file)
+}
+
+/// Splits `--define NAME=VALUE`.
+fn parse_definition(definition: &str) -> Result<(String, String), String> {
+    match definition.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err("expected NAME=VALUE, like `n=10`".into()),
+    }
+}
+
+/// The program made of the term at `path`, with the `--define` bindings
+/// around it. Exits with the input error code when the path leads nowhere,
+/// or when the term uses variables of the skipped code that weren't
+/// defined.
+fn select_entry(command: &RunArgs, path: &str, text: &str) -> ast::File {
+    let file = match load(&command.main, text, command.from) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("{error:?}");
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    };
+    let entry = match rinha::path::select(&file, path) {
+        Ok(entry) => entry.clone(),
+        Err(error) => {
+            eprintln!("{:?}", miette::Report::new(error));
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    };
+
+    let missing: Vec<String> = rinha::path::unbound(&entry)
+        .into_iter()
+        .map(|var| var.text.to_string())
+        .filter(|name| !command.define.iter().any(|(defined, _)| defined == name))
+        .collect();
+    if !missing.is_empty() {
+        let names = missing.join("`, `");
+        eprintln!(
+            "{:?}",
+            miette::miette!(
+                help = format!("bind them with `--define {}=VALUE`", missing[0]),
+                "the term at `{path}` uses `{names}`, bound outside of it"
+            )
+        );
+        process::exit(input::InputError::EXIT_CODE);
+    }
+
+    let mut definitions = vec![];
+    for (name, value) in &command.define {
+        let filename = format!("--define {name}");
+        match parser::parse_or_report(&filename, value) {
+            Ok(value) => {
+                let location = value.location.clone();
+                let name = parser::Var {
+                    text: Symbol::intern(name),
+                    location,
+                    address: Default::default(),
+                };
+                definitions.push((name, value.expression));
+            }
+            Err(error) => {
+                eprintln!("{:?}", miette::Report::new(error));
+                process::exit(input::InputError::EXIT_CODE);
+            }
+        }
+    }
+
+    let location = entry.location().clone();
+    ast::File {
+        expression: rinha::path::with_definitions(entry, definitions),
+        location,
+        ..file
+    }
+}
+
+/// Renders the calls of every function, then of each of its call sites. A
+/// body that ran few times but was served many times is hot, just cached.
+fn coverage_report(stats: &Stats, source: Option<&str>) -> String {
+    let mut functions: BTreeMap<&str, Vec<(&ast::Location, &CallCounts)>> = BTreeMap::new();
+    for (location, counts) in &stats.call_sites {
+        functions
+            .entry(&counts.function)
+            .or_default()
+            .push((location, counts));
+    }
+
+    let mut report = String::from("coverage:\n");
+    for (function, mut sites) in functions {
+        sites.sort_by_key(|(location, _)| location.start);
+        let executed: u64 = sites.iter().map(|(_, counts)| counts.executed).sum();
+        let served: u64 = sites.iter().map(|(_, counts)| counts.served).sum();
+        report.push_str(&format!(
+            "  {function}: {executed} executed, {served} served by the memo\n"
+        ));
+
+        for (location, counts) in sites {
+            report.push_str(&format!(
+                "    call at {}: {} executed, {} served\n",
+                position(location, source),
+                counts.executed,
+                counts.served
+            ));
+        }
+    }
+    report
+}
+
+/// Where the location starts, as a line and a column when the source is
+/// at hand, or as an offset into the file otherwise.
+fn position(location: &ast::Location, source: Option<&str>) -> String {
+    match source {
+        Some(source) => {
+            let (line, column) = location.line_column(source);
+            format!("{}:{line}:{column}", location.filename)
+        }
+        None => format!("{}@{}", location.filename, location.start),
+    }
+}
+
+fn print_warning(warning: &PrintWarning, source: Option<&str>) -> String {
+    match warning {
+        PrintWarning::Depth {
+            depth,
+            threshold,
+            location,
+        } => format!(
+            "warning: the print at {} nests tuples {depth} deep, more than the \
+             {threshold} of --warn-print-depth",
+            position(location, source)
+        ),
+        PrintWarning::Bytes {
+            bytes,
+            threshold,
+            location,
+        } => format!(
+            "warning: the print at {} wrote {bytes} bytes, more than the \
+             {threshold} of --warn-print-bytes",
+            position(location, source)
+        ),
+    }
+}
+
+/// Programs that evaluate to a value without printing anything look like
+/// they did nothing, so tell the user where the value went.
+fn note_silent_value(outcome: &rinha::RunOutcome, int_format: IntFormat) {
+    let silent = outcome.stats.printed_bytes == 0;
+    if silent && !matches!(outcome.value, Primitive::None) {
+        eprintln!(
+            "note: program evaluated to {}; use print(...) or --print-result to see values",
+            outcome.value.display(int_format)
+        );
+    }
+}
+
+/// Runs the static analyses, exiting with 1 when there's any error. The
+/// warnings alone don't fail the check.
+fn check(args: CheckArgs, error_format: ErrorFormat) -> ! {
+    let file = read_program(&args.main);
+    let mut diagnostics = check::Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    if args.types {
+        for signature in check::types::infer(&file, &mut diagnostics) {
+            println!("{signature}");
+        }
+    }
+
+    let code = match diagnostics.max_severity() {
+        Some(check::Severity::Error) => 1,
+        Some(check::Severity::Warning) | None => 0,
+    };
+    if !diagnostics.is_empty() {
+        emit_findings(
+            diagnostics,
+            &file.location.filename,
+            args.max_findings as usize,
+            error_format,
+        );
+    }
+    process::exit(code)
+}
+
+/// Checks the program about to run, with the variables it starts with,
+/// reporting the findings when there's an error. Whether it can run.
+fn check_before_run(command: &RunArgs, text: &str, error_format: ErrorFormat) -> bool {
+    // The errors of the parser are the run's to report.
+    let Ok(file) = load(&command.main, text, command.from) else {
+        return true;
+    };
+    let mut bound: Vec<&str> = command.args.iter().map(|arg| arg.name.as_str()).collect();
+    if command.extensions {
+        bound.push("par_pair");
+        bound.extend(rinha::runtime::list::NATIVES);
+        bound.extend(rinha::runtime::reference::NATIVES);
+        bound.extend(rinha::runtime::string::NATIVES);
+    }
+    let mut diagnostics = check::Diagnostics::new();
+    check::check_with(&file, &bound, &mut diagnostics);
+    if diagnostics.max_severity() != Some(check::Severity::Error) {
+        return true;
+    }
+    emit_findings(
+        diagnostics,
+        &file.location.filename,
+        usize::MAX,
+        error_format,
+    );
+    false
+}
+
+fn compile(args: CompileArgs) -> ! {
+    let text = read_source(&args.main);
+    let file = match load(&args.main, &text, args.from) {
+        Ok(file) => {
+            let expression = input::bind_args(file.expression, &args.args, &file.location.filename);
+            ast::File {
+                expression: optimize::optimize(expression, args.opt_level, args.overflow),
+                ..file
+            }
+        }
+        Err(error) => {
+            eprintln!("{error:?}");
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    };
+    // The positions of the errors come from the source code, which the
+    // JSON ASTs only name.
+    let source = match Format::of(args.from, &args.main, &text) {
+        Format::Source => Some(text),
+        Format::Json => fs::read_to_string(file.location.filename.as_str()).ok(),
+    };
+    let options = wasm::Options {
+        overflow: args.overflow,
+        source: source.as_deref(),
+    };
+    let module = match args.text {
+        true => wasm::to_wat(&file, &options).map(String::into_bytes),
+        false => wasm::compile(&file, &options),
+    };
+    let module = match module {
+        Ok(module) => module,
+        Err(error) => {
+            let report = miette::Report::new(error);
+            let report = match &source {
+                Some(source) => report.with_source_code(miette::NamedSource::new(
+                    &file.location.filename,
+                    source.clone(),
+                )),
+                None => report,
+            };
+            eprintln!("{report:?}");
+            process::exit(1);
+        }
+    };
+    let extension = match (args.target, args.text) {
+        (Target::Wasm32, false) => "wasm",
+        (Target::Wasm32, true) => "wat",
+    };
+    let output = args.output.unwrap_or_else(|| {
+        let stem = match args.main.as_str() {
+            "-" => "main",
+            path => path,
+        };
+        std::path::Path::new(stem)
+            .with_extension(extension)
+            .to_string_lossy()
+            .into_owned()
+    });
+    if let Err(error) = fs::write(&output, module) {
+        eprintln!("error: can't write `{output}`: {error}");
+        process::exit(1);
+    }
+    process::exit(0)
+}
+
+/// Prints the findings into stderr, at most `max_findings` of them for
+/// humans, and every one of them as a JSON array for tools.
+fn emit_findings(
+    diagnostics: check::Diagnostics,
+    filename: &str,
+    max_findings: usize,
+    error_format: ErrorFormat,
+) {
+    match error_format {
+        // One finding per line, so tools don't have to page.
+        ErrorFormat::Json => {
+            let lines: Vec<String> = diagnostics
+                .into_findings()
+                .iter()
+                .map(|finding| serde_json::to_string(finding).unwrap())
+                .collect();
+            eprintln!("[\n{}\n]", lines.join(",\n"));
+        }
+        ErrorFormat::Human => {
+            let report = diagnostics.into_report(max_findings);
+            let report = match fs::read_to_string(filename) {
+                Ok(source) => miette::Report::new(report)
+                    .with_source_code(miette::NamedSource::new(filename, source)),
+                Err(_) => miette::Report::new(report),
+            };
+            eprintln!("{report:?}");
+        }
+    }
+}
+
+/// Sets how every [`miette::Report`] is rendered when printed.
+fn install_report_handler(color: Color, error_format: ErrorFormat) {
+    let _ = miette::set_hook(Box::new(move |_| match error_format {
+        ErrorFormat::Json => Box::new(miette::JSONReportHandler::new()),
+        ErrorFormat::Human => {
+            let opts = miette::MietteHandlerOpts::new();
+            let opts = match color {
+                Color::Auto => opts,
+                Color::Always => opts.color(true),
+                Color::Never => opts.color(false),
+            };
+            Box::new(opts.build())
+        }
+    }));
+}
+
+/// Attaches the source code to the error, so the locations are shown. The
+/// locations of JSON ASTs point to the original `.rinha` file, which is
+/// used when it's around.
+fn report(error: RinhaError, path: &str, text: String, from: Option<Format>) -> miette::Report {
+    let is_source = Format::of(from, path, &text) == Format::Source;
+    let (filename, source) = match &error {
+        RinhaError::Runtime(error) if !is_source => {
+            let filename = error.location.filename.to_string();
+            match fs::read_to_string(&filename) {
+                Ok(source) => (filename, source),
+                Err(_) => return miette::Report::new(error.clone()),
+            }
+        }
+        // The locations of the stack point into a source that isn't known.
+        RinhaError::Internal(error) if !is_source => return miette::Report::new(*error.clone()),
+        _ => (path.to_string(), text),
+    };
+
+    miette::Report::new(error).with_source_code(miette::NamedSource::new(filename, source))
+}
+
+/// Reads the program at `path`, exiting with the input error code when it
+/// can't be done.
+fn read_source(path: &str) -> String {
+    match input::read_to_string(path) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("{:?}", miette::Report::new(error));
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+}
+
+/// Reads the JSON AST, warning when a newer parser wrote it.
+fn parse_json(path: &str, text: &str) -> Result<ast::File, input::InputError> {
+    input::parse_ast(path, text, |metadata| {
+        eprintln!(
+            "warning: `{path}` was written by the parser of rinha {}, newer than this rinha {}, \
+             it may not be understood",
+            metadata.parser_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    })
+}
+
+/// Reads the JSON AST, exiting with the input error code when it can't be
+/// done.
+fn read_json(path: &str, text: &str) -> ast::File {
+    match parse_json(path, text) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("{:?}", miette::Report::new(error));
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+}
+
+/// Reads and loads the program at `path`, exiting with the input error code
+/// when it can't be done.
+fn read_program(path: &str) -> ast::File {
+    let file = read_source(path);
+    match load(path, &file, None) {
+        Ok(ast) => ast,
+        Err(error) => {
+            eprintln!("{error:?}");
+            process::exit(input::InputError::EXIT_CODE);
+        }
+    }
+}
+
+/// Loads the program, parsing it when it's source code, or reading the
+/// JSON AST otherwise.
+fn load(filename: &str, text: &str, from: Option<Format>) -> miette::Result<ast::File> {
+    if Format::of(from, filename, text) == Format::Source {
+        return Ok(parser::parse_or_report(filename, text)?);
+    }
+
+    Ok(parse_json(filename, text)?)
+}
@@ -0,0 +1,151 @@
+use crate::{teardown::Ending, ErrorFormat};
+use rinha::{ast, check, input, RinhaError, RunConfig};
+use std::time::Instant;
+
+/// The findings shown when the check stage reports.
+const MAX_FINDINGS: usize = 50;
+
+#[derive(clap::Args, Debug)]
+pub struct PipelineArgs {
+    /// The program to run, a JSON AST or a `.rinha` source file, `-` for
+    /// the standard input
+    main: String,
+
+    /// The last stage to run
+    #[arg(long, value_enum, default_value_t = Stage::Execute)]
+    until: Stage,
+}
+
+/// The stages of the pipeline, in the order they run.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Reads the program into an AST
+    Parse,
+    /// Runs the static analyses, the warnings aren't fatal
+    Check,
+    /// Runs the program
+    Execute,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Check => "check",
+            Stage::Execute => "execute",
+        }
+    }
+}
+
+/// How a stage ended, with the diagnostics to show after its summary
+/// line. The fatal ones carry the exit code.
+struct Outcome {
+    status: String,
+    fatal: Option<i32>,
+    diagnostics: Option<Box<dyn FnOnce()>>,
+}
+
+impl Outcome {
+    fn ok() -> Self {
+        Self {
+            status: "ok".into(),
+            fatal: None,
+            diagnostics: None,
+        }
+    }
+
+    fn failed(code: i32, diagnostics: impl FnOnce() + 'static) -> Self {
+        Self {
+            status: "failed".into(),
+            fatal: Some(code),
+            diagnostics: Some(Box::new(diagnostics)),
+        }
+    }
+}
+
+/// Runs every stage up to `--until`, returning the exit code.
+pub fn run(args: PipelineArgs, error_format: ErrorFormat) -> i32 {
+    let text = crate::read_source(&args.main);
+    let mut file: Option<ast::File> = None;
+
+    for stage in [Stage::Parse, Stage::Check, Stage::Execute] {
+        if stage > args.until {
+            break;
+        }
+
+        let time = Instant::now();
+        let outcome = match stage {
+            Stage::Parse => match crate::load(&args.main, &text, None) {
+                Ok(ast) => {
+                    file = Some(ast);
+                    Outcome::ok()
+                }
+                Err(error) => {
+                    Outcome::failed(input::InputError::EXIT_CODE, move || eprintln!("{error:?}"))
+                }
+            },
+            Stage::Check => check_stage(file.as_ref().unwrap(), error_format),
+            Stage::Execute => execute_stage(file.take().unwrap(), &args.main, text.clone()),
+        };
+        let elapsed_ms = time.elapsed().as_secs_f64() * 1000.0;
+
+        eprintln!(
+            "{:<8} {} in {elapsed_ms:.2}ms",
+            stage.name(),
+            outcome.status
+        );
+        if let Some(diagnostics) = outcome.diagnostics {
+            diagnostics();
+        }
+        if let Some(code) = outcome.fatal {
+            return code;
+        }
+    }
+    0
+}
+
+fn check_stage(file: &ast::File, error_format: ErrorFormat) -> Outcome {
+    let mut diagnostics = check::Diagnostics::new();
+    check::check(file, &mut diagnostics);
+
+    let errors = diagnostics.count(check::Severity::Error);
+    let warnings = diagnostics.count(check::Severity::Warning);
+    if diagnostics.is_empty() {
+        return Outcome::ok();
+    }
+
+    let filename = file.location.filename.clone();
+    let emit = move || crate::emit_findings(diagnostics, &filename, MAX_FINDINGS, error_format);
+    if errors > 0 {
+        Outcome {
+            status: format!("failed, {errors} error(s) and {warnings} warning(s)"),
+            ..Outcome::failed(1, emit)
+        }
+    } else {
+        Outcome {
+            status: format!("ok, {warnings} warning(s)"),
+            fatal: None,
+            diagnostics: Some(Box::new(emit)),
+        }
+    }
+}
+
+fn execute_stage(file: ast::File, path: &str, text: String) -> Outcome {
+    let config = RunConfig {
+        filename: path.to_string(),
+        ..RunConfig::default()
+    };
+
+    match rinha::run_ast(file, &config) {
+        Ok(_) => Outcome::ok(),
+        Err(error) => {
+            let code = match error {
+                RinhaError::Parse(_) => input::InputError::EXIT_CODE,
+                RinhaError::Runtime(_) => 1,
+                RinhaError::Internal(_) => Ending::Panicked.exit_code(),
+            };
+            let report = crate::report(error, path, text, None);
+            Outcome::failed(code, move || eprintln!("{report:?}"))
+        }
+    }
+}
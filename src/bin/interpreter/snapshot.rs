@@ -0,0 +1,173 @@
+use miette::{IntoDiagnostic, WrapErr};
+use rinha::{Backend, RunConfig};
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct TestArgs {
+    /// The directory of the programs, JSON ASTs and `.rinha` source files
+    /// next to the `.expected` files of what they print
+    #[arg(default_value = "programs")]
+    dir: PathBuf,
+
+    /// Writes what the programs printed into their `.expected` files,
+    /// instead of comparing them
+    #[arg(long)]
+    bless: bool,
+
+    /// Provides the natives outside of the specification to the programs
+    #[arg(long)]
+    extensions: bool,
+
+    /// The engine that runs the programs
+    #[arg(long, value_enum, default_value_t)]
+    backend: Backend,
+}
+
+/// How the snapshot of one program compared.
+enum Verdict {
+    Passed,
+    Blessed,
+    Missing,
+    Differs {
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Runs every program of the directory, returning whether all of them
+/// printed what their `.expected` file says.
+pub fn run(args: TestArgs) -> miette::Result<bool> {
+    let programs = programs(&args.dir)?;
+    if programs.is_empty() {
+        miette::bail!(
+            "no `.json` or `.rinha` programs in `{}`",
+            args.dir.display()
+        );
+    }
+
+    let (mut blessed, mut failed) = (0, 0);
+    for program in &programs {
+        let actual = snapshot(program, &args);
+        let expected_path = expected_path(program);
+        let expected = fs::read_to_string(&expected_path).ok();
+        let verdict = match expected {
+            Some(expected) if expected == actual => Verdict::Passed,
+            _ if args.bless => {
+                fs::write(&expected_path, &actual)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("writing `{}`", expected_path.display()))?;
+                Verdict::Blessed
+            }
+            None => Verdict::Missing,
+            Some(expected) => difference(&expected, &actual),
+        };
+
+        let name = program.display();
+        match verdict {
+            Verdict::Passed => println!("ok      {name}"),
+            Verdict::Blessed => {
+                blessed += 1;
+                println!("blessed {name}");
+            }
+            Verdict::Missing => {
+                failed += 1;
+                println!("FAILED  {name}: no `.expected` file, `--bless` writes it");
+            }
+            Verdict::Differs {
+                line,
+                expected,
+                actual,
+            } => {
+                failed += 1;
+                println!("FAILED  {name}: line {line} differs");
+                println!("  expected: {expected:?}");
+                println!("  actual:   {actual:?}");
+            }
+        }
+    }
+    let passed = programs.len() - blessed - failed;
+    println!("{passed} passed, {blessed} blessed, {failed} failed");
+    Ok(failed == 0)
+}
+
+/// The programs of `dir`, sorted so every run goes through them in the
+/// same order.
+fn programs(dir: &Path) -> miette::Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("reading `{}`", dir.display()))?;
+    let mut programs = Vec::new();
+    for entry in entries {
+        let path = entry.into_diagnostic()?.path();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        if path.is_file() && matches!(extension, Some("json" | "rinha")) {
+            programs.push(path);
+        }
+    }
+    programs.sort();
+    Ok(programs)
+}
+
+/// Where the snapshot of `program` is, its whole name followed by
+/// `.expected`, so a JSON AST and a source file of the same name don't
+/// share one.
+fn expected_path(program: &Path) -> PathBuf {
+    let mut path = program.as_os_str().to_owned();
+    path.push(".expected");
+    PathBuf::from(path)
+}
+
+/// What the program printed, followed by the error it ended with. Nothing
+/// in it depends on the build or the machine, so the snapshots hold
+/// everywhere.
+fn snapshot(program: &Path, args: &TestArgs) -> String {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        filename: program.display().to_string(),
+        backend: args.backend,
+        assert_none: false,
+        output: Some(output.clone()),
+        extensions: args.extensions,
+        ..RunConfig::default()
+    };
+    let ended = fs::read_to_string(program)
+        .map_err(|error| error.to_string())
+        .and_then(|text| {
+            crate::load(&config.filename, &text, None).map_err(|error| error.to_string())
+        })
+        .and_then(|file| rinha::run_ast(file, &config).map_err(|error| error.to_string()));
+
+    let mut snapshot = String::from_utf8_lossy(&output.borrow()).into_owned();
+    if let Err(error) = ended {
+        if !snapshot.is_empty() && !snapshot.ends_with('\n') {
+            snapshot.push('\n');
+        }
+        snapshot.push_str(&format!("error: {error}\n"));
+    }
+    snapshot
+}
+
+/// The first line where the snapshots part ways, counting from one.
+fn difference(expected: &str, actual: &str) -> Verdict {
+    let mut expected_lines = expected.split_inclusive('\n');
+    let mut actual_lines = actual.split_inclusive('\n');
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(expected), Some(actual)) if expected == actual => line += 1,
+            (expected, actual) => {
+                return Verdict::Differs {
+                    line,
+                    expected: expected.unwrap_or_default().into(),
+                    actual: actual.unwrap_or_default().into(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use rinha::{input, interpreter::Stats};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    process,
+    rc::Rc,
+};
+
+/// The exit code of runs stopped by Ctrl-C, like the shells do.
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// The exit code of runs whose output was closed, like the shells report
+/// for the programs stopped by `SIGPIPE`.
+pub const BROKEN_PIPE_EXIT_CODE: i32 = 141;
+
+/// The exit code of a panic, it's what Rust uses.
+const PANICKED_EXIT_CODE: i32 = 101;
+
+/// How a run ended, as recorded in the summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    /// The program finished, with its value.
+    Completed,
+    /// The program didn't parse or failed at runtime.
+    Failed(i32),
+    /// The program was stopped by Ctrl-C.
+    Interrupted,
+    /// The interpreter panicked.
+    Panicked,
+}
+
+impl Ending {
+    fn status(self) -> &'static str {
+        match self {
+            Ending::Completed => "completed",
+            Ending::Failed(_) => "failed",
+            Ending::Interrupted => "interrupted",
+            Ending::Panicked => "panicked",
+        }
+    }
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Ending::Completed => 0,
+            Ending::Failed(code) => code,
+            Ending::Interrupted => INTERRUPTED_EXIT_CODE,
+            Ending::Panicked => PANICKED_EXIT_CODE,
+        }
+    }
+
+    /// Whether everything the program would write was written.
+    fn is_whole(self) -> bool {
+        matches!(self, Ending::Completed | Ending::Failed(_))
+    }
+}
+
+/// Every file a run writes, closed exactly once however the run ends.
+///
+/// The regular files are written next to their path with a `.partial`
+/// suffix, and only get their name once the run ended on its own, with its
+/// value or with an error. The runs cut short by Ctrl-C or by a panic leave
+/// the `.partial` files behind, so they're never mistaken for whole ones.
+/// The devices, the pipes and the other files that aren't regular are
+/// written in place, renaming over them would replace them. The standard
+/// output is flushed on every ending, and a file that can't be written
+/// makes a run that completed exit with the input error code.
+#[derive(Default)]
+pub struct Teardown {
+    tees: Vec<(Partial, Rc<RefCell<io::BufWriter<fs::File>>>)>,
+    summary: Option<Partial>,
+    /// The limits given, by the name they have in the summary.
+    limits: Vec<(&'static str, u64)>,
+    semantic_flags: Vec<String>,
+    events: bool,
+    finished: bool,
+}
+
+/// A file being written under a temporary name, or in place when it isn't
+/// a regular file.
+struct Partial {
+    path: String,
+    /// The flag that asked for the file, named when it can't be written.
+    flag: &'static str,
+    /// Whether the file is written in place, see [`Teardown`].
+    in_place: bool,
+}
+
+impl Partial {
+    fn new(path: &str, flag: &'static str) -> Self {
+        // The links are followed, `/dev/stderr` is the device it names.
+        let in_place = fs::metadata(path).is_ok_and(|metadata| !metadata.is_file());
+        Self {
+            path: path.to_string(),
+            flag,
+            in_place,
+        }
+    }
+
+    /// Where the file is written until it's whole.
+    fn temporary(&self) -> String {
+        match self.in_place {
+            true => self.path.clone(),
+            false => format!("{}.partial", self.path),
+        }
+    }
+
+    fn create(&self) -> fs::File {
+        match fs::File::create(self.temporary()) {
+            Ok(file) => file,
+            Err(error) => {
+                let (path, flag) = (self.temporary(), self.flag);
+                eprintln!(
+                    "{:?}",
+                    miette::miette!("could not create `{path}`, asked for by {flag}: {error}")
+                );
+                process::exit(input::InputError::EXIT_CODE);
+            }
+        }
+    }
+
+    /// Gives the file its name, once it's whole.
+    fn complete(&self) -> io::Result<()> {
+        match self.in_place {
+            true => Ok(()),
+            false => fs::rename(self.temporary(), &self.path),
+        }
+    }
+
+    /// Reports that the file couldn't be written.
+    fn report(&self, error: impl std::fmt::Display) {
+        let (path, flag) = (&self.path, self.flag);
+        eprintln!(
+            "{:?}",
+            miette::miette!("could not write `{path}`, asked for by {flag}: {error}")
+        );
+    }
+}
+
+impl Teardown {
+    /// Creates the `--tee` file, exiting with the input error code when it
+    /// can't be done.
+    pub fn tee(&mut self, path: &str) -> Rc<RefCell<dyn io::Write>> {
+        let partial = Partial::new(path, "--tee");
+        let writer = Rc::new(RefCell::new(io::BufWriter::new(partial.create())));
+        self.tees.push((partial, writer.clone()));
+        writer
+    }
+
+    /// Writes a JSON summary of the run into `path` when it ends. The file
+    /// is created right away, so a path that can't be written fails early.
+    pub fn summary(&mut self, path: &str) {
+        let partial = Partial::new(path, "--summary");
+        partial.create();
+        self.summary = Some(partial);
+    }
+
+    /// Records a limit, like the one of `--max-output-bytes`, in the
+    /// summary as `name`.
+    pub fn limit(&mut self, name: &'static str, limit: u64) {
+        self.limits.push((name, limit));
+    }
+
+    /// Records the flags in effect that change what the program does in
+    /// the summary.
+    pub fn semantic_flags(&mut self, flags: Vec<String>) {
+        self.semantic_flags = flags;
+    }
+
+    /// Reports the ending as the `done` event, see [`crate::events`].
+    pub fn events(&mut self) {
+        self.events = true;
+    }
+
+    /// Flushes and closes every file, then exits with the code of the
+    /// ending. It's the only way out of a run.
+    pub fn exit(mut self, ending: Ending, stats: Option<&Stats>) -> ! {
+        let ending = self.finish(ending, stats);
+        process::exit(ending.exit_code())
+    }
+
+    /// Closes the files, returning the ending, which becomes a failure
+    /// when a run that completed couldn't write one of them.
+    fn finish(&mut self, mut ending: Ending, stats: Option<&Stats>) -> Ending {
+        if self.finished {
+            return ending;
+        }
+        self.finished = true;
+
+        let _ = io::stdout().flush();
+        let mut written = true;
+        for (partial, writer) in &self.tees {
+            let flushed = writer.borrow_mut().flush();
+            let completed = match (flushed, ending.is_whole()) {
+                (Ok(()), true) => partial.complete(),
+                (Ok(()), false) => Ok(()),
+                (Err(error), _) => Err(error),
+            };
+            if let Err(error) = completed {
+                partial.report(error);
+                written = false;
+            }
+        }
+        if !written && ending == Ending::Completed {
+            ending = Ending::Failed(input::InputError::EXIT_CODE);
+        }
+
+        if let Some(partial) = &self.summary {
+            let mut summary = serde_json::json!({
+                "status": ending.status(),
+                "exit_code": ending.exit_code(),
+                "calls": stats.map(|stats| stats.calls),
+                "memo_hits": stats.map(|stats| stats.memo_hits),
+                "memo_evictions": stats.map(|stats| stats.memo_evictions),
+                "printed_bytes": stats.map(|stats| stats.printed_bytes),
+                "max_print_depth": stats.map(|stats| stats.max_print_depth),
+                "max_print_bytes": stats.map(|stats| stats.max_print_bytes),
+                "semantic_flags": self.semantic_flags,
+            });
+            for (name, limit) in &self.limits {
+                summary[*name] = (*limit).into();
+            }
+            // The summary is always whole, it tells how the run ended.
+            let mut file = io::BufWriter::new(partial.create());
+            let written = serde_json::to_writer_pretty(&mut file, &summary)
+                .map_err(io::Error::from)
+                .and_then(|()| file.flush())
+                .and_then(|()| partial.complete());
+            if let Err(error) = written {
+                partial.report(error);
+                if ending == Ending::Completed {
+                    ending = Ending::Failed(input::InputError::EXIT_CODE);
+                }
+            }
+        }
+
+        // The last word, once every file is closed.
+        if self.events {
+            crate::events::done(ending);
+        }
+        ending
+    }
+}
+
+/// The panics unwind through here, the other endings go through
+/// [`Teardown::exit`].
+impl Drop for Teardown {
+    fn drop(&mut self) {
+        self.finish(Ending::Panicked, None);
+    }
+}
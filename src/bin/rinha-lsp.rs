@@ -0,0 +1,274 @@
+//! The language server of `rinha`, for the editors that speak the Language
+//! Server Protocol through the standard input and output. It reports the
+//! problems found by the parser, by `check` and by the types of
+//! `check --types`, shows the types of the variables on hover and jumps to
+//! where they're bound.
+
+use miette::Diagnostic as _;
+use rinha::{
+    ast,
+    check::{self, types, Diagnostics, Severity},
+    parser,
+};
+use std::{collections::HashMap, sync::Mutex};
+use tower_lsp::{
+    jsonrpc::Result,
+    lsp_types::{
+        Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+        HoverContents, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult,
+        Location, MarkupContent, MarkupKind, OneOf, Position, Range, ServerCapabilities,
+        ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    },
+    Client, LanguageServer, LspService, Server,
+};
+
+/// The native stack of the threads that analyze the programs, the
+/// analyses recurse as deep as the terms nest.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+struct Backend {
+    client: Client,
+    /// The text of the open documents, as the editor last sent it.
+    documents: Mutex<HashMap<Url, String>>,
+}
+
+impl Backend {
+    fn text(&self, uri: &Url) -> Option<String> {
+        self.documents.lock().unwrap().get(uri).cloned()
+    }
+
+    async fn publish(&self, uri: Url, text: &str, version: Option<i32>) {
+        let diagnostics = diagnostics(uri.path(), text);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "rinha-lsp".into(),
+                version: Some(env!("CARGO_PKG_VERSION").into()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(document.uri.clone(), document.text.clone());
+        self.publish(document.uri, &document.text, Some(document.version))
+            .await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // The whole text comes with every change, see `initialize`.
+        let Some(change) = params.content_changes.into_iter().last() else {
+            return;
+        };
+        let uri = params.text_document.uri;
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(uri.clone(), change.text.clone());
+        self.publish(uri, &change.text, Some(params.text_document.version))
+            .await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.lock().unwrap().remove(&uri);
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let at = params.text_document_position_params;
+        let Some(text) = self.text(&at.text_document.uri) else {
+            return Ok(None);
+        };
+        let Ok(file) = parser::parse_or_report(at.text_document.uri.path(), &text) else {
+            return Ok(None);
+        };
+        let offset = offset(&text, at.position);
+        let annotations = types::annotate(&file, &mut Diagnostics::new());
+        let hovered = annotations
+            .iter()
+            .filter(|annotation| contains(&annotation.location, offset))
+            .min_by_key(|annotation| annotation.location.end - annotation.location.start);
+        Ok(hovered.map(|annotation| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```rinha\n{}: {}\n```", annotation.name, annotation.ty),
+            }),
+            range: Some(range(&text, &annotation.location)),
+        }))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let at = params.text_document_position_params;
+        let uri = at.text_document.uri;
+        let Some(text) = self.text(&uri) else {
+            return Ok(None);
+        };
+        let Ok(file) = parser::parse_or_report(uri.path(), &text) else {
+            return Ok(None);
+        };
+        let offset = offset(&text, at.position);
+        let reference = check::references(&file)
+            .into_iter()
+            .find(|reference| contains(&reference.usage, offset));
+        Ok(reference.map(|reference| {
+            let range = range(&text, &reference.definition);
+            GotoDefinitionResponse::Scalar(Location { uri, range })
+        }))
+    }
+}
+
+/// The problems found in the program: the syntax errors, or else the
+/// findings of `check` and of the types.
+fn diagnostics(filename: &str, text: &str) -> Vec<Diagnostic> {
+    let file = match parser::parse_or_report(filename, text) {
+        Ok(file) => file,
+        Err(error) => {
+            let related = error.related().into_iter().flatten();
+            return related
+                .map(|error| {
+                    let span = error
+                        .labels()
+                        .and_then(|mut labels| labels.next())
+                        .map_or(0..0, |label| label.offset()..label.offset() + label.len());
+                    let location = ast::Location::new(span.start, span.end, filename);
+                    let message = match error.help() {
+                        Some(help) => format!("{error}, {help}"),
+                        None => error.to_string(),
+                    };
+                    diagnostic(text, &location, DiagnosticSeverity::ERROR, message)
+                })
+                .collect();
+        }
+    };
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    types::infer(&file, &mut diagnostics);
+    diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| {
+            let severity = match finding.severity {
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Error => DiagnosticSeverity::ERROR,
+            };
+            let message = match finding.help {
+                Some(help) => format!("{}, {help}", finding.message),
+                None => finding.message,
+            };
+            diagnostic(text, &finding.location, severity, message)
+        })
+        .collect()
+}
+
+fn diagnostic(
+    text: &str,
+    location: &ast::Location,
+    severity: DiagnosticSeverity,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: range(text, location),
+        severity: Some(severity),
+        source: Some("rinha".into()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+/// Whether the cursor at `offset` is on the term, its end included so the
+/// cursor right after a name is on it.
+fn contains(location: &ast::Location, offset: usize) -> bool {
+    let (start, end) = (location.start as usize, location.end as usize);
+    (start..=end).contains(&offset)
+}
+
+fn range(text: &str, location: &ast::Location) -> Range {
+    Range {
+        start: position(text, location.start as usize),
+        end: position(text, location.end as usize),
+    }
+}
+
+/// The line and the column of the byte at `offset`. The columns count the
+/// UTF-16 code units, like the protocol does by default.
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..floor_char_boundary(text, offset)];
+    let line = before.matches('\n').count();
+    let column = before.rsplit('\n').next().unwrap_or_default();
+    Position {
+        line: line as u32,
+        character: column.encode_utf16().count() as u32,
+    }
+}
+
+/// The byte at the line and the column, the inverse of [`position`].
+fn offset(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (number, line) in text.split_inclusive('\n').enumerate() {
+        if number == position.line as usize {
+            let mut units = 0;
+            for (index, char) in line.char_indices() {
+                if units >= position.character as usize || char == '\n' {
+                    return offset + index;
+                }
+                units += char.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+    offset
+}
+
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_stack_size(STACK_SIZE)
+        .enable_all()
+        .build()
+        .expect("the runtime of the server starts");
+    runtime.block_on(async {
+        let (service, socket) = LspService::new(|client| Backend {
+            client,
+            documents: Mutex::new(HashMap::new()),
+        });
+        Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+            .serve(service)
+            .await;
+    });
+}
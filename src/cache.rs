@@ -0,0 +1,136 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The version of the layout of the cache files, bumped when it changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// A directory of cache files, shared by every process that runs with it.
+///
+/// Each file starts with a header line holding the versions, the length and
+/// the checksum of the payload, which are verified when it's read. Files
+/// are written under a temporary name and renamed into place, so readers
+/// never see them half written and the last writer of a key wins.
+///
+/// ```
+/// use rinha::cache::Cache;
+///
+/// let dir = std::env::temp_dir().join(format!("rinha-cache-doc-{}", std::process::id()));
+/// let cache = Cache::new(&dir);
+///
+/// cache.store("answer", b"42").unwrap();
+/// assert_eq!(cache.load("answer").unwrap(), Some(b"42".to_vec()));
+/// assert_eq!(cache.load("question").unwrap(), None);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Why a cache file can't be used. They're all cache misses, the file is
+/// replaced by the next store.
+#[derive(thiserror::Error, Debug)]
+pub enum CacheError {
+    #[error("could not read the cache file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("not a cache file")]
+    Header,
+
+    #[error("written by version {found}, this is {expected}")]
+    Version { found: String, expected: String },
+
+    #[error("truncated, {found} of {expected} bytes")]
+    Truncated { found: u64, expected: u64 },
+
+    #[error("the checksum doesn't match")]
+    Checksum,
+}
+
+/// Makes the temporary names unique between the threads of a process.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The file of `key`, the keys must be valid file names.
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// Writes the payload of `key`, replacing whatever was there.
+    pub fn store(&self, key: &str, payload: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let write = WRITES.fetch_add(1, Ordering::Relaxed);
+        let temporary = self
+            .dir
+            .join(format!(".{key}.{}.{write}.tmp", std::process::id()));
+
+        let mut contents = header(payload).into_bytes();
+        contents.extend_from_slice(payload);
+        let written =
+            fs::write(&temporary, contents).and_then(|()| fs::rename(&temporary, self.path(key)));
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        written
+    }
+
+    /// Reads the payload of `key`, `None` when it was never stored.
+    pub fn load(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        let contents = match fs::read(self.path(key)) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let newline = contents.iter().position(|&byte| byte == b'\n');
+        let newline = newline.ok_or(CacheError::Header)?;
+        let header = std::str::from_utf8(&contents[..newline]).map_err(|_| CacheError::Header)?;
+        let payload = &contents[newline + 1..];
+
+        let fields: Vec<&str> = header.split(' ').collect();
+        let ["rinha-cache", format, version, len, checksum] = fields[..] else {
+            return Err(CacheError::Header);
+        };
+        let found = format!("{format}/{version}");
+        let expected = format!("{FORMAT_VERSION}/{}", env!("CARGO_PKG_VERSION"));
+        if found != expected {
+            return Err(CacheError::Version { found, expected });
+        }
+
+        let len: u64 = len.parse().map_err(|_| CacheError::Header)?;
+        if payload.len() as u64 != len {
+            return Err(CacheError::Truncated {
+                found: payload.len() as u64,
+                expected: len,
+            });
+        }
+        if checksum != format!("{:016x}", fnv1a(payload)) {
+            return Err(CacheError::Checksum);
+        }
+        Ok(Some(payload.to_vec()))
+    }
+}
+
+fn header(payload: &[u8]) -> String {
+    format!(
+        "rinha-cache {FORMAT_VERSION} {} {} {:016x}\n",
+        env!("CARGO_PKG_VERSION"),
+        payload.len(),
+        fnv1a(payload)
+    )
+}
+
+/// The 64-bit FNV-1a hash. Unlike the hasher of the standard library, it's
+/// the same in every build, so it can be stored.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,537 @@
+use crate::{
+    ast::{self, Element, Term},
+    interpreter,
+};
+use std::fmt;
+
+/// The types inferred for the programs, an analysis stricter than the
+/// interpreter that runs only when asked for.
+pub mod types;
+
+/// How bad a finding is. The order matters, the highest severity of a
+/// check decides its outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found by a static analysis, pointing at the offending term.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[error("{message}")]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+    pub location: ast::Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl miette::Diagnostic for Finding {
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(match self.severity {
+            Severity::Warning => miette::Severity::Warning,
+            Severity::Error => miette::Severity::Error,
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let span = miette::SourceSpan::from(self.location.clone());
+        let label = miette::LabeledSpan::new_with_span(Some("here".into()), span);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// The sink every analysis reports into. It de-duplicates the findings and
+/// sorts them by their position in the source.
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    findings: Vec<Finding>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, finding: Finding) {
+        if !self.findings.contains(&finding) {
+            self.findings.push(finding);
+        }
+    }
+
+    pub fn error(&mut self, message: impl Into<String>, location: &ast::Location) {
+        self.report(Severity::Error, message.into(), location, None);
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>, location: &ast::Location) {
+        self.report(Severity::Warning, message.into(), location, None);
+    }
+
+    /// Reports a finding with a hint on how to fix it.
+    pub fn report(
+        &mut self,
+        severity: Severity,
+        message: String,
+        location: &ast::Location,
+        help: Option<String>,
+    ) {
+        self.push(Finding {
+            severity,
+            message,
+            location: location.clone(),
+            help,
+        });
+    }
+
+    /// The highest severity found, `None` when everything is fine.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|finding| finding.severity).max()
+    }
+
+    /// How many findings have the severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// The findings sorted by file and offset.
+    pub fn into_findings(mut self) -> Vec<Finding> {
+        self.findings.sort_by(|a, b| {
+            let a_key = (&a.location.filename, a.location.start, a.location.end);
+            let b_key = (&b.location.filename, b.location.start, b.location.end);
+            a_key.cmp(&b_key)
+        });
+        self.findings
+    }
+
+    /// Renders the findings in a single report, showing at most `max` of
+    /// them.
+    pub fn into_report(self, max: usize) -> Report {
+        let errors = self.count(Severity::Error);
+        let warnings = self.count(Severity::Warning);
+        let mut findings = self.into_findings();
+        let omitted = findings.len().saturating_sub(max);
+        findings.truncate(max);
+
+        Report {
+            errors,
+            warnings,
+            findings,
+            more: (omitted > 0).then(|| format!("and {omitted} more")),
+        }
+    }
+}
+
+/// All the findings of a check, rendered as one diagnostic.
+#[derive(miette::Diagnostic, thiserror::Error, Debug)]
+#[error("found {errors} error(s) and {warnings} warning(s)")]
+pub struct Report {
+    pub errors: usize,
+    pub warnings: usize,
+
+    #[related]
+    pub findings: Vec<Finding>,
+
+    /// The trailer when some findings were left out.
+    #[help]
+    pub more: Option<String>,
+}
+
+/// Runs every static analysis on the file, reporting into `diagnostics`.
+pub fn check(file: &ast::File, diagnostics: &mut Diagnostics) {
+    check_with(file, &[], diagnostics)
+}
+
+/// Like [`check`], for a program that starts with the variables of `bound`
+/// already in scope, like the ones of `--arg` or the natives. Nothing is
+/// known about their values.
+///
+/// ```
+/// use rinha::{check, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "print(n)").unwrap();
+/// let mut diagnostics = check::Diagnostics::new();
+/// check::check_with(&file, &["n"], &mut diagnostics);
+/// assert!(diagnostics.is_empty());
+/// ```
+pub fn check_with(file: &ast::File, bound: &[&str], diagnostics: &mut Diagnostics) {
+    let scope = bound
+        .iter()
+        .map(|name| Binding {
+            name: name.to_string(),
+            kind: None,
+            location: ast::Location::new(0, 0, &file.location.filename),
+            used: false,
+            parameter: true,
+        })
+        .collect();
+    let mut checker = Checker {
+        diagnostics,
+        scope,
+        references: vec![],
+    };
+    checker.visit(&file.expression);
+}
+
+/// A variable used by the program, with where it was bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub name: String,
+    /// Where the variable is used.
+    pub usage: ast::Location,
+    /// The name bound by the `let` or the parameter.
+    pub definition: ast::Location,
+}
+
+/// The variables used by the program and bound by it, in the order they
+/// show up. The ones it doesn't bind are left out.
+///
+/// ```
+/// use rinha::{check, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "let x = 1; print(x)").unwrap();
+/// let references = check::references(&file);
+/// assert_eq!(references[0].usage.start, 17);
+/// assert_eq!(references[0].definition.start, 4);
+/// ```
+pub fn references(file: &ast::File) -> Vec<Reference> {
+    let mut diagnostics = Diagnostics::new();
+    let mut checker = Checker {
+        diagnostics: &mut diagnostics,
+        scope: vec![],
+        references: vec![],
+    };
+    checker.visit(&file.expression);
+    checker.references
+}
+
+/// What is statically known about a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Str,
+    Bool,
+    Tuple,
+    Record,
+    /// A function literal and its arity.
+    Function(usize),
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Int => write!(f, "Int"),
+            Kind::Str => write!(f, "Str"),
+            Kind::Bool => write!(f, "Bool"),
+            Kind::Tuple => write!(f, "Tuple"),
+            Kind::Record => write!(f, "Record"),
+            Kind::Function(_) => write!(f, "closure"),
+        }
+    }
+}
+
+/// A name in scope, with what's known about its value and whether it was
+/// ever read.
+struct Binding {
+    name: String,
+    kind: Option<Kind>,
+    location: ast::Location,
+    used: bool,
+    /// Parameters aren't reported when unused.
+    parameter: bool,
+}
+
+struct Checker<'a> {
+    diagnostics: &'a mut Diagnostics,
+    scope: Vec<Binding>,
+    references: Vec<Reference>,
+}
+
+impl Checker<'_> {
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scope
+            .iter_mut()
+            .rev()
+            .find(|binding| binding.name == name)
+    }
+
+    fn bind(&mut self, var: &crate::parser::Var, kind: Option<Kind>, parameter: bool) {
+        self.scope.push(Binding {
+            name: var.text.to_string(),
+            kind,
+            location: var.location.clone(),
+            used: false,
+            parameter,
+        });
+    }
+
+    /// Drops the bindings made after `len`, reporting the unused ones.
+    fn unbind(&mut self, len: usize) {
+        for binding in self.scope.drain(len..) {
+            if !binding.used && !binding.parameter && !binding.name.starts_with('_') {
+                self.diagnostics.report(
+                    Severity::Warning,
+                    format!("`{}` is never used", binding.name),
+                    &binding.location,
+                    Some(format!("rename it to `_{}` if it's intended", binding.name)),
+                );
+            }
+        }
+    }
+
+    /// What's statically known about the term, without evaluating it.
+    fn kind_of(&mut self, term: &Term) -> Option<Kind> {
+        match term {
+            Term::Int(_) => Some(Kind::Int),
+            Term::Str(_) => Some(Kind::Str),
+            Term::Bool(_) => Some(Kind::Bool),
+            Term::Tuple(_) => Some(Kind::Tuple),
+            Term::Record(_) => Some(Kind::Record),
+            Term::Function(function) => Some(Kind::Function(function.parameters.len())),
+            Term::Var(var) => self.lookup(&var.text).and_then(|binding| binding.kind),
+            Term::Binary(binary) => {
+                let lhs = self.kind_of(&binary.lhs)?;
+                let rhs = self.kind_of(&binary.rhs)?;
+                binary_kind(&binary.op, lhs, rhs).ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn visit(&mut self, term: &Term) {
+        match term {
+            Term::Error(error) => self.diagnostics.error(&error.message, &error.location),
+            Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => {}
+            Term::Var(var) => match self.lookup(&var.text) {
+                Some(binding) => {
+                    binding.used = true;
+                    let definition = binding.location.clone();
+                    self.references.push(Reference {
+                        name: var.text.to_string(),
+                        usage: var.location.clone(),
+                        definition,
+                    });
+                }
+                None => self.diagnostics.error(
+                    format!("variable `{}` is not defined", var.text),
+                    &var.location,
+                ),
+            },
+            Term::Binary(binary) => {
+                self.visit(&binary.lhs);
+                self.visit(&binary.rhs);
+                let lhs = self.kind_of(&binary.lhs);
+                let rhs = self.kind_of(&binary.rhs);
+                if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+                    if let Err(message) = binary_kind(&binary.op, lhs, rhs) {
+                        self.diagnostics.error(message, &binary.location);
+                    }
+                }
+            }
+            Term::Let(let_) => {
+                let kind = self.kind_of(&let_.value);
+                let len = self.scope.len();
+                if let Term::Function(_) = *let_.value {
+                    // Functions can call themselves through their own name.
+                    self.bind(&let_.name, kind, false);
+                    self.visit(&let_.value);
+                } else {
+                    self.visit(&let_.value);
+                    self.bind(&let_.name, kind, false);
+                }
+                self.visit(&let_.next);
+                self.unbind(len);
+            }
+            Term::Function(function) => {
+                let len = self.scope.len();
+                for parameter in &function.parameters {
+                    self.bind(parameter, None, true);
+                }
+                self.visit(&function.value);
+                self.unbind(len);
+            }
+            Term::Call(call) => {
+                self.visit(&call.callee);
+                for argument in &call.arguments {
+                    self.visit(argument);
+                }
+                match self.kind_of(&call.callee) {
+                    Some(Kind::Function(arity)) if arity != call.arguments.len() => {
+                        self.diagnostics.error(
+                            format!(
+                                "the function {}",
+                                interpreter::arity(arity, call.arguments.len())
+                            ),
+                            &call.location,
+                        );
+                    }
+                    Some(
+                        kind @ (Kind::Int | Kind::Str | Kind::Bool | Kind::Tuple | Kind::Record),
+                    ) => {
+                        self.diagnostics.error(
+                            format!("a value of type {kind} can't be called"),
+                            call.callee.location(),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            Term::If(if_) => {
+                self.visit(&if_.condition);
+                if let Some(kind) = self.kind_of(&if_.condition) {
+                    if kind != Kind::Bool {
+                        self.diagnostics.error(
+                            format!("the condition must be a Bool, but it's {kind}"),
+                            if_.condition.location(),
+                        );
+                    }
+                }
+                self.visit(&if_.then);
+                self.visit(&if_.otherwise);
+            }
+            Term::Print(print) => self.visit(&print.value),
+            Term::First(first) => self.visit_projection("first", &first.value),
+            Term::Second(second) => self.visit_projection("second", &second.value),
+            Term::Tuple(tuple) => {
+                self.visit(&tuple.first);
+                self.visit(&tuple.second);
+            }
+            Term::Record(record) => {
+                for field in &record.fields {
+                    self.visit(&field.value);
+                }
+            }
+            Term::Access(access) => {
+                self.visit(&access.value);
+                match self.kind_of(&access.value) {
+                    Some(Kind::Record) | None => {}
+                    Some(kind) => self.diagnostics.error(
+                        format!(
+                            "`.{}` must be used on records, but it's {kind}",
+                            access.field.text
+                        ),
+                        access.value.location(),
+                    ),
+                }
+            }
+            Term::Match(match_) => self.visit_match(match_),
+        }
+    }
+
+    /// The variables of the patterns are in scope in their guard and their
+    /// body. A pattern of another kind than the value is reported, only
+    /// the kind of the whole value is known.
+    fn visit_match(&mut self, match_: &ast::Match) {
+        self.visit(&match_.value);
+        let kind = self.kind_of(&match_.value);
+        for arm in &match_.arms {
+            let pattern = match &arm.pattern {
+                ast::Pattern::Int(_) => Some(Kind::Int),
+                ast::Pattern::Str(_) => Some(Kind::Str),
+                ast::Pattern::Bool(_) => Some(Kind::Bool),
+                ast::Pattern::Tuple(_) => Some(Kind::Tuple),
+                ast::Pattern::Wildcard(_) | ast::Pattern::Var(_) => None,
+            };
+            if let (Some(kind), Some(pattern)) = (kind, pattern) {
+                if kind != pattern {
+                    self.diagnostics.error(
+                        format!("the pattern is {pattern}, but the value is {kind}"),
+                        arm.pattern.location(),
+                    );
+                }
+            }
+            let len = self.scope.len();
+            self.bind_pattern(&arm.pattern, kind);
+            if let Some(guard) = &arm.guard {
+                self.visit(guard);
+                match self.kind_of(guard) {
+                    Some(Kind::Bool) | None => {}
+                    Some(kind) => self.diagnostics.error(
+                        format!("the guard must be a Bool, but it's {kind}"),
+                        guard.location(),
+                    ),
+                }
+            }
+            self.visit(&arm.body);
+            self.unbind(len);
+        }
+    }
+
+    /// Binds the variables of the pattern, the one of the whole value with
+    /// its kind.
+    fn bind_pattern(&mut self, pattern: &ast::Pattern, kind: Option<Kind>) {
+        match pattern {
+            ast::Pattern::Var(var) => self.bind(var, kind, false),
+            ast::Pattern::Tuple(tuple) => {
+                self.bind_pattern(&tuple.first, None);
+                self.bind_pattern(&tuple.second, None);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_projection(&mut self, name: &str, value: &Term) {
+        self.visit(value);
+        if let Some(kind) = self.kind_of(value) {
+            if kind != Kind::Tuple {
+                self.diagnostics.error(
+                    format!("`{name}` must be used on tuples, but it's {kind}"),
+                    value.location(),
+                );
+            }
+        }
+    }
+}
+
+/// The kind of a binary operation result, following the interpreter rules.
+fn binary_kind(op: &ast::BinaryOp, lhs: Kind, rhs: Kind) -> Result<Kind, String> {
+    use ast::BinaryOp::*;
+
+    match (op, lhs, rhs) {
+        (Add, Kind::Int, Kind::Int) => Ok(Kind::Int),
+        (Add, Kind::Int | Kind::Str, Kind::Int | Kind::Str) => Ok(Kind::Str),
+        (Sub | Mul | Div | Rem, Kind::Int, Kind::Int) => Ok(Kind::Int),
+        (Lt | Gt | Lte | Gte, Kind::Int, Kind::Int) => Ok(Kind::Bool),
+        (Eq | Neq, Kind::Int, Kind::Int)
+        | (Eq | Neq, Kind::Str, Kind::Str)
+        | (Eq | Neq, Kind::Bool, Kind::Bool)
+        | (Eq | Neq, Kind::Tuple, Kind::Tuple)
+        | (Eq | Neq, Kind::Record, Kind::Record) => Ok(Kind::Bool),
+        (And | Or, Kind::Bool, Kind::Bool) => Ok(Kind::Bool),
+        _ => Err(format!("can't apply `{}` to {lhs} and {rhs}", symbol(op))),
+    }
+}
+
+fn symbol(op: &ast::BinaryOp) -> &'static str {
+    use ast::BinaryOp::*;
+
+    match op {
+        Add => "+",
+        Sub => "-",
+        Mul => "*",
+        Div => "/",
+        Rem => "%",
+        Eq => "==",
+        Neq => "!=",
+        Lt => "<",
+        Gt => ">",
+        Lte => "<=",
+        Gte => ">=",
+        And => "&&",
+        Or => "||",
+    }
+}
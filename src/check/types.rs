@@ -0,0 +1,869 @@
+//! The types of the programs, inferred the Hindley–Milner way: every term
+//! gets a type from how it's used, and the functions bound by `let` are
+//! generalized, so `let id = fn (x) => { x }` can take an `Int` and a `Str`.
+//!
+//! It's stricter than the interpreter, the branches of an `if` must agree
+//! and a function must always take the same types, so it's a separate pass
+//! the `check` command only runs when asked for.
+//!
+//! `+` is the only operator with several meanings on the same operands, an
+//! `Int` plus a `Str` is a `Str`. Its operands are left undecided until
+//! something else tells their types, and the functions that add undecided
+//! operands aren't generalized, so their first call decides them.
+//!
+//! The records have the fields they're written with. A field read from a
+//! value whose type isn't known yet, like a parameter's, takes any type.
+
+use super::Diagnostics;
+use crate::{
+    ast::{self, BinaryOp, Element, Term},
+    interpreter::arity,
+    runtime::symbol::Symbol,
+};
+use std::fmt;
+
+/// The type of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Str,
+    Bool,
+    /// Only with the extensions of the interpreter.
+    Float,
+    Tuple(Box<Type>, Box<Type>),
+    /// The fields and their types, sorted by name. Only with the
+    /// extensions, like the floats.
+    Record(Vec<(Symbol, Type)>),
+    /// The types of the parameters, and the type of the result.
+    Function(Vec<Type>, Box<Type>),
+    /// A type left open, any type fits. Shown as `'a`, `'b` and so on.
+    Var(usize),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &mut Vec::new())
+    }
+}
+
+impl Type {
+    /// Writes the type, naming its variables in the order they show up.
+    fn write(&self, f: &mut fmt::Formatter<'_>, names: &mut Vec<usize>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Str => write!(f, "Str"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Float => write!(f, "Float"),
+            Type::Tuple(first, second) => {
+                write!(f, "(")?;
+                first.write(f, names)?;
+                write!(f, ", ")?;
+                second.write(f, names)?;
+                write!(f, ")")
+            }
+            Type::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: ")?;
+                    ty.write(f, names)?;
+                }
+                write!(f, "}}")
+            }
+            Type::Function(parameters, result) => {
+                write!(f, "fn (")?;
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    parameter.write(f, names)?;
+                }
+                write!(f, ") -> ")?;
+                result.write(f, names)
+            }
+            Type::Var(var) => {
+                let index = match names.iter().position(|name| name == var) {
+                    Some(index) => index,
+                    None => {
+                        names.push(*var);
+                        names.len() - 1
+                    }
+                };
+                match u8::try_from(index).ok().filter(|index| *index < 26) {
+                    Some(index) => write!(f, "'{}", char::from(b'a' + index)),
+                    None => write!(f, "'t{index}"),
+                }
+            }
+        }
+    }
+
+    /// The name of what the value is, like the other analyses name it.
+    fn head(&self) -> Option<&'static str> {
+        match self {
+            Type::Int => Some("Int"),
+            Type::Str => Some("Str"),
+            Type::Bool => Some("Bool"),
+            Type::Float => Some("Float"),
+            Type::Tuple(..) => Some("Tuple"),
+            Type::Record(_) => Some("Record"),
+            Type::Function(..) => Some("closure"),
+            Type::Var(_) => None,
+        }
+    }
+}
+
+/// The type inferred for a variable bound by the `let` chain at the top of
+/// the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub location: ast::Location,
+    pub ty: Type,
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.ty)
+    }
+}
+
+/// Infers the types of the program, reporting into `diagnostics` where
+/// they don't fit. The variables it doesn't bind are left to
+/// [`super::check`], they take any type here.
+///
+/// ```
+/// use rinha::{check::{types, Diagnostics}, parser};
+///
+/// let source = "let add = fn (a, b) => { a - b }; print(add(1, \"a\"))";
+/// let file = parser::parse_or_report("main.rinha", source).unwrap();
+/// let mut diagnostics = Diagnostics::new();
+/// let signatures = types::infer(&file, &mut diagnostics);
+///
+/// assert_eq!(signatures[0].to_string(), "add: fn (Int, Int) -> Int");
+/// let findings = diagnostics.into_findings();
+/// assert_eq!(findings[0].message, "expected Int, but found Str");
+/// ```
+pub fn infer(file: &ast::File, diagnostics: &mut Diagnostics) -> Vec<Signature> {
+    Inference::new(diagnostics).file(file).0
+}
+
+/// The type of a variable where it's bound or used, the uses of the
+/// generalized functions get the types of the call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub name: String,
+    pub location: ast::Location,
+    pub ty: Type,
+}
+
+/// Infers the types of the program like [`infer`], returning the type of
+/// every variable where it's bound or used, in the order they show up.
+///
+/// ```
+/// use rinha::{check::{types, Diagnostics}, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "let id = fn (x) => { x }; id(1)").unwrap();
+/// let annotations = types::annotate(&file, &mut Diagnostics::new());
+/// let shown: Vec<String> = annotations.iter().map(|a| format!("{}: {}", a.name, a.ty)).collect();
+/// assert_eq!(shown, ["id: fn ('a) -> 'a", "x: 'a", "x: 'a", "id: fn (Int) -> Int"]);
+/// ```
+pub fn annotate(file: &ast::File, diagnostics: &mut Diagnostics) -> Vec<Annotation> {
+    let mut annotations = Inference::new(diagnostics).file(file).1;
+    annotations.sort_by_key(|annotation| annotation.location.start);
+    annotations
+}
+
+/// A type with the variables that each use of it replaces with new ones.
+struct Scheme {
+    quantified: Vec<usize>,
+    ty: Type,
+}
+
+/// An operator whose operands weren't known yet, checked once they are.
+struct Pending {
+    op: BinaryOp,
+    lhs: Type,
+    rhs: Type,
+    result: Type,
+    location: ast::Location,
+}
+
+struct Inference<'a> {
+    diagnostics: &'a mut Diagnostics,
+    /// What each variable stands for, `None` while it's open.
+    bound: Vec<Option<Type>>,
+    /// The depth of the `let` each open variable belongs to. The ones
+    /// deeper than the `let` being bound are generalized.
+    levels: Vec<usize>,
+    level: usize,
+    scope: Vec<(Symbol, Scheme)>,
+    pending: Vec<Pending>,
+    annotations: Vec<Annotation>,
+}
+
+impl<'a> Inference<'a> {
+    fn new(diagnostics: &'a mut Diagnostics) -> Self {
+        Inference {
+            diagnostics,
+            bound: Vec::new(),
+            levels: Vec::new(),
+            level: 0,
+            scope: Vec::new(),
+            pending: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Infers the whole program, returning the signatures of the `let`
+    /// chain at its top and the types of its variables.
+    fn file(mut self, file: &ast::File) -> (Vec<Signature>, Vec<Annotation>) {
+        let mut signatures = Vec::new();
+        let mut term = &file.expression;
+        while let Term::Let(let_) = term {
+            let ty = self.bind_let(let_);
+            signatures.push(Signature {
+                name: let_.name.text.to_string(),
+                location: let_.name.location.clone(),
+                ty,
+            });
+            term = &let_.next;
+        }
+        self.infer(term);
+        self.discharge();
+        // The calls after a binding can decide what it left open.
+        for signature in &mut signatures {
+            signature.ty = self.zonk(&signature.ty);
+        }
+        let mut annotations = std::mem::take(&mut self.annotations);
+        for annotation in &mut annotations {
+            annotation.ty = self.zonk(&annotation.ty);
+        }
+        (signatures, annotations)
+    }
+
+    fn annotate(&mut self, var: &crate::parser::Var, ty: &Type) {
+        self.annotations.push(Annotation {
+            name: var.text.to_string(),
+            location: var.location.clone(),
+            ty: ty.clone(),
+        });
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.bound.push(None);
+        self.levels.push(self.level);
+        Type::Var(self.bound.len() - 1)
+    }
+
+    /// Follows the variables bound already, up to the outermost constructor.
+    fn resolve(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty {
+            match &self.bound[var] {
+                Some(bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// The type with every variable bound replaced by what it stands for.
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Tuple(first, second) => {
+                Type::Tuple(Box::new(self.zonk(&first)), Box::new(self.zonk(&second)))
+            }
+            Type::Record(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), self.zonk(ty)))
+                    .collect(),
+            ),
+            Type::Function(parameters, result) => Type::Function(
+                parameters.iter().map(|ty| self.zonk(ty)).collect(),
+                Box::new(self.zonk(&result)),
+            ),
+            ty => ty,
+        }
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), ()> {
+        match (self.resolve(a), self.resolve(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(());
+                }
+                self.bound[var] = Some(ty);
+                Ok(())
+            }
+            (Type::Int, Type::Int)
+            | (Type::Str, Type::Str)
+            | (Type::Bool, Type::Bool)
+            | (Type::Float, Type::Float) => Ok(()),
+            (Type::Tuple(a1, a2), Type::Tuple(b1, b2)) => {
+                self.unify(&a1, &b1)?;
+                self.unify(&a2, &b2)
+            }
+            (Type::Record(a), Type::Record(b))
+                if a.iter()
+                    .map(|(name, _)| name)
+                    .eq(b.iter().map(|(name, _)| name)) =>
+            {
+                for ((_, a), (_, b)) in a.iter().zip(&b) {
+                    self.unify(a, b)?;
+                }
+                Ok(())
+            }
+            (Type::Function(a, a_result), Type::Function(b, b_result)) if a.len() == b.len() => {
+                for (a, b) in a.iter().zip(&b) {
+                    self.unify(a, b)?;
+                }
+                self.unify(&a_result, &b_result)
+            }
+            _ => Err(()),
+        }
+    }
+
+    /// Whether `var` shows up in `ty`, which would make it infinite. The
+    /// variables of `ty` are moved to the level of `var` on the way, they
+    /// can't be generalized sooner than it.
+    fn occurs(&mut self, var: usize, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(other) => {
+                self.levels[other] = self.levels[other].min(self.levels[var]);
+                other == var
+            }
+            Type::Tuple(first, second) => self.occurs(var, &first) || self.occurs(var, &second),
+            Type::Record(fields) => fields.iter().any(|(_, ty)| self.occurs(var, ty)),
+            Type::Function(parameters, result) => {
+                parameters.iter().any(|ty| self.occurs(var, ty)) || self.occurs(var, &result)
+            }
+            _ => false,
+        }
+    }
+
+    /// Unifies the types, reporting where the one found doesn't fit.
+    fn expect(&mut self, expected: &Type, found: &Type, location: &ast::Location) {
+        let (shown_expected, shown_found) = (self.zonk(expected), self.zonk(found));
+        if self.unify(expected, found).is_err() {
+            self.diagnostics.error(
+                format!("expected {shown_expected}, but found {shown_found}"),
+                location,
+            );
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh: Vec<(usize, Type)> = scheme
+            .quantified
+            .iter()
+            .map(|var| (*var, self.fresh()))
+            .collect();
+        substitute(&scheme.ty, &fresh)
+    }
+
+    /// Generalizes the variables of `ty` made inside the `let` just left,
+    /// except the ones an operator still waits on.
+    fn generalize(&mut self, ty: &Type) -> Scheme {
+        self.discharge();
+        let pending: Vec<Type> = self
+            .pending
+            .iter()
+            .flat_map(|pending| [&pending.lhs, &pending.rhs, &pending.result])
+            .cloned()
+            .collect();
+        for ty in pending {
+            self.lower(&ty);
+        }
+        let ty = self.zonk(ty);
+        let mut quantified = Vec::new();
+        self.open_vars(&ty, &mut quantified);
+        Scheme { quantified, ty }
+    }
+
+    /// Keeps the variables of `ty` from being generalized by the current
+    /// `let`.
+    fn lower(&mut self, ty: &Type) {
+        match self.resolve(ty) {
+            Type::Var(var) => self.levels[var] = self.levels[var].min(self.level),
+            Type::Tuple(first, second) => {
+                self.lower(&first);
+                self.lower(&second);
+            }
+            Type::Record(fields) => {
+                for (_, ty) in &fields {
+                    self.lower(ty);
+                }
+            }
+            Type::Function(parameters, result) => {
+                for parameter in &parameters {
+                    self.lower(parameter);
+                }
+                self.lower(&result);
+            }
+            _ => {}
+        }
+    }
+
+    fn open_vars(&self, ty: &Type, vars: &mut Vec<usize>) {
+        match ty {
+            Type::Var(var) if self.levels[*var] > self.level && !vars.contains(var) => {
+                vars.push(*var)
+            }
+            Type::Tuple(first, second) => {
+                self.open_vars(first, vars);
+                self.open_vars(second, vars);
+            }
+            Type::Record(fields) => {
+                for (_, ty) in fields {
+                    self.open_vars(ty, vars);
+                }
+            }
+            Type::Function(parameters, result) => {
+                for parameter in parameters {
+                    self.open_vars(parameter, vars);
+                }
+                self.open_vars(result, vars);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks the operators whose operands are known by now.
+    fn discharge(&mut self) {
+        loop {
+            let before = self.pending.len();
+            for pending in std::mem::take(&mut self.pending) {
+                if !self.check_operator(&pending) {
+                    self.pending.push(pending);
+                }
+            }
+            if self.pending.len() == before {
+                return;
+            }
+        }
+    }
+
+    /// Checks the operator once both operands are known, returning whether
+    /// they were.
+    fn check_operator(&mut self, pending: &Pending) -> bool {
+        let (lhs, rhs) = (self.resolve(&pending.lhs), self.resolve(&pending.rhs));
+        if lhs.head().is_none() || rhs.head().is_none() {
+            return false;
+        }
+        let result = match operator_result(&pending.op, &lhs, &rhs) {
+            Some(result) => result,
+            None => {
+                self.operator_error(&pending.op, &lhs, &rhs, &pending.location);
+                return true;
+            }
+        };
+        if let (Type::Tuple(..), Type::Tuple(..)) | (Type::Record(_), Type::Record(_)) =
+            (&lhs, &rhs)
+        {
+            if self.unify(&lhs, &rhs).is_err() {
+                self.operator_error(&pending.op, &lhs, &rhs, &pending.location);
+                return true;
+            }
+        }
+        self.expect(&pending.result, &result, &pending.location);
+        true
+    }
+
+    fn operator_error(&mut self, op: &BinaryOp, lhs: &Type, rhs: &Type, location: &ast::Location) {
+        let name = |ty: &Type| match ty.head() {
+            Some(head) => head.to_string(),
+            None => ty.to_string(),
+        };
+        self.diagnostics.error(
+            format!(
+                "can't apply `{}` to {} and {}",
+                super::symbol(op),
+                name(lhs),
+                name(rhs)
+            ),
+            location,
+        );
+    }
+
+    /// Infers the value of the `let` and binds its name, returning the
+    /// type the name was given.
+    fn bind_let(&mut self, let_: &ast::Let) -> Type {
+        self.level += 1;
+        let ty = match &*let_.value {
+            // Functions can call themselves through their own name.
+            Term::Function(_) => {
+                let itself = self.fresh();
+                let scheme = Scheme {
+                    quantified: vec![],
+                    ty: itself.clone(),
+                };
+                self.scope.push((let_.name.text.clone(), scheme));
+                let ty = self.infer(&let_.value);
+                self.scope.pop();
+                let (used_as, ty_shown) = (self.zonk(&itself), self.zonk(&ty));
+                if self.unify(&itself, &ty).is_err() {
+                    self.diagnostics.error(
+                        format!(
+                            "`{}` is called as {used_as}, but it's {ty_shown}",
+                            let_.name.text
+                        ),
+                        &let_.name.location,
+                    );
+                }
+                ty
+            }
+            value => self.infer(value),
+        };
+        self.level -= 1;
+        let scheme = self.generalize(&ty);
+        let ty = scheme.ty.clone();
+        self.annotate(&let_.name, &ty);
+        self.scope.push((let_.name.text.clone(), scheme));
+        ty
+    }
+
+    fn infer(&mut self, term: &Term) -> Type {
+        match term {
+            Term::Int(_) => Type::Int,
+            Term::Str(_) => Type::Str,
+            Term::Bool(_) => Type::Bool,
+            Term::Float(_) => Type::Float,
+            Term::Error(_) => self.fresh(),
+            Term::Var(var) => {
+                let scheme = self
+                    .scope
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| *name == var.text)
+                    .map(|(_, scheme)| (scheme.quantified.clone(), scheme.ty.clone()));
+                let ty = match scheme {
+                    Some((quantified, ty)) => self.instantiate(&Scheme { quantified, ty }),
+                    None => self.fresh(),
+                };
+                self.annotate(var, &ty);
+                ty
+            }
+            Term::Let(let_) => {
+                let len = self.scope.len();
+                self.bind_let(let_);
+                let ty = self.infer(&let_.next);
+                self.scope.truncate(len);
+                ty
+            }
+            Term::Function(function) => {
+                let len = self.scope.len();
+                let mut parameters = Vec::new();
+                for parameter in &function.parameters {
+                    let ty = self.fresh();
+                    let scheme = Scheme {
+                        quantified: vec![],
+                        ty: ty.clone(),
+                    };
+                    self.annotate(parameter, &ty);
+                    self.scope.push((parameter.text.clone(), scheme));
+                    parameters.push(ty);
+                }
+                let result = self.infer(&function.value);
+                self.scope.truncate(len);
+                Type::Function(parameters, Box::new(result))
+            }
+            Term::Call(call) => self.infer_call(call),
+            Term::If(if_) => {
+                let condition = self.infer(&if_.condition);
+                if self.unify(&condition, &Type::Bool).is_err() {
+                    let found = self.resolve(&condition);
+                    self.diagnostics.error(
+                        format!(
+                            "the condition must be a Bool, but it's {}",
+                            found.head().unwrap_or("open")
+                        ),
+                        if_.condition.location(),
+                    );
+                }
+                let then = self.infer(&if_.then);
+                let otherwise = self.infer(&if_.otherwise);
+                let (then_shown, otherwise_shown) = (self.zonk(&then), self.zonk(&otherwise));
+                if self.unify(&then, &otherwise).is_err() {
+                    self.diagnostics.error(
+                        format!(
+                            "the branches have different types, {then_shown} and {otherwise_shown}"
+                        ),
+                        &if_.location,
+                    );
+                }
+                then
+            }
+            Term::Binary(binary) => {
+                let lhs = self.infer(&binary.lhs);
+                let rhs = self.infer(&binary.rhs);
+                self.infer_binary(&binary.op, lhs, rhs, &binary.location)
+            }
+            Term::Print(print) => self.infer(&print.value),
+            Term::First(first) => self.infer_projection("first", &first.value).0,
+            Term::Second(second) => self.infer_projection("second", &second.value).1,
+            Term::Tuple(tuple) => {
+                let first = self.infer(&tuple.first);
+                let second = self.infer(&tuple.second);
+                Type::Tuple(Box::new(first), Box::new(second))
+            }
+            Term::Record(record) => {
+                let mut fields: Vec<(Symbol, Type)> = record
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.text.clone(), self.infer(&field.value)))
+                    .collect();
+                fields.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+                Type::Record(fields)
+            }
+            Term::Access(access) => self.infer_access(access),
+            Term::Match(match_) => self.infer_match(match_),
+        }
+    }
+
+    fn infer_call(&mut self, call: &ast::Call) -> Type {
+        let callee = self.infer(&call.callee);
+        let arguments: Vec<Type> = call
+            .arguments
+            .iter()
+            .map(|argument| self.infer(argument))
+            .collect();
+        match self.resolve(&callee) {
+            Type::Function(parameters, result) if parameters.len() != arguments.len() => {
+                self.diagnostics.error(
+                    format!("the function {}", arity(parameters.len(), arguments.len())),
+                    &call.location,
+                );
+                *result
+            }
+            Type::Function(parameters, result) => {
+                for ((parameter, argument), term) in
+                    parameters.iter().zip(&arguments).zip(&call.arguments)
+                {
+                    self.expect(parameter, argument, term.location());
+                }
+                *result
+            }
+            Type::Var(_) => {
+                let result = self.fresh();
+                let function = Type::Function(arguments, Box::new(result.clone()));
+                self.expect(&callee, &function, &call.location);
+                result
+            }
+            other => {
+                self.diagnostics.error(
+                    format!(
+                        "a value of type {} can't be called",
+                        other.head().unwrap_or("open")
+                    ),
+                    call.callee.location(),
+                );
+                self.fresh()
+            }
+        }
+    }
+
+    fn infer_access(&mut self, access: &ast::Access) -> Type {
+        let ty = self.infer(&access.value);
+        let name = &access.field.text;
+        let message = match self.resolve(&ty) {
+            Type::Record(fields) => match fields.iter().find(|(field, _)| field == name) {
+                Some((_, ty)) => return ty.clone(),
+                None => format!(
+                    "the record has no field `{name}`, it's {}",
+                    self.zonk(&Type::Record(fields))
+                ),
+            },
+            Type::Var(_) => return self.fresh(),
+            other => format!(
+                "`.{name}` must be used on records, but it's {}",
+                other.head().unwrap_or("open")
+            ),
+        };
+        self.diagnostics.error(message, &access.location);
+        self.fresh()
+    }
+
+    /// The patterns take the type of the value, the guards are Bool and the
+    /// bodies agree, like the branches of an `if`.
+    fn infer_match(&mut self, match_: &ast::Match) -> Type {
+        let value = self.infer(&match_.value);
+        let result = self.fresh();
+        for arm in &match_.arms {
+            let len = self.scope.len();
+            self.bind_pattern(&arm.pattern, &value);
+            if let Some(guard) = &arm.guard {
+                let ty = self.infer(guard);
+                if self.unify(&ty, &Type::Bool).is_err() {
+                    let found = self.resolve(&ty);
+                    self.diagnostics.error(
+                        format!(
+                            "the guard must be a Bool, but it's {}",
+                            found.head().unwrap_or("open")
+                        ),
+                        guard.location(),
+                    );
+                }
+            }
+            let body = self.infer(&arm.body);
+            let (result_shown, body_shown) = (self.zonk(&result), self.zonk(&body));
+            if self.unify(&result, &body).is_err() {
+                self.diagnostics.error(
+                    format!("the arms have different types, {result_shown} and {body_shown}"),
+                    arm.body.location(),
+                );
+            }
+            self.scope.truncate(len);
+        }
+        result
+    }
+
+    /// Binds the variables of the pattern, whose value is of type `ty`.
+    fn bind_pattern(&mut self, pattern: &ast::Pattern, ty: &Type) {
+        let literal = match pattern {
+            ast::Pattern::Wildcard(_) => return,
+            ast::Pattern::Var(var) => {
+                self.annotate(var, ty);
+                let scheme = Scheme {
+                    quantified: vec![],
+                    ty: ty.clone(),
+                };
+                self.scope.push((var.text.clone(), scheme));
+                return;
+            }
+            ast::Pattern::Tuple(tuple) => {
+                let (first, second) = (self.fresh(), self.fresh());
+                let pair = Type::Tuple(Box::new(first.clone()), Box::new(second.clone()));
+                self.expect(ty, &pair, &tuple.location);
+                self.bind_pattern(&tuple.first, &first);
+                self.bind_pattern(&tuple.second, &second);
+                return;
+            }
+            ast::Pattern::Int(_) => Type::Int,
+            ast::Pattern::Str(_) => Type::Str,
+            ast::Pattern::Bool(_) => Type::Bool,
+        };
+        self.expect(ty, &literal, pattern.location());
+    }
+
+    fn infer_projection(&mut self, name: &str, value: &Term) -> (Type, Type) {
+        let ty = self.infer(value);
+        let (first, second) = (self.fresh(), self.fresh());
+        let tuple = Type::Tuple(Box::new(first.clone()), Box::new(second.clone()));
+        if self.unify(&ty, &tuple).is_err() {
+            let found = self.resolve(&ty);
+            self.diagnostics.error(
+                format!(
+                    "`{name}` must be used on tuples, but it's {}",
+                    found.head().unwrap_or("open")
+                ),
+                value.location(),
+            );
+        }
+        (first, second)
+    }
+
+    /// The type of the operator, checked right away when the operands are
+    /// known. Otherwise the operators that only take integers or booleans
+    /// decide them, and the others wait for them.
+    fn infer_binary(
+        &mut self,
+        op: &BinaryOp,
+        lhs: Type,
+        rhs: Type,
+        location: &ast::Location,
+    ) -> Type {
+        use BinaryOp::*;
+
+        let floats = [&lhs, &rhs]
+            .iter()
+            .any(|ty| self.resolve(ty) == Type::Float);
+        let decided = match op {
+            And | Or => Some((Type::Bool, Type::Bool)),
+            Sub | Mul | Div | Rem if !floats => Some((Type::Int, Type::Int)),
+            Lt | Gt | Lte | Gte if !floats => Some((Type::Int, Type::Bool)),
+            _ => None,
+        };
+        let pending = |result| Pending {
+            op: op.clone(),
+            lhs: lhs.clone(),
+            rhs: rhs.clone(),
+            result,
+            location: location.clone(),
+        };
+        if let Some((operand, result)) = decided {
+            let (lhs_ok, rhs_ok) = (
+                self.unify(&lhs, &operand).is_ok(),
+                self.unify(&rhs, &operand).is_ok(),
+            );
+            if !(lhs_ok && rhs_ok) {
+                let (lhs, rhs) = (self.resolve(&lhs), self.resolve(&rhs));
+                self.operator_error(op, &lhs, &rhs, location);
+            }
+            return result;
+        }
+        if matches!(op, Eq | Neq) && !floats && self.unify(&lhs, &rhs).is_err() {
+            let (lhs, rhs) = (self.resolve(&lhs), self.resolve(&rhs));
+            self.operator_error(op, &lhs, &rhs, location);
+            return Type::Bool;
+        }
+        let result = match op {
+            Eq | Neq | Lt | Gt | Lte | Gte => Type::Bool,
+            Sub | Mul | Div => Type::Float,
+            _ => self.fresh(),
+        };
+        let pending = pending(result.clone());
+        if !self.check_operator(&pending) {
+            self.pending.push(pending);
+        }
+        result
+    }
+}
+
+/// The type of the result of the operator on values of these types, the
+/// same rules the interpreter follows. `None` when it fails on them.
+fn operator_result(op: &BinaryOp, lhs: &Type, rhs: &Type) -> Option<Type> {
+    use BinaryOp::*;
+    use Type::*;
+
+    let number = |ty: &Type| matches!(ty, Int | Float);
+    match (op, lhs, rhs) {
+        (Add | Sub | Mul | Div | Rem, Int, Int) => Some(Int),
+        (Add, Str, Int | Str | Float) | (Add, Int | Float, Str) => Some(Str),
+        (Add | Sub | Mul | Div, lhs, rhs) if number(lhs) && number(rhs) => Some(Float),
+        (Lt | Gt | Lte | Gte | Eq | Neq, lhs, rhs) if number(lhs) && number(rhs) => Some(Bool),
+        (Eq | Neq, Str, Str) | (Eq | Neq, Bool, Bool) | (Eq | Neq, Tuple(..), Tuple(..)) => {
+            Some(Bool)
+        }
+        (Eq | Neq, Record(_), Record(_)) => Some(Bool),
+        (And | Or, Bool, Bool) => Some(Bool),
+        _ => None,
+    }
+}
+
+fn substitute(ty: &Type, fresh: &[(usize, Type)]) -> Type {
+    match ty {
+        Type::Var(var) => fresh
+            .iter()
+            .find(|(quantified, _)| quantified == var)
+            .map_or_else(|| ty.clone(), |(_, fresh)| fresh.clone()),
+        Type::Tuple(first, second) => Type::Tuple(
+            Box::new(substitute(first, fresh)),
+            Box::new(substitute(second, fresh)),
+        ),
+        Type::Record(fields) => Type::Record(
+            fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), substitute(ty, fresh)))
+                .collect(),
+        ),
+        Type::Function(parameters, result) => Type::Function(
+            parameters.iter().map(|ty| substitute(ty, fresh)).collect(),
+            Box::new(substitute(result, fresh)),
+        ),
+        ty => ty.clone(),
+    }
+}
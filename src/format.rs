@@ -0,0 +1,293 @@
+use crate::ast::{self, BinaryOp, Term};
+use std::fmt::Write;
+
+/// Renders the term back into `rinha` source code. The output parses into a
+/// term that evaluates the same, the parentheses are added where the
+/// precedence or the associativity of the grammar would change the meaning.
+///
+/// ```
+/// use rinha::{format, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "let x = (1 + 2) * 3; print(x)").unwrap();
+/// assert_eq!(format::to_source(&file.expression), "let x = (1 + 2) * 3;\nprint(x)");
+/// ```
+pub fn to_source(term: &Term) -> String {
+    let mut printer = Printer {
+        output: String::new(),
+        indent: 0,
+    };
+    printer.term(term);
+    printer.output
+}
+
+/// How tightly a term binds, following the grammar. An operand needs
+/// parentheses when it binds looser than its position requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    /// `let`, `if`, `match`, `fn` and tuples, they're only allowed as whole
+    /// terms.
+    Term,
+    /// `&&`, `||` and the comparisons.
+    Logical,
+    /// `+` and `-`.
+    Arithmetic,
+    /// `*`, `/` and `%`.
+    Factor,
+    /// Literals, variables, calls and anything parenthesized.
+    Apply,
+}
+
+fn op_level(op: &BinaryOp) -> Level {
+    match op {
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => Level::Factor,
+        BinaryOp::Add | BinaryOp::Sub => Level::Arithmetic,
+        _ => Level::Logical,
+    }
+}
+
+fn level(term: &Term) -> Level {
+    match term {
+        Term::Binary(binary) => op_level(&binary.op),
+        // Negative numbers are written as a subtraction.
+        Term::Int(int) if int.value < 0 => Level::Apply,
+        Term::Let(_) | Term::If(_) | Term::Match(_) | Term::Function(_) | Term::Tuple(_) => {
+            Level::Term
+        }
+        _ => Level::Apply,
+    }
+}
+
+pub(crate) fn symbol(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Rem => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Lte => "<=",
+        BinaryOp::Gte => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+    }
+}
+
+struct Printer {
+    output: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        self.output.push('\n');
+        for _ in 0..self.indent {
+            self.output.push_str("  ");
+        }
+    }
+
+    /// Writes the term, in parentheses when it binds looser than `at`.
+    fn operand(&mut self, term: &Term, at: Level) {
+        if level(term) < at {
+            self.output.push('(');
+            self.term(term);
+            self.output.push(')');
+        } else {
+            self.term(term);
+        }
+    }
+
+    /// Writes the term inside braces, on its own indented lines.
+    fn block(&mut self, term: &Term) {
+        self.output.push('{');
+        self.indent += 1;
+        self.newline();
+        self.term(term);
+        self.indent -= 1;
+        self.newline();
+        self.output.push('}');
+    }
+
+    fn term(&mut self, term: &Term) {
+        match term {
+            Term::Error(error) => self.output.push_str(&error.full_text),
+            Term::Int(int) => self.int(int),
+            Term::Float(float) => self.float(float.value),
+            Term::Str(str) => {
+                let _ = write!(self.output, "\"{}\"", str.value);
+            }
+            Term::Bool(bool) => {
+                let _ = write!(self.output, "{}", bool.value);
+            }
+            Term::Var(var) => self.output.push_str(&var.text),
+            Term::Binary(binary) => {
+                // The operators are right associative, so only the right
+                // operand can be at the same level without parentheses.
+                let at = op_level(&binary.op);
+                self.operand(&binary.lhs, next(at));
+                let _ = write!(self.output, " {} ", symbol(&binary.op));
+                self.operand(&binary.rhs, at);
+            }
+            Term::Call(call) => {
+                self.operand(&call.callee, Level::Apply);
+                self.output.push('(');
+                for (index, argument) in call.arguments.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.term(argument);
+                }
+                self.output.push(')');
+            }
+            Term::Function(function) => {
+                let parameters: Vec<&str> = function
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.text.as_str())
+                    .collect();
+                let _ = write!(self.output, "fn ({}) => ", parameters.join(", "));
+                self.block(&function.value);
+            }
+            Term::Let(let_) => {
+                let _ = write!(self.output, "let {} = ", let_.name.text);
+                match *let_.value {
+                    Term::Let(_) => self.block(&let_.value),
+                    _ => self.term(&let_.value),
+                }
+                self.output.push(';');
+                self.newline();
+                self.term(&let_.next);
+            }
+            Term::If(if_) => {
+                self.output.push_str("if (");
+                self.term(&if_.condition);
+                self.output.push_str(") ");
+                self.block(&if_.then);
+                self.output.push_str(" else ");
+                self.block(&if_.otherwise);
+            }
+            Term::Print(print) => self.call("print", &print.value),
+            Term::First(first) => self.call("first", &first.value),
+            Term::Second(second) => self.call("second", &second.value),
+            Term::Tuple(tuple) => {
+                self.output.push('(');
+                self.term(&tuple.first);
+                self.output.push_str(", ");
+                self.term(&tuple.second);
+                self.output.push(')');
+            }
+            Term::Record(record) if record.fields.is_empty() => self.output.push_str("{}"),
+            Term::Record(record) => {
+                self.output.push_str("{ ");
+                for (index, field) in record.fields.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    let _ = write!(self.output, "{}: ", field.name.text);
+                    self.term(&field.value);
+                }
+                self.output.push_str(" }");
+            }
+            Term::Access(access) => {
+                self.operand(&access.value, Level::Apply);
+                let _ = write!(self.output, ".{}", access.field.text);
+            }
+            Term::Match(match_) => {
+                self.output.push_str("match (");
+                self.term(&match_.value);
+                self.output.push_str(") {");
+                self.indent += 1;
+                for arm in &match_.arms {
+                    self.newline();
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.output.push_str(" if ");
+                        self.term(guard);
+                    }
+                    self.output.push_str(" => ");
+                    match arm.body {
+                        Term::Let(_) => self.block(&arm.body),
+                        _ => self.term(&arm.body),
+                    }
+                    self.output.push(',');
+                }
+                self.indent -= 1;
+                if !match_.arms.is_empty() {
+                    self.newline();
+                }
+                self.output.push('}');
+            }
+        }
+    }
+
+    /// The literals of the patterns are written as they are, the grammar
+    /// has no negative ones.
+    fn pattern(&mut self, pattern: &ast::Pattern) {
+        let _ = match pattern {
+            ast::Pattern::Wildcard(_) => write!(self.output, "_"),
+            ast::Pattern::Var(var) => write!(self.output, "{}", var.text),
+            ast::Pattern::Int(int) => write!(self.output, "{}", int.value),
+            ast::Pattern::Str(str) => write!(self.output, "\"{}\"", str.value),
+            ast::Pattern::Bool(bool) => write!(self.output, "{}", bool.value),
+            ast::Pattern::Tuple(tuple) => {
+                self.output.push('(');
+                self.pattern(&tuple.first);
+                self.output.push_str(", ");
+                self.pattern(&tuple.second);
+                self.output.push(')');
+                Ok(())
+            }
+        };
+    }
+
+    fn call(&mut self, name: &str, argument: &Term) {
+        let _ = write!(self.output, "{name}(");
+        self.term(argument);
+        self.output.push(')');
+    }
+
+    /// The literals can't be negative, so those are written as a
+    /// subtraction from zero.
+    fn int(&mut self, int: &ast::Int) {
+        let _ = match int.value {
+            i32::MIN => write!(self.output, "((0 - {}) - 1)", i32::MAX),
+            value if value < 0 => write!(self.output, "(0 - {})", -value),
+            value => write!(self.output, "{value}"),
+        };
+    }
+
+    /// Writes the float as a literal, or as the operations making it when
+    /// there's no literal for it, like for `NaN` and the negative ones.
+    fn float(&mut self, value: f64) {
+        let literal = |value: f64| match value.is_infinite() {
+            true => "(1.0 / 0.0)".to_string(),
+            false => {
+                let digits = value.to_string();
+                match digits.contains('.') {
+                    true => digits,
+                    false => format!("{digits}.0"),
+                }
+            }
+        };
+        let _ = match value {
+            value if value.is_nan() => write!(self.output, "(0.0 / 0.0)"),
+            value if value == 0.0 && value.is_sign_negative() => {
+                write!(self.output, "(0.0 * (0.0 - 1.0))")
+            }
+            value if value < 0.0 => write!(self.output, "(0.0 - {})", literal(-value)),
+            value => write!(self.output, "{}", literal(value)),
+        };
+    }
+}
+
+/// The level right above `level`, for the left operands.
+fn next(level: Level) -> Level {
+    match level {
+        Level::Term => Level::Logical,
+        Level::Logical => Level::Arithmetic,
+        Level::Arithmetic => Level::Factor,
+        Level::Factor | Level::Apply => Level::Apply,
+    }
+}
@@ -0,0 +1,343 @@
+//! Random programs for the fuzzers, built with [`arbitrary`] from the bytes
+//! the fuzzer gives. Only the `fuzz` feature builds it.
+//!
+//! The programs are well formed: every variable is bound where it's used,
+//! and the calls of the functions in scope mostly give them as many
+//! arguments as they take. They're not well typed, so the errors of the
+//! runtime are exercised too. [`check`] runs a program on the engines and
+//! tells whether one of them panicked, or whether they disagree.
+
+use crate::{
+    ast::{self, BinaryOp, Term},
+    interpreter::ErrorKind,
+    parser::Var,
+    resolve::Address,
+    run::InternalError,
+    runtime::symbol::Symbol,
+    Backend, RinhaError, RunConfig,
+};
+use arbitrary::{Arbitrary, Unstructured};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+/// The filename of the generated programs.
+pub const FILENAME: &str = "fuzz.rinha";
+
+/// How deep the terms nest, so the programs stay small and the engines
+/// don't overflow the stack walking them.
+const MAX_NESTING: u32 = 8;
+
+/// The limits of the runs of [`check`]. The recursions of the generated
+/// functions can loop forever or nest without end, they stop at these.
+const MAX_DEPTH: u64 = 64;
+const MAX_STEPS: u64 = 100_000;
+
+/// The operators, in the order they're picked from.
+const OPERATORS: [BinaryOp; 13] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::Rem,
+    BinaryOp::Eq,
+    BinaryOp::Neq,
+    BinaryOp::Lt,
+    BinaryOp::Gt,
+    BinaryOp::Lte,
+    BinaryOp::Gte,
+    BinaryOp::And,
+    BinaryOp::Or,
+];
+
+/// The strings of the programs, few so the comparisons are sometimes true.
+const STRINGS: [&str; 4] = ["", "a", "b", "rinha"];
+
+/// A generated program, for the fuzz targets taking their input as an
+/// [`Arbitrary`] value.
+///
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use rinha::fuzz::{self, Program};
+///
+/// let bytes: Vec<u8> = (0..=255).collect();
+/// let program = Program::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+/// fuzz::check(&program.0).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Program(pub ast::File);
+
+impl<'a> Arbitrary<'a> for Program {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        file(u).map(Program)
+    }
+}
+
+/// Generates a program from the bytes of `u`. Once they run out the terms
+/// left are the simplest ones, so every input makes a program.
+pub fn file(u: &mut Unstructured) -> arbitrary::Result<ast::File> {
+    let mut generator = Generator {
+        u,
+        scope: Vec::new(),
+        offset: 0,
+        names: 0,
+    };
+    let expression = generator.term(0)?;
+    Ok(ast::File {
+        name: FILENAME.into(),
+        location: ast::Location::new(0, generator.offset, FILENAME),
+        expression,
+        metadata: None,
+    })
+}
+
+/// A variable in scope, with the number of parameters of the functions.
+struct Bound {
+    name: Symbol,
+    arity: Option<usize>,
+}
+
+struct Generator<'u, 'a> {
+    u: &'u mut Unstructured<'a>,
+    scope: Vec<Bound>,
+    /// The start of the next location. Every term has its own, the names
+    /// of the functions and the memo tell the terms apart by them.
+    offset: usize,
+    /// How many variables were named, so the names are all different.
+    names: usize,
+}
+
+impl Generator<'_, '_> {
+    fn location(&mut self) -> ast::Location {
+        self.offset += 1;
+        ast::Location::new(self.offset - 1, self.offset, FILENAME)
+    }
+
+    fn var(&mut self, name: Symbol) -> Var {
+        Var {
+            text: name,
+            location: self.location(),
+            address: Address::default(),
+        }
+    }
+
+    fn fresh(&mut self, prefix: &str) -> Symbol {
+        self.names += 1;
+        Symbol::from(format!("{prefix}{}", self.names - 1))
+    }
+
+    fn boxed(&mut self, nesting: u32) -> arbitrary::Result<Box<Term>> {
+        self.term(nesting + 1).map(Box::new)
+    }
+
+    fn term(&mut self, nesting: u32) -> arbitrary::Result<Term> {
+        let kinds = match nesting < MAX_NESTING {
+            true => 12,
+            false => 3,
+        };
+        let location = self.location();
+        let term = match self.u.choose_index(kinds)? {
+            0 => Term::Int(ast::Int {
+                value: match self.u.ratio(1, 8)? {
+                    true => self.u.arbitrary()?,
+                    false => self.u.int_in_range(-10..=10)?,
+                },
+                location,
+            }),
+            1 if !self.scope.is_empty() => {
+                let index = self.u.choose_index(self.scope.len())?;
+                let name = self.scope[index].name.clone();
+                Term::Var(Var {
+                    text: name,
+                    location,
+                    address: Address::default(),
+                })
+            }
+            1 => Term::Bool(ast::Bool {
+                value: self.u.arbitrary()?,
+                location,
+            }),
+            2 => Term::Str(ast::Str {
+                value: Symbol::from(*self.u.choose(&STRINGS)?),
+                location,
+            }),
+            3 => Term::Binary(ast::Binary {
+                lhs: self.boxed(nesting)?,
+                op: self.u.choose(&OPERATORS)?.clone(),
+                rhs: self.boxed(nesting)?,
+                location,
+            }),
+            4 => Term::If(ast::If {
+                condition: self.boxed(nesting)?,
+                then: self.boxed(nesting)?,
+                otherwise: self.boxed(nesting)?,
+                location,
+            }),
+            5 | 6 => self.bind(nesting, location)?,
+            7 => Term::Function(self.function(nesting, None, location)?),
+            8 => self.call(nesting, location)?,
+            9 => Term::Print(ast::Print {
+                value: self.boxed(nesting)?,
+                location,
+            }),
+            10 => Term::Tuple(ast::Tuple {
+                first: self.boxed(nesting)?,
+                second: self.boxed(nesting)?,
+                location,
+            }),
+            _ => match self.u.arbitrary()? {
+                true => Term::First(ast::First {
+                    value: self.boxed(nesting)?,
+                    location,
+                }),
+                false => Term::Second(ast::Second {
+                    value: self.boxed(nesting)?,
+                    location,
+                }),
+            },
+        };
+        Ok(term)
+    }
+
+    /// A `let`, binding a function half of the time. The functions see
+    /// their own name, so they can recurse.
+    fn bind(&mut self, nesting: u32, location: ast::Location) -> arbitrary::Result<Term> {
+        let name = self.fresh("v");
+        let var = self.var(name.clone());
+        let (value, arity) = match self.u.arbitrary()? {
+            true => {
+                let function_location = self.location();
+                let function = self.function(nesting, Some(name.clone()), function_location)?;
+                let arity = function.parameters.len();
+                (Term::Function(function), Some(arity))
+            }
+            false => (self.term(nesting + 1)?, None),
+        };
+        self.scope.push(Bound { name, arity });
+        let next = self.boxed(nesting);
+        self.scope.pop();
+        Ok(Term::Let(ast::Let {
+            name: var,
+            value: Box::new(value),
+            next: next?,
+            location,
+        }))
+    }
+
+    fn function(
+        &mut self,
+        nesting: u32,
+        name: Option<Symbol>,
+        location: ast::Location,
+    ) -> arbitrary::Result<ast::Function> {
+        let arity = self.u.int_in_range(0..=3)?;
+        let parameters: Vec<Var> = (0..arity)
+            .map(|_| {
+                let name = self.fresh("p");
+                self.var(name)
+            })
+            .collect();
+        let bound = self.scope.len();
+        if let Some(name) = name {
+            self.scope.push(Bound {
+                name,
+                arity: Some(arity),
+            });
+        }
+        for parameter in &parameters {
+            self.scope.push(Bound {
+                name: parameter.text.clone(),
+                arity: None,
+            });
+        }
+        let body = self.term(nesting + 1);
+        self.scope.truncate(bound);
+        Ok(ast::Function {
+            parameters,
+            value: Arc::new(body?),
+            location,
+        })
+    }
+
+    /// A call of a function in scope with as many arguments as it takes,
+    /// or of any term with any arguments, which is mostly an error.
+    fn call(&mut self, nesting: u32, location: ast::Location) -> arbitrary::Result<Term> {
+        let functions: Vec<(Symbol, usize)> = self
+            .scope
+            .iter()
+            .filter_map(|bound| Some((bound.name.clone(), bound.arity?)))
+            .collect();
+        let (callee, arity) = match functions.is_empty() || self.u.ratio(1, 8)? {
+            true => (self.term(nesting + 1)?, self.u.int_in_range(0..=3)?),
+            false => {
+                let (name, arity) = self.u.choose(&functions)?.clone();
+                (Term::Var(self.var(name)), arity)
+            }
+        };
+        let arguments = (0..arity)
+            .map(|_| self.term(nesting + 1))
+            .collect::<arbitrary::Result<_>>()?;
+        Ok(Term::Call(ast::Call {
+            callee: Box::new(callee),
+            arguments,
+            location,
+        }))
+    }
+}
+
+/// What a run printed and how it ended, the text of its value or its
+/// error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub printed: String,
+    pub ended: Result<String, ErrorKind>,
+}
+
+/// A bug found by [`check`].
+#[derive(Debug)]
+pub enum Finding {
+    /// The engine panicked, which is a bug whatever the program does.
+    Panicked(Backend, Box<InternalError>),
+    /// The engines printed something else, or ended differently.
+    Diverged { tree: Box<Run>, vm: Box<Run> },
+}
+
+/// Runs the program on the tree-walking interpreter and on the bytecode,
+/// with limits on the depth and the steps of the runs. The runs stopped by
+/// a limit aren't compared, the engines don't count their steps alike.
+pub fn check(file: &ast::File) -> Result<(), Finding> {
+    let tree = run(file, Backend::Tree)?;
+    let vm = run(file, Backend::Vm)?;
+    let limited = |run: &Run| {
+        matches!(
+            run.ended,
+            Err(ErrorKind::DepthLimit(_) | ErrorKind::StepLimit(_))
+        )
+    };
+    match tree == vm || limited(&tree) || limited(&vm) {
+        true => Ok(()),
+        false => Err(Finding::Diverged {
+            tree: Box::new(tree),
+            vm: Box::new(vm),
+        }),
+    }
+}
+
+fn run(file: &ast::File, backend: Backend) -> Result<Run, Finding> {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        filename: FILENAME.into(),
+        backend,
+        assert_none: false,
+        output: Some(output.clone()),
+        max_depth: Some(MAX_DEPTH),
+        max_steps: Some(MAX_STEPS),
+        ..RunConfig::default()
+    };
+    let ended = match crate::run_ast(file.clone(), &config) {
+        Ok(outcome) => Ok(outcome.value.to_string()),
+        Err(RinhaError::Runtime(error)) => Err(error.kind),
+        Err(RinhaError::Internal(error)) => return Err(Finding::Panicked(backend, error)),
+        Err(RinhaError::Parse(error)) => unreachable!("the AST was parsed already: {error}"),
+    };
+    let printed = String::from_utf8_lossy(&output.borrow()).into_owned();
+    Ok(Run { printed, ended })
+}
@@ -0,0 +1,322 @@
+use crate::{ast, runtime::symbol::Symbol};
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    str::FromStr,
+};
+
+/// Extensions of the files that the interpreter knows how to run. They're
+/// used to suggest a file when the user passes a directory.
+const CANDIDATE_EXTENSIONS: &[&str] = &["json", "rinha"];
+
+/// The path that stands for the standard input.
+pub const STDIN: &str = "-";
+
+/// The error type for reading the input program. It distinguishes the
+/// reasons a file can't be read so the user knows how to fix it.
+#[derive(miette::Diagnostic, thiserror::Error, Debug)]
+pub enum InputError {
+    /// The path points to a directory instead of a file.
+    #[error("`{path}` is a directory, not a file")]
+    #[diagnostic(code(rinha::input::is_a_directory))]
+    IsADirectory {
+        path: String,
+
+        /// The suggestion when there's exactly one candidate file inside
+        /// the directory.
+        #[help]
+        help: Option<String>,
+    },
+
+    /// The path doesn't exist.
+    #[error("`{path}` does not exist")]
+    #[diagnostic(
+        code(rinha::input::not_found),
+        help("check the path for typos, it's relative to the current directory")
+    )]
+    NotFound { path: String },
+
+    /// The path exists, but we can't read it.
+    #[error("permission denied while reading `{path}`")]
+    #[diagnostic(
+        code(rinha::input::permission_denied),
+        help("check the file permissions, e.g. `chmod +r {path}`")
+    )]
+    PermissionDenied { path: String },
+
+    /// The file was read, but its contents aren't valid UTF-8.
+    #[error("`{path}` is not valid UTF-8")]
+    #[diagnostic(
+        code(rinha::input::invalid_utf8),
+        help("the program must be an UTF-8 encoded `.json` AST or `.rinha` source")
+    )]
+    InvalidUtf8 { path: String },
+
+    /// The file was read, but it isn't an AST this build understands.
+    #[error("`{path}` is not a valid JSON AST: {source}")]
+    #[diagnostic(code(rinha::input::invalid_ast))]
+    InvalidAst {
+        path: String,
+        source: serde_json::Error,
+
+        /// Which versions are involved, when a newer parser wrote it.
+        #[help]
+        help: Option<String>,
+    },
+
+    /// Any other IO error, we just forward the message.
+    #[error("could not read `{path}`: {source}")]
+    #[diagnostic(code(rinha::input::io))]
+    Io { path: String, source: io::Error },
+}
+
+impl InputError {
+    /// The exit code used by the binaries when the input can't be read.
+    pub const EXIT_CODE: i32 = 2;
+}
+
+/// Reads the program at `path` into a string, or the standard input when
+/// it's [`STDIN`], reporting a friendly [`InputError`] when it can't be
+/// done.
+///
+/// ```
+/// use rinha::input::{self, InputError};
+///
+/// let error = input::read_to_string("does/not/exist.json").unwrap_err();
+/// assert!(matches!(error, InputError::NotFound { .. }));
+/// ```
+pub fn read_to_string(path: &str) -> Result<String, InputError> {
+    let bytes = match path {
+        STDIN => {
+            let mut bytes = Vec::new();
+            match io::stdin().lock().read_to_end(&mut bytes) {
+                Ok(_) => bytes,
+                Err(source) => {
+                    return Err(InputError::Io {
+                        path: path.into(),
+                        source,
+                    })
+                }
+            }
+        }
+        _ => match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) => return Err(classify(path, error)),
+        },
+    };
+
+    String::from_utf8(bytes).map_err(|_| InputError::InvalidUtf8 { path: path.into() })
+}
+
+/// Whether the program is `rinha` source code rather than a JSON AST. The
+/// `.rinha` files are, and so is any other text that isn't a JSON object,
+/// like the programs piped into the standard input.
+///
+/// ```
+/// use rinha::input;
+///
+/// assert!(input::is_source("main.rinha", "print(1)"));
+/// assert!(input::is_source("-", "print(1)"));
+/// assert!(!input::is_source("-", r#"{"name": "main.rinha"}"#));
+/// ```
+pub fn is_source(path: &str, text: &str) -> bool {
+    path.ends_with(".rinha") || !text.trim_start().starts_with('{')
+}
+
+/// What a program is written as.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `rinha` source code
+    Source,
+    /// A JSON AST
+    Json,
+}
+
+impl Format {
+    /// The format given, or the one told by [`is_source`] when none is.
+    ///
+    /// ```
+    /// use rinha::input::Format;
+    ///
+    /// assert_eq!(Format::of(None, "main.rinha", "print(1)"), Format::Source);
+    /// assert_eq!(Format::of(Some(Format::Json), "main.rinha", "{}"), Format::Json);
+    /// ```
+    pub fn of(given: Option<Format>, path: &str, text: &str) -> Format {
+        match given {
+            Some(format) => format,
+            None if is_source(path, text) => Format::Source,
+            None => Format::Json,
+        }
+    }
+}
+
+/// Reads the JSON AST of the program at `path`. When a newer parser wrote
+/// it, `on_newer` is told before reading, since it may not be understood.
+///
+/// ```
+/// use rinha::input;
+///
+/// let json = r#"{"name": "main.rinha", "expression": {"kind": "Int", "value": 1,
+///   "location": {"start": 0, "end": 1, "filename": "main.rinha"}},
+///   "location": {"start": 0, "end": 1, "filename": "main.rinha"},
+///   "metadata": {"parser_version": "99.0.0"}}"#;
+/// let mut newer = None;
+/// let file = input::parse_ast("main.json", json, |metadata| newer = Some(metadata.clone()));
+///
+/// assert!(file.is_ok());
+/// assert_eq!(newer.unwrap().parser_version, "99.0.0");
+/// ```
+pub fn parse_ast(
+    path: &str,
+    json: &str,
+    on_newer: impl FnOnce(&ast::Metadata),
+) -> Result<ast::File, InputError> {
+    // Only the metadata is read first, every other field is skipped.
+    #[derive(serde::Deserialize)]
+    struct Header {
+        #[serde(default)]
+        metadata: Option<ast::Metadata>,
+    }
+    let header: Option<Header> = serde_json::from_str(json).ok();
+    let newer = header
+        .and_then(|header| header.metadata)
+        .filter(ast::Metadata::is_newer);
+    if let Some(metadata) = &newer {
+        on_newer(metadata);
+    }
+
+    serde_json::from_str(json).map_err(|source| InputError::InvalidAst {
+        path: path.into(),
+        source,
+        help: newer.map(|metadata| {
+            format!(
+                "it was written by the parser of rinha {}, this is rinha {}",
+                metadata.parser_version,
+                env!("CARGO_PKG_VERSION")
+            )
+        }),
+    })
+}
+
+/// Translates the IO error into the matching [`InputError`].
+fn classify(path: &str, error: io::Error) -> InputError {
+    if Path::new(path).is_dir() {
+        return InputError::IsADirectory {
+            path: path.into(),
+            help: suggest_candidate(path),
+        };
+    }
+
+    match error.kind() {
+        io::ErrorKind::NotFound => InputError::NotFound { path: path.into() },
+        io::ErrorKind::PermissionDenied => InputError::PermissionDenied { path: path.into() },
+        _ => InputError::Io {
+            path: path.into(),
+            source: error,
+        },
+    }
+}
+
+/// A value given to the program on the command line, as `name=value`.
+///
+/// ```
+/// use rinha::input::Arg;
+///
+/// let arg: Arg = "n=30".parse().unwrap();
+/// assert_eq!((arg.name.as_str(), arg.value.as_str()), ("n", "30"));
+/// assert!("30".parse::<Arg>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for Arg {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let Some((name, value)) = text.split_once('=') else {
+            return Err(format!("`{text}` isn't `name=value`"));
+        };
+        let mut chars = name.chars();
+        let first = chars.next().is_some_and(|char| char.is_ascii_alphabetic());
+        if !first || !chars.all(|char| char.is_ascii_alphanumeric() || char == '_') {
+            return Err(format!("`{name}` isn't the name of a variable"));
+        }
+        Ok(Arg {
+            name: name.into(),
+            value: value.into(),
+        })
+    }
+}
+
+/// Binds the arguments around the program, each as a `let` the program can
+/// read or shadow. The values that are integers or booleans bind those,
+/// so the programs can compute with them, the others bind strings.
+///
+/// ```
+/// use rinha::{format, input::{self, Arg}, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "print(n)").unwrap();
+/// let args = ["n=30".parse::<Arg>().unwrap(), "name=fib".parse().unwrap()];
+/// let term = input::bind_args(file.expression, &args, "main.rinha");
+/// assert_eq!(format::to_source(&term), "let n = 30;\nlet name = \"fib\";\nprint(n)");
+/// ```
+pub fn bind_args(expression: ast::Term, args: &[Arg], filename: &str) -> ast::Term {
+    // They're nowhere in the source, so they're where it starts.
+    let location = ast::Location::new(0, 0, filename);
+    args.iter().rev().fold(expression, |next, arg| {
+        let value = if let Ok(value) = arg.value.parse() {
+            ast::Term::Int(ast::Int {
+                value,
+                location: location.clone(),
+            })
+        } else if let Ok(value) = arg.value.parse() {
+            ast::Term::Bool(ast::Bool {
+                value,
+                location: location.clone(),
+            })
+        } else {
+            ast::Term::Str(ast::Str {
+                value: arg.value.as_str().into(),
+                location: location.clone(),
+            })
+        };
+        ast::Term::Let(ast::Let {
+            name: crate::parser::Var {
+                text: Symbol::intern(&arg.name),
+                location: location.clone(),
+                address: Default::default(),
+            },
+            value: Box::new(value),
+            next: Box::new(next),
+            location: location.clone(),
+        })
+    })
+}
+
+/// If the directory contains exactly one runnable file, suggest it.
+fn suggest_candidate(directory: &str) -> Option<String> {
+    let mut candidates = fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| CANDIDATE_EXTENSIONS.contains(&extension))
+        });
+
+    let candidate = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+
+    Some(format!(
+        "did you mean to pass `{}` inside this directory?",
+        candidate.display()
+    ))
+}
@@ -0,0 +1,3099 @@
+use crate::{
+    ast::{self, Element},
+    memo::{self, FunctionId, Lru, MemoKey, Snapshot},
+    names::FunctionNames,
+    parser, path,
+    resolve::{self, Address, Layout},
+    runtime::{
+        bigint::BigInt,
+        list::{self, List},
+        record::{self, Record},
+        reference::{self, Ref},
+        string,
+        symbol::Symbol,
+        tuple,
+    },
+};
+use std::{
+    collections, fmt, io,
+    io::Write,
+    ops::ControlFlow,
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A runtime value.
+#[derive(Debug, Clone)]
+pub enum Primitive {
+    Str(Symbol),
+    /// The integers are 32 bits wide with [`Overflow::Trap`] and
+    /// [`Overflow::Wrap32`], they go past that with the other modes.
+    Int(i64),
+    /// The floats, only made with [`Interpreter::extensions`].
+    Float(f64),
+    Bool(bool),
+    Function {
+        name: Symbol,
+        parameters: Arc<[Symbol]>,
+        value: Arc<ast::Term>,
+        env: Scope,
+    },
+    Tuple([Box<Primitive>; 2]),
+    /// The lists, only made with [`Interpreter::extensions`].
+    List(List),
+    /// The records, only made with [`Interpreter::extensions`].
+    Record(Record),
+    /// The references, only made with [`Interpreter::extensions`].
+    Ref(Ref),
+    /// The integers past 64 bits, only made by [`Overflow::Widen`]. The
+    /// integers that fit in 64 bits are always an `Int`.
+    BigInt(BigInt),
+    /// The unit value, produced by terms that don't evaluate to anything
+    /// meaningful. It's rendered as `unit`, and never as an empty string.
+    None,
+    /// A value not computed yet, only bound to the variables by
+    /// [`Interpreter::lazy`]. Looking the variable up computes it.
+    Thunk(Thunk),
+}
+
+/// A term and the scope it's evaluated in, once its value is needed. The
+/// value is kept, so it's computed once, and shared by the scopes that
+/// bound it.
+#[derive(Clone)]
+pub struct Thunk(Arc<Mutex<Delayed>>);
+
+enum Delayed {
+    Pending { term: Arc<ast::Term>, env: Scope },
+    Done(Primitive),
+}
+
+impl Thunk {
+    fn new(term: Arc<ast::Term>, env: Scope) -> Thunk {
+        Thunk(Arc::new(Mutex::new(Delayed::Pending { term, env })))
+    }
+
+    /// The value, when it was computed already.
+    pub fn value(&self) -> Option<Primitive> {
+        match &*self.lock() {
+            Delayed::Done(value) => Some(value.clone()),
+            Delayed::Pending { .. } => None,
+        }
+    }
+
+    /// The state, the lock is only held while reading or writing it.
+    fn lock(&self) -> MutexGuard<'_, Delayed> {
+        self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// The address of the state and how many references share it, for the
+    /// [`Collector`](reference::Collector).
+    pub(crate) fn node(&self) -> (usize, usize) {
+        (Arc::as_ptr(&self.0) as usize, Arc::strong_count(&self.0))
+    }
+
+    /// Calls `held` with the scope of the term not computed yet, or with
+    /// the value.
+    pub(crate) fn trace(&self, held: &mut dyn FnMut(reference::Traced<'_>)) {
+        match &*self.lock() {
+            Delayed::Pending { env, .. } if env.node().is_some() => {
+                held(reference::Traced::Shared(reference::Shared::Scope(
+                    env.clone(),
+                )));
+            }
+            Delayed::Pending { .. } => {}
+            Delayed::Done(value) => held(reference::Traced::Value(value)),
+        }
+    }
+}
+
+impl fmt::Debug for Thunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value() {
+            Some(value) => f.debug_tuple("Thunk").field(&value).finish(),
+            None => f.write_str("Thunk(<pending>)"),
+        }
+    }
+}
+
+/// The runtime values, by the name the embedders know them.
+pub type Value = Primitive;
+
+impl Primitive {
+    /// Converts the value to JSON. Tuples become two-element arrays, lists
+    /// become arrays of their elements, records objects, closures and
+    /// references become the `<#closure>` and `<#ref>` strings and unit
+    /// becomes `null`. The integers
+    /// past 64 bits become strings of their digits, JSON readers rarely
+    /// take numbers that large, and so do the floats JSON numbers can't
+    /// hold, like `NaN`.
+    ///
+    /// ```
+    /// use rinha::interpreter::Primitive;
+    ///
+    /// let tuple = Primitive::Tuple([Box::new(Primitive::Int(1)), Box::new(Primitive::None)]);
+    /// assert_eq!(tuple.to_json(), serde_json::json!([1, null]));
+    /// ```
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Primitive::Str(v) => serde_json::Value::from(v.as_str()),
+            Primitive::Int(v) => serde_json::Value::from(*v),
+            Primitive::BigInt(v) => serde_json::Value::from(v.to_string()),
+            Primitive::Float(v) if v.is_finite() => serde_json::Value::from(*v),
+            Primitive::Float(v) => serde_json::Value::from(format!("{v:?}")),
+            Primitive::Bool(v) => serde_json::Value::from(*v),
+            Primitive::Function { .. } => serde_json::Value::from("<#closure>"),
+            Primitive::Ref(_) => serde_json::Value::from("<#ref>"),
+            Primitive::Tuple([first, second]) => {
+                serde_json::Value::Array(vec![first.to_json(), second.to_json()])
+            }
+            Primitive::List(list) => list.iter().map(Primitive::to_json).collect(),
+            Primitive::Record(record) => record
+                .fields()
+                .map(|(name, value)| (name.to_string(), value.to_json()))
+                .collect(),
+            Primitive::None => serde_json::Value::Null,
+            Primitive::Thunk(thunk) => match thunk.value() {
+                Some(value) => value.to_json(),
+                None => serde_json::Value::from("<#thunk>"),
+            },
+        }
+    }
+}
+
+/// Renders the value the way `print` shows it.
+impl fmt::Display for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(IntFormat::Plain).fmt(f)
+    }
+}
+
+/// The values are equal when they have the same type and contents. The
+/// functions are equal when they come from the same definition and captured
+/// equal values. Unlike `==` in the language, every value can be compared,
+/// and the floats are equal when they have the same bits, so `NaN` is equal
+/// to itself.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::tuple};
+///
+/// let pair = tuple::new(Primitive::Int(1), Primitive::None);
+/// assert_eq!(pair, tuple::new(Primitive::Int(1), Primitive::None));
+/// assert_ne!(Primitive::Int(1), Primitive::Str("1".into()));
+/// ```
+impl PartialEq for Primitive {
+    fn eq(&self, other: &Self) -> bool {
+        // The tuples are walked with a stack, they can nest deeper than the
+        // native stack allows to recurse.
+        let mut pending = vec![(self, other)];
+        while let Some(pair) = pending.pop() {
+            let equal = match pair {
+                (Primitive::Tuple([lhs_0, lhs_1]), Primitive::Tuple([rhs_0, rhs_1])) => {
+                    pending.push((lhs_1, rhs_1));
+                    pending.push((lhs_0, rhs_0));
+                    true
+                }
+                (Primitive::List(lhs), Primitive::List(rhs)) => {
+                    pending.extend(lhs.iter().zip(rhs.iter()));
+                    lhs.len() == rhs.len()
+                }
+                (Primitive::Record(lhs), Primitive::Record(rhs)) => {
+                    pending.extend(
+                        lhs.fields()
+                            .zip(rhs.fields())
+                            .map(|(lhs, rhs)| (lhs.1, rhs.1)),
+                    );
+                    lhs.fields()
+                        .map(|(name, _)| name)
+                        .eq(rhs.fields().map(|(name, _)| name))
+                }
+                (Primitive::Str(lhs), Primitive::Str(rhs)) => lhs == rhs,
+                (Primitive::Int(lhs), Primitive::Int(rhs)) => lhs == rhs,
+                (Primitive::BigInt(lhs), Primitive::BigInt(rhs)) => lhs == rhs,
+                (Primitive::Float(lhs), Primitive::Float(rhs)) => lhs.to_bits() == rhs.to_bits(),
+                (Primitive::Bool(lhs), Primitive::Bool(rhs)) => lhs == rhs,
+                (Primitive::None, Primitive::None) => true,
+                (Primitive::Thunk(lhs), Primitive::Thunk(rhs)) => Arc::ptr_eq(&lhs.0, &rhs.0),
+                (Primitive::Ref(lhs), Primitive::Ref(rhs)) => lhs.same(rhs),
+                (
+                    Primitive::Function {
+                        name,
+                        parameters,
+                        value,
+                        env,
+                    },
+                    Primitive::Function {
+                        name: other_name,
+                        parameters: other_parameters,
+                        value: other_value,
+                        env: other_env,
+                    },
+                ) => {
+                    name == other_name
+                        && parameters == other_parameters
+                        && value.location() == other_value.location()
+                        && env == other_env
+                }
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The floats are compared by their bits and the thunks by their cell, so
+/// every value is equal to itself.
+impl Eq for Primitive {}
+
+/// Hashes the values [`PartialEq`] says are equal the same, for the keys of
+/// the memo. It's stricter than [`tuple::hash`], which follows `==`: the
+/// floats hash their bits, and the thunks their cell instead of their value,
+/// so a key doesn't change its hash once a thunk in it is forced.
+impl std::hash::Hash for Primitive {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut pending = vec![self];
+        while let Some(value) = pending.pop() {
+            std::mem::discriminant(value).hash(state);
+            match value {
+                Primitive::Tuple([first, second]) => {
+                    pending.push(second);
+                    pending.push(first);
+                }
+                Primitive::List(list) => {
+                    list.len().hash(state);
+                    let elements: Vec<_> = list.iter().collect();
+                    pending.extend(elements.into_iter().rev());
+                }
+                Primitive::Record(record) => {
+                    let fields: Vec<_> = record.fields().collect();
+                    fields.len().hash(state);
+                    for (name, value) in fields.into_iter().rev() {
+                        name.hash(state);
+                        pending.push(value);
+                    }
+                }
+                Primitive::Str(value) => value.hash(state),
+                Primitive::Int(value) => value.hash(state),
+                Primitive::BigInt(value) => value.hash(state),
+                Primitive::Float(value) => value.to_bits().hash(state),
+                Primitive::Bool(value) => value.hash(state),
+                Primitive::None => {}
+                Primitive::Thunk(thunk) => (Arc::as_ptr(&thunk.0) as usize).hash(state),
+                Primitive::Ref(reference) => reference.address().hash(state),
+                // The captured values are left out, the closures of the same
+                // definition only differ by them and compare them anyway.
+                Primitive::Function {
+                    name,
+                    parameters,
+                    value,
+                    ..
+                } => {
+                    name.hash(state);
+                    parameters.hash(state);
+                    value.location().hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// How the integers are rendered by `print`. Anything but `Plain` is an
+/// extension of the specification, meant for reading large numbers.
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IntFormat {
+    /// Just the digits, like the specification says
+    #[default]
+    Plain,
+    /// Digits grouped by three with `_`, like `1_000_000`
+    Grouped,
+    /// Hexadecimal, like `0xf4240`
+    Hex,
+}
+
+/// What happens when the result of an arithmetic operation doesn't fit in
+/// the integers. The results are computed exactly, then the mode decides.
+///
+/// ```
+/// use rinha::{
+///     ast::BinaryOp,
+///     interpreter::{Overflow, Primitive},
+/// };
+///
+/// let add = |overflow| BinaryOp::Add.apply(Primitive::Int(i32::MAX.into()), Primitive::Int(1), overflow);
+/// assert_eq!(add(Overflow::Wrap32).unwrap().to_string(), "-2147483648");
+/// assert_eq!(add(Overflow::Widen).unwrap().to_string(), "2147483648");
+/// assert!(add(Overflow::Trap).is_err());
+///
+/// let square = BinaryOp::Mul.apply(Primitive::Int(i64::MAX), Primitive::Int(i64::MAX), Overflow::Widen);
+/// assert_eq!(square.unwrap().to_string(), "85070591730234615847396907784232501249");
+/// ```
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// Wraps around in 32 bits, like the reference implementation, it's
+    /// the mode of `--spec`
+    Wrap32,
+    /// Wraps around in 64 bits
+    Wrap64,
+    /// Fails with an integer overflow error
+    Trap,
+    /// Goes past 32 bits and past 64 bits, the integers are as large as
+    /// they need, it's the mode outside of `--spec`. It's also called `big`
+    #[default]
+    #[value(alias = "big")]
+    Widen,
+}
+
+impl Overflow {
+    /// Fits the exact result of `operation` into the integers of the mode.
+    fn int(self, operation: &'static str, exact: i128) -> Result<Primitive, ErrorKind> {
+        let overflow = || ErrorKind::IntegerOverflow(operation);
+        let value = match self {
+            // The truncations wrap around, in two's complement.
+            Overflow::Wrap32 => i64::from(exact as i32),
+            Overflow::Wrap64 => exact as i64,
+            Overflow::Trap => i64::from(i32::try_from(exact).map_err(|_| overflow())?),
+            Overflow::Widen => return self.big(operation, BigInt::from(exact)),
+        };
+        Ok(Primitive::Int(value))
+    }
+
+    /// Fits the exact result of an operation on the integers past 64 bits.
+    fn big(self, operation: &'static str, exact: BigInt) -> Result<Primitive, ErrorKind> {
+        match (self, exact.to_i128()) {
+            (Overflow::Widen, _) => Ok(match exact.to_i64() {
+                Some(value) => Primitive::Int(value),
+                None => Primitive::BigInt(exact),
+            }),
+            (_, Some(exact)) => self.int(operation, exact),
+            (Overflow::Wrap32, None) => Ok(Primitive::Int(i64::from(exact.wrapping_i64() as i32))),
+            (Overflow::Wrap64, None) => Ok(Primitive::Int(exact.wrapping_i64())),
+            (Overflow::Trap, None) => Err(ErrorKind::IntegerOverflow(operation)),
+        }
+    }
+}
+
+impl Primitive {
+    /// Renders the value with the integers in the given format, including
+    /// the ones inside tuples.
+    ///
+    /// ```
+    /// use rinha::interpreter::{IntFormat, Primitive};
+    ///
+    /// let value = Primitive::Tuple([Box::new(Primitive::Int(-1234567)), Box::new(Primitive::Int(255))]);
+    /// assert_eq!(value.display(IntFormat::Grouped).to_string(), "(-1_234_567, 255)");
+    /// assert_eq!(value.display(IntFormat::Hex).to_string(), "(-0x12d687, 0xff)");
+    /// ```
+    pub fn display(&self, format: IntFormat) -> Rendered<'_> {
+        Rendered {
+            value: self,
+            format,
+        }
+    }
+}
+
+/// A value rendered with an [`IntFormat`], see [`Primitive::display`].
+pub struct Rendered<'a> {
+    value: &'a Primitive,
+    format: IntFormat,
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tuple::render(f, self.value, self.format)
+    }
+}
+
+/// How [`Primitive::pretty`] lays the values out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// The spaces added by each level of nesting.
+    pub indent: usize,
+    /// The tuples nested deeper than this are shown as `(...)`.
+    pub max_depth: Option<usize>,
+    /// The tuples that fit in this many columns, counting the indentation,
+    /// stay on a single line.
+    pub width: usize,
+    /// Shows the tuples that repeat once, with a `#N` label, and refers to
+    /// the later ones as `&ref#N`.
+    pub share: bool,
+    pub int_format: IntFormat,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            max_depth: None,
+            width: 80,
+            share: false,
+            int_format: IntFormat::Plain,
+        }
+    }
+}
+
+impl Primitive {
+    /// Renders the value for reading, with the tuples that don't fit in the
+    /// width broken into one element per line. `print` keeps the compact
+    /// format of the specification, this is for debugging.
+    ///
+    /// ```
+    /// use rinha::interpreter::{PrettyOptions, Primitive};
+    ///
+    /// let pair = |a, b| Primitive::Tuple([Box::new(a), Box::new(b)]);
+    /// let value = pair(Primitive::Int(1), pair(Primitive::Int(2), Primitive::Int(3)));
+    /// let options = PrettyOptions {
+    ///     width: 8,
+    ///     ..PrettyOptions::default()
+    /// };
+    /// assert_eq!(value.pretty(&options), "(\n  1,\n  (2, 3)\n)");
+    /// ```
+    pub fn pretty(&self, options: &PrettyOptions) -> String {
+        let mut repeated = collections::HashSet::new();
+        if options.share {
+            let mut seen = collections::HashSet::new();
+            self.collect_repeated(options.int_format, &mut seen, &mut repeated);
+        }
+
+        let mut pretty = Pretty {
+            options,
+            repeated,
+            labels: collections::HashMap::new(),
+            flat: false,
+        };
+        let mut output = String::new();
+        pretty.value(self, 0, &mut output);
+        output
+    }
+
+    /// The compact renderings of the tuples showing up more than once.
+    fn collect_repeated(
+        &self,
+        format: IntFormat,
+        seen: &mut collections::HashSet<String>,
+        repeated: &mut collections::HashSet<String>,
+    ) {
+        if let Primitive::Tuple([first, second]) = self {
+            let compact = self.display(format).to_string();
+            if !seen.insert(compact.clone()) {
+                repeated.insert(compact);
+                // Its elements were counted by the first occurrence.
+                return;
+            }
+            first.collect_repeated(format, seen, repeated);
+            second.collect_repeated(format, seen, repeated);
+        }
+    }
+}
+
+/// The state of [`Primitive::pretty`]. The tuples are identified by their
+/// compact rendering, the values are trees, so the same tuple can only show
+/// up as copies.
+struct Pretty<'a> {
+    options: &'a PrettyOptions,
+    repeated: collections::HashSet<String>,
+    labels: collections::HashMap<String, usize>,
+    /// Whether everything goes on the current line.
+    flat: bool,
+}
+
+impl Pretty<'_> {
+    fn value(&mut self, value: &Primitive, depth: usize, output: &mut String) {
+        let Primitive::Tuple([first, second]) = value else {
+            output.push_str(&value.display(self.options.int_format).to_string());
+            return;
+        };
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            output.push_str("(...)");
+            return;
+        }
+
+        let compact = value.display(self.options.int_format).to_string();
+        if self.repeated.contains(&compact) {
+            if let Some(label) = self.labels.get(&compact) {
+                output.push_str(&format!("&ref#{label}"));
+                return;
+            }
+            let label = self.labels.len() + 1;
+            self.labels.insert(compact, label);
+            output.push_str(&format!("#{label} "));
+        }
+
+        if self.flat {
+            output.push('(');
+            self.value(first, depth + 1, output);
+            output.push_str(", ");
+            self.value(second, depth + 1, output);
+            output.push(')');
+            return;
+        }
+
+        // Tries the single line first, forgetting its labels when it's
+        // too wide.
+        let labels = self.labels.clone();
+        let mut line = String::from("(");
+        self.flat = true;
+        self.value(first, depth + 1, &mut line);
+        line.push_str(", ");
+        self.value(second, depth + 1, &mut line);
+        self.flat = false;
+        line.push(')');
+        let column = output.rsplit('\n').next().map_or(0, str::len);
+        if column + line.len() <= self.options.width {
+            output.push_str(&line);
+            return;
+        }
+        self.labels = labels;
+
+        let inner = " ".repeat((depth + 1) * self.options.indent);
+        output.push_str("(\n");
+        output.push_str(&inner);
+        self.value(first, depth + 1, output);
+        output.push_str(",\n");
+        output.push_str(&inner);
+        self.value(second, depth + 1, output);
+        output.push('\n');
+        output.push_str(&" ".repeat(depth * self.options.indent));
+        output.push(')');
+    }
+}
+
+pub(crate) fn write_int(f: &mut fmt::Formatter<'_>, value: i64, format: IntFormat) -> fmt::Result {
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    match format {
+        IntFormat::Plain => write!(f, "{value}"),
+        IntFormat::Hex => write!(f, "{sign}0x{magnitude:x}"),
+        IntFormat::Grouped => write_grouped(f, sign, &magnitude.to_string()),
+    }
+}
+
+/// Writes an integer past 64 bits in `format`, like [`write_int`].
+pub(crate) fn write_big_int(
+    f: &mut fmt::Formatter<'_>,
+    value: &BigInt,
+    format: IntFormat,
+) -> fmt::Result {
+    let sign = if value.is_negative() { "-" } else { "" };
+    match format {
+        IntFormat::Plain => write!(f, "{value}"),
+        IntFormat::Hex => write!(f, "{sign}0x{}", value.hex_magnitude()),
+        IntFormat::Grouped => write_grouped(f, sign, &value.decimal_magnitude()),
+    }
+}
+
+/// Writes the decimal `digits` in groups of three, after the `sign`.
+fn write_grouped(f: &mut fmt::Formatter<'_>, sign: &str, digits: &str) -> fmt::Result {
+    let head = match digits.len() % 3 {
+        0 => 3,
+        rest => rest,
+    };
+    write!(f, "{sign}{}", &digits[..head])?;
+    for group in digits.as_bytes()[head..].chunks(3) {
+        write!(f, "_{}", std::str::from_utf8(group).unwrap())?;
+    }
+    Ok(())
+}
+
+pub use crate::runtime::scope::Scope;
+
+/// How the arity errors say that `expected` arguments were `given`, the
+/// words agreeing with the counts.
+pub(crate) fn arity(expected: usize, given: usize) -> String {
+    let arguments = if expected == 1 {
+        "argument"
+    } else {
+        "arguments"
+    };
+    let were = if given == 1 { "was" } else { "were" };
+    format!("expects {expected} {arguments}, but {given} {were} given")
+}
+
+/// What went wrong while running a program.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    #[error("variable `{0}` not found in the scope")]
+    UndefinedVariable(String),
+
+    #[error("function `{name}` {}", arity(*expected, *given))]
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        given: usize,
+    },
+
+    /// The operands of a binary operation have the wrong types.
+    #[error("{0}")]
+    TypeMismatch(&'static str),
+
+    #[error("the condition inside `if` must evaluate to Bool")]
+    ConditionNotBool,
+
+    /// The callee of a call evaluated to something that isn't a function.
+    #[error("only functions can be called")]
+    NotAFunction,
+
+    /// `first` or `second` used on something that isn't a tuple.
+    #[error("`{0}` must be used on tuples")]
+    NotATuple(&'static str),
+
+    /// A native of the lists used on something that isn't a list.
+    #[error("`{0}` must be used on lists")]
+    NotAList(&'static str),
+
+    /// `head` or `tail` used on the empty list.
+    #[error("`{0}` of the empty list")]
+    EmptyList(&'static str),
+
+    /// A native of the references used on something that isn't one.
+    #[error("`{0}` must be used on references")]
+    NotARef(&'static str),
+
+    /// A native of the strings used on something that isn't a string.
+    #[error("`{0}` must be used on strings")]
+    NotAStr(&'static str),
+
+    /// A native given something that isn't an integer where it takes one.
+    #[error("`{0}` must be given integers")]
+    NotAnInt(&'static str),
+
+    /// A native of the strings given an index past the end of the string.
+    #[error("`{name}` at {index} is out of the {len} characters of the string")]
+    OutOfRange {
+        name: &'static str,
+        index: i64,
+        len: usize,
+    },
+
+    /// `int_of_string` given a text that isn't an integer.
+    #[error("{0:?} isn't an integer")]
+    NotAnInteger(String),
+
+    /// A function given to [`Interpreter::register_builtin`] failed.
+    #[error("`{name}` failed: {message}")]
+    BuiltinFailed { name: String, message: String },
+
+    /// A field read from something that isn't a record.
+    #[error("`.{0}` must be used on records")]
+    NotARecord(String),
+
+    /// A field read from a record that hasn't it.
+    #[error("the record has no field `{0}`")]
+    NoSuchField(String),
+
+    /// A record written with the same field twice, only the JSON ASTs can
+    /// have them.
+    #[error("the field `{0}` is given twice")]
+    DuplicateField(String),
+
+    /// No arm of a `match` takes the value.
+    #[error("no arm of the match takes the value")]
+    NoMatch,
+
+    /// A unit value was produced while asserting that it never is, the
+    /// text tells what produced it.
+    #[error("unit value produced by {0}")]
+    UnexpectedUnit(&'static str),
+
+    /// The result of an arithmetic operation doesn't fit in the integers
+    /// of the [`Overflow`] mode.
+    #[error("integer overflow in `{0}`")]
+    IntegerOverflow(&'static str),
+
+    /// The right operand of `/` or `%` is zero.
+    #[error("division by zero in `{0}`")]
+    DivisionByZero(&'static str),
+
+    /// The flag given to [`Interpreter::interrupt_on`] was raised.
+    #[error("interrupted")]
+    Interrupted,
+
+    /// The program printed as many bytes as
+    /// [`Interpreter::max_output_bytes`] allows, and tried to print more.
+    #[error("the output reached its limit of {0} bytes")]
+    OutputLimit(u64),
+
+    /// The output was closed while the program printed, like when it's
+    /// piped into `head`.
+    #[error("the output was closed")]
+    BrokenPipe,
+
+    /// The output, or one of the tees, couldn't be written.
+    #[error("could not write the output: {0}")]
+    Output(String),
+
+    /// The program made more calls than [`Interpreter::fuel`] allows.
+    #[error("the run used up its fuel of {0} calls")]
+    OutOfFuel(u64),
+
+    /// The calls nested deeper than [`Interpreter::max_depth`] allows.
+    #[error("resource limit exceeded: the calls nested deeper than {0}")]
+    DepthLimit(u64),
+
+    /// The program took more steps than [`Interpreter::max_steps`] allows.
+    #[error("resource limit exceeded: the run took more than {0} steps")]
+    StepLimit(u64),
+
+    /// The run went on past the `timeout` of its
+    /// [`RunConfig`](crate::RunConfig).
+    #[error("resource limit exceeded: the run took longer than its timeout of {0:?}")]
+    Timeout(Duration),
+
+    /// The program used something outside of the specification, without
+    /// [`Interpreter::extensions`].
+    #[error("{0} are outside of the specification, they need the extensions")]
+    NeedsExtensions(&'static str),
+}
+
+impl From<io::Error> for ErrorKind {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::BrokenPipe => ErrorKind::BrokenPipe,
+            _ => ErrorKind::Output(error.to_string()),
+        }
+    }
+}
+
+/// A runtime error, pointing at the term that failed.
+#[derive(miette::Diagnostic, thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{kind}")]
+#[diagnostic(code(rinha::runtime_error))]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+
+    /// The location of the term that failed.
+    #[label("here")]
+    pub location: ast::Location,
+
+    /// The calls being evaluated when it failed, the innermost first. The
+    /// calls in tail position replace the one that made them, so only the
+    /// last of them is there.
+    pub trace: Vec<Frame>,
+
+    /// The counters of the run up to the error, filled in by
+    /// [`crate::run_source`] and the other runs of a [`crate::RunConfig`].
+    pub stats: Option<Box<Stats>>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind, location: ast::Location) -> Self {
+        Self {
+            kind,
+            location,
+            trace: Vec::new(),
+            stats: None,
+        }
+    }
+}
+
+/// Counters collected while running a program.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Function calls performed, including the ones served by the memo.
+    pub calls: u64,
+    /// Function calls served by the memo.
+    pub memo_hits: u64,
+    /// Terms evaluated by the tree walker, or instructions run by the
+    /// bytecode. The functions compiled by the JIT count none.
+    pub terms: u64,
+    /// The most calls in progress at once, the calls in tail position
+    /// replace the one they end.
+    pub max_depth: u64,
+    /// The `par_pair` calls that ran their halves on two threads, see
+    /// [`Interpreter::extensions`].
+    pub parallel_pairs: u64,
+    /// The memo hits served by the shared memo, see
+    /// [`Interpreter::shared_memo`].
+    pub shared_hits: u64,
+    /// The memo entries dropped to make room for newer ones, see
+    /// [`Interpreter::memo_capacity`].
+    pub memo_evictions: u64,
+    /// Bytes written by `print`, including the newlines.
+    pub printed_bytes: u64,
+    /// The deepest nesting of tuples printed, zero when no tuple was.
+    pub max_print_depth: u64,
+    /// The longest line written by a single `print`, with its newline.
+    pub max_print_bytes: u64,
+    /// How many times each `print` term ran.
+    pub prints_by_site: collections::HashMap<ast::Location, u64>,
+    /// The calls made at each call site, keyed by the location of the call.
+    pub call_sites: collections::HashMap<ast::Location, CallCounts>,
+}
+
+/// How the calls made at one site were answered, by running the body of
+/// the function or by the memo.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CallCounts {
+    /// The name of the function called, see [`FunctionNames`].
+    pub function: Symbol,
+    /// Calls that ran the body of the function.
+    pub executed: u64,
+    /// Calls answered by the memo, without running the body.
+    pub served: u64,
+}
+
+/// Soft limits on the printed values, past them the interpreter warns once
+/// and prints anyway, see [`Interpreter::warn_prints`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrintThresholds {
+    /// How deep the tuples can nest.
+    pub depth: Option<u64>,
+    /// How many bytes a single `print` can write, with the newline.
+    pub bytes: Option<u64>,
+}
+
+/// A `print` past one of the [`PrintThresholds`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrintWarning {
+    /// The value nests tuples `depth` deep. It's reported before the value
+    /// is written, which is what takes long.
+    Depth {
+        depth: u64,
+        threshold: u64,
+        location: ast::Location,
+    },
+    /// The `print` wrote `bytes`.
+    Bytes {
+        bytes: u64,
+        threshold: u64,
+        location: ast::Location,
+    },
+}
+
+/// Called with the first [`PrintWarning`] of each threshold.
+pub type PrintWarningHook = Box<dyn FnMut(&PrintWarning)>;
+
+/// A function that calls itself and prints. Its calls that print aren't
+/// memoized, so it may take exponential time where it wouldn't without
+/// the prints, see [`Interpreter::on_impure_recursion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpureRecursion {
+    /// The name of the function, see [`FunctionNames`].
+    pub function: Symbol,
+    /// The first `print` of its body.
+    pub print: ast::Location,
+}
+
+/// Called with each recursive function the prints kept out of the memo.
+pub type ImpureRecursionHook = Box<dyn FnMut(&ImpureRecursion)>;
+
+/// A `par_pair` that called its function on one thread after the other,
+/// since the function or its arguments may print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequentialPair {
+    /// The name of the function, see [`FunctionNames`].
+    pub function: Symbol,
+    /// The `par_pair` call.
+    pub call: ast::Location,
+}
+
+/// Called with every `par_pair` that ran sequentially.
+pub type SequentialPairHook = Box<dyn FnMut(&SequentialPair)>;
+
+/// How far a run got, see [`Interpreter::on_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The function calls performed so far.
+    pub calls: u64,
+    /// The time since the hook was installed.
+    pub elapsed: Duration,
+}
+
+/// Called with the [`Progress`] of the run at a regular interval.
+pub type ProgressHook = Box<dyn FnMut(&Progress)>;
+
+/// The counters of a run at a checkpoint, see [`Interpreter::on_checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The function calls performed so far.
+    pub calls: u64,
+    /// The calls that weren't made before with the same function and
+    /// arguments, whether the memo served the repeated ones or not.
+    pub distinct_calls: u64,
+    /// The calls served by the memo so far.
+    pub memo_hits: u64,
+    /// The bytes printed so far.
+    pub printed_bytes: u64,
+    /// The time since the hook was installed.
+    pub elapsed: Duration,
+}
+
+/// Called with the [`Checkpoint`] of the run every so many calls.
+pub type CheckpointHook = Box<dyn FnMut(&Checkpoint)>;
+
+/// Told about the terms as the interpreter gets to them, to stop and look
+/// at the run, see [`Interpreter::debugger`].
+pub trait Debugger {
+    /// Called before `term` is evaluated in `scope`, with the calls in
+    /// progress, the innermost last. Each `let` of a chain is a term of its
+    /// own. Breaking stops the run with [`ErrorKind::Interrupted`].
+    fn before(&mut self, term: &ast::Term, scope: &Scope, stack: &[Frame]) -> ControlFlow<()>;
+}
+
+/// When the next [`Checkpoint`] is due, and the calls already seen.
+struct Checkpoints {
+    every: u64,
+    next: u64,
+    started: Instant,
+    /// The hashes of the memo keys of the calls made.
+    seen: collections::HashSet<u64>,
+    hook: CheckpointHook,
+}
+
+/// When the next [`Progress`] is due.
+struct Cadence {
+    interval: Duration,
+    started: Instant,
+    next: Duration,
+    hook: ProgressHook,
+}
+
+impl Cadence {
+    fn tick(&mut self, calls: u64) {
+        let elapsed = self.started.elapsed();
+        if elapsed >= self.next {
+            self.next = elapsed + self.interval;
+            (self.hook)(&Progress { calls, elapsed });
+        }
+    }
+}
+
+/// The tree-walking interpreter, it memoizes the function calls unless
+/// told otherwise. The calls that print are never memoized, so they print
+/// every time they're made.
+pub struct Interpreter {
+    memo: Lru<MemoKey, Primitive>,
+    memoize: bool,
+    shared: Option<Arc<Snapshot>>,
+    /// The frames of the function bodies, see [`resolve`], by the address
+    /// of the body. The bodies are kept, so their addresses aren't reused.
+    /// They're shared with the threads of `par_pair`.
+    layouts: Arc<Layouts>,
+    lazy: bool,
+    /// The terms of the thunks, by their location, so each one is copied
+    /// out of its parent once.
+    thunk_terms: collections::HashMap<ast::Location, Arc<ast::Term>>,
+    /// Bumped by every print, and every read or write of a reference, the
+    /// calls during which it changed aren't memoized.
+    effects: u64,
+    /// The cells of the references made, to free their cycles.
+    cells: reference::Collector,
+    /// Bumped by every print, the calls whose only effects were prints are
+    /// hinted, see [`Interpreter::on_impure_recursion`].
+    prints: u64,
+    interrupt: Option<Arc<AtomicBool>>,
+    progress: Option<Cadence>,
+    fuel: Option<u64>,
+    max_depth: Option<u64>,
+    max_steps: Option<u64>,
+    max_stack: Option<usize>,
+    /// Where the native stack was at the outermost
+    /// [`Interpreter::interpret`], the calls are measured from there.
+    stack_base: Option<usize>,
+    /// The calls in progress and the steps taken by the interpreter that
+    /// started this one on a thread of `par_pair`, they count against the
+    /// limits too.
+    outer: (u64, u64),
+    checkpoints: Option<Checkpoints>,
+    assert_none: bool,
+    output: Box<dyn io::Write>,
+    tees: Vec<Box<dyn io::Write>>,
+    max_output_bytes: Option<u64>,
+    on_print: Option<PrintHook>,
+    print_thresholds: PrintThresholds,
+    on_print_warning: Option<PrintWarningHook>,
+    /// Whether the depth and the bytes thresholds were passed already.
+    warned: (bool, bool),
+    names: FunctionNames,
+    int_format: IntFormat,
+    overflow: Overflow,
+    extensions: bool,
+    on_sequential_pair: Option<SequentialPairHook>,
+    on_impure_recursion: Option<ImpureRecursionHook>,
+    /// The functions already looked at by `on_impure_recursion`.
+    hinted: collections::HashSet<Symbol>,
+    builtins: collections::HashMap<Symbol, Builtin>,
+    debugger: Option<Box<dyn Debugger>>,
+    /// The function bodies being evaluated, the innermost last.
+    frames: Vec<Frame>,
+    /// Panics at the print after this many, to test the panics of the
+    /// interpreter.
+    #[cfg(feature = "debug-invariants")]
+    panic_after_prints: Option<u64>,
+    stats: Stats,
+}
+
+/// The native stack of the threads that run the halves of `par_pair`. It's
+/// only reserved, the pages are used as the recursion gets there.
+const WORKER_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// How the threads of `par_pair` call the function, like the interpreter
+/// that started them. They print nothing, nothing they run can print.
+#[derive(Clone)]
+struct Worker {
+    memoize: bool,
+    memo_capacity: usize,
+    assert_none: bool,
+    overflow: Overflow,
+    limits: (Option<u64>, Option<u64>),
+    outer: (u64, u64),
+    interrupt: Option<Arc<AtomicBool>>,
+    names: FunctionNames,
+    layouts: Arc<Layouts>,
+}
+
+impl Worker {
+    fn apply(
+        self,
+        function: Primitive,
+        argument: Primitive,
+        location: &ast::Location,
+    ) -> Result<(Primitive, Stats, reference::Collector), RuntimeError> {
+        let mut interpreter = Interpreter::with_output(io::sink())
+            .memoize(self.memoize)
+            .memo_capacity(self.memo_capacity)
+            .assert_none(self.assert_none)
+            .overflow(self.overflow)
+            .function_names(self.names)
+            .extensions(true);
+        interpreter.layouts = self.layouts;
+        (interpreter.max_depth, interpreter.max_steps) = self.limits;
+        interpreter.outer = self.outer;
+        if let Some(interrupt) = self.interrupt {
+            interpreter = interpreter.interrupt_on(interrupt);
+        }
+        let value = interpreter.apply(function, argument, location)?;
+        Ok((value, interpreter.stats, interpreter.cells))
+    }
+}
+
+/// The frames of the function bodies, by the address of the body.
+type Layouts = collections::HashMap<usize, (Arc<ast::Term>, Layout)>;
+
+/// The value of the variable called `name`, where [`resolve`] says it is.
+fn lookup<'s>(name: &str, address: Address, scope: &'s Scope) -> Option<&'s Primitive> {
+    match address {
+        Address::Named => scope.get(name),
+        Address::Local(slot) => scope.local(slot),
+        Address::Captured(slot) => scope.captured(slot),
+    }
+}
+
+/// Whether calling `value`, or the functions inside of it, may print or
+/// reach a reference or one of the `builtins`, whose effects must keep
+/// their order too. The functions are followed through the variables their
+/// bodies use.
+fn may_print(value: &Primitive, builtins: &collections::HashMap<Symbol, Builtin>) -> bool {
+    let mut pending = vec![value];
+    let mut seen = collections::HashSet::new();
+    while let Some(value) = pending.pop() {
+        match value {
+            Primitive::Function { value, env, .. } => {
+                if !seen.insert(value.location()) {
+                    continue;
+                }
+                if path::first_print(value).is_some() {
+                    return true;
+                }
+                let used = path::unbound(value);
+                let mut unbound = used.iter().filter(|var| !env.contains_key(&var.text));
+                if unbound.any(|var| builtins.contains_key(&var.text)) {
+                    return true;
+                }
+                pending.extend(used.iter().filter_map(|var| env.get(&var.text)));
+            }
+            Primitive::Tuple([first, second]) => {
+                pending.push(first);
+                pending.push(second);
+            }
+            Primitive::List(list) => pending.extend(list.iter()),
+            Primitive::Record(record) => pending.extend(record.fields().map(|(_, value)| value)),
+            Primitive::Ref(_) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whether `value` is a list or a reference or holds one, in its tuples or
+/// its records. The memo keys hash and compare the lists whole, so the
+/// calls given one, or whose function captured one, aren't memoized: the
+/// loops over a list would hash what's left of it at every step. And what
+/// a reference holds changes between the calls.
+fn holds_list_or_ref(value: &Primitive) -> bool {
+    let mut pending = vec![value];
+    while let Some(value) = pending.pop() {
+        match value {
+            Primitive::List(_) | Primitive::Ref(_) => return true,
+            Primitive::Tuple([first, second]) => {
+                pending.push(first);
+                pending.push(second);
+            }
+            Primitive::Record(record) => pending.extend(record.fields().map(|(_, value)| value)),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// The memo key of calling the function `name` whose body is `body` with
+/// `arguments`. The functions are told apart by where their body is, and
+/// by the values they captured, so the closures made by the same
+/// definition don't share their entries.
+fn memo_key(name: &Symbol, body: &ast::Term, env: &Scope, arguments: &[Primitive]) -> MemoKey {
+    let captured = env
+        .locals()
+        .map(|(name, value)| (name.clone(), value.clone()));
+    MemoKey {
+        function: FunctionId {
+            name: name.clone(),
+            location: body.location().clone(),
+        },
+        captured: captured.collect(),
+        arguments: arguments.into(),
+    }
+}
+
+/// A function body being evaluated, see [`Interpreter::stack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The name of the function, see [`FunctionNames`].
+    pub function: Symbol,
+    /// Where it was called.
+    pub call: ast::Location,
+}
+
+/// Called with every printed value and the location of its `print`.
+pub type PrintHook = Box<dyn FnMut(&Primitive, &ast::Location)>;
+
+/// A function of the embedder, called with the values of the arguments,
+/// see [`Interpreter::register_builtin`].
+pub type Builtin = Box<dyn Fn(&[Primitive]) -> Result<Primitive, ErrorKind>>;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// Creates an interpreter that prints to the standard output.
+    pub fn new() -> Interpreter {
+        Interpreter::with_output(io::stdout())
+    }
+    /// Creates an interpreter that prints into `output`.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{Interpreter, Scope}, parser};
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "print(1 + 1)").unwrap();
+    /// let mut interpreter = Interpreter::with_output(std::io::sink());
+    /// let value = interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(value.to_string(), "2");
+    /// assert_eq!(interpreter.stats().printed_bytes, 2);
+    /// ```
+    pub fn with_output(output: impl io::Write + 'static) -> Interpreter {
+        Interpreter {
+            memo: Lru::new(memo::DEFAULT_CAPACITY),
+            memoize: true,
+            shared: None,
+            layouts: Arc::default(),
+            lazy: false,
+            thunk_terms: collections::HashMap::new(),
+            effects: 0,
+            cells: reference::Collector::default(),
+            prints: 0,
+            interrupt: None,
+            progress: None,
+            fuel: None,
+            max_depth: None,
+            max_steps: None,
+            max_stack: None,
+            stack_base: None,
+            outer: (0, 0),
+            checkpoints: None,
+            assert_none: cfg!(debug_assertions),
+            output: Box::new(output),
+            tees: Vec::new(),
+            max_output_bytes: None,
+            on_print: None,
+            print_thresholds: PrintThresholds::default(),
+            on_print_warning: None,
+            warned: (false, false),
+            names: FunctionNames::default(),
+            int_format: IntFormat::default(),
+            overflow: Overflow::default(),
+            extensions: false,
+            on_sequential_pair: None,
+            on_impure_recursion: None,
+            hinted: collections::HashSet::new(),
+            builtins: collections::HashMap::new(),
+            debugger: None,
+            frames: Vec::new(),
+            #[cfg(feature = "debug-invariants")]
+            panic_after_prints: None,
+            stats: Stats::default(),
+        }
+    }
+    /// Turns the memoization of function calls on or off.
+    pub fn memoize(mut self, memoize: bool) -> Interpreter {
+        self.memoize = memoize;
+        self
+    }
+    /// Keeps at most `entries` memo entries, dropping the ones used the
+    /// longest ago to make room, see [`Lru`]. It's
+    /// [`memo::DEFAULT_CAPACITY`] unless told otherwise.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{Interpreter, Scope}, parser};
+    ///
+    /// let source = "let sq = fn (n) => { n * n }; (sq(1), (sq(2), (sq(1), sq(2))))";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let mut interpreter = Interpreter::with_output(std::io::sink()).memo_capacity(1);
+    /// interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(interpreter.stats().memo_hits, 0);
+    /// assert_eq!(interpreter.stats().memo_evictions, 3);
+    /// ```
+    pub fn memo_capacity(mut self, entries: usize) -> Interpreter {
+        self.memo = Lru::new(entries);
+        self
+    }
+    /// Evaluates the values of the `let` and the arguments of the calls
+    /// the first time their variable is used, instead of before binding
+    /// them, and once. The ones never used are never evaluated, so their
+    /// prints don't happen. The calls aren't memoized, their memo keys
+    /// would need the values of the arguments.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{Interpreter, Scope}, parser};
+    ///
+    /// let source = "let never = fn (n) => { never(n) }; let f = fn (a, b) => { a }; f(1, never(0))";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let mut interpreter = Interpreter::new().lazy(true);
+    /// let value = interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(value.to_string(), "1");
+    /// ```
+    pub fn lazy(mut self, lazy: bool) -> Interpreter {
+        self.lazy = lazy;
+        self
+    }
+    /// Also looks the calls up in `snapshot`, the memo entries shared by
+    /// earlier runs of the same program, see [`crate::memo::SharedMemo`].
+    pub fn shared_memo(mut self, snapshot: Arc<Snapshot>) -> Interpreter {
+        self.shared = Some(snapshot);
+        self
+    }
+    /// The memo entries, the ones that can be shared with other runs. The
+    /// calls that printed have none.
+    pub fn pure_memo(&self) -> impl Iterator<Item = (&MemoKey, &Primitive)> {
+        self.memo.iter()
+    }
+    /// Turns unit values into errors pointing at the term that produced
+    /// them. It's on by default in debug builds.
+    pub fn assert_none(mut self, assert_none: bool) -> Interpreter {
+        self.assert_none = assert_none;
+        self
+    }
+    /// Also writes everything printed into `sink`.
+    pub fn tee(mut self, sink: impl io::Write + 'static) -> Interpreter {
+        self.tees.push(Box::new(sink));
+        self
+    }
+    /// Stops the program with [`ErrorKind::OutputLimit`] once it printed
+    /// `limit` bytes and tries to print more. The output and the tees get
+    /// the same bytes, up to the limit exactly.
+    pub fn max_output_bytes(mut self, limit: u64) -> Interpreter {
+        self.max_output_bytes = Some(limit);
+        self
+    }
+    /// Calls `hook` with every printed value, before it's written.
+    pub fn on_print(
+        mut self,
+        hook: impl FnMut(&Primitive, &ast::Location) + 'static,
+    ) -> Interpreter {
+        self.on_print = Some(Box::new(hook));
+        self
+    }
+    /// Calls `hook` the first time a printed value passes each of the
+    /// `thresholds`.
+    pub fn warn_prints(
+        mut self,
+        thresholds: PrintThresholds,
+        hook: impl FnMut(&PrintWarning) + 'static,
+    ) -> Interpreter {
+        self.print_thresholds = thresholds;
+        self.on_print_warning = Some(Box::new(hook));
+        self
+    }
+    /// Uses `names` for the functions, instead of naming them when the
+    /// program starts. It's how the anonymous functions get their line and
+    /// column.
+    pub fn function_names(mut self, names: FunctionNames) -> Interpreter {
+        self.names = names;
+        self
+    }
+    /// Stops at the next function call once `flag` is raised, failing with
+    /// [`ErrorKind::Interrupted`]. Every loop of a program is a recursion,
+    /// so it's checked often enough.
+    pub fn interrupt_on(mut self, flag: Arc<AtomicBool>) -> Interpreter {
+        self.interrupt = Some(flag);
+        self
+    }
+    /// Calls `hook` with the progress of the run every `interval`, or at
+    /// the first function call after it. The first call reports right away.
+    pub fn on_progress(
+        mut self,
+        interval: Duration,
+        hook: impl FnMut(&Progress) + 'static,
+    ) -> Interpreter {
+        self.progress = Some(Cadence {
+            interval,
+            started: Instant::now(),
+            next: Duration::ZERO,
+            hook: Box::new(hook),
+        });
+        self
+    }
+    /// Stops the run with [`ErrorKind::OutOfFuel`] at the call after the
+    /// first `calls`. The calls served by the memo count too.
+    pub fn fuel(mut self, calls: u64) -> Interpreter {
+        self.fuel = Some(calls);
+        self
+    }
+    /// Stops the run with [`ErrorKind::DepthLimit`] at the call that would
+    /// make more than `calls` calls in progress at once, see
+    /// [`Stats::max_depth`]. The calls in tail position replace the one
+    /// they end, so the loops never get there.
+    pub fn max_depth(mut self, calls: u64) -> Interpreter {
+        self.max_depth = Some(calls);
+        self
+    }
+    /// Stops the run with [`ErrorKind::DepthLimit`] at the call that would
+    /// take more than `bytes` of the native stack, counted from where
+    /// [`Interpreter::interpret`] started. The calls that aren't in tail
+    /// position grow it, so a thread that knows its stack fails with the
+    /// error instead of overflowing it.
+    pub fn max_stack(mut self, bytes: usize) -> Interpreter {
+        self.max_stack = Some(bytes);
+        self
+    }
+    /// Stops the run with [`ErrorKind::StepLimit`] at the term evaluated
+    /// after the first `steps`, see [`Stats::terms`].
+    ///
+    /// ```
+    /// use rinha::{interpreter::{ErrorKind, Interpreter, Scope}, parser};
+    ///
+    /// let source = "let loop = fn (n) => { loop(n + 1) }; loop(0)";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let mut interpreter = Interpreter::with_output(std::io::sink()).max_steps(1000);
+    /// let error = interpreter.interpret(file.expression, &mut Scope::new()).unwrap_err();
+    ///
+    /// assert_eq!(error.kind, ErrorKind::StepLimit(1000));
+    /// ```
+    pub fn max_steps(mut self, steps: u64) -> Interpreter {
+        self.max_steps = Some(steps);
+        self
+    }
+    /// Calls `hook` with the counters of the run after every `every`
+    /// calls. Telling the repeated calls apart costs a memo key per call,
+    /// even without the memo.
+    pub fn on_checkpoint(
+        mut self,
+        every: u64,
+        hook: impl FnMut(&Checkpoint) + 'static,
+    ) -> Interpreter {
+        self.checkpoints = Some(Checkpoints {
+            every: every.max(1),
+            next: every.max(1),
+            started: Instant::now(),
+            seen: collections::HashSet::new(),
+            hook: Box::new(hook),
+        });
+        self
+    }
+    /// How `print` renders the integers.
+    pub fn int_format(mut self, int_format: IntFormat) -> Interpreter {
+        self.int_format = int_format;
+        self
+    }
+    /// What the arithmetic does when the results don't fit.
+    pub fn overflow(mut self, overflow: Overflow) -> Interpreter {
+        self.overflow = overflow;
+        self
+    }
+    /// Provides the natives that aren't part of the specification. They're
+    /// only used when the program doesn't bind their names itself:
+    ///
+    /// - `par_pair(f, a, b)` is the tuple of `f(a)` and `f(b)`. The calls
+    ///   run on two threads when nothing they can reach prints, and one
+    ///   after the other otherwise, so the prints keep their order.
+    /// - `list`, `cons`, `head`, `tail` and `len` build and take apart the
+    ///   lists, see [`list::call`]. They print like `[1, 2, 3]`.
+    /// - `ref`, `deref` and `assign` make, read and write the references,
+    ///   see [`reference::call`]. They print like `<#ref>`.
+    /// - `str_len`, `substr`, `char_at`, `int_of_string` and
+    ///   `string_of_int` read the strings and turn them into integers and
+    ///   back, see [`string::call`].
+    ///
+    /// And the floats, like `1.5`, are the 64 bits floats of IEEE 754. The
+    /// integers they meet are turned into floats, and they're printed so
+    /// they read back the same, like `2.0` for the whole ones. The records,
+    /// like `{ x: 1, y: 2 }`, have their fields read like `point.x`, see
+    /// [`Record`], and print like `{x: 1, y: 2}`. Without the extensions
+    /// they're an error.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{Interpreter, Scope}, parser};
+    ///
+    /// let source = "let double = fn (n) => { n * 2 }; par_pair(double, 1, 2)";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let mut interpreter = Interpreter::with_output(std::io::sink()).extensions(true);
+    /// let value = interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(value.to_string(), "(2, 4)");
+    /// assert_eq!(interpreter.stats().parallel_pairs, 1);
+    /// ```
+    pub fn extensions(mut self, extensions: bool) -> Interpreter {
+        self.extensions = extensions;
+        self
+    }
+    /// Provides `function` to the programs under `name`, like the natives
+    /// of the extensions: the calls of `name` the program doesn't bind
+    /// itself call `function` with the values of their arguments, and its
+    /// errors point at the call. It can't be used as a value, only called.
+    /// It may read or write anything, so the calls during which it runs
+    /// aren't memoized, and `par_pair` calls the functions reaching it one
+    /// after the other.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{ErrorKind, Interpreter, Primitive, Scope}, parser};
+    ///
+    /// let source = "let hypot = fn (a, b) => { isqrt(a * a + b * b) }; hypot(3, 4)";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let mut interpreter = Interpreter::with_output(std::io::sink()).register_builtin(
+    ///     "isqrt",
+    ///     |arguments| match arguments {
+    ///         [Primitive::Int(n)] if *n >= 0 => Ok(Primitive::Int(n.isqrt())),
+    ///         _ => Err(ErrorKind::BuiltinFailed {
+    ///             name: "isqrt".into(),
+    ///             message: "expects one integer, not negative".into(),
+    ///         }),
+    ///     },
+    /// );
+    /// let value = interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(value.to_string(), "5");
+    /// ```
+    pub fn register_builtin(
+        mut self,
+        name: &str,
+        function: impl Fn(&[Primitive]) -> Result<Primitive, ErrorKind> + 'static,
+    ) -> Interpreter {
+        self.builtins.insert(Symbol::from(name), Box::new(function));
+        self
+    }
+    /// Calls `hook` with every `par_pair` that couldn't run on two threads.
+    pub fn on_sequential_pair(
+        mut self,
+        hook: impl FnMut(&SequentialPair) + 'static,
+    ) -> Interpreter {
+        self.on_sequential_pair = Some(Box::new(hook));
+        self
+    }
+    /// Calls `hook` once for each function that calls itself, the first
+    /// time one of its calls isn't memoized only because it printed.
+    pub fn on_impure_recursion(
+        mut self,
+        hook: impl FnMut(&ImpureRecursion) + 'static,
+    ) -> Interpreter {
+        self.on_impure_recursion = Some(Box::new(hook));
+        self
+    }
+    /// Tells `debugger` about every term before evaluating it. The calls
+    /// served by the memo don't evaluate their bodies, turn it off to see
+    /// every call.
+    ///
+    /// ```
+    /// use rinha::{ast::{Element, Term}, interpreter::{Debugger, Frame, Interpreter, Scope}, parser};
+    /// use std::{cell::RefCell, ops::ControlFlow, rc::Rc};
+    ///
+    /// struct Lets(Rc<RefCell<Vec<String>>>);
+    ///
+    /// impl Debugger for Lets {
+    ///     fn before(&mut self, term: &Term, _: &Scope, _: &[Frame]) -> ControlFlow<()> {
+    ///         if let Term::Let(let_) = term {
+    ///             self.0.borrow_mut().push(let_.name.text.to_string());
+    ///         }
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// }
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "let x = 1; let y = x + 1; y").unwrap();
+    /// let lets = Rc::new(RefCell::new(Vec::new()));
+    /// let mut interpreter = Interpreter::new().debugger(Lets(lets.clone()));
+    /// interpreter.interpret(file.expression, &mut Scope::new()).unwrap();
+    ///
+    /// assert_eq!(*lets.borrow(), ["x", "y"]);
+    /// ```
+    pub fn debugger(mut self, debugger: impl Debugger + 'static) -> Interpreter {
+        self.debugger = Some(Box::new(debugger));
+        self
+    }
+    /// The counters collected so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// The function bodies being evaluated, the innermost last. It's empty
+    /// once the program finishes, and tells where it was when it didn't.
+    pub fn stack(&self) -> &[Frame] {
+        &self.frames
+    }
+    /// Evaluates the program in a scope of its own, returning the value it
+    /// produces.
+    ///
+    /// ```
+    /// use rinha::{interpreter::{Interpreter, Value}, parser};
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "let x = 20; x * 2 + 2").unwrap();
+    /// let value = Interpreter::with_output(std::io::sink()).eval(file).unwrap();
+    ///
+    /// assert_eq!(value, Value::Int(42));
+    /// ```
+    pub fn eval(&mut self, file: ast::File) -> Result<Value, RuntimeError> {
+        self.interpret(file.expression, &mut Scope::new())
+    }
+    /// Evaluates the term, returning the value it produces. It can be
+    /// called again with other terms and the same scope, to evaluate them
+    /// where the bindings of the earlier ones are known. The terms must come
+    /// from sources of different filenames, their functions are told apart
+    /// by where they're defined.
+    pub fn interpret(
+        &mut self,
+        ast: ast::Term,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        self.names.extend(FunctionNames::new(&ast, None));
+        let mut ast = ast;
+        let resolution = resolve::resolve(&mut ast, &self.names);
+        let layouts = resolution.functions.into_iter();
+        Arc::make_mut(&mut self.layouts)
+            .extend(layouts.map(|(body, layout)| (Arc::as_ptr(&body) as usize, (body, layout))));
+
+        let depth = self.frames.len();
+        if depth == 0 {
+            self.stack_base = Some(std::ptr::addr_of!(depth) as usize);
+        }
+        // The bindings of the term stay in the scope, for the next terms.
+        let mut top = scope.with_slots(resolution.top.slots);
+        let result = self.visit(&ast, &mut top);
+        *scope = top;
+        drop_let_chain(ast);
+        self.cells.collect();
+        self.invariant(
+            |this| this.frames.len() == depth,
+            || {
+                format!(
+                    "the call depth is {}, but it was {depth} before",
+                    self.frames.len()
+                )
+            },
+        );
+        self.check_invariants();
+        result
+    }
+    /// Pretends a frame was popped twice, to test the invariant checks.
+    #[cfg(feature = "debug-invariants")]
+    #[doc(hidden)]
+    pub fn pop_frame_for_tests(&mut self) {
+        self.leave_frame();
+    }
+    /// Adds a memo entry that belongs to no function, to test the invariant
+    /// checks.
+    #[cfg(feature = "debug-invariants")]
+    #[doc(hidden)]
+    pub fn corrupt_memo_for_tests(&mut self, name: &str) {
+        let key = MemoKey {
+            function: FunctionId {
+                name: name.into(),
+                location: ast::Location::default(),
+            },
+            captured: Box::new([]),
+            arguments: Box::new([]),
+        };
+        self.memo.insert(key, Primitive::None);
+    }
+    /// Panics at the print after `prints` of them, to test how the panics
+    /// of the interpreter are reported.
+    #[cfg(feature = "debug-invariants")]
+    #[doc(hidden)]
+    pub fn panic_after_prints_for_tests(mut self, prints: u64) -> Interpreter {
+        self.panic_after_prints = Some(prints);
+        self
+    }
+    fn leave_frame(&mut self) {
+        self.invariant(
+            |this| !this.frames.is_empty(),
+            || "a frame was popped twice".into(),
+        );
+        self.frames.pop();
+    }
+    /// Evaluates `term` in a loop, the terms in tail position replace it
+    /// instead of being evaluated by a recursive call. The calls in tail
+    /// position replace the frame of the body they end, so loops written as
+    /// tail calls run in constant native stack. Their memo entries are
+    /// stored once the last of them returns.
+    fn visit(&mut self, term: &ast::Term, scope: &mut Scope) -> Result<Primitive, RuntimeError> {
+        let mut body: Option<(Arc<ast::Term>, Scope)> = None;
+        // Only the calls to memoize are kept, a loop without the memo runs
+        // in constant space.
+        let mut returns: Vec<Return> = Vec::new();
+        let result = 'body: loop {
+            let (mut term, scope) = match &mut body {
+                Some((body, body_scope)) => (&**body, body_scope),
+                None => (term, &mut *scope),
+            };
+            let call = loop {
+                match self.step(term, scope) {
+                    Ok(Step::Value(value)) => break 'body Ok(value),
+                    Ok(Step::Tail(next)) => term = next,
+                    Ok(Step::Call(call)) => break call,
+                    Err(error) => break 'body Err(error),
+                }
+            };
+            if body.is_some() {
+                self.leave_frame();
+            }
+            self.frames.push(call.frame);
+            self.stats.max_depth = self.stats.max_depth.max(self.frames.len() as u64);
+            if self.memoizes() {
+                returns.push(call.ret);
+            }
+            body = Some((call.body, call.scope));
+            let depth = self.outer.0 + self.frames.len() as u64;
+            let max = match (self.max_stack, self.stack_base) {
+                (Some(bytes), Some(base))
+                    if base.abs_diff(std::ptr::addr_of!(depth) as usize) > bytes =>
+                {
+                    Some(depth - 1)
+                }
+                _ => self.max_depth.filter(|max| depth > *max),
+            };
+            if let Some(max) = max {
+                let frame = self.frames.last().expect("the call was just entered");
+                let error = RuntimeError::new(ErrorKind::DepthLimit(max), frame.call.clone());
+                break 'body Err(error);
+            }
+        };
+
+        if body.is_none() {
+            return result;
+        }
+        // The error leaves the body, through the call that's left of it.
+        let result = result.map_err(|mut error| {
+            error.trace.extend(self.frames.last().cloned());
+            error
+        });
+        self.leave_frame();
+        let value = result?;
+        for ret in returns.into_iter().rev() {
+            // The calls that printed run again, so they print again.
+            let Some(key) = ret.key else {
+                continue;
+            };
+            if self.effects == ret.effects {
+                self.invariant(
+                    |this| this.is_live(&key),
+                    || format!("memo entry `{key}` belongs to no function"),
+                );
+                if self.memo.insert(key, value.clone()).is_some() {
+                    self.stats.memo_evictions += 1;
+                }
+            } else if self.effects - ret.effects == self.prints - ret.prints {
+                self.hint_impure_recursion(&key.function.name, ret.body);
+            }
+        }
+        Ok(value)
+    }
+    fn step<'t>(
+        &mut self,
+        term: &'t ast::Term,
+        scope: &mut Scope,
+    ) -> Result<Step<'t>, RuntimeError> {
+        self.stats.terms += 1;
+        if let Some(max) = self
+            .max_steps
+            .filter(|max| self.outer.1 + self.stats.terms > *max)
+        {
+            return Err(RuntimeError::new(
+                ErrorKind::StepLimit(max),
+                term.location().clone(),
+            ));
+        }
+        self.debug(term, scope)?;
+        let value = match term {
+            ast::Term::Let(v) => return self.visit_let(v, scope),
+            ast::Term::Call(v) => return self.visit_call(v, scope),
+            ast::Term::If(v) => return self.visit_conditional(v, scope),
+            ast::Term::Match(v) => return self.visit_match(v, scope),
+            ast::Term::Int(v) => self.visit_int(v, scope),
+            ast::Term::Float(v) => self.visit_float(v, scope),
+            ast::Term::Str(v) => self.visit_str(v, scope),
+            ast::Term::Bool(v) => self.visit_bool(v, scope),
+            ast::Term::Binary(v) => self.visit_bin_op(v, scope),
+            ast::Term::Var(v) => self.visit_var(v, scope),
+            ast::Term::Print(v) => self.visit_print(v, scope),
+            ast::Term::Function(v) => self.visit_function(v, scope),
+            ast::Term::Tuple(v) => self.visit_tuple(v, scope),
+            ast::Term::First(v) => self.visit_first(v, scope),
+            ast::Term::Second(v) => self.visit_second(v, scope),
+            ast::Term::Record(v) => self.visit_record(v, scope),
+            ast::Term::Access(v) => self.visit_access(v, scope),
+            ast::Term::Error(v) => self.unit("a syntax error", &v.location),
+        };
+        value.map(Step::Value)
+    }
+    /// Whether the calls are memoized, they can't be with
+    /// [`Interpreter::lazy`].
+    fn memoizes(&self) -> bool {
+        self.memoize && !self.lazy
+    }
+    /// Tells the debugger about the term, stopping the run when it says so.
+    fn debug(&mut self, term: &ast::Term, scope: &Scope) -> Result<(), RuntimeError> {
+        let Some(debugger) = &mut self.debugger else {
+            return Ok(());
+        };
+        match debugger.before(term, scope, &self.frames) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(RuntimeError::new(
+                ErrorKind::Interrupted,
+                term.location().clone(),
+            )),
+        }
+    }
+    /// Produces the unit value, unless asserting that it never shows up.
+    fn unit(
+        &self,
+        origin: &'static str,
+        location: &ast::Location,
+    ) -> Result<Primitive, RuntimeError> {
+        if self.assert_none {
+            return Err(RuntimeError::new(
+                ErrorKind::UnexpectedUnit(origin),
+                location.clone(),
+            ));
+        }
+        Ok(Primitive::None)
+    }
+    /// `&&` and `||` leave their right operand alone when the left one
+    /// decides the result.
+    fn visit_bin_op(
+        &mut self,
+        binary: &ast::Binary,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let left = self.visit(&binary.lhs, scope)?;
+        if binary.op.short_circuits(&left) {
+            return Ok(left);
+        }
+        let right = self.visit(&binary.rhs, scope)?;
+        binary
+            .op
+            .apply(left, right, self.overflow)
+            .map_err(|kind| RuntimeError::new(kind, binary.location.clone()))
+    }
+    /// Walks the chains of `let` in a loop instead of recursing into each
+    /// `next`, generated programs can have hundreds of thousands of them.
+    fn visit_let<'t>(
+        &mut self,
+        mut let_param: &'t ast::Let,
+        scope: &mut Scope,
+    ) -> Result<Step<'t>, RuntimeError> {
+        loop {
+            let raw_var_value = match self.lazy {
+                true => self.defer(&let_param.value, scope)?,
+                false => self.visit(&let_param.value, scope)?,
+            };
+            self.bind(&let_param.name, raw_var_value, scope);
+            match &*let_param.next {
+                ast::Term::Let(next) => {
+                    self.debug(&let_param.next, scope)?;
+                    let_param = next;
+                }
+                next => return Ok(Step::Tail(next)),
+            }
+        }
+    }
+    fn bind(&mut self, name: &parser::Var, mut raw_var_value: Primitive, scope: &mut Scope) {
+        // The function also sees the variables of where it's bound, like
+        // the ones of the scope a call returned it into.
+        if let Primitive::Function { env, .. } = &mut raw_var_value {
+            env.fill(scope);
+        }
+        match name.address {
+            Address::Local(slot) => scope.set(slot, raw_var_value),
+            _ => scope.insert(name.text.clone(), raw_var_value),
+        }
+    }
+    /// The value to bind for `term` with [`Interpreter::lazy`]. The literals
+    /// and the functions cost nothing to evaluate, and a variable shares
+    /// the value, or the thunk, it's bound to.
+    fn defer(&mut self, term: &ast::Term, scope: &mut Scope) -> Result<Primitive, RuntimeError> {
+        match term {
+            ast::Term::Var(var) => {
+                if let Some(value) = lookup(&var.text, var.address, scope) {
+                    return Ok(value.clone());
+                }
+            }
+            ast::Term::Int(_)
+            | ast::Term::Float(_)
+            | ast::Term::Str(_)
+            | ast::Term::Bool(_)
+            | ast::Term::Function(_) => return self.visit(term, scope),
+            _ => {}
+        }
+        let term = self
+            .thunk_terms
+            .entry(term.location().clone())
+            .or_insert_with(|| Arc::new(term.clone()))
+            .clone();
+        Ok(Primitive::Thunk(Thunk::new(term, scope.clone())))
+    }
+    /// Computes the value of the thunk, unless it was computed already.
+    fn force(&mut self, thunk: &Thunk) -> Result<Primitive, RuntimeError> {
+        let (term, mut env) = match &*thunk.lock() {
+            Delayed::Done(value) => return Ok(value.clone()),
+            Delayed::Pending { term, env } => (term.clone(), env.clone()),
+        };
+        let value = self.visit(&term, &mut env)?;
+        *thunk.lock() = Delayed::Done(value.clone());
+        Ok(value)
+    }
+    fn visit_var(&mut self, var: &parser::Var, scope: &Scope) -> Result<Primitive, RuntimeError> {
+        match lookup(&var.text, var.address, scope) {
+            Some(Primitive::Thunk(thunk)) => {
+                let thunk = thunk.clone();
+                self.force(&thunk)
+            }
+            Some(var_stored) => Ok(var_stored.clone()),
+            None => Err(RuntimeError::new(
+                ErrorKind::UndefinedVariable(var.text.to_string()),
+                var.location.clone(),
+            )),
+        }
+    }
+    fn visit_function(
+        &mut self,
+        func: &ast::Function,
+        scope: &Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let parameters: Arc<[Symbol]> = func
+            .parameters
+            .iter()
+            .map(|param| param.text.clone())
+            .collect();
+
+        // Only the variables the body uses are kept, so the closure doesn't
+        // keep the rest of the scope alive, like the values a loop is done
+        // with.
+        let (name, env) = match self.layouts.get(&(Arc::as_ptr(&func.value) as usize)) {
+            Some((_, layout)) => {
+                let names = layout.captures.iter().map(|(name, _)| name.clone());
+                let mut env = Scope::new().with_slots(Arc::new(names.collect()));
+                for (slot, (name, from)) in layout.captures.iter().enumerate() {
+                    if let Some(value) = lookup(name, *from, scope) {
+                        env.set(slot, value.clone());
+                    }
+                }
+                (layout.slots[0].clone(), env)
+            }
+            // Not resolved, the body looks its variables up by name.
+            None => {
+                let name = match self.names.get(&func.location) {
+                    Some(name) => Symbol::intern(name),
+                    None => {
+                        format!("anon@{}:{}", func.location.filename, func.location.start).into()
+                    }
+                };
+                let env = path::unbound(&func.value)
+                    .into_iter()
+                    .filter(|var| var.text != name && !parameters.contains(&var.text))
+                    .filter_map(|var| Some((var.text.clone(), scope.get(&var.text)?.clone())))
+                    .collect();
+                (name, env)
+            }
+        };
+        Ok(Primitive::Function {
+            name,
+            value: func.value.clone(),
+            env,
+            parameters,
+        })
+    }
+    fn visit_call(
+        &mut self,
+        call: &ast::Call,
+        scope: &mut Scope,
+    ) -> Result<Step<'static>, RuntimeError> {
+        if let ast::Term::Var(callee) = &*call.callee {
+            if self.builtins.contains_key(&callee.text) && !scope.contains_key(&callee.text) {
+                return self
+                    .visit_builtin(&callee.text, call, scope)
+                    .map(Step::Value);
+            }
+            if self.extensions && callee.text == "par_pair" && !scope.contains_key("par_pair") {
+                return self.visit_par_pair(call, scope).map(Step::Value);
+            }
+            let natives = list::NATIVES
+                .iter()
+                .chain(&reference::NATIVES)
+                .chain(&string::NATIVES);
+            let native = natives.copied().find(|native| callee.text == *native);
+            if let Some(native) = native.filter(|_| self.extensions) {
+                if !scope.contains_key(native) {
+                    return self.visit_native(native, call, scope).map(Step::Value);
+                }
+            }
+        }
+        let callee_location = call.callee.location().clone();
+        let function = self.visit(&call.callee, scope)?;
+        if let Primitive::Function {
+            name,
+            parameters,
+            value,
+            env,
+        } = function
+        {
+            if call.arguments.len() != parameters.len() {
+                return Err(RuntimeError::new(
+                    ErrorKind::ArityMismatch {
+                        name: name.to_string(),
+                        expected: parameters.len(),
+                        given: call.arguments.len(),
+                    },
+                    call.location.clone(),
+                ));
+            }
+
+            let mut arguments = Vec::with_capacity(parameters.len());
+            for argument in &call.arguments {
+                arguments.push(match self.lazy {
+                    true => self.defer(argument, scope)?,
+                    false => self.visit(argument, scope)?,
+                });
+            }
+            let memoized = self.memoizes()
+                && !arguments.iter().any(holds_list_or_ref)
+                && !env.locals().any(|(_, value)| holds_list_or_ref(value));
+            let func_call_key = match memoized || self.checkpoints.is_some() {
+                true => Some(memo_key(&name, &value, &env, &arguments)),
+                false => None,
+            };
+
+            // The function itself is in the first slot, and its parameters
+            // in the next ones, see `resolve`.
+            let slots = match self.layouts.get(&(Arc::as_ptr(&value) as usize)) {
+                Some((_, layout)) => layout.slots.clone(),
+                None => {
+                    let names = std::iter::once(&name).chain(parameters.iter()).cloned();
+                    Arc::new(names.collect())
+                }
+            };
+            let mut local_scope = env.with_slots(slots);
+            local_scope.set(
+                0,
+                Primitive::Function {
+                    value: value.clone(),
+                    env,
+                    name: name.clone(),
+                    parameters: parameters.clone(),
+                },
+            );
+            for (slot, argument) in arguments.into_iter().enumerate() {
+                local_scope.set(slot + 1, argument);
+            }
+            if let Some(interrupt) = &self.interrupt {
+                if interrupt.load(Ordering::Relaxed) {
+                    return Err(RuntimeError::new(
+                        ErrorKind::Interrupted,
+                        call.location.clone(),
+                    ));
+                }
+            }
+
+            self.stats.calls += 1;
+            if self.fuel.is_some_and(|fuel| self.stats.calls > fuel) {
+                let fuel = self.fuel.unwrap_or_default();
+                return Err(RuntimeError::new(
+                    ErrorKind::OutOfFuel(fuel),
+                    call.location.clone(),
+                ));
+            }
+            if let Some(progress) = &mut self.progress {
+                progress.tick(self.stats.calls);
+            }
+            self.checkpoint(func_call_key.as_ref());
+            let site = self
+                .stats
+                .call_sites
+                .entry(call.location.clone())
+                .or_insert_with(|| CallCounts {
+                    function: name.clone(),
+                    ..CallCounts::default()
+                });
+            if let Some(key) = func_call_key.as_ref().filter(|_| memoized) {
+                if let Some(memoization) = self.memo.get(key).cloned() {
+                    site.served += 1;
+                    self.stats.memo_hits += 1;
+                    return Ok(Step::Value(memoization));
+                }
+                let shared = self.shared.as_ref();
+                if let Some(memoization) = shared.and_then(|shared| shared.get(key)) {
+                    site.served += 1;
+                    self.stats.memo_hits += 1;
+                    self.stats.shared_hits += 1;
+                    return Ok(Step::Value(memoization.clone()));
+                }
+            }
+            site.executed += 1;
+
+            let hinted = self.on_impure_recursion.as_ref().map(|_| value.clone());
+            return Ok(Step::Call(Invocation {
+                body: value,
+                scope: local_scope,
+                frame: Frame {
+                    function: name.clone(),
+                    call: call.location.clone(),
+                },
+                ret: Return {
+                    key: func_call_key.filter(|_| memoized),
+                    effects: self.effects,
+                    prints: self.prints,
+                    body: hinted,
+                },
+            }));
+        }
+        Err(RuntimeError::new(ErrorKind::NotAFunction, callee_location))
+    }
+    /// Counts the call of `key`, and reports a [`Checkpoint`] when one is
+    /// due.
+    fn checkpoint(&mut self, key: Option<&MemoKey>) {
+        let (Some(checkpoints), Some(key)) = (&mut self.checkpoints, key) else {
+            return;
+        };
+        checkpoints.seen.insert(key.digest());
+        if self.stats.calls >= checkpoints.next {
+            checkpoints.next += checkpoints.every;
+            (checkpoints.hook)(&Checkpoint {
+                calls: self.stats.calls,
+                distinct_calls: checkpoints.seen.len() as u64,
+                memo_hits: self.stats.memo_hits,
+                printed_bytes: self.stats.printed_bytes,
+                elapsed: checkpoints.started.elapsed(),
+            });
+        }
+    }
+    /// The natives of the lists, of the references and of the strings, see
+    /// [`list::call`], [`reference::call`] and [`string::call`]. Reading or
+    /// writing a reference is an effect like printing, the calls during
+    /// which it happens aren't memoized.
+    fn visit_native(
+        &mut self,
+        native: &'static str,
+        call: &ast::Call,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let mut arguments = Vec::with_capacity(call.arguments.len());
+        for argument in &call.arguments {
+            arguments.push(self.visit(argument, scope)?);
+        }
+        let result = match reference::NATIVES.contains(&native) {
+            true => {
+                self.effects += 1;
+                let result = reference::call(native, arguments);
+                if let Ok(Primitive::Ref(cell)) = &result {
+                    self.cells.track(cell);
+                    if self.cells.due() {
+                        self.cells.collect();
+                    }
+                }
+                result
+            }
+            false if string::NATIVES.contains(&native) => {
+                string::call(native, arguments, self.overflow)
+            }
+            false => list::call(native, arguments),
+        };
+        result.map_err(|kind| RuntimeError::new(kind, call.location.clone()))
+    }
+    /// A function of the embedder, see [`Interpreter::register_builtin`].
+    fn visit_builtin(
+        &mut self,
+        name: &Symbol,
+        call: &ast::Call,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let mut arguments = Vec::with_capacity(call.arguments.len());
+        for argument in &call.arguments {
+            arguments.push(self.visit(argument, scope)?);
+        }
+        self.effects += 1;
+        (self.builtins[name])(&arguments)
+            .map_err(|kind| RuntimeError::new(kind, call.location.clone()))
+    }
+    /// `par_pair(f, a, b)`, see [`Interpreter::extensions`].
+    fn visit_par_pair(
+        &mut self,
+        call: &ast::Call,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let [callee, first, second] = &call.arguments[..] else {
+            return Err(RuntimeError::new(
+                ErrorKind::ArityMismatch {
+                    name: "par_pair".into(),
+                    expected: 3,
+                    given: call.arguments.len(),
+                },
+                call.location.clone(),
+            ));
+        };
+        let callee_location = callee.location().clone();
+        let function = self.visit(callee, scope)?;
+        let first = self.visit(first, scope)?;
+        let second = self.visit(second, scope)?;
+        let Primitive::Function { name, .. } = &function else {
+            return Err(RuntimeError::new(ErrorKind::NotAFunction, callee_location));
+        };
+
+        let builtins = &self.builtins;
+        let reached = [&function, &first, &second];
+        if reached.into_iter().any(|value| may_print(value, builtins)) {
+            if let Some(hook) = &mut self.on_sequential_pair {
+                hook(&SequentialPair {
+                    function: name.clone(),
+                    call: call.location.clone(),
+                });
+            }
+            let first = self.apply(function.clone(), first, &call.location)?;
+            let second = self.apply(function, second, &call.location)?;
+            return Ok(tuple::new(first, second));
+        }
+
+        self.stats.parallel_pairs += 1;
+        let worker = Worker {
+            memoize: self.memoizes(),
+            memo_capacity: self.memo.capacity(),
+            assert_none: self.assert_none,
+            overflow: self.overflow,
+            limits: (self.max_depth, self.max_steps),
+            outer: (
+                self.outer.0 + self.frames.len() as u64,
+                self.outer.1 + self.stats.terms,
+            ),
+            interrupt: self.interrupt.clone(),
+            names: self.names.clone(),
+            layouts: self.layouts.clone(),
+        };
+        let location = &call.location;
+        let (first, second) = thread::scope(|threads| {
+            let spawned = {
+                let (worker, function, first) = (worker.clone(), function.clone(), first.clone());
+                thread::Builder::new()
+                    .name("par_pair".into())
+                    .stack_size(WORKER_STACK_SIZE)
+                    .spawn_scoped(threads, move || worker.apply(function, first, location))
+            };
+            let second = worker.clone().apply(function.clone(), second, location);
+            let first = match spawned {
+                Ok(spawned) => spawned
+                    .join()
+                    .unwrap_or_else(|panicked| panic::resume_unwind(panicked)),
+                // Without a thread, the first half runs here too.
+                Err(_) => worker.apply(function, first, location),
+            };
+            (first, second)
+        });
+        let mut value = |half: Result<(Primitive, Stats, reference::Collector), RuntimeError>| {
+            half.map(|(value, stats, cells)| {
+                self.cells.adopt(cells);
+                self.stats.calls += stats.calls;
+                self.stats.memo_hits += stats.memo_hits;
+                self.stats.memo_evictions += stats.memo_evictions;
+                self.stats.parallel_pairs += stats.parallel_pairs;
+                self.stats.terms += stats.terms;
+                let depth = self.frames.len() as u64 + stats.max_depth;
+                self.stats.max_depth = self.stats.max_depth.max(depth);
+                value
+            })
+        };
+        let first = value(first)?;
+        Ok(tuple::new(first, value(second)?))
+    }
+    /// Calls `function` with `argument`, as if the call was written at
+    /// `location`.
+    fn apply(
+        &mut self,
+        function: Primitive,
+        argument: Primitive,
+        location: &ast::Location,
+    ) -> Result<Primitive, RuntimeError> {
+        // Names no program can bind, they have a space.
+        const FUNCTION: &str = "par_pair function";
+        const ARGUMENT: &str = "par_pair argument";
+
+        let var = |text: &str| {
+            ast::Term::Var(parser::Var {
+                text: text.into(),
+                location: location.clone(),
+                address: Address::Named,
+            })
+        };
+        let call = ast::Term::Call(ast::Call {
+            callee: Box::new(var(FUNCTION)),
+            arguments: vec![var(ARGUMENT)],
+            location: location.clone(),
+        });
+        let mut scope = Scope::from([
+            (FUNCTION.to_string(), function),
+            (ARGUMENT.to_string(), argument),
+        ]);
+        self.visit(&call, &mut scope)
+    }
+    fn visit_conditional<'t>(
+        &mut self,
+        conditional: &'t ast::If,
+        scope: &mut Scope,
+    ) -> Result<Step<'t>, RuntimeError> {
+        match self.visit(&conditional.condition, scope)? {
+            Primitive::Bool(true) => Ok(Step::Tail(&conditional.then)),
+            Primitive::Bool(false) => Ok(Step::Tail(&conditional.otherwise)),
+            _ => Err(RuntimeError::new(
+                ErrorKind::ConditionNotBool,
+                conditional.condition.location().clone(),
+            )),
+        }
+    }
+    /// The arms are tried in order, matching the value in the steps of
+    /// [`ast::Pattern::parts`], then asking the guard. The body of the arm
+    /// taken is in tail position, with the variables of its pattern bound.
+    fn visit_match<'t>(
+        &mut self,
+        match_: &'t ast::Match,
+        scope: &mut Scope,
+    ) -> Result<Step<'t>, RuntimeError> {
+        let value = self.visit(&match_.value, scope)?;
+        'arms: for arm in &match_.arms {
+            let (literals, others): (Vec<_>, Vec<_>) = arm
+                .pattern
+                .parts()
+                .into_iter()
+                .partition(ast::Part::is_literal);
+            for part in literals {
+                let literal = match part.pattern {
+                    ast::Pattern::Int(int) => Primitive::Int(int.value.into()),
+                    ast::Pattern::Str(str) => Primitive::Str(str.value.clone()),
+                    ast::Pattern::Bool(bool) => Primitive::Bool(bool.value),
+                    _ => unreachable!("the part is a literal"),
+                };
+                let equal = ast::BinaryOp::Eq
+                    .apply(part_of(&value, &part)?, literal, self.overflow)
+                    .map_err(|kind| RuntimeError::new(kind, part.pattern.location().clone()))?;
+                if equal != Primitive::Bool(true) {
+                    continue 'arms;
+                }
+            }
+            for part in others {
+                let taken = part_of(&value, &part)?;
+                if let ast::Pattern::Var(var) = part.pattern {
+                    self.bind(var, taken, scope);
+                }
+            }
+            if let Some(guard) = &arm.guard {
+                match self.visit(guard, scope)? {
+                    Primitive::Bool(true) => {}
+                    Primitive::Bool(false) => continue,
+                    _ => {
+                        return Err(RuntimeError::new(
+                            ErrorKind::ConditionNotBool,
+                            guard.location().clone(),
+                        ))
+                    }
+                }
+            }
+            return Ok(Step::Tail(&arm.body));
+        }
+        Err(RuntimeError::new(
+            ErrorKind::NoMatch,
+            match_.location.clone(),
+        ))
+    }
+    fn visit_int(&self, int: &ast::Int, _scope: &Scope) -> Result<Primitive, RuntimeError> {
+        Ok(Primitive::Int(int.value.into()))
+    }
+    fn visit_float(&self, float: &ast::Float, _scope: &Scope) -> Result<Primitive, RuntimeError> {
+        self.needs_extensions("floats", &float.location)?;
+        Ok(Primitive::Float(float.value))
+    }
+    /// The fields are evaluated in the order they're written.
+    fn visit_record(
+        &mut self,
+        record: &ast::Record,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        self.needs_extensions("records", &record.location)?;
+        let mut fields = Vec::with_capacity(record.fields.len());
+        for field in &record.fields {
+            fields.push((field.name.text.clone(), self.visit(&field.value, scope)?));
+        }
+        Record::new(fields)
+            .map(Primitive::Record)
+            .map_err(|kind| RuntimeError::new(kind, record.location.clone()))
+    }
+    fn visit_access(
+        &mut self,
+        access: &ast::Access,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        self.needs_extensions("records", &access.location)?;
+        let value = self.visit(&access.value, scope)?;
+        record::field(&value, &access.field.text)
+            .map_err(|kind| RuntimeError::new(kind, access.location.clone()))
+    }
+    /// Fails at `location` without [`Interpreter::extensions`].
+    fn needs_extensions(
+        &self,
+        what: &'static str,
+        location: &ast::Location,
+    ) -> Result<(), RuntimeError> {
+        match self.extensions {
+            true => Ok(()),
+            false => Err(RuntimeError::new(
+                ErrorKind::NeedsExtensions(what),
+                location.clone(),
+            )),
+        }
+    }
+    fn visit_bool(&self, bool: &ast::Bool, _scope: &Scope) -> Result<Primitive, RuntimeError> {
+        Ok(Primitive::Bool(bool.value))
+    }
+    fn visit_str(&self, str: &ast::Str, _scope: &Scope) -> Result<Primitive, RuntimeError> {
+        Ok(Primitive::Str(str.value.clone()))
+    }
+    fn visit_tuple(
+        &mut self,
+        tuple: &ast::Tuple,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let first = self.visit(&tuple.first, scope)?;
+        let second = self.visit(&tuple.second, scope)?;
+        Ok(tuple::new(first, second))
+    }
+    fn visit_first(
+        &mut self,
+        first: &ast::First,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let value = self.visit(&first.value, scope)?;
+        tuple::first(value).map_err(|kind| RuntimeError::new(kind, first.location.clone()))
+    }
+    fn visit_second(
+        &mut self,
+        second: &ast::Second,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let value = self.visit(&second.value, scope)?;
+        tuple::second(value).map_err(|kind| RuntimeError::new(kind, second.location.clone()))
+    }
+    /// Every observer of `print` goes through here, in a fixed order: the
+    /// value is evaluated, the hook is notified, the value is rendered once
+    /// into every sink, and then the counters are updated.
+    fn visit_print(
+        &mut self,
+        print: &ast::Print,
+        scope: &mut Scope,
+    ) -> Result<Primitive, RuntimeError> {
+        let result = self.visit(&print.value, scope)?;
+        #[cfg(feature = "debug-invariants")]
+        if let Some(prints) = &mut self.panic_after_prints {
+            match prints.checked_sub(1) {
+                Some(left) => *prints = left,
+                None => panic!("deliberate panic, asked for by the tests"),
+            }
+        }
+        if let Some(hook) = &mut self.on_print {
+            hook(&result, &print.location);
+        }
+
+        let depth = tuple::depth(&result);
+        self.stats.max_print_depth = self.stats.max_print_depth.max(depth);
+        match self.print_thresholds.depth {
+            Some(threshold) if depth > threshold && !self.warned.0 => {
+                self.warned.0 = true;
+                self.warn_print(PrintWarning::Depth {
+                    depth,
+                    threshold,
+                    location: print.location.clone(),
+                });
+            }
+            _ => {}
+        }
+
+        let bytes = write_print(
+            &mut *self.output,
+            &mut self.tees,
+            self.max_output_bytes,
+            result.display(self.int_format),
+            &print.location,
+            &mut self.stats,
+        )?;
+        match self.print_thresholds.bytes {
+            Some(threshold) if bytes > threshold && !self.warned.1 => {
+                self.warned.1 = true;
+                self.warn_print(PrintWarning::Bytes {
+                    bytes,
+                    threshold,
+                    location: print.location.clone(),
+                });
+            }
+            _ => {}
+        }
+        self.effects += 1;
+        self.prints += 1;
+        Ok(result)
+    }
+    /// Tells the hook about the function `name` the first time one of its
+    /// calls printed, when it calls itself.
+    fn hint_impure_recursion(&mut self, name: &Symbol, body: Option<Arc<ast::Term>>) {
+        let (Some(hook), Some(body)) = (&mut self.on_impure_recursion, body) else {
+            return;
+        };
+        if !self.hinted.insert(name.clone()) {
+            return;
+        }
+        let recursive = path::unbound(&body).iter().any(|var| var.text == **name);
+        if let Some(print) = path::first_print(&body).filter(|_| recursive) {
+            hook(&ImpureRecursion {
+                function: name.clone(),
+                print: print.clone(),
+            });
+        }
+    }
+    fn warn_print(&mut self, warning: PrintWarning) {
+        if let Some(hook) = &mut self.on_print_warning {
+            hook(&warning);
+        }
+    }
+}
+
+/// The checks of the invariants. They're compiled out unless the
+/// `debug-invariants` feature is on, and panic with a dump of the state
+/// when violated.
+impl Interpreter {
+    /// How many memo entries are shown in the dump.
+    const DUMPED_MEMO_ENTRIES: usize = 8;
+
+    fn invariant(&self, holds: impl FnOnce(&Self) -> bool, violation: impl FnOnce() -> String) {
+        if cfg!(feature = "debug-invariants") && !holds(self) {
+            panic!("invariant violated: {}\n{}", violation(), self.dump());
+        }
+    }
+
+    /// The invariants that are too expensive to check at every step, they
+    /// are checked once the program finishes.
+    fn check_invariants(&self) {
+        self.invariant(
+            |this| this.stats.memo_hits <= this.stats.calls,
+            || "more memo hits than calls".into(),
+        );
+        self.invariant(
+            |this| this.stats.prints_by_site.values().sum::<u64>() <= this.stats.printed_bytes,
+            || "more prints than printed bytes".into(),
+        );
+        for key in self.memo.keys() {
+            self.invariant(
+                |this| this.is_live(key),
+                || format!("memo entry `{key}` belongs to no function"),
+            );
+        }
+    }
+
+    /// Whether the memo key was made by a function of the program.
+    fn is_live(&self, key: &MemoKey) -> bool {
+        let name = &key.function.name;
+        self.names.contains_name(name) || name.starts_with("anon@")
+    }
+
+    /// A bounded rendering of the state, for the invariant violations.
+    fn dump(&self) -> String {
+        let mut keys: Vec<String> = self.memo.keys().map(MemoKey::to_string).collect();
+        keys.sort();
+        keys.truncate(Self::DUMPED_MEMO_ENTRIES);
+
+        let stats = &self.stats;
+        format!(
+            "interpreter state:\n  depth: {}\n  calls: {}, memo hits: {}, printed bytes: {}\n  memo: {} entries, starting with {keys:?}",
+            self.frames.len(),
+            stats.calls,
+            stats.memo_hits,
+            stats.printed_bytes,
+            self.memo.len(),
+        )
+    }
+}
+
+/// Drops a chain of `let` one link at a time, dropping it whole would
+/// recurse once per link.
+pub(crate) fn drop_let_chain(mut term: ast::Term) {
+    while let ast::Term::Let(let_) = term {
+        term = *let_.next;
+    }
+}
+
+/// What's left of a term after a step of [`Interpreter::visit`].
+enum Step<'t> {
+    /// The value of the term.
+    Value(Primitive),
+    /// The term whose value is the value of the term, in the same scope.
+    Tail(&'t ast::Term),
+    /// The body of the function called by the term.
+    Call(Invocation),
+}
+
+/// The body of a called function, to evaluate in its own scope.
+struct Invocation {
+    body: Arc<ast::Term>,
+    scope: Scope,
+    frame: Frame,
+    ret: Return,
+}
+
+/// What's left to do once a function body has its value: memoizing it,
+/// unless it printed.
+struct Return {
+    /// The key of the value in the memo, none when it isn't memoized, see
+    /// [`holds_list_or_ref`].
+    key: Option<MemoKey>,
+    /// The effects when the body started.
+    effects: u64,
+    /// The prints when the body started.
+    prints: u64,
+    /// The body, kept for [`Interpreter::on_impure_recursion`] when there's
+    /// a hook.
+    body: Option<Arc<ast::Term>>,
+}
+
+/// Writes every chunk rendered by `print` into the output and the tees,
+/// counting the bytes. The values are streamed instead of being rendered
+/// into a string first, so every sink sees the same bytes without a copy.
+struct Fanout<'a> {
+    output: &'a mut dyn io::Write,
+    tees: &'a mut [Box<dyn io::Write>],
+    bytes: u64,
+    /// How many more bytes can be written, when the output is limited.
+    room: Option<u64>,
+}
+
+/// Writes `text` and a newline into `output`, the way every backend
+/// prints: the bytes are counted into `stats`, the output stops at
+/// `max_output_bytes`, and the errors of the output are runtime errors at
+/// `location`. Returns how many bytes were written.
+pub(crate) fn write_print(
+    output: &mut dyn io::Write,
+    tees: &mut [Box<dyn io::Write>],
+    max_output_bytes: Option<u64>,
+    text: impl fmt::Display,
+    location: &ast::Location,
+    stats: &mut Stats,
+) -> Result<u64, RuntimeError> {
+    let mut output = Fanout {
+        output,
+        tees,
+        bytes: 0,
+        room: max_output_bytes.map(|limit| limit.saturating_sub(stats.printed_bytes)),
+    };
+    let written = writeln!(output, "{text}");
+
+    stats.printed_bytes += output.bytes;
+    stats.max_print_bytes = stats.max_print_bytes.max(output.bytes);
+    if output.room == Some(0) && written.is_err() {
+        let limit = max_output_bytes.unwrap_or_default();
+        return Err(RuntimeError::new(
+            ErrorKind::OutputLimit(limit),
+            location.clone(),
+        ));
+    }
+    if let Err(error) = written {
+        return Err(RuntimeError::new(error.into(), location.clone()));
+    }
+    *stats.prints_by_site.entry(location.clone()).or_default() += 1;
+    Ok(output.bytes)
+}
+
+impl io::Write for Fanout<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let buf = match &mut self.room {
+            Some(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Some(room) => {
+                let fits = buf.len().min(usize::try_from(*room).unwrap_or(usize::MAX));
+                *room -= fits as u64;
+                &buf[..fits]
+            }
+            None => buf,
+        };
+        self.output.write_all(buf)?;
+        for tee in self.tees.iter_mut() {
+            tee.write_all(buf)?;
+        }
+        self.bytes += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()?;
+        for tee in self.tees.iter_mut() {
+            tee.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl ast::BinaryOp {
+    /// Whether `left` is the result whatever the right operand is, like
+    /// `false` for `&&`.
+    ///
+    /// ```
+    /// use rinha::{ast::BinaryOp, interpreter::Primitive};
+    ///
+    /// assert!(BinaryOp::Or.short_circuits(&Primitive::Bool(true)));
+    /// assert!(!BinaryOp::And.short_circuits(&Primitive::Bool(true)));
+    /// ```
+    pub fn short_circuits(&self, left: &Primitive) -> bool {
+        matches!(
+            (self, left),
+            (ast::BinaryOp::And, Primitive::Bool(false))
+                | (ast::BinaryOp::Or, Primitive::Bool(true))
+        )
+    }
+    /// Applies the operator to the values of its operands. Every engine
+    /// goes through here, `tests/snapshots/operators.tsv` lists what it
+    /// does for each combination of types.
+    ///
+    /// ```
+    /// use rinha::{
+    ///     ast::BinaryOp,
+    ///     interpreter::{Overflow, Primitive},
+    /// };
+    ///
+    /// let value = BinaryOp::Add.apply(Primitive::Int(1), Primitive::Str("a".into()), Overflow::Trap);
+    /// assert_eq!(value.unwrap().to_string(), "1a");
+    /// ```
+    pub fn apply(
+        &self,
+        left: Primitive,
+        right: Primitive,
+        overflow: Overflow,
+    ) -> Result<Primitive, ErrorKind> {
+        let compound = |value: &Primitive| {
+            matches!(
+                value,
+                Primitive::Tuple(_) | Primitive::List(_) | Primitive::Record(_)
+            )
+        };
+        let tuples = compound(&left) || compound(&right);
+        match self {
+            ast::BinaryOp::Eq if tuples => return tuple::equal(&left, &right).map(Primitive::Bool),
+            ast::BinaryOp::Neq if tuples => {
+                return tuple::equal(&left, &right).map(|equal| Primitive::Bool(!equal))
+            }
+            _ => {}
+        }
+        if matches!(left, Primitive::Float(_)) || matches!(right, Primitive::Float(_)) {
+            return float_two_primitives(self, left, right, overflow);
+        }
+        if matches!(left, Primitive::BigInt(_)) || matches!(right, Primitive::BigInt(_)) {
+            return big_two_primitives(self, left, right, overflow);
+        }
+        match self {
+            ast::BinaryOp::Add => add_two_primitives(left, right, overflow),
+            ast::BinaryOp::Sub => sub_two_primitives(left, right, overflow),
+            ast::BinaryOp::Mul => mul_two_primitives(left, right, overflow),
+            ast::BinaryOp::Div => div_two_primitives(left, right, overflow),
+            ast::BinaryOp::Rem => rem_two_primitives(left, right, overflow),
+            ast::BinaryOp::Eq => eq_two_primitives(left, right),
+            ast::BinaryOp::Neq => neq_two_primitives(left, right),
+            ast::BinaryOp::Lt => lt_two_primitives(left, right),
+            ast::BinaryOp::Gt => gt_two_primitives(left, right),
+            ast::BinaryOp::Lte => lte_two_primitives(left, right),
+            ast::BinaryOp::Gte => gte_two_primitives(left, right),
+            ast::BinaryOp::And => and_two_primitives(left, right),
+            ast::BinaryOp::Or => or_two_primitives(left, right),
+        }
+    }
+}
+
+/// The operators on the integers past 64 bits, with integers of any size on
+/// both sides. The other operands get the concatenations and the errors of
+/// the 64 bits integers.
+fn big_two_primitives(
+    op: &ast::BinaryOp,
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    use ast::BinaryOp::*;
+
+    let as_big = |value: &Primitive| match value {
+        Primitive::Int(value) => Some(BigInt::from(*value)),
+        Primitive::BigInt(value) => Some(value.clone()),
+        _ => None,
+    };
+    let (Some(lhs), Some(rhs)) = (as_big(&p1), as_big(&p2)) else {
+        let small = |value| match value {
+            Primitive::BigInt(_) => Primitive::Int(0),
+            value => value,
+        };
+        return match (op, p1, p2) {
+            (Add, Primitive::BigInt(lhs), Primitive::Str(rhs)) => {
+                Ok(Primitive::Str(format!("{lhs}{rhs}").into()))
+            }
+            (Add, Primitive::Str(lhs), Primitive::BigInt(rhs)) => {
+                Ok(Primitive::Str(format!("{lhs}{rhs}").into()))
+            }
+            (op, p1, p2) => op.apply(small(p1), small(p2), overflow),
+        };
+    };
+
+    let divided = |operation| {
+        lhs.div_rem(&rhs)
+            .ok_or(ErrorKind::DivisionByZero(operation))
+    };
+    let (operation, exact) = match op {
+        Add => ("+", &lhs + &rhs),
+        Sub => ("-", &lhs - &rhs),
+        Mul => ("*", &lhs * &rhs),
+        Div => ("/", divided("/")?.0),
+        Rem => ("%", divided("%")?.1),
+        Eq => return Ok(Primitive::Bool(lhs == rhs)),
+        Neq => return Ok(Primitive::Bool(lhs != rhs)),
+        Lt => return Ok(Primitive::Bool(lhs < rhs)),
+        Gt => return Ok(Primitive::Bool(lhs > rhs)),
+        Lte => return Ok(Primitive::Bool(lhs <= rhs)),
+        Gte => return Ok(Primitive::Bool(lhs >= rhs)),
+        And | Or => return op.apply(Primitive::Int(0), Primitive::Int(0), overflow),
+    };
+    overflow.big(operation, exact)
+}
+
+/// The operators with a float on either side, the integers on the other
+/// side are turned into floats. They follow IEEE 754, so dividing by zero
+/// is an infinity and `NaN` is unequal to everything, itself included. The
+/// remainder is only for the integers. The other operands get the
+/// concatenations and the errors of the integers.
+fn float_two_primitives(
+    op: &ast::BinaryOp,
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    use ast::BinaryOp::*;
+
+    let as_float = |value: &Primitive| match value {
+        Primitive::Float(value) => Some(*value),
+        Primitive::Int(value) => Some(*value as f64),
+        Primitive::BigInt(value) => value.to_string().parse().ok(),
+        _ => None,
+    };
+    let (Some(lhs), Some(rhs)) = (as_float(&p1), as_float(&p2)) else {
+        let int = |value| match value {
+            Primitive::Float(_) => Primitive::Int(0),
+            value => value,
+        };
+        return match (op, p1, p2) {
+            (Add, lhs @ Primitive::Float(_), Primitive::Str(rhs)) => {
+                Ok(Primitive::Str(format!("{lhs}{rhs}").into()))
+            }
+            (Add, Primitive::Str(lhs), rhs @ Primitive::Float(_)) => {
+                Ok(Primitive::Str(format!("{lhs}{rhs}").into()))
+            }
+            (op, p1, p2) => op.apply(int(p1), int(p2), overflow),
+        };
+    };
+
+    let value = match op {
+        Add => Primitive::Float(lhs + rhs),
+        Sub => Primitive::Float(lhs - rhs),
+        Mul => Primitive::Float(lhs * rhs),
+        Div => Primitive::Float(lhs / rhs),
+        Rem => {
+            return Err(ErrorKind::TypeMismatch(
+                "Remainder operation can only be done between two Int",
+            ))
+        }
+        Eq => Primitive::Bool(lhs == rhs),
+        Neq => Primitive::Bool(lhs != rhs),
+        Lt => Primitive::Bool(lhs < rhs),
+        Gt => Primitive::Bool(lhs > rhs),
+        Lte => Primitive::Bool(lhs <= rhs),
+        Gte => Primitive::Bool(lhs >= rhs),
+        And | Or => return op.apply(Primitive::Int(0), Primitive::Int(0), overflow),
+    };
+    Ok(value)
+}
+
+fn add_two_primitives(
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => overflow.int("+", i128::from(p1_int) + i128::from(p2_int)),
+            Primitive::Str(p2_str) => Ok(Primitive::Str(format!("{p1_int}{p2_str}").into())),
+            _ => Err(ErrorKind::TypeMismatch(
+                "Int can only be sum with Int and Str",
+            )),
+        },
+        Primitive::Str(p1_str) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Str(format!("{p1_str}{p2_int}").into())),
+            Primitive::Str(p2_str) => Ok(Primitive::Str(format!("{p1_str}{p2_str}").into())),
+            _ => Err(ErrorKind::TypeMismatch(
+                "Str can only be sum with Int and Str",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Sum operation can only be done between Int and Str",
+        )),
+    }
+}
+
+fn sub_two_primitives(
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => overflow.int("-", i128::from(p1_int) - i128::from(p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only subtract Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Subtract operation can only be done between two Int",
+        )),
+    }
+}
+
+fn mul_two_primitives(
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => overflow.int("*", i128::from(p1_int) * i128::from(p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only multiply Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Multiplication operation can only be done between two Int",
+        )),
+    }
+}
+
+fn div_two_primitives(
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(0) => Err(ErrorKind::DivisionByZero("/")),
+            Primitive::Int(p2_int) => overflow.int("/", i128::from(p1_int) / i128::from(p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only divide Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Divide operation can only be done between two Int",
+        )),
+    }
+}
+
+fn rem_two_primitives(
+    p1: Primitive,
+    p2: Primitive,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(0) => Err(ErrorKind::DivisionByZero("%")),
+            Primitive::Int(p2_int) => overflow.int("%", i128::from(p1_int) % i128::from(p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only remainder Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Remainder operation can only be done between two Int",
+        )),
+    }
+}
+
+fn eq_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int == p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test equality of Int by another Int",
+            )),
+        },
+        Primitive::Str(p1_str) => match p2 {
+            Primitive::Str(p2_str) => Ok(Primitive::Bool(p1_str == p2_str)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test equality of Str by another Str",
+            )),
+        },
+        Primitive::Bool(p1_bool) => match p2 {
+            Primitive::Bool(p2_bool) => Ok(Primitive::Bool(p1_bool == p2_bool)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test equality of Bool by another Bool",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Equality operation can only be done between Int, Str and Bool",
+        )),
+    }
+}
+
+fn neq_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int != p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test inequality of Int by another Int",
+            )),
+        },
+        Primitive::Str(p1_str) => match p2 {
+            Primitive::Str(p2_str) => Ok(Primitive::Bool(p1_str != p2_str)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test inequality of Str by another Str",
+            )),
+        },
+        Primitive::Bool(p1_bool) => match p2 {
+            Primitive::Bool(p2_bool) => Ok(Primitive::Bool(p1_bool != p2_bool)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test inequality of Bool by another Bool",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "Inequality operation can only be done between Int, Str and Bool",
+        )),
+    }
+}
+
+fn lt_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int < p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test 'lower than' of Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "'Lower than' test operator can only be done with Int",
+        )),
+    }
+}
+
+fn gt_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int > p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test 'greater than' of Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "'Greater than' test operator can only be done with Int",
+        )),
+    }
+}
+
+fn lte_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int <= p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test 'lower than or equal' of Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "'Lower than or equal' test operator can only be done with Int",
+        )),
+    }
+}
+
+fn gte_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Int(p1_int) => match p2 {
+            Primitive::Int(p2_int) => Ok(Primitive::Bool(p1_int >= p2_int)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only test 'greater than or equal' of Int by another Int",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "'Greater than or equal' test operator can only be done with Int",
+        )),
+    }
+}
+
+/// The part of `value` a pattern takes, failing at the tuple pattern whose
+/// value isn't a tuple.
+fn part_of(value: &Primitive, part: &ast::Part) -> Result<Primitive, RuntimeError> {
+    part.path
+        .iter()
+        .try_fold(value.clone(), |value, (side, location)| {
+            let taken = match side {
+                ast::Side::First => tuple::first(value),
+                ast::Side::Second => tuple::second(value),
+            };
+            taken.map_err(|kind| RuntimeError::new(kind, (*location).clone()))
+        })
+}
+
+fn and_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Bool(p1_bool) => match p2 {
+            Primitive::Bool(p2_bool) => Ok(Primitive::Bool(p1_bool && p2_bool)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only use 'and' operator between Bool",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "You can only use 'and' operator between Bool",
+        )),
+    }
+}
+
+fn or_two_primitives(p1: Primitive, p2: Primitive) -> Result<Primitive, ErrorKind> {
+    match p1 {
+        Primitive::Bool(p1_bool) => match p2 {
+            Primitive::Bool(p2_bool) => Ok(Primitive::Bool(p1_bool || p2_bool)),
+            _ => Err(ErrorKind::TypeMismatch(
+                "You can only use 'or' operator between Bool",
+            )),
+        },
+        _ => Err(ErrorKind::TypeMismatch(
+            "You can only use 'or' operator between Bool",
+        )),
+    }
+}
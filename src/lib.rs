@@ -22,6 +22,69 @@ pub mod ast;
 /// generate a parser and lexer for the language.
 pub mod parser;
 
+/// Rendering of the AST back into `rinha` source code.
+pub mod format;
+
+/// Rewriting the programs into simpler ones that do the same, before
+/// running or compiling them.
+pub mod optimize;
+
+/// The tree-walking interpreter and the runtime values of the
+/// language.
+pub mod interpreter;
+
+/// The bytecode engine, a stack machine that runs the programs lowered
+/// into instructions.
+pub mod vm;
+
+/// Compiling the programs into WebAssembly modules, with a runtime of
+/// their own for the closures, the tuples and the strings.
+pub mod wasm;
+
+/// The runtime shared by the engines, so they all treat the values the
+/// same way.
+pub mod runtime;
+
+/// The memo entries shared between the runs of the same program.
+pub mod memo;
+
+/// Selecting the terms inside a program by their path, like
+/// `expression.next.value`.
+pub mod path;
+
+/// The names of the functions, shared by every feature that shows them.
+pub mod names;
+
+/// Resolving the variables of the programs to the slots of the frames
+/// their values are in.
+pub mod resolve;
+
+/// Parsing and running a program in a single call, it's the entry point
+/// for embedders and for the interpreter binary.
+pub mod run;
+
+pub use run::{run_ast, run_parsed, run_source, Backend, RinhaError, RunConfig, RunOutcome};
+
+/// Cache files shared between processes, written atomically and verified
+/// when read.
+pub mod cache;
+
+/// The static analyses, they report every problem found in a program
+/// without running it.
+pub mod check;
+
+/// The changes to the behavior of the programs, with the flags that
+/// restore the old one.
+pub mod semantics;
+
+/// Random programs for the fuzzers, and the checks run on them.
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+
+/// Reading of the input programs, with friendly errors when the
+/// file can't be read.
+pub mod input;
+
 /// Simple program to run `rinha` language.
 #[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None)]
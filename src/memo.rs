@@ -0,0 +1,345 @@
+use crate::{ast, interpreter::Primitive, runtime::symbol::Symbol};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, RwLock},
+};
+
+/// The entries the memo of a run keeps by default, see [`Lru`].
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+/// The function of a memo key, told apart from the others by where its
+/// body is. The name is only there to show the key, the functions with no
+/// name, or sharing one, don't share their entries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FunctionId {
+    pub name: Symbol,
+    pub location: ast::Location,
+}
+
+impl fmt::Display for FunctionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = &self.location;
+        write!(f, "{}@{}:{}", self.name, location.filename, location.start)
+    }
+}
+
+/// The memo key of a call: the function, the values it captured and the
+/// arguments. The values are kept, so the keys only match when the values
+/// are equal, and they're hashed by the [`Hash`] of [`Primitive`] instead
+/// of being written into a string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoKey {
+    pub function: FunctionId,
+    pub captured: Box<[(Symbol, Primitive)]>,
+    pub arguments: Box<[Primitive]>,
+}
+
+impl MemoKey {
+    /// The hash of the key, the same in every run of the program unless
+    /// it holds a thunk or a reference.
+    pub fn digest(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl fmt::Display for MemoKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function)?;
+        for (name, value) in self.captured.iter() {
+            write!(f, ",{name}={value}")?;
+        }
+        f.write_str("(")?;
+        for (index, argument) in self.arguments.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{argument}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// A map keeping at most `capacity` entries. Once it's full, inserting
+/// drops the entry looked up or inserted the longest ago. The entries are
+/// linked from the newest to the oldest through their indexes, so looking
+/// one up and dropping the oldest take constant time.
+///
+/// ```
+/// use rinha::memo::Lru;
+///
+/// let mut memo = Lru::new(2);
+/// memo.insert("a", 1);
+/// memo.insert("b", 2);
+/// assert_eq!(memo.get("a"), Some(&1));
+/// assert_eq!(memo.insert("c", 3), Some(("b", 2)));
+/// assert_eq!(memo.get("b"), None);
+/// assert_eq!(memo.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lru<K, V> {
+    capacity: usize,
+    indexes: HashMap<K, usize>,
+    entries: Vec<Entry<K, V>>,
+    newest: Option<usize>,
+    oldest: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    newer: Option<usize>,
+    older: Option<usize>,
+}
+
+impl<K: Hash + Eq + Clone, V> Lru<K, V> {
+    /// Creates an empty map, a `capacity` of zero keeps nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            indexes: HashMap::new(),
+            entries: Vec::new(),
+            newest: None,
+            oldest: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The value of `key`, which becomes the newest entry.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = *self.indexes.get(key)?;
+        self.unlink(index);
+        self.link_newest(index);
+        Some(&self.entries[index].value)
+    }
+
+    /// Inserts the entry as the newest one, replacing the value of `key`
+    /// or dropping the oldest entry when the map is full, which is
+    /// returned. A map with a `capacity` of zero drops the entry, which
+    /// evicts nothing.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(&index) = self.indexes.get(&key) {
+            self.entries[index].value = value;
+            self.unlink(index);
+            self.link_newest(index);
+            return None;
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            newer: None,
+            older: None,
+        };
+        let (index, evicted) = match self.oldest {
+            // The slot of the oldest entry is reused.
+            Some(oldest) if self.entries.len() >= self.capacity => {
+                self.unlink(oldest);
+                let evicted = std::mem::replace(&mut self.entries[oldest], entry);
+                self.indexes.remove(&evicted.key);
+                (oldest, Some((evicted.key, evicted.value)))
+            }
+            _ => {
+                self.entries.push(entry);
+                (self.entries.len() - 1, None)
+            }
+        };
+        self.indexes.insert(key, index);
+        self.link_newest(index);
+        evicted
+    }
+
+    /// The entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|entry| (&entry.key, &entry.value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|entry| &entry.key)
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let Entry { newer, older, .. } = self.entries[index];
+        match newer {
+            Some(newer) => self.entries[newer].older = older,
+            None => self.newest = older,
+        }
+        match older {
+            Some(older) => self.entries[older].newer = newer,
+            None => self.oldest = newer,
+        }
+    }
+
+    fn link_newest(&mut self, index: usize) {
+        self.entries[index].newer = None;
+        self.entries[index].older = self.newest;
+        match self.newest {
+            Some(newest) => self.entries[newest].newer = Some(index),
+            None => self.oldest = Some(index),
+        }
+        self.newest = Some(index);
+    }
+}
+
+/// The memo entries of a program, shared by every run of it. A run takes
+/// the current [`Snapshot`] when it starts and reads it without locking,
+/// then merges the entries of its pure functions back when it's done, which
+/// publishes a new snapshot for the runs that start after it.
+///
+/// ```
+/// use rinha::{memo::SharedMemo, RunConfig};
+/// use std::sync::Arc;
+///
+/// let config = RunConfig {
+///     shared_memo: Some(Arc::new(SharedMemo::new(1024))),
+///     ..RunConfig::default()
+/// };
+/// let source = "let sq = fn (n) => { n * n }; sq(12)";
+///
+/// let first = rinha::run_source(source, &config).unwrap();
+/// let second = rinha::run_source(source, &config).unwrap();
+/// assert_eq!(first.stats.shared_hits, 0);
+/// assert_eq!(second.stats.shared_hits, 1);
+/// assert_eq!(second.value.to_string(), "144");
+/// ```
+#[derive(Debug)]
+pub struct SharedMemo {
+    capacity: usize,
+    programs: RwLock<HashMap<ProgramKey, Arc<Snapshot>>>,
+}
+
+/// Identifies a program by the hash of its source code, and of the
+/// settings it's run with, see [`crate::RunConfig::program_key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProgramKey(u64);
+
+impl ProgramKey {
+    pub fn of_source(source: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    /// The key of the program run with `settings`, the runs with other
+    /// settings get other snapshots.
+    pub fn with(self, settings: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (self.0, settings).hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+/// A read-only copy of the memo entries of one program.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    version: u64,
+    entries: HashMap<MemoKey, Primitive>,
+}
+
+impl Snapshot {
+    /// How many merges published entries into this snapshot.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn get(&self, key: &MemoKey) -> Option<&Primitive> {
+        self.entries.get(key)
+    }
+
+    /// The keys of the entries, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &MemoKey> {
+        self.entries.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl SharedMemo {
+    /// Creates an empty cache, keeping at most `capacity` entries for each
+    /// program.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            programs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The latest snapshot of the program, empty when no run merged into it
+    /// yet.
+    pub fn snapshot(&self, program: ProgramKey) -> Arc<Snapshot> {
+        let programs = self
+            .programs
+            .read()
+            .unwrap_or_else(|error| error.into_inner());
+        programs.get(&program).cloned().unwrap_or_default()
+    }
+
+    /// Publishes the entries that aren't known yet, while there's room for
+    /// them. The snapshots taken before keep seeing the old entries.
+    pub fn merge<'a>(
+        &self,
+        program: ProgramKey,
+        entries: impl IntoIterator<Item = (&'a MemoKey, &'a Primitive)>,
+    ) {
+        let mut programs = self
+            .programs
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        let current = programs.get(&program).cloned().unwrap_or_default();
+
+        // The entries kept when the capacity runs out are the first ones by
+        // the digest of their key, not the first ones of the random order
+        // of a hash map.
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by_cached_key(|(key, _)| key.digest());
+
+        let mut merged: Option<HashMap<MemoKey, Primitive>> = None;
+        for (key, value) in entries {
+            let known = merged.as_ref().unwrap_or(&current.entries);
+            if known.len() >= self.capacity {
+                break;
+            }
+            if !known.contains_key(key) {
+                merged
+                    .get_or_insert_with(|| current.entries.clone())
+                    .insert(key.clone(), value.clone());
+            }
+        }
+
+        if let Some(entries) = merged {
+            let snapshot = Snapshot {
+                version: current.version + 1,
+                entries,
+            };
+            programs.insert(program, Arc::new(snapshot));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::ast::{self, Term};
+use std::collections::{HashMap, HashSet};
+
+/// The names of every function of a program, keyed by the location of its
+/// `fn` term. Functions bound by `let` take the name of the binding, the
+/// others get a synthetic one, like `anon#3@main.rinha:12:5`, made of their
+/// ordinal in the program and the place they're defined.
+///
+/// ```
+/// use rinha::{
+///     ast::{Element, Term},
+///     names::FunctionNames,
+///     parser,
+/// };
+///
+/// let source = "let f = fn (x) => { x };\nf(fn (y) => { y })";
+/// let file = parser::parse_or_report("main.rinha", source).unwrap();
+/// let names = FunctionNames::new(&file.expression, Some(source));
+///
+/// let Term::Let(let_) = &file.expression else { unreachable!() };
+/// let Term::Call(call) = &*let_.next else { unreachable!() };
+/// assert_eq!(names.get(&let_.value.location()), Some("f"));
+/// assert_eq!(names.get(&call.arguments[0].location()), Some("anon#2@main.rinha:2:3"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct FunctionNames {
+    names: HashMap<ast::Location, String>,
+    /// The names of more than one function, the bindings that shadow
+    /// others.
+    shared: HashSet<String>,
+}
+
+impl FunctionNames {
+    /// Names the functions inside `term`. The source code is used to show
+    /// the line and the column of the anonymous functions, they're left out
+    /// without it.
+    pub fn new(term: &Term, source: Option<&str>) -> Self {
+        let mut namer = Namer {
+            source,
+            ordinal: 0,
+            names: HashMap::new(),
+        };
+        namer.visit(term, None);
+        let mut seen = HashSet::new();
+        let shared = namer
+            .names
+            .values()
+            .filter(|name| !seen.insert(name.as_str()))
+            .cloned()
+            .collect();
+        Self {
+            names: namer.names,
+            shared,
+        }
+    }
+
+    /// Adds the names of `other`, for the functions that don't have one yet.
+    pub fn extend(&mut self, other: FunctionNames) {
+        let known: HashSet<String> = self.names.values().cloned().collect();
+        self.shared.extend(other.shared);
+        for (location, name) in other.names {
+            if self.names.contains_key(&location) {
+                continue;
+            }
+            if known.contains(&name) {
+                self.shared.insert(name.clone());
+            }
+            self.names.insert(location, name);
+        }
+    }
+
+    /// The name of the function defined at `location`.
+    pub fn get(&self, location: &ast::Location) -> Option<&str> {
+        self.names.get(location).map(String::as_str)
+    }
+
+    /// Whether some function is called `name`.
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.names.values().any(|known| known == name)
+    }
+
+    /// Whether more than one function is called `name`, like when a
+    /// binding shadows another.
+    pub fn is_shared(&self, name: &str) -> bool {
+        self.shared.contains(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+struct Namer<'a> {
+    source: Option<&'a str>,
+    ordinal: usize,
+    names: HashMap<ast::Location, String>,
+}
+
+impl Namer<'_> {
+    /// Walks the terms in the order they're written, so the ordinals follow
+    /// the source.
+    fn visit(&mut self, term: &Term, binding: Option<&str>) {
+        match term {
+            Term::Function(function) => {
+                self.ordinal += 1;
+                let name = match binding {
+                    Some(binding) => binding.to_string(),
+                    None => self.synthetic(&function.location),
+                };
+                self.names.insert(function.location.clone(), name);
+                self.visit(&function.value, None);
+            }
+            Term::Let(let_) => {
+                // The chains of `let` are walked in a loop, they can be far
+                // longer than the stack allows to recurse.
+                let mut let_ = let_;
+                loop {
+                    self.visit(&let_.value, Some(&let_.name.text));
+                    match &*let_.next {
+                        Term::Let(next) => let_ = next,
+                        next => return self.visit(next, None),
+                    }
+                }
+            }
+            Term::Call(call) => {
+                self.visit(&call.callee, None);
+                for argument in &call.arguments {
+                    self.visit(argument, None);
+                }
+            }
+            Term::Binary(binary) => {
+                self.visit(&binary.lhs, None);
+                self.visit(&binary.rhs, None);
+            }
+            Term::If(if_) => {
+                self.visit(&if_.condition, None);
+                self.visit(&if_.then, None);
+                self.visit(&if_.otherwise, None);
+            }
+            Term::Tuple(tuple) => {
+                self.visit(&tuple.first, None);
+                self.visit(&tuple.second, None);
+            }
+            Term::Print(print) => self.visit(&print.value, None),
+            Term::First(first) => self.visit(&first.value, None),
+            Term::Second(second) => self.visit(&second.value, None),
+            Term::Record(record) => {
+                for field in &record.fields {
+                    self.visit(&field.value, None);
+                }
+            }
+            Term::Access(access) => self.visit(&access.value, None),
+            Term::Match(match_) => {
+                self.visit(&match_.value, None);
+                for arm in &match_.arms {
+                    if let Some(guard) = &arm.guard {
+                        self.visit(guard, None);
+                    }
+                    self.visit(&arm.body, None);
+                }
+            }
+            Term::Error(_)
+            | Term::Int(_)
+            | Term::Float(_)
+            | Term::Str(_)
+            | Term::Bool(_)
+            | Term::Var(_) => {}
+        }
+    }
+
+    fn synthetic(&self, location: &ast::Location) -> String {
+        let Some(source) = self.source else {
+            return format!("anon#{}@{}", self.ordinal, location.filename);
+        };
+
+        let (line, column) = location.line_column(source);
+        format!(
+            "anon#{}@{}:{line}:{column}",
+            self.ordinal, location.filename
+        )
+    }
+}
@@ -0,0 +1,525 @@
+//! Rewrites the programs into simpler ones that print the same, fail the
+//! same way and end with the same value. Each level runs the passes of the
+//! one before it, and one more.
+
+use crate::{
+    ast::{self, Element, Term},
+    interpreter::{Overflow, Primitive},
+    parser, path,
+    resolve::Address,
+    runtime::symbol::Symbol,
+};
+use std::{collections::HashSet, sync::Arc};
+
+/// How much the programs are rewritten before running them.
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptLevel {
+    /// Runs the program as written
+    #[default]
+    #[value(name = "0")]
+    O0,
+    /// Folds the operators on constants and the `if` on constant
+    /// conditions
+    #[value(name = "1")]
+    O1,
+    /// Also drops the `let` whose variable is never used
+    #[value(name = "2")]
+    O2,
+}
+
+/// A rewrite of the whole program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Replaces the operators on literals by their result, when it's a
+    /// literal too. The ones that fail are left to fail when run.
+    FoldConstants,
+    /// Replaces the `if` on a literal boolean by the branch it takes.
+    PruneBranches,
+    /// Drops the `let` whose variable is never used, when its value can't
+    /// print, fail or loop.
+    DropUnusedLets,
+    /// Replaces the `match` by the `let` and `if` it's made of, see
+    /// [`lower_match`]. No level runs it, the interpreter runs them as they
+    /// are, it's for the backends that don't.
+    LowerMatches,
+}
+
+impl OptLevel {
+    /// The passes of the level, in the order they run.
+    pub fn passes(self) -> &'static [Pass] {
+        match self {
+            OptLevel::O0 => &[],
+            OptLevel::O1 => &[Pass::FoldConstants, Pass::PruneBranches],
+            OptLevel::O2 => &[
+                Pass::FoldConstants,
+                Pass::PruneBranches,
+                Pass::DropUnusedLets,
+            ],
+        }
+    }
+}
+
+/// Runs the passes of the level on the term. The constants are folded
+/// with the arithmetic of `overflow`, the one the program runs with.
+///
+/// ```
+/// use rinha::{format, interpreter::Overflow, optimize::{self, OptLevel}, parser};
+///
+/// let source = "let unused = 2; let x = 1 + 2 * 3; if (x > 5) { print(x) } else { 0 }";
+/// let file = parser::parse_or_report("main.rinha", source).unwrap();
+/// let term = optimize::optimize(file.expression, OptLevel::O2, Overflow::Trap);
+/// assert_eq!(format::to_source(&term), "let x = 7;\nif (x > 5) {\n  print(x)\n} else {\n  0\n}");
+/// ```
+pub fn optimize(term: Term, level: OptLevel, overflow: Overflow) -> Term {
+    level
+        .passes()
+        .iter()
+        .fold(term, |term, pass| run(*pass, term, overflow))
+}
+
+/// Runs a single pass on the term.
+pub fn run(pass: Pass, term: Term, overflow: Overflow) -> Term {
+    match pass {
+        Pass::FoldConstants => rewrite(term, &mut |term| fold(term, overflow)),
+        Pass::PruneBranches => rewrite(term, &mut prune),
+        Pass::DropUnusedLets => drop_unused(term).0,
+        Pass::LowerMatches => rewrite(term, &mut |term| match term {
+            Term::Match(match_) => lower_match(match_),
+            term => term,
+        }),
+    }
+}
+
+/// The `let` and `if` the `match` is made of, taking the steps of the
+/// interpreter, see [`ast::Pattern::parts`]: the value is bound to a name
+/// no program can bind, each arm compares its literals with `==` and then
+/// binds its variables to the elements of the tuples. The match without
+/// arms at the end fails like when no arm takes the value. The arms are
+/// left as they are, with the matches inside of them.
+///
+/// ```
+/// use rinha::{format, optimize, parser, ast::Term};
+///
+/// let file = parser::parse_or_report("main.rinha", "match (p) { (0, y) => y, _ => 1 }").unwrap();
+/// let Term::Match(match_) = file.expression else { unreachable!() };
+/// assert_eq!(
+///     format::to_source(&optimize::lower_match(match_)),
+///     "let match 0 = p;\nif (first(match 0) == 0) {\n  let y = second(match 0);\n  y\n} else {\n  1\n}",
+/// );
+/// ```
+pub fn lower_match(match_: ast::Match) -> Term {
+    if match_.arms.is_empty() {
+        return Term::Match(match_);
+    }
+    let name = Symbol::from(format!("match {}", match_.location.start));
+    let value = parser::Var {
+        text: name.clone(),
+        location: match_.value.location().clone(),
+        address: Address::Named,
+    };
+    let no_match = Term::Match(ast::Match {
+        value: Box::new(Term::Var(value.clone())),
+        arms: Vec::new(),
+        location: match_.location.clone(),
+    });
+    let arms = match_.arms.into_iter().rev();
+    let arms = arms.fold(no_match, |rest, arm| lower_arm(arm, &name, rest));
+    Term::Let(ast::Let {
+        name: value,
+        value: match_.value,
+        next: Box::new(arms),
+        location: match_.location,
+    })
+}
+
+/// The arm, falling through to `rest` when it doesn't take the value bound
+/// to `name`.
+fn lower_arm(arm: ast::Arm, name: &Symbol, rest: Term) -> Term {
+    let parts = arm.pattern.parts();
+    let var = |text: &Symbol, location: &ast::Location| parser::Var {
+        text: text.clone(),
+        location: location.clone(),
+        address: Address::Named,
+    };
+    let part = |part: &ast::Part| {
+        let value = Term::Var(var(name, arm.pattern.location()));
+        part.path.iter().fold(value, |value, (side, location)| {
+            let value = Box::new(value);
+            let location = (*location).clone();
+            match side {
+                ast::Side::First => Term::First(ast::First { value, location }),
+                ast::Side::Second => Term::Second(ast::Second { value, location }),
+            }
+        })
+    };
+    let mut tests = Vec::new();
+    let mut bindings = Vec::new();
+    for taken in &parts {
+        let location = taken.pattern.location().clone();
+        let literal = match taken.pattern {
+            ast::Pattern::Int(int) => Term::Int(int.clone()),
+            ast::Pattern::Str(str) => Term::Str(str.clone()),
+            ast::Pattern::Bool(bool) => Term::Bool(bool.clone()),
+            ast::Pattern::Var(bound) => {
+                bindings.push((bound.clone(), part(taken)));
+                continue;
+            }
+            // The elements under the wildcards are taken too, so the tuple
+            // patterns fail on the values that aren't tuples.
+            ast::Pattern::Wildcard(_) if !taken.path.is_empty() => {
+                let discarded = Symbol::from(format!("{name} _"));
+                bindings.push((var(&discarded, &location), part(taken)));
+                continue;
+            }
+            ast::Pattern::Wildcard(_) | ast::Pattern::Tuple(_) => continue,
+        };
+        tests.push(Term::Binary(ast::Binary {
+            lhs: Box::new(part(taken)),
+            op: ast::BinaryOp::Eq,
+            rhs: Box::new(literal),
+            location,
+        }));
+    }
+    let bind = |term: Term| path::with_definitions(term, bindings.clone());
+    let tests = tests.into_iter().reduce(|lhs, rhs| {
+        let location = rhs.location().clone();
+        Term::Binary(ast::Binary {
+            lhs: Box::new(lhs),
+            op: ast::BinaryOp::And,
+            rhs: Box::new(rhs),
+            location,
+        })
+    });
+    // The guard keeps its location, a guard that isn't a Bool fails there.
+    let condition = match (tests, arm.guard) {
+        (None, None) => return bind(arm.body),
+        (Some(tests), None) => tests,
+        (None, Some(guard)) => bind(guard),
+        (Some(tests), Some(guard)) => {
+            let location = guard.location().clone();
+            Term::If(ast::If {
+                condition: Box::new(tests),
+                then: Box::new(bind(guard)),
+                otherwise: Box::new(Term::Bool(ast::Bool {
+                    value: false,
+                    location: location.clone(),
+                })),
+                location,
+            })
+        }
+    };
+    Term::If(ast::If {
+        condition: Box::new(condition),
+        then: Box::new(bind(arm.body)),
+        otherwise: Box::new(rest),
+        location: arm.pattern.location().clone(),
+    })
+}
+
+/// Rewrites every term after its children, with `f`. The chains of `let`
+/// are walked in a loop, the generated programs can have hundreds of
+/// thousands of them.
+fn rewrite(term: Term, f: &mut impl FnMut(Term) -> Term) -> Term {
+    let mut chain = Vec::new();
+    let mut term = term;
+    while let Term::Let(mut let_) = term {
+        term = std::mem::replace(&mut *let_.next, unit());
+        *let_.value = rewrite(*let_.value, f);
+        chain.push(let_);
+    }
+    let term = rewrite_children(term, f);
+    let mut term = f(term);
+    while let Some(mut let_) = chain.pop() {
+        *let_.next = term;
+        term = f(Term::Let(let_));
+    }
+    term
+}
+
+fn rewrite_children(term: Term, f: &mut impl FnMut(Term) -> Term) -> Term {
+    let mut boxed = |term: Box<Term>| Box::new(rewrite(*term, f));
+    match term {
+        Term::Call(call) => {
+            let callee = boxed(call.callee);
+            Term::Call(ast::Call {
+                callee,
+                arguments: call
+                    .arguments
+                    .into_iter()
+                    .map(|argument| rewrite(argument, f))
+                    .collect(),
+                location: call.location,
+            })
+        }
+        Term::Binary(binary) => Term::Binary(ast::Binary {
+            lhs: boxed(binary.lhs),
+            rhs: boxed(binary.rhs),
+            ..binary
+        }),
+        Term::Function(function) => Term::Function(ast::Function {
+            value: Arc::new(rewrite(Arc::unwrap_or_clone(function.value), f)),
+            ..function
+        }),
+        Term::If(if_) => Term::If(ast::If {
+            condition: boxed(if_.condition),
+            then: boxed(if_.then),
+            otherwise: boxed(if_.otherwise),
+            location: if_.location,
+        }),
+        Term::Print(print) => Term::Print(ast::Print {
+            value: boxed(print.value),
+            location: print.location,
+        }),
+        Term::First(first) => Term::First(ast::First {
+            value: boxed(first.value),
+            location: first.location,
+        }),
+        Term::Second(second) => Term::Second(ast::Second {
+            value: boxed(second.value),
+            location: second.location,
+        }),
+        Term::Tuple(tuple) => Term::Tuple(ast::Tuple {
+            first: boxed(tuple.first),
+            second: boxed(tuple.second),
+            location: tuple.location,
+        }),
+        Term::Access(access) => Term::Access(ast::Access {
+            value: boxed(access.value),
+            ..access
+        }),
+        Term::Record(record) => Term::Record(ast::Record {
+            fields: record
+                .fields
+                .into_iter()
+                .map(|field| ast::Field {
+                    value: rewrite(field.value, f),
+                    ..field
+                })
+                .collect(),
+            location: record.location,
+        }),
+        Term::Match(match_) => Term::Match(ast::Match {
+            value: boxed(match_.value),
+            arms: match_
+                .arms
+                .into_iter()
+                .map(|arm| ast::Arm {
+                    guard: arm.guard.map(|guard| rewrite(guard, f)),
+                    body: rewrite(arm.body, f),
+                    pattern: arm.pattern,
+                })
+                .collect(),
+            location: match_.location,
+        }),
+        term => term,
+    }
+}
+
+/// A placeholder while a term is moved out of its parent.
+fn unit() -> Term {
+    Term::Bool(ast::Bool::default())
+}
+
+/// The value of a literal the operators can be folded on. The floats are
+/// left alone, they fail without the extensions.
+fn literal(term: &Term) -> Option<Primitive> {
+    match term {
+        Term::Int(int) => Some(Primitive::Int(int.value.into())),
+        Term::Str(str) => Some(Primitive::Str(str.value.clone())),
+        Term::Bool(bool) => Some(Primitive::Bool(bool.value)),
+        _ => None,
+    }
+}
+
+/// The literal of a value, `None` for the integers a literal can't hold.
+fn to_term(value: Primitive, location: ast::Location) -> Option<Term> {
+    Some(match value {
+        Primitive::Int(value) => Term::Int(ast::Int {
+            value: value.try_into().ok()?,
+            location,
+        }),
+        Primitive::Str(value) => Term::Str(ast::Str { value, location }),
+        Primitive::Bool(value) => Term::Bool(ast::Bool { value, location }),
+        _ => return None,
+    })
+}
+
+fn fold(term: Term, overflow: Overflow) -> Term {
+    let Term::Binary(binary) = term else {
+        return term;
+    };
+    let Some(left) = literal(&binary.lhs) else {
+        return Term::Binary(binary);
+    };
+    // The right operand isn't evaluated at all.
+    if binary.op.short_circuits(&left) {
+        return *binary.lhs;
+    }
+    let Some(right) = literal(&binary.rhs) else {
+        return Term::Binary(binary);
+    };
+    match binary.op.apply(left, right, overflow) {
+        Ok(value) => to_term(value, binary.location.clone()).unwrap_or(Term::Binary(binary)),
+        Err(_) => Term::Binary(binary),
+    }
+}
+
+fn prune(term: Term) -> Term {
+    match term {
+        Term::If(if_) => match *if_.condition {
+            Term::Bool(ast::Bool { value: true, .. }) => *if_.then,
+            Term::Bool(ast::Bool { value: false, .. }) => *if_.otherwise,
+            condition => Term::If(ast::If {
+                condition: Box::new(condition),
+                ..if_
+            }),
+        },
+        term => term,
+    }
+}
+
+/// Whether evaluating the term can't print, fail or loop, so it can be
+/// left out when its value isn't used.
+fn is_pure(term: &Term) -> bool {
+    match term {
+        Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Function(_) => true,
+        Term::Tuple(tuple) => is_pure(&tuple.first) && is_pure(&tuple.second),
+        _ => false,
+    }
+}
+
+/// Drops the unused `let` inside the term, returning it with the variables
+/// it uses without binding them.
+fn drop_unused(term: Term) -> (Term, HashSet<Symbol>) {
+    let mut chain = Vec::new();
+    let mut term = term;
+    while let Term::Let(mut let_) = term {
+        term = std::mem::replace(&mut *let_.next, unit());
+        chain.push(let_);
+    }
+    let (mut term, mut free) = drop_unused_children(term);
+    while let Some(mut let_) = chain.pop() {
+        let name = &let_.name.text;
+        if !free.contains(name) && is_pure(&let_.value) {
+            continue;
+        }
+        let (value, mut value_free) = drop_unused(std::mem::replace(&mut *let_.value, unit()));
+        // The functions see the variable they're bound to, the other
+        // values see the one of an outer `let`.
+        if let Term::Function(_) = value {
+            value_free.remove(name);
+        }
+        free.remove(name);
+        free.extend(value_free);
+        *let_.value = value;
+        *let_.next = term;
+        term = Term::Let(let_);
+    }
+    (term, free)
+}
+
+fn drop_unused_children(term: Term) -> (Term, HashSet<Symbol>) {
+    let term = match term {
+        Term::Var(var) => {
+            let free = HashSet::from([var.text.clone()]);
+            return (Term::Var(var), free);
+        }
+        Term::Function(function) => {
+            let (body, mut free) = drop_unused(Arc::unwrap_or_clone(function.value));
+            for parameter in &function.parameters {
+                free.remove(&parameter.text);
+            }
+            let function = ast::Function {
+                value: Arc::new(body),
+                ..function
+            };
+            return (Term::Function(function), free);
+        }
+        term => term,
+    };
+    let mut free = HashSet::new();
+    let mut visit = |term: Term| {
+        let (term, used) = drop_unused(term);
+        free.extend(used);
+        term
+    };
+    let term = match term {
+        Term::Call(call) => Term::Call(ast::Call {
+            callee: Box::new(visit(*call.callee)),
+            arguments: call.arguments.into_iter().map(&mut visit).collect(),
+            location: call.location,
+        }),
+        Term::Binary(binary) => Term::Binary(ast::Binary {
+            lhs: Box::new(visit(*binary.lhs)),
+            rhs: Box::new(visit(*binary.rhs)),
+            ..binary
+        }),
+        Term::If(if_) => Term::If(ast::If {
+            condition: Box::new(visit(*if_.condition)),
+            then: Box::new(visit(*if_.then)),
+            otherwise: Box::new(visit(*if_.otherwise)),
+            location: if_.location,
+        }),
+        Term::Print(print) => Term::Print(ast::Print {
+            value: Box::new(visit(*print.value)),
+            location: print.location,
+        }),
+        Term::First(first) => Term::First(ast::First {
+            value: Box::new(visit(*first.value)),
+            location: first.location,
+        }),
+        Term::Second(second) => Term::Second(ast::Second {
+            value: Box::new(visit(*second.value)),
+            location: second.location,
+        }),
+        Term::Tuple(tuple) => Term::Tuple(ast::Tuple {
+            first: Box::new(visit(*tuple.first)),
+            second: Box::new(visit(*tuple.second)),
+            location: tuple.location,
+        }),
+        Term::Access(access) => Term::Access(ast::Access {
+            value: Box::new(visit(*access.value)),
+            ..access
+        }),
+        Term::Record(record) => Term::Record(ast::Record {
+            fields: record
+                .fields
+                .into_iter()
+                .map(|field| ast::Field {
+                    value: visit(field.value),
+                    ..field
+                })
+                .collect(),
+            location: record.location,
+        }),
+        Term::Match(match_) => {
+            let value = Box::new(visit(*match_.value));
+            let arms = match_.arms.into_iter().map(|arm| {
+                let (guard, guard_free) = match arm.guard.map(drop_unused) {
+                    Some((guard, free)) => (Some(guard), free),
+                    None => (None, HashSet::new()),
+                };
+                let (body, mut arm_free) = drop_unused(arm.body);
+                arm_free.extend(guard_free);
+                let mut bound = Vec::new();
+                path::bind_pattern(&arm.pattern, &mut bound);
+                for name in &bound {
+                    arm_free.remove(name);
+                }
+                free.extend(arm_free);
+                ast::Arm {
+                    pattern: arm.pattern,
+                    guard,
+                    body,
+                }
+            });
+            Term::Match(ast::Match {
+                value,
+                arms: arms.collect(),
+                location: match_.location,
+            })
+        }
+        term => term,
+    };
+    (term, free)
+}
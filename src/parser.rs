@@ -1,4 +1,8 @@
-use crate::ast::{Element, Location};
+use crate::{
+    ast::{Element, Location},
+    resolve::Address,
+    runtime::symbol::Symbol,
+};
 
 use miette::{NamedSource, SourceSpan};
 
@@ -10,8 +14,11 @@ pub use crate::rinha::*;
 /// and the name itself to be resolved later.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Var {
-    pub text: String,
+    pub text: Symbol,
     pub location: Location,
+    /// Where its value is, set by [`crate::resolve`].
+    #[serde(skip)]
+    pub address: Address,
 }
 
 impl Element for Var {
@@ -76,17 +83,13 @@ pub enum InnerError {
         err_span: SourceSpan,
     },
 
-    #[error("record index isn't supported yet")]
-    #[diagnostic(
-        code(zu::unsupported_record_index),
-        url(docsrs),
-        help("maybe open a PR")
-    )]
-    UnsupportedRecordIndex {
-        /// The expression that is a record index. It's the span of the
-        /// expression that will be pointed in the error message.
-        ///
-        /// It's not supported yet, but it will be implemented in the future.
+    #[error("the field `{name}` is given twice")]
+    #[diagnostic(code(zu::duplicate_field), url(docsrs))]
+    DuplicateField {
+        name: String,
+
+        /// The second time the field is given. It's the span of the name
+        /// that will be pointed in the error message.
         #[label = "here"]
         err_span: SourceSpan,
     },
@@ -165,14 +168,35 @@ fn fmt_expected(expected: &[String]) -> String {
     f
 }
 
+/// Turns a leading shebang line (`#!/usr/bin/env interpreter`) into a line
+/// comment, so executable scripts can be parsed.
+///
+/// Both markers have two bytes, so every location after it stays the same
+/// and diagnostics keep pointing at the right lines.
+fn mask_shebang(text: &str) -> std::borrow::Cow<'_, str> {
+    match text.strip_prefix("#!") {
+        Some(rest) => format!("//{rest}").into(),
+        None => text.into(),
+    }
+}
+
 /// Parses or report the error. It takes a filename to report errors and locations
 /// pointing to the file in the error message.
 ///
 /// It does return a result of a parsed file or a parse error, that can contain a
 /// lot of sub-errors.
+///
+/// ```
+/// use rinha::{ast::Term, parser};
+///
+/// let file = parser::parse_or_report("main.rinha", "print(1)").unwrap();
+/// assert!(matches!(file.expression, Term::Print(_)));
+/// assert!(parser::parse_or_report("main.rinha", "print(").is_err());
+/// ```
 pub fn parse_or_report(filename: &str, text: &str) -> Result<crate::ast::File, ParseError> {
+    let source = mask_shebang(text);
     let mut errors = vec![];
-    let ast = match crate::rinha::FileParser::new().parse(&mut errors, filename, text) {
+    let ast = match crate::rinha::FileParser::new().parse(&mut errors, filename, &source) {
         Ok(ast) => ast,
         Err(error) => {
             // Build up the list with at least one recovery error.
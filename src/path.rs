@@ -0,0 +1,352 @@
+use crate::{
+    ast::{self, Element, Term},
+    runtime::symbol::Symbol,
+};
+use std::collections::HashSet;
+
+/// A path that doesn't lead to a term.
+#[derive(miette::Diagnostic, thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[diagnostic(code(rinha::path))]
+pub enum PathError {
+    #[error("paths start at `expression`, but `{0}` starts with `{1}`")]
+    BadRoot(String, String),
+
+    #[error("`{prefix}` is a {kind} term, it has no `{segment}`")]
+    #[diagnostic(help("the terms inside a {kind} are: {fields}"))]
+    NoSuchField {
+        prefix: String,
+        kind: &'static str,
+        segment: String,
+        fields: &'static str,
+    },
+
+    #[error("`{prefix}` has {len} arguments, there's no `{segment}`")]
+    NoSuchArgument {
+        prefix: String,
+        segment: String,
+        len: usize,
+    },
+
+    #[error("`{prefix}` has {len} fields, there's no `{segment}`")]
+    NoSuchRecordField {
+        prefix: String,
+        segment: String,
+        len: usize,
+    },
+
+    #[error("`{prefix}` has {len} arms, there's no `{segment}`")]
+    NoSuchArm {
+        prefix: String,
+        segment: String,
+        len: usize,
+    },
+}
+
+/// The kind of the term, as written in the JSON AST.
+pub fn kind(term: &Term) -> &'static str {
+    match term {
+        Term::Error(_) => "Error",
+        Term::Int(_) => "Int",
+        Term::Float(_) => "Float",
+        Term::Str(_) => "Str",
+        Term::Call(_) => "Call",
+        Term::Binary(_) => "Binary",
+        Term::Function(_) => "Function",
+        Term::Let(_) => "Let",
+        Term::If(_) => "If",
+        Term::Print(_) => "Print",
+        Term::First(_) => "First",
+        Term::Second(_) => "Second",
+        Term::Bool(_) => "Bool",
+        Term::Tuple(_) => "Tuple",
+        Term::Record(_) => "Record",
+        Term::Access(_) => "Access",
+        Term::Match(_) => "Match",
+        Term::Var(_) => "Var",
+    }
+}
+
+/// Finds the term at `path`, the names of the fields of the JSON AST joined
+/// by dots, with the arguments of the calls, the fields of the records and
+/// the arms of the matches picked by their index.
+///
+/// ```
+/// use rinha::{ast::Term, parser, path};
+///
+/// let file = parser::parse_or_report("main.rinha", "let f = fn (x) => { x + 1 }; f(2)").unwrap();
+/// let body = path::select(&file, "expression.value.value").unwrap();
+/// assert!(matches!(body, Term::Binary(_)));
+///
+/// let argument = path::select(&file, "expression.next.arguments.0").unwrap();
+/// assert!(matches!(argument, Term::Int(_)));
+/// ```
+pub fn select<'a>(file: &'a ast::File, path: &str) -> Result<&'a Term, PathError> {
+    let mut segments = path.split('.');
+    let root = segments.next().unwrap_or_default();
+    if root != "expression" {
+        return Err(PathError::BadRoot(path.into(), root.into()));
+    }
+
+    let mut term = &file.expression;
+    let mut prefix = String::from(root);
+    while let Some(segment) = segments.next() {
+        if let (Term::Call(call), "arguments") = (term, segment) {
+            let Some(index) = segments.next() else {
+                return Err(no_such_field(prefix, term, segment));
+            };
+            let argument = index
+                .parse()
+                .ok()
+                .and_then(|i: usize| call.arguments.get(i));
+            let Some(argument) = argument else {
+                return Err(PathError::NoSuchArgument {
+                    prefix,
+                    segment: index.into(),
+                    len: call.arguments.len(),
+                });
+            };
+            term = argument;
+            prefix = format!("{prefix}.arguments.{index}");
+            continue;
+        }
+        if let (Term::Record(record), "fields") = (term, segment) {
+            let (Some(index), Some("value")) = (segments.next(), segments.next()) else {
+                return Err(no_such_field(prefix, term, segment));
+            };
+            let field = index.parse().ok().and_then(|i: usize| record.fields.get(i));
+            let Some(field) = field else {
+                return Err(PathError::NoSuchRecordField {
+                    prefix,
+                    segment: index.into(),
+                    len: record.fields.len(),
+                });
+            };
+            term = &field.value;
+            prefix = format!("{prefix}.fields.{index}.value");
+            continue;
+        }
+        if let (Term::Match(match_), "arms") = (term, segment) {
+            let (Some(index), Some(field)) = (segments.next(), segments.next()) else {
+                return Err(no_such_field(prefix, term, segment));
+            };
+            let Some(arm) = index.parse().ok().and_then(|i: usize| match_.arms.get(i)) else {
+                return Err(PathError::NoSuchArm {
+                    prefix,
+                    segment: index.into(),
+                    len: match_.arms.len(),
+                });
+            };
+            term = match (field, &arm.guard) {
+                ("body", _) => &arm.body,
+                ("guard", Some(guard)) => guard,
+                _ => return Err(no_such_field(prefix, term, segment)),
+            };
+            prefix = format!("{prefix}.arms.{index}.{field}");
+            continue;
+        }
+
+        term = match (term, segment) {
+            (Term::Call(call), "callee") => &call.callee,
+            (Term::Binary(binary), "lhs") => &binary.lhs,
+            (Term::Binary(binary), "rhs") => &binary.rhs,
+            (Term::Function(function), "value") => &function.value,
+            (Term::Let(let_), "value") => &let_.value,
+            (Term::Let(let_), "next") => &let_.next,
+            (Term::If(if_), "condition") => &if_.condition,
+            (Term::If(if_), "then") => &if_.then,
+            (Term::If(if_), "otherwise") => &if_.otherwise,
+            (Term::Print(print), "value") => &print.value,
+            (Term::First(first), "value") => &first.value,
+            (Term::Second(second), "value") => &second.value,
+            (Term::Tuple(tuple), "first") => &tuple.first,
+            (Term::Tuple(tuple), "second") => &tuple.second,
+            (Term::Access(access), "value") => &access.value,
+            (Term::Match(match_), "value") => &match_.value,
+            _ => return Err(no_such_field(prefix, term, segment)),
+        };
+        prefix = format!("{prefix}.{segment}");
+    }
+    Ok(term)
+}
+
+fn no_such_field(prefix: String, term: &Term, segment: &str) -> PathError {
+    let fields = match term {
+        Term::Call(_) => "callee, arguments.N",
+        Term::Binary(_) => "lhs, rhs",
+        Term::Function(_) | Term::Print(_) | Term::First(_) | Term::Second(_) => "value",
+        Term::Access(_) => "value",
+        Term::Record(_) => "fields.N.value",
+        Term::Match(_) => "value, arms.N.guard, arms.N.body",
+        Term::Let(_) => "value, next",
+        Term::If(_) => "condition, then, otherwise",
+        Term::Tuple(_) => "first, second",
+        _ => "none",
+    };
+    PathError::NoSuchField {
+        prefix,
+        kind: kind(term),
+        segment: segment.into(),
+        fields,
+    }
+}
+
+/// The variables used by the term without being bound inside of it, in
+/// the order they're first used.
+///
+/// ```
+/// use rinha::{parser, path};
+///
+/// let file = parser::parse_or_report("main.rinha", "let y = x + 1; f(y, z)").unwrap();
+/// let unbound: Vec<_> = path::unbound(&file.expression).into_iter().map(|var| var.text).collect();
+/// assert_eq!(unbound, ["x", "f", "z"]);
+/// ```
+pub fn unbound(term: &Term) -> Vec<crate::parser::Var> {
+    let mut unbound = Unbound::default();
+    unbound.visit(term);
+    unbound.found
+}
+
+#[derive(Default)]
+struct Unbound {
+    bound: Vec<Symbol>,
+    seen: HashSet<Symbol>,
+    found: Vec<crate::parser::Var>,
+}
+
+impl Unbound {
+    fn visit(&mut self, term: &Term) {
+        match term {
+            Term::Var(var) => {
+                let bound = self.bound.contains(&var.text);
+                if !bound && self.seen.insert(var.text.clone()) {
+                    self.found.push(var.clone());
+                }
+            }
+            Term::Let(let_) => {
+                // The functions can call themselves through their binding.
+                self.bound.push(let_.name.text.clone());
+                self.visit(&let_.value);
+                self.visit(&let_.next);
+                self.bound.pop();
+            }
+            Term::Function(function) => {
+                let parameters = function.parameters.iter().map(|p| p.text.clone());
+                let len = self.bound.len();
+                self.bound.extend(parameters);
+                self.visit(&function.value);
+                self.bound.truncate(len);
+            }
+            Term::Call(call) => {
+                self.visit(&call.callee);
+                for argument in &call.arguments {
+                    self.visit(argument);
+                }
+            }
+            Term::Binary(binary) => {
+                self.visit(&binary.lhs);
+                self.visit(&binary.rhs);
+            }
+            Term::If(if_) => {
+                self.visit(&if_.condition);
+                self.visit(&if_.then);
+                self.visit(&if_.otherwise);
+            }
+            Term::Tuple(tuple) => {
+                self.visit(&tuple.first);
+                self.visit(&tuple.second);
+            }
+            Term::Print(print) => self.visit(&print.value),
+            Term::First(first) => self.visit(&first.value),
+            Term::Second(second) => self.visit(&second.value),
+            Term::Record(record) => {
+                for field in &record.fields {
+                    self.visit(&field.value);
+                }
+            }
+            Term::Access(access) => self.visit(&access.value),
+            Term::Match(match_) => {
+                self.visit(&match_.value);
+                for arm in &match_.arms {
+                    let len = self.bound.len();
+                    bind_pattern(&arm.pattern, &mut self.bound);
+                    if let Some(guard) = &arm.guard {
+                        self.visit(guard);
+                    }
+                    self.visit(&arm.body);
+                    self.bound.truncate(len);
+                }
+            }
+            Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => {}
+        }
+    }
+}
+
+/// Pushes the names the pattern binds.
+pub(crate) fn bind_pattern(pattern: &ast::Pattern, bound: &mut Vec<Symbol>) {
+    match pattern {
+        ast::Pattern::Var(var) => bound.push(var.text.clone()),
+        ast::Pattern::Tuple(tuple) => {
+            bind_pattern(&tuple.first, bound);
+            bind_pattern(&tuple.second, bound);
+        }
+        _ => {}
+    }
+}
+
+/// The location of the first `print` of the term, in the order of the
+/// source, counting the ones inside nested functions.
+///
+/// ```
+/// use rinha::{parser, path};
+///
+/// let file = parser::parse_or_report("main.rinha", "let f = fn (x) => { print(x) }; f(1)").unwrap();
+/// assert_eq!(path::first_print(&file.expression).unwrap().start, 20);
+/// ```
+pub fn first_print(term: &Term) -> Option<&ast::Location> {
+    let children: Vec<&Term> = match term {
+        Term::Print(print) => return Some(&print.location),
+        Term::Let(let_) => vec![&let_.value, &let_.next],
+        Term::Function(function) => vec![&function.value],
+        Term::Call(call) => std::iter::once(&*call.callee)
+            .chain(&call.arguments)
+            .collect(),
+        Term::Binary(binary) => vec![&binary.lhs, &binary.rhs],
+        Term::If(if_) => vec![&if_.condition, &if_.then, &if_.otherwise],
+        Term::Tuple(tuple) => vec![&tuple.first, &tuple.second],
+        Term::First(first) => vec![&first.value],
+        Term::Second(second) => vec![&second.value],
+        Term::Record(record) => record.fields.iter().map(|field| &field.value).collect(),
+        Term::Access(access) => vec![&access.value],
+        Term::Match(match_) => std::iter::once(&*match_.value)
+            .chain(
+                match_
+                    .arms
+                    .iter()
+                    .flat_map(|arm| arm.guard.iter().chain([&arm.body])),
+            )
+            .collect(),
+        Term::Error(_)
+        | Term::Var(_)
+        | Term::Int(_)
+        | Term::Float(_)
+        | Term::Str(_)
+        | Term::Bool(_) => vec![],
+    };
+    children.into_iter().find_map(first_print)
+}
+
+/// Wraps the term in a `let` for each definition, the first one outermost.
+pub fn with_definitions(term: Term, definitions: Vec<(crate::parser::Var, Term)>) -> Term {
+    definitions
+        .into_iter()
+        .rev()
+        .fold(term, |next, (name, value)| {
+            let location = next.location().clone();
+            Term::Let(ast::Let {
+                name,
+                value: Box::new(value),
+                next: Box::new(next),
+                location,
+            })
+        })
+}
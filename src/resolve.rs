@@ -0,0 +1,257 @@
+//! Resolves the variables of a program to the slots of the frames their
+//! values are in, before the interpreter runs it. Each function body, and
+//! the top level expression, runs in a frame of its own: the function
+//! itself is in the first slot, its parameters in the next ones and then
+//! the `let` and the variables of the patterns of the body, each in a slot
+//! of its own. The variables a body uses and doesn't bind are captured
+//! where the function is made, into the frame of its closure. Reading a
+//! variable is then reading a slot, without hashing its name nor walking
+//! the frames.
+//!
+//! The variables the program doesn't bind are left to be looked up by
+//! name, like the ones an embedder binds in the scope it runs the program
+//! in.
+
+use crate::{
+    ast::{Pattern, Term},
+    names::FunctionNames,
+    runtime::symbol::Symbol,
+};
+use std::sync::Arc;
+
+/// Where the value of a variable is, see [`resolve`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Address {
+    /// Looked up by name, the variables the program doesn't bind and the
+    /// ones of the terms that weren't resolved.
+    #[default]
+    Named,
+    /// A slot of the frame of the function body, or of the top level
+    /// expression.
+    Local(usize),
+    /// A slot of the frame of the closure, the variables the function
+    /// captured.
+    Captured(usize),
+}
+
+/// The slots of a function body, or of the top level expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The name of each slot of a frame: the function, named like
+    /// [`FunctionNames`] does, its parameters and the `let` of the body.
+    /// The top level expression only has its `let`.
+    pub slots: Arc<Vec<Symbol>>,
+    /// The variables the function captures, in the order of the slots of
+    /// its closure, with where they are where the function is made.
+    pub captures: Vec<(Symbol, Address)>,
+}
+
+/// The layouts of the frames of a resolved program.
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    /// The frame of the top level expression.
+    pub top: Layout,
+    /// The frames of the function bodies of the resolved term, with the
+    /// bodies. They're told apart by their address, the terms built by hand
+    /// can share their locations.
+    pub functions: Vec<(Arc<Term>, Layout)>,
+}
+
+/// Sets the [`Address`] of every variable of `term`, and of the names its
+/// `let` and its parameters bind, returning the layouts of its frames. The
+/// functions are named by `names`, so their bodies call themselves through
+/// their first slot.
+///
+/// ```
+/// use rinha::{ast::Term, names::FunctionNames, parser, resolve::{self, Address}};
+///
+/// let source = "let y = 1; let f = fn (x) => { x + y }; f(y)";
+/// let mut file = parser::parse_or_report("main.rinha", source).unwrap();
+/// let names = FunctionNames::new(&file.expression, None);
+/// let resolution = resolve::resolve(&mut file.expression, &names);
+///
+/// assert_eq!(*resolution.top.slots, ["y", "f"]);
+/// let (_, layout) = &resolution.functions[0];
+/// assert_eq!(*layout.slots, ["f", "x"]);
+/// assert_eq!(layout.captures, [("y".into(), Address::Local(0))]);
+/// ```
+pub fn resolve(term: &mut Term, names: &FunctionNames) -> Resolution {
+    let mut resolver = Resolver {
+        names,
+        bodies: vec![Body::default()],
+        functions: Vec::new(),
+    };
+    resolver.visit(term);
+    let top = resolver.bodies.pop().unwrap_or_default();
+    Resolution {
+        top: Layout {
+            slots: Arc::new(top.slots),
+            captures: top.captures,
+        },
+        functions: resolver.functions,
+    }
+}
+
+/// A function body, or the top level expression, being resolved.
+#[derive(Default)]
+struct Body {
+    slots: Vec<Symbol>,
+    /// The variables in scope and their slots, the innermost last.
+    scope: Vec<(Symbol, usize)>,
+    captures: Vec<(Symbol, Address)>,
+}
+
+impl Body {
+    /// A slot for a new variable, in scope from now on.
+    fn bind(&mut self, name: &Symbol) -> usize {
+        let slot = self.slots.len();
+        self.slots.push(name.clone());
+        self.scope.push((name.clone(), slot));
+        slot
+    }
+}
+
+struct Resolver<'a> {
+    names: &'a FunctionNames,
+    /// The bodies being resolved, the innermost last. The first one is the
+    /// top level expression.
+    bodies: Vec<Body>,
+    functions: Vec<(Arc<Term>, Layout)>,
+}
+
+impl Resolver<'_> {
+    /// Where `name` is for the body at `depth`, capturing it into the
+    /// functions between where it's bound and there.
+    fn lookup(&mut self, name: &Symbol, depth: usize) -> Address {
+        let body = &self.bodies[depth];
+        if let Some((_, slot)) = body.scope.iter().rev().find(|(bound, _)| bound == name) {
+            return Address::Local(*slot);
+        }
+        if let Some(slot) = body
+            .captures
+            .iter()
+            .position(|(captured, _)| captured == name)
+        {
+            return Address::Captured(slot);
+        }
+        // The top level expression doesn't capture, what it doesn't bind is
+        // the embedder's.
+        if depth == 0 {
+            return Address::Named;
+        }
+        let from = self.lookup(name, depth - 1);
+        let captures = &mut self.bodies[depth].captures;
+        captures.push((name.clone(), from));
+        Address::Captured(captures.len() - 1)
+    }
+
+    fn body(&mut self) -> &mut Body {
+        self.bodies
+            .last_mut()
+            .expect("the top level expression is a body")
+    }
+
+    fn bind_pattern(&mut self, pattern: &mut Pattern) {
+        match pattern {
+            Pattern::Var(var) => var.address = Address::Local(self.body().bind(&var.text)),
+            Pattern::Tuple(tuple) => {
+                self.bind_pattern(&mut tuple.first);
+                self.bind_pattern(&mut tuple.second);
+            }
+            Pattern::Wildcard(_) | Pattern::Int(_) | Pattern::Str(_) | Pattern::Bool(_) => {}
+        }
+    }
+
+    fn visit(&mut self, term: &mut Term) {
+        match term {
+            Term::Var(var) => var.address = self.lookup(&var.text, self.bodies.len() - 1),
+            Term::Let(let_) => {
+                let len = self.body().scope.len();
+                // The chains of `let` are walked in a loop, they can be far
+                // longer than the stack allows to recurse.
+                let mut let_ = let_;
+                loop {
+                    self.visit(&mut let_.value);
+                    let slot = self.body().bind(&let_.name.text);
+                    let_.name.address = Address::Local(slot);
+                    match &mut *let_.next {
+                        Term::Let(next) => let_ = next,
+                        next => {
+                            self.visit(next);
+                            break;
+                        }
+                    }
+                }
+                self.body().scope.truncate(len);
+            }
+            Term::Function(function) => {
+                let name = match self.names.get(&function.location) {
+                    Some(name) => Symbol::intern(name),
+                    None => Symbol::from(format!(
+                        "anon@{}:{}",
+                        function.location.filename, function.location.start
+                    )),
+                };
+                let mut body = Body::default();
+                body.bind(&name);
+                for parameter in &mut function.parameters {
+                    parameter.address = Address::Local(body.bind(&parameter.text));
+                }
+                self.bodies.push(body);
+                self.visit(Arc::make_mut(&mut function.value));
+                let body = self.bodies.pop().expect("the body was pushed");
+                self.functions.push((
+                    function.value.clone(),
+                    Layout {
+                        slots: Arc::new(body.slots),
+                        captures: body.captures,
+                    },
+                ));
+            }
+            Term::Call(call) => {
+                self.visit(&mut call.callee);
+                for argument in &mut call.arguments {
+                    self.visit(argument);
+                }
+            }
+            Term::Binary(binary) => {
+                self.visit(&mut binary.lhs);
+                self.visit(&mut binary.rhs);
+            }
+            Term::If(if_) => {
+                self.visit(&mut if_.condition);
+                self.visit(&mut if_.then);
+                self.visit(&mut if_.otherwise);
+            }
+            Term::Tuple(tuple) => {
+                self.visit(&mut tuple.first);
+                self.visit(&mut tuple.second);
+            }
+            Term::Print(print) => self.visit(&mut print.value),
+            Term::First(first) => self.visit(&mut first.value),
+            Term::Second(second) => self.visit(&mut second.value),
+            // The names of the fields aren't variables.
+            Term::Record(record) => {
+                for field in &mut record.fields {
+                    self.visit(&mut field.value);
+                }
+            }
+            Term::Access(access) => self.visit(&mut access.value),
+            // The variables of each pattern are in scope in its guard and
+            // its body.
+            Term::Match(match_) => {
+                self.visit(&mut match_.value);
+                for arm in &mut match_.arms {
+                    let len = self.body().scope.len();
+                    self.bind_pattern(&mut arm.pattern);
+                    if let Some(guard) = &mut arm.guard {
+                        self.visit(guard);
+                    }
+                    self.visit(&mut arm.body);
+                    self.body().scope.truncate(len);
+                }
+            }
+            Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => {}
+        }
+    }
+}
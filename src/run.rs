@@ -0,0 +1,658 @@
+use crate::{
+    ast, format,
+    input::{self, Arg},
+    interpreter::{
+        ErrorKind, Frame, ImpureRecursion, IntFormat, Interpreter, Overflow, Primitive,
+        PrintThresholds, PrintWarning, Progress, RuntimeError, Scope, SequentialPair, Stats,
+    },
+    memo::{self, ProgramKey, SharedMemo},
+    names::FunctionNames,
+    optimize::{self, OptLevel},
+    parser,
+    vm::Vm,
+};
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Once,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The settings of a run.
+#[derive(Clone)]
+pub struct RunConfig {
+    /// The name of the program, used in the locations of the diagnostics.
+    pub filename: String,
+
+    /// The engine that runs the program.
+    pub backend: Backend,
+
+    /// Whether the function calls are memoized.
+    pub memoize: bool,
+
+    /// The most memo entries kept, see [`Interpreter::memo_capacity`].
+    pub memo_capacity: usize,
+
+    /// Whether producing a unit value is an error, see
+    /// [`Interpreter::assert_none`].
+    pub assert_none: bool,
+
+    /// Where `print` writes to, the standard output when it's `None`.
+    pub output: Option<Rc<RefCell<dyn io::Write>>>,
+
+    /// How `print` renders the integers.
+    pub int_format: IntFormat,
+
+    /// What the arithmetic does when the results don't fit.
+    pub overflow: Overflow,
+
+    /// Whether the natives outside of the specification are provided, see
+    /// [`Interpreter::extensions`].
+    pub extensions: bool,
+
+    /// The passes run on the program before running it, see
+    /// [`crate::optimize`].
+    pub opt_level: OptLevel,
+
+    /// The variables the program starts with, see [`input::bind_args`].
+    pub args: Vec<Arg>,
+
+    /// Whether the values are evaluated once they're needed, see
+    /// [`Interpreter::lazy`]. Only the tree-walking interpreter does it.
+    pub lazy: bool,
+
+    /// Told about the `par_pair` calls that ran sequentially, see
+    /// [`Interpreter::on_sequential_pair`].
+    pub on_sequential_pair: Option<Rc<OnSequentialPair>>,
+
+    /// Told about the recursive functions the prints kept out of the memo,
+    /// see [`Interpreter::on_impure_recursion`].
+    pub on_impure_recursion: Option<Rc<OnImpureRecursion>>,
+
+    /// The functions of the embedder the program can call by their name,
+    /// see [`Interpreter::register_builtin`]. Only the tree-walking
+    /// interpreter calls them.
+    pub builtins: Vec<(String, Rc<OnBuiltin>)>,
+
+    /// Other places that receive a copy of everything printed.
+    pub tees: Vec<Rc<RefCell<dyn io::Write>>>,
+
+    /// The most bytes the program can print, see
+    /// [`Interpreter::max_output_bytes`].
+    pub max_output_bytes: Option<u64>,
+
+    /// The most calls in progress at once, see
+    /// [`Interpreter::max_depth`].
+    pub max_depth: Option<u64>,
+
+    /// The most bytes of native stack the calls of the tree-walking
+    /// interpreter take, see [`Interpreter::max_stack`].
+    pub max_stack: Option<usize>,
+
+    /// The most terms evaluated, or instructions run by the bytecode, see
+    /// [`Interpreter::max_steps`].
+    pub max_steps: Option<u64>,
+
+    /// How long the run can take, it stops with [`ErrorKind::Timeout`] at
+    /// the first call after it. A thread watches the clock, the engines
+    /// only check a flag like they do for `interrupt`.
+    pub timeout: Option<Duration>,
+
+    /// The memo entries shared between the runs of the same program, only
+    /// used when memoizing.
+    pub shared_memo: Option<Arc<SharedMemo>>,
+
+    /// Raising it stops the run, see [`Interpreter::interrupt_on`].
+    pub interrupt: Option<Arc<AtomicBool>>,
+
+    /// The printed values that are worth a warning.
+    pub print_thresholds: PrintThresholds,
+
+    /// Receives the warnings about the printed values, see
+    /// [`Interpreter::warn_prints`]. Nothing is checked without it.
+    pub on_print_warning: Option<Rc<OnPrintWarning>>,
+
+    /// How often `on_progress` is called.
+    pub progress_interval: Duration,
+
+    /// Told how far the run got, see [`Interpreter::on_progress`].
+    pub on_progress: Option<Rc<OnProgress>>,
+
+    /// Panics at the print after this many, see
+    /// [`Interpreter::panic_after_prints_for_tests`].
+    #[cfg(feature = "debug-invariants")]
+    #[doc(hidden)]
+    pub panic_after_prints: Option<u64>,
+}
+
+impl RunConfig {
+    /// The key of the snapshots of [`RunConfig::shared_memo`] for the
+    /// program `source` run with this config. The settings that change
+    /// what the functions return are part of it, so the runs with other
+    /// ones don't share their entries.
+    ///
+    /// ```
+    /// use rinha::{interpreter::Overflow, RunConfig};
+    ///
+    /// let wrapping = RunConfig {
+    ///     overflow: Overflow::Wrap32,
+    ///     ..RunConfig::default()
+    /// };
+    /// let source = "1 + 1";
+    /// assert_ne!(wrapping.program_key(source), RunConfig::default().program_key(source));
+    /// ```
+    pub fn program_key(&self, source: &str) -> ProgramKey {
+        let args: Vec<_> = self
+            .args
+            .iter()
+            .map(|arg| (&arg.name, &arg.value))
+            .collect();
+        ProgramKey::of_source(source).with((
+            self.overflow,
+            self.extensions,
+            self.lazy,
+            self.opt_level,
+            args,
+        ))
+    }
+}
+
+type OnPrintWarning = dyn Fn(&PrintWarning);
+type OnProgress = dyn Fn(&Progress);
+type OnSequentialPair = dyn Fn(&SequentialPair);
+type OnImpureRecursion = dyn Fn(&ImpureRecursion);
+type OnBuiltin = dyn Fn(&[Primitive]) -> Result<Primitive, ErrorKind>;
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            filename: "main.rinha".into(),
+            backend: Backend::default(),
+            memoize: true,
+            memo_capacity: memo::DEFAULT_CAPACITY,
+            assert_none: cfg!(debug_assertions),
+            output: None,
+            int_format: IntFormat::default(),
+            overflow: Overflow::default(),
+            extensions: false,
+            opt_level: OptLevel::default(),
+            args: Vec::new(),
+            lazy: false,
+            on_sequential_pair: None,
+            on_impure_recursion: None,
+            builtins: Vec::new(),
+            tees: Vec::new(),
+            max_output_bytes: None,
+            max_depth: None,
+            max_stack: None,
+            max_steps: None,
+            timeout: None,
+            shared_memo: None,
+            interrupt: None,
+            print_thresholds: PrintThresholds::default(),
+            on_print_warning: None,
+            progress_interval: Duration::from_secs(1),
+            on_progress: None,
+            #[cfg(feature = "debug-invariants")]
+            panic_after_prints: None,
+        }
+    }
+}
+
+/// The engines that run the programs.
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The tree-walking interpreter, it has every feature
+    #[default]
+    Tree,
+    /// The bytecode machine, it doesn't memoize, it provides no natives and
+    /// it calls none of the hooks, see [`crate::vm`]
+    #[value(
+        help = "The bytecode machine, it doesn't memoize, it provides no natives and it calls none of the hooks"
+    )]
+    Vm,
+    /// The bytecode machine, running the functions on integers as machine
+    /// code, see [`crate::vm::jit`]
+    #[value(help = "The bytecode machine, running the functions on integers as machine code")]
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+/// The result of a successful run.
+#[derive(Debug, Clone)]
+pub struct RunOutcome {
+    /// The value of the top level expression.
+    pub value: Primitive,
+
+    /// The counters collected while running.
+    pub stats: Stats,
+}
+
+/// Everything that can go wrong from the source text to the final value.
+#[derive(miette::Diagnostic, thiserror::Error, Debug)]
+pub enum RinhaError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] parser::ParseError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Runtime(#[from] RuntimeError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Internal(Box<InternalError>),
+}
+
+/// A panic of the interpreter, caught where the run starts. It's a bug of
+/// the interpreter, whatever the program does.
+#[derive(miette::Diagnostic, thiserror::Error, Debug, Clone)]
+#[error("internal interpreter error: {message}")]
+#[diagnostic(code(rinha::internal))]
+pub struct InternalError {
+    /// What the panic said.
+    pub message: String,
+
+    /// Where the interpreter panicked, in its own source code.
+    pub panicked_at: Option<String>,
+
+    /// The innermost call being evaluated, `None` when it was the top level
+    /// expression.
+    #[label("while evaluating this call")]
+    pub node: Option<miette::SourceSpan>,
+
+    /// The calls being evaluated, the innermost first, with the line and
+    /// the column of each call when the source is known.
+    pub stack: Vec<String>,
+
+    /// The version of the interpreter.
+    pub version: &'static str,
+
+    #[help]
+    help: String,
+}
+
+/// Where the bugs of the interpreter are reported.
+const ISSUES: &str = "https://github.com/FabioSeixas/rinha-de-compiler/issues";
+
+impl InternalError {
+    fn new(
+        message: String,
+        panicked_at: Option<String>,
+        frames: &[Frame],
+        src: Option<&str>,
+    ) -> Self {
+        let stack: Vec<String> = frames
+            .iter()
+            .rev()
+            .map(|frame| format!("{} at {}", frame.function, position(&frame.call, src)))
+            .collect();
+
+        let mut help = format!(
+            "this is a bug of the interpreter, not of the program. Please report it at \
+             {ISSUES}, with the program and this report.\nversion: rinha {}",
+            env!("CARGO_PKG_VERSION")
+        );
+        if let Some(at) = &panicked_at {
+            help.push_str(&format!("\npanicked at: {at}"));
+        }
+        match stack.is_empty() {
+            true => help.push_str("\nstack: the top level expression"),
+            false => help.push_str(&format!(
+                "\nstack, innermost first:\n  {}",
+                stack.join("\n  ")
+            )),
+        }
+
+        Self {
+            message,
+            panicked_at,
+            node: frames.last().map(|frame| frame.call.clone().into()),
+            stack,
+            version: env!("CARGO_PKG_VERSION"),
+            help,
+        }
+    }
+}
+
+/// Where `location` starts, as `file:line:column` when the source is known.
+fn position(location: &ast::Location, src: Option<&str>) -> String {
+    match src {
+        Some(src) => {
+            let (line, column) = location.line_column(src);
+            format!("{}:{line}:{column}", location.filename)
+        }
+        None => format!("{}@{}", location.filename, location.start),
+    }
+}
+
+/// Parses and runs the `rinha` source code.
+///
+/// ```
+/// use rinha::RunConfig;
+///
+/// let outcome = rinha::run_source("let x = 40; x + 2", &RunConfig::default()).unwrap();
+/// assert_eq!(outcome.value.to_string(), "42");
+/// ```
+pub fn run_source(src: &str, cfg: &RunConfig) -> Result<RunOutcome, RinhaError> {
+    let file = parser::parse_or_report(&cfg.filename, src)?;
+    run_parsed(file, src, cfg)
+}
+
+/// Runs a program already parsed from `src`, like one read from a
+/// [`crate::cache::Cache`]. The source gives the anonymous functions their
+/// line and column.
+pub fn run_parsed(file: ast::File, src: &str, cfg: &RunConfig) -> Result<RunOutcome, RinhaError> {
+    let names = FunctionNames::new(&file.expression, Some(src));
+    let program = cfg.shared_memo.as_ref().map(|_| cfg.program_key(src));
+    run_file(file, names, program, Some(src), cfg)
+}
+
+/// Runs an already parsed program, like the ones read from JSON.
+///
+/// ```
+/// use rinha::{parser, RunConfig};
+/// use std::{cell::RefCell, rc::Rc};
+///
+/// let file = parser::parse_or_report("main.rinha", "print((1, true))").unwrap();
+/// let output = Rc::new(RefCell::new(Vec::new()));
+/// let config = RunConfig {
+///     output: Some(output.clone()),
+///     ..RunConfig::default()
+/// };
+///
+/// rinha::run_ast(file, &config).unwrap();
+/// assert_eq!(output.borrow().as_slice(), b"(1, true)\n");
+/// ```
+pub fn run_ast(file: ast::File, cfg: &RunConfig) -> Result<RunOutcome, RinhaError> {
+    let names = FunctionNames::new(&file.expression, None);
+    // Programs read from JSON are told apart by the source they format to.
+    let program = cfg
+        .shared_memo
+        .as_ref()
+        .map(|_| cfg.program_key(&format::to_source(&file.expression)));
+    run_file(file, names, program, None, cfg)
+}
+
+fn run_file(
+    file: ast::File,
+    names: FunctionNames,
+    program: Option<ProgramKey>,
+    src: Option<&str>,
+    cfg: &RunConfig,
+) -> Result<RunOutcome, RinhaError> {
+    let expression = input::bind_args(file.expression, &cfg.args, &file.location.filename);
+    let file = ast::File {
+        expression: optimize::optimize(expression, cfg.opt_level, cfg.overflow),
+        ..file
+    };
+    let Some(timeout) = cfg.timeout else {
+        return run_engine(file, names, program, src, cfg);
+    };
+    let watchdog = Watchdog::start(timeout, cfg.interrupt.clone());
+    let cfg = RunConfig {
+        interrupt: Some(watchdog.flag.clone()),
+        ..cfg.clone()
+    };
+    let outcome = run_engine(file, names, program, src, &cfg);
+    let timed_out = watchdog.stop();
+    outcome.map_err(|error| match error {
+        RinhaError::Runtime(mut error) if timed_out && error.kind == ErrorKind::Interrupted => {
+            error.kind = ErrorKind::Timeout(timeout);
+            RinhaError::Runtime(error)
+        }
+        error => error,
+    })
+}
+
+fn run_engine(
+    file: ast::File,
+    names: FunctionNames,
+    program: Option<ProgramKey>,
+    src: Option<&str>,
+    cfg: &RunConfig,
+) -> Result<RunOutcome, RinhaError> {
+    if cfg.backend != Backend::Tree {
+        return run_vm(file, names, src, cfg);
+    }
+    let interpreter = match &cfg.output {
+        Some(output) => Interpreter::with_output(SharedOutput(output.clone())),
+        None => Interpreter::new(),
+    };
+    let mut interpreter = interpreter
+        .memoize(cfg.memoize)
+        .memo_capacity(cfg.memo_capacity)
+        .assert_none(cfg.assert_none)
+        .function_names(names)
+        .int_format(cfg.int_format)
+        .overflow(cfg.overflow)
+        .extensions(cfg.extensions)
+        .lazy(cfg.lazy);
+    for tee in &cfg.tees {
+        interpreter = interpreter.tee(SharedOutput(tee.clone()));
+    }
+    if let Some(limit) = cfg.max_output_bytes {
+        interpreter = interpreter.max_output_bytes(limit);
+    }
+    if let Some(limit) = cfg.max_depth {
+        interpreter = interpreter.max_depth(limit);
+    }
+    if let Some(limit) = cfg.max_stack {
+        interpreter = interpreter.max_stack(limit);
+    }
+    if let Some(limit) = cfg.max_steps {
+        interpreter = interpreter.max_steps(limit);
+    }
+    if let Some(interrupt) = &cfg.interrupt {
+        interpreter = interpreter.interrupt_on(interrupt.clone());
+    }
+    if let Some(hook) = &cfg.on_print_warning {
+        let hook = hook.clone();
+        interpreter = interpreter.warn_prints(cfg.print_thresholds, move |warning| hook(warning));
+    }
+    if let Some(hook) = &cfg.on_sequential_pair {
+        let hook = hook.clone();
+        interpreter = interpreter.on_sequential_pair(move |pair| hook(pair));
+    }
+    if let Some(hook) = &cfg.on_impure_recursion {
+        let hook = hook.clone();
+        interpreter = interpreter.on_impure_recursion(move |recursion| hook(recursion));
+    }
+    for (name, builtin) in &cfg.builtins {
+        let builtin = builtin.clone();
+        interpreter = interpreter.register_builtin(name, move |arguments| builtin(arguments));
+    }
+    if let Some(hook) = &cfg.on_progress {
+        let hook = hook.clone();
+        interpreter =
+            interpreter.on_progress(cfg.progress_interval, move |progress| hook(progress));
+    }
+    #[cfg(feature = "debug-invariants")]
+    if let Some(prints) = cfg.panic_after_prints {
+        interpreter = interpreter.panic_after_prints_for_tests(prints);
+    }
+    let shared_memo = cfg.shared_memo.as_ref().zip(program);
+    let shared_memo = shared_memo.filter(|_| cfg.memoize);
+    if let Some((shared_memo, program)) = shared_memo {
+        interpreter = interpreter.shared_memo(shared_memo.snapshot(program));
+    }
+
+    let value = catch_panics(|| interpreter.interpret(file.expression, &mut Scope::new()));
+    let value = match value {
+        Ok(value) => value.map_err(|error| with_stats(error, interpreter.stats()))?,
+        Err((message, panicked_at)) => {
+            let stack = interpreter.stack();
+            let error = InternalError::new(message, panicked_at, stack, src);
+            return Err(RinhaError::Internal(Box::new(error)));
+        }
+    };
+    if let Some((shared_memo, program)) = shared_memo {
+        shared_memo.merge(program, interpreter.pure_memo());
+    }
+    Ok(RunOutcome {
+        value,
+        stats: interpreter.stats().clone(),
+    })
+}
+
+fn run_vm(
+    file: ast::File,
+    names: FunctionNames,
+    src: Option<&str>,
+    cfg: &RunConfig,
+) -> Result<RunOutcome, RinhaError> {
+    let vm = match &cfg.output {
+        Some(output) => Vm::with_output(SharedOutput(output.clone())),
+        None => Vm::new(),
+    };
+    let mut vm = vm
+        .assert_none(cfg.assert_none)
+        .function_names(names)
+        .int_format(cfg.int_format)
+        .overflow(cfg.overflow)
+        .extensions(cfg.extensions);
+    #[cfg(feature = "jit")]
+    {
+        vm = vm.jit(cfg.backend == Backend::Jit);
+    }
+    for tee in &cfg.tees {
+        vm = vm.tee(SharedOutput(tee.clone()));
+    }
+    if let Some(limit) = cfg.max_output_bytes {
+        vm = vm.max_output_bytes(limit);
+    }
+    if let Some(limit) = cfg.max_depth {
+        vm = vm.max_depth(limit);
+    }
+    if let Some(limit) = cfg.max_steps {
+        vm = vm.max_steps(limit);
+    }
+    if let Some(interrupt) = &cfg.interrupt {
+        vm = vm.interrupt_on(interrupt.clone());
+    }
+
+    let value = match catch_panics(|| vm.run(file.expression)) {
+        Ok(value) => value.map_err(|error| with_stats(error, vm.stats()))?,
+        Err((message, panicked_at)) => {
+            let error = InternalError::new(message, panicked_at, vm.stack(), src);
+            return Err(RinhaError::Internal(Box::new(error)));
+        }
+    };
+    Ok(RunOutcome {
+        value,
+        stats: vm.stats().clone(),
+    })
+}
+
+/// Keeps the counters of the run with its error, so the runs that failed
+/// still tell how far they got.
+fn with_stats(mut error: RuntimeError, stats: &Stats) -> RuntimeError {
+    error.stats = Some(Box::new(stats.clone()));
+    error
+}
+
+/// Raises the interrupt flag of a run once its timeout is over, or once
+/// the flag of the embedder is raised, which it never writes to.
+struct Watchdog {
+    flag: Arc<AtomicBool>,
+    done: mpsc::Sender<()>,
+    thread: thread::JoinHandle<bool>,
+}
+
+impl Watchdog {
+    /// How often the flag of the embedder is looked at.
+    const POLL: Duration = Duration::from_millis(10);
+
+    fn start(timeout: Duration, interrupt: Option<Arc<AtomicBool>>) -> Watchdog {
+        let flag = Arc::new(AtomicBool::new(false));
+        let (done, finished) = mpsc::channel();
+        let raised = flag.clone();
+        let thread = thread::spawn(move || {
+            let deadline = Instant::now() + timeout;
+            loop {
+                let now = Instant::now();
+                if now >= deadline {
+                    raised.store(true, Ordering::Relaxed);
+                    return true;
+                }
+                if interrupt
+                    .as_ref()
+                    .is_some_and(|flag| flag.load(Ordering::Relaxed))
+                {
+                    raised.store(true, Ordering::Relaxed);
+                    return false;
+                }
+                match finished.recv_timeout(Self::POLL.min(deadline - now)) {
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    _ => return false,
+                }
+            }
+        });
+        Watchdog { flag, done, thread }
+    }
+
+    /// Stops watching, telling whether the timeout went off.
+    fn stop(self) -> bool {
+        let _ = self.done.send(());
+        self.thread.join().unwrap_or(false)
+    }
+}
+
+thread_local! {
+    /// Whether this thread is running a program, its panics are reported
+    /// as an [`InternalError`] instead.
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+
+    /// Where the last panic of the running program happened.
+    static PANICKED_AT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs `run`, turning its panics into their message and where they
+/// happened. The panics of the running program aren't printed by the panic
+/// hook, every other panic still is.
+fn catch_panics<T>(run: impl FnOnce() -> T) -> Result<T, (String, Option<String>)> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| match RUNNING.with(Cell::get) {
+            true => PANICKED_AT.with(|at| {
+                *at.borrow_mut() = info.location().map(|location| location.to_string());
+            }),
+            false => previous(info),
+        }));
+    });
+
+    let running = RUNNING.with(|running| running.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(run));
+    RUNNING.with(|flag| flag.set(running));
+    result.map_err(|payload| {
+        let message = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(message), _) => message.to_string(),
+            (_, Some(message)) => message.clone(),
+            (None, None) => "the panic said nothing".to_string(),
+        };
+        (message, PANICKED_AT.with(RefCell::take))
+    })
+}
+
+/// Lets the interpreter write into a writer the caller keeps a handle to.
+struct SharedOutput(Rc<RefCell<dyn io::Write>>);
+
+impl io::Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
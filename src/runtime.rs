@@ -0,0 +1,24 @@
+/// The tuples: building them, taking them apart, comparing, hashing and
+/// rendering them.
+pub mod tuple;
+
+/// The lists of the extensions, sharing their cells.
+pub mod list;
+
+/// The records of the extensions, their fields sorted by name.
+pub mod record;
+
+/// The references of the extensions, cells their copies share.
+pub mod reference;
+
+/// The natives of the extensions reading the strings.
+pub mod string;
+
+/// The integers past 64 bits.
+pub mod bigint;
+
+/// The variables in scope, in frames shared with the closures.
+pub mod scope;
+
+/// The names and the strings, interned or shared instead of copied.
+pub mod symbol;
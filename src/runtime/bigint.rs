@@ -0,0 +1,287 @@
+//! The integers past 64 bits, made by [`crate::interpreter::Overflow::Widen`].
+//! They're stored as a sign and the 32 bits digits of the magnitude, the
+//! least significant first. Only what the operators need is implemented,
+//! with the textbook algorithms.
+
+use std::{cmp::Ordering, fmt, ops};
+
+/// An integer of any size.
+///
+/// ```
+/// use rinha::runtime::bigint::BigInt;
+///
+/// let big = &BigInt::from(i64::MAX) * &BigInt::from(4i64);
+/// assert_eq!(big.to_string(), "36893488147419103228");
+/// assert_eq!(big.to_i64(), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Never ends with a zero, so zero is empty and every value has a
+    /// single representation.
+    digits: Vec<u32>,
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> Self {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = Vec::new();
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        Self {
+            negative: value < 0,
+            digits,
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        Self::from(i128::from(value))
+    }
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The value, when it fits in 128 bits.
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+        let magnitude = self.digits.iter().rev().fold(0u128, |magnitude, &digit| {
+            magnitude << 32 | u128::from(digit)
+        });
+        match self.negative {
+            true if magnitude == 1 << 127 => Some(i128::MIN),
+            true => i128::try_from(magnitude).ok().map(|magnitude| -magnitude),
+            false => i128::try_from(magnitude).ok(),
+        }
+    }
+
+    /// The value, when it fits in 64 bits.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_i128().and_then(|value| i64::try_from(value).ok())
+    }
+
+    /// The lowest 64 bits of the value in two's complement, what wrapping
+    /// it around in 64 bits leaves.
+    pub fn wrapping_i64(&self) -> i64 {
+        let low = u64::from(self.digits.first().copied().unwrap_or_default())
+            | u64::from(self.digits.get(1).copied().unwrap_or_default()) << 32;
+        match self.negative {
+            true => (low as i64).wrapping_neg(),
+            false => low as i64,
+        }
+    }
+
+    /// The quotient rounded towards zero and the remainder with the sign of
+    /// the dividend, like the 64 bits integers. `None` when dividing by
+    /// zero.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        // Long division, one bit at a time.
+        let mut quotient = vec![0u32; self.digits.len()];
+        let mut remainder: Vec<u32> = Vec::new();
+        for index in (0..self.digits.len() * 32).rev() {
+            shift_left_one(&mut remainder);
+            if self.digits[index / 32] >> (index % 32) & 1 == 1 {
+                match remainder.first_mut() {
+                    Some(lowest) => *lowest |= 1,
+                    None => remainder.push(1),
+                }
+            }
+            if compare_magnitudes(&remainder, &other.digits) != Ordering::Less {
+                remainder = sub_magnitudes(&remainder, &other.digits);
+                quotient[index / 32] |= 1 << (index % 32);
+            }
+        }
+        Some((
+            BigInt::new(self.negative != other.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        ))
+    }
+
+    /// The digits of the magnitude in hexadecimal, without a prefix.
+    pub fn hex_magnitude(&self) -> String {
+        let Some((last, rest)) = self.digits.split_last() else {
+            return "0".into();
+        };
+        let mut hex = format!("{last:x}");
+        for digit in rest.iter().rev() {
+            hex.push_str(&format!("{digit:08x}"));
+        }
+        hex
+    }
+
+    /// The digits of the magnitude in decimal.
+    pub fn decimal_magnitude(&self) -> String {
+        const CHUNK: u64 = 1_000_000_000;
+        let mut digits = self.digits.clone();
+        let mut chunks = Vec::new();
+        while !digits.is_empty() {
+            let mut remainder = 0u64;
+            for digit in digits.iter_mut().rev() {
+                let current = remainder << 32 | u64::from(*digit);
+                *digit = (current / CHUNK) as u32;
+                remainder = current % CHUNK;
+            }
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+            chunks.push(remainder);
+        }
+        let Some((last, rest)) = chunks.split_last() else {
+            return "0".into();
+        };
+        let mut decimal = last.to_string();
+        for chunk in rest.iter().rev() {
+            decimal.push_str(&format!("{chunk:09}"));
+        }
+        decimal
+    }
+}
+
+impl ops::Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.digits.clone())
+    }
+}
+
+impl ops::Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitudes(&self.digits, &other.digits));
+        }
+        match compare_magnitudes(&self.digits, &other.digits) {
+            Ordering::Less => {
+                BigInt::new(other.negative, sub_magnitudes(&other.digits, &self.digits))
+            }
+            _ => BigInt::new(self.negative, sub_magnitudes(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl ops::Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl ops::Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, &lhs) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &rhs) in other.digits.iter().enumerate() {
+                let product = u64::from(lhs) * u64::from(rhs) + u64::from(digits[i + j]) + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        BigInt::new(self.negative != other.negative, digits)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.digits, &other.digits),
+            (true, true) => compare_magnitudes(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.negative { "-" } else { "" };
+        write!(f, "{sign}{}", self.decimal_magnitude())
+    }
+}
+
+fn compare_magnitudes(lhs: &[u32], rhs: &[u32]) -> Ordering {
+    lhs.len()
+        .cmp(&rhs.len())
+        .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+fn add_magnitudes(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::with_capacity(lhs.len().max(rhs.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..lhs.len().max(rhs.len()) {
+        let sum = u64::from(lhs.get(index).copied().unwrap_or_default())
+            + u64::from(rhs.get(index).copied().unwrap_or_default())
+            + carry;
+        digits.push(sum as u32);
+        carry = sum >> 32;
+    }
+    digits.push(carry as u32);
+    digits
+}
+
+/// `lhs - rhs`, the magnitude of `lhs` must be the largest.
+fn sub_magnitudes(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::with_capacity(lhs.len());
+    let mut borrow = 0i64;
+    for (index, &digit) in lhs.iter().enumerate() {
+        let mut difference =
+            i64::from(digit) - i64::from(rhs.get(index).copied().unwrap_or_default()) - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        digits.push(difference as u32);
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+fn shift_left_one(digits: &mut Vec<u32>) {
+    let mut carry = 0;
+    for digit in digits.iter_mut() {
+        let next = *digit >> 31;
+        *digit = *digit << 1 | carry;
+        carry = next;
+    }
+    if carry != 0 {
+        digits.push(carry);
+    }
+}
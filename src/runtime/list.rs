@@ -0,0 +1,182 @@
+//! The lists of [`crate::interpreter::Interpreter::extensions`]. A list is
+//! a chain of cells, each holding an element and the rest of the list, so
+//! `cons` and `tail` share the rest instead of copying it and the programs
+//! walking a list one element at a time take as long as it is long. The
+//! comparisons, the hashes and the rendering of the lists are the tuples',
+//! see [`crate::runtime::tuple`], so the values nested in both are walked
+//! the same way.
+
+use crate::{
+    interpreter::{ErrorKind, Primitive},
+    runtime::reference::{Shared, Traced},
+};
+use std::{fmt, sync::Arc};
+
+/// The names of the natives of the lists, see [`call`].
+pub const NATIVES: [&str; 5] = ["list", "cons", "head", "tail", "len"];
+
+/// An immutable list, copied by sharing its cells.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::list::List};
+///
+/// let rest = List::from_iter([Primitive::Int(2), Primitive::Int(3)]);
+/// let list = List::cons(Primitive::Int(1), rest.clone());
+/// assert_eq!(list.len(), 3);
+/// assert_eq!(list.tail(), Some(rest));
+/// ```
+#[derive(Clone, Default)]
+pub struct List(Option<Arc<Cell>>);
+
+struct Cell {
+    head: Primitive,
+    tail: List,
+    len: usize,
+}
+
+impl List {
+    /// The address of the first cell and how many references share it,
+    /// for the [`Collector`](crate::runtime::reference::Collector).
+    pub(crate) fn node(&self) -> Option<(usize, usize)> {
+        let cell = self.0.as_ref()?;
+        Some((Arc::as_ptr(cell) as usize, Arc::strong_count(cell)))
+    }
+
+    /// Calls `held` with what the first cell holds, its element and the
+    /// rest of the list.
+    pub(crate) fn trace(&self, held: &mut dyn FnMut(Traced<'_>)) {
+        let Some(cell) = &self.0 else {
+            return;
+        };
+        held(Traced::Value(&cell.head));
+        if cell.tail.0.is_some() {
+            held(Traced::Shared(Shared::List(cell.tail.clone())));
+        }
+    }
+
+    /// The list of `head` followed by the elements of `tail`.
+    pub fn cons(head: Primitive, tail: List) -> List {
+        let len = tail.len() + 1;
+        List(Some(Arc::new(Cell { head, tail, len })))
+    }
+
+    pub fn head(&self) -> Option<&Primitive> {
+        self.0.as_ref().map(|cell| &cell.head)
+    }
+
+    /// The elements after the first one, `None` for the empty list.
+    pub fn tail(&self) -> Option<List> {
+        self.0.as_ref().map(|cell| cell.tail.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |cell| cell.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Primitive> {
+        let mut cell = self.0.as_deref();
+        std::iter::from_fn(move || {
+            let current = cell?;
+            cell = current.tail.0.as_deref();
+            Some(&current.head)
+        })
+    }
+}
+
+/// The cells are freed one after the other, the longest lists would
+/// overflow the native stack dropping them one inside of the other.
+impl Drop for List {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(cell) = next {
+            next = match Arc::try_unwrap(cell) {
+                Ok(mut cell) => cell.tail.0.take(),
+                // The rest is still used by another list.
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl FromIterator<Primitive> for List {
+    fn from_iter<T: IntoIterator<Item = Primitive>>(values: T) -> Self {
+        let values: Vec<Primitive> = values.into_iter().collect();
+        values
+            .into_iter()
+            .rev()
+            .fold(List::default(), |tail, head| List::cons(head, tail))
+    }
+}
+
+/// The lists are equal when their elements are, see [`Primitive`].
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Calls the native `name` of the lists with the values of its arguments:
+///
+/// - `list(a, b, ...)` is the list of its arguments, `list()` the empty one.
+/// - `cons(x, l)` is the list of `x` followed by the elements of `l`.
+/// - `head(l)` and `tail(l)` are the first element of `l` and the list of
+///   the others, they fail on the empty list.
+/// - `len(l)` is the number of elements of `l`.
+///
+/// ```
+/// use rinha::{interpreter::{ErrorKind, Primitive}, runtime::list};
+///
+/// let numbers = list::call("list", vec![Primitive::Int(1), Primitive::Int(2)]).unwrap();
+/// let longer = list::call("cons", vec![Primitive::Int(0), numbers]).unwrap();
+/// assert_eq!(longer.to_string(), "[0, 1, 2]");
+/// assert_eq!(list::call("len", vec![longer]).unwrap(), Primitive::Int(3));
+///
+/// let empty = list::call("list", vec![]).unwrap();
+/// assert_eq!(list::call("head", vec![empty]).unwrap_err(), ErrorKind::EmptyList("head"));
+/// ```
+pub fn call(name: &'static str, arguments: Vec<Primitive>) -> Result<Primitive, ErrorKind> {
+    let given = arguments.len();
+    let arity = |expected: usize| ErrorKind::ArityMismatch {
+        name: name.into(),
+        expected,
+        given,
+    };
+    if name == "list" {
+        return Ok(Primitive::List(arguments.into_iter().collect()));
+    }
+    let mut arguments = arguments.into_iter();
+    match (name, arguments.next(), arguments.next(), arguments.next()) {
+        ("cons", Some(head), Some(tail), None) => {
+            Ok(Primitive::List(List::cons(head, list(name, tail)?)))
+        }
+        ("head", Some(value), None, None) => {
+            let list = list(name, value)?;
+            list.head().cloned().ok_or(ErrorKind::EmptyList(name))
+        }
+        ("tail", Some(value), None, None) => {
+            let tail = list(name, value)?.tail();
+            tail.map(Primitive::List).ok_or(ErrorKind::EmptyList(name))
+        }
+        ("len", Some(value), None, None) => Ok(Primitive::Int(list(name, value)?.len() as i64)),
+        ("cons", ..) => Err(arity(2)),
+        _ => Err(arity(1)),
+    }
+}
+
+/// The list `value` is, failing for the other values.
+fn list(name: &'static str, value: Primitive) -> Result<List, ErrorKind> {
+    match value {
+        Primitive::List(list) => Ok(list),
+        _ => Err(ErrorKind::NotAList(name)),
+    }
+}
@@ -0,0 +1,97 @@
+//! The records of [`crate::interpreter::Interpreter::extensions`], like
+//! `{ x: 1, y: 2 }`. Their fields are kept sorted by name, so the records
+//! with the same fields are the same whatever the order they're written in,
+//! and reading a field is a binary search. Like the lists, their
+//! comparisons, hashes and rendering are the tuples', see
+//! [`crate::runtime::tuple`].
+
+use crate::{
+    interpreter::{ErrorKind, Primitive},
+    runtime::{reference::Traced, symbol::Symbol},
+};
+use std::sync::Arc;
+
+/// An immutable record, copied by sharing its fields.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::record::Record};
+///
+/// let point = Record::new(vec![
+///     ("y".into(), Primitive::Int(2)),
+///     ("x".into(), Primitive::Int(1)),
+/// ])
+/// .unwrap();
+/// assert_eq!(point.get("x"), Some(&Primitive::Int(1)));
+/// assert_eq!(Primitive::Record(point).to_string(), "{x: 1, y: 2}");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record(Arc<[(Symbol, Primitive)]>);
+
+impl Record {
+    /// The address of the fields and how many references share them, for
+    /// the [`Collector`](crate::runtime::reference::Collector).
+    pub(crate) fn node(&self) -> (usize, usize) {
+        (
+            Arc::as_ptr(&self.0) as *const u8 as usize,
+            Arc::strong_count(&self.0),
+        )
+    }
+
+    /// Calls `held` with the values of the fields.
+    pub(crate) fn trace(&self, held: &mut dyn FnMut(Traced<'_>)) {
+        self.0
+            .iter()
+            .for_each(|(_, value)| held(Traced::Value(value)));
+    }
+
+    /// The record of the fields, failing when a name is given twice.
+    pub fn new(mut fields: Vec<(Symbol, Primitive)>) -> Result<Record, ErrorKind> {
+        fields.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+        if let Some(pair) = fields.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(ErrorKind::DuplicateField(pair[0].0.to_string()));
+        }
+        Ok(Record(fields.into()))
+    }
+
+    /// The value of the field `name`, `None` when the record hasn't it.
+    pub fn get(&self, name: &str) -> Option<&Primitive> {
+        let at = self
+            .0
+            .binary_search_by(|(field, _)| field.as_str().cmp(name));
+        at.ok().map(|at| &self.0[at].1)
+    }
+
+    /// The fields and their values, sorted by name.
+    pub fn fields(&self) -> impl Iterator<Item = (&Symbol, &Primitive)> {
+        self.0.iter().map(|(name, value)| (name, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The field `name` of `value`, failing when it isn't a record or when the
+/// record hasn't the field.
+///
+/// ```
+/// use rinha::{interpreter::{ErrorKind, Primitive}, runtime::record::{self, Record}};
+///
+/// let point = Primitive::Record(Record::new(vec![("x".into(), Primitive::Int(1))]).unwrap());
+/// assert_eq!(record::field(&point, "x"), Ok(Primitive::Int(1)));
+/// assert_eq!(record::field(&point, "y"), Err(ErrorKind::NoSuchField("y".into())));
+/// assert_eq!(record::field(&Primitive::Int(1), "x"), Err(ErrorKind::NotARecord("x".into())));
+/// ```
+pub fn field(value: &Primitive, name: &str) -> Result<Primitive, ErrorKind> {
+    match value {
+        Primitive::Record(record) => record
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ErrorKind::NoSuchField(name.into())),
+        _ => Err(ErrorKind::NotARecord(name.into())),
+    }
+}
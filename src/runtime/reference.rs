@@ -0,0 +1,310 @@
+//! The references of [`crate::interpreter::Interpreter::extensions`], cells
+//! holding a value that `assign` replaces. The copies of a reference share
+//! its cell, so what one of them assigns the others read, and the programs
+//! can keep the tables of the imperative algorithms in them. A reference is
+//! only equal to itself, it can't be compared with `==`, and it prints like
+//! `<#ref>` whatever it holds, so the cells holding themselves print too.
+//!
+//! The cells are freed by counting the references, which doesn't free a
+//! cell that holds itself, or a closure or a tuple that refers to it. The
+//! [`Collector`] of the interpreter finds these cycles and breaks them.
+
+use crate::{
+    interpreter::{ErrorKind, Primitive, Thunk},
+    runtime::{list::List, record::Record, scope::Scope},
+};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, Weak},
+};
+
+/// The names of the natives of the references, see [`call`].
+pub const NATIVES: [&str; 3] = ["ref", "deref", "assign"];
+
+/// A mutable cell, copied by sharing it.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::reference::Ref};
+///
+/// let counter = Ref::new(Primitive::Int(0));
+/// counter.clone().assign(Primitive::Int(1));
+/// assert_eq!(counter.get(), Primitive::Int(1));
+/// ```
+#[derive(Clone)]
+pub struct Ref(Arc<Mutex<Primitive>>);
+
+impl Ref {
+    pub fn new(value: Primitive) -> Ref {
+        Ref(Arc::new(Mutex::new(value)))
+    }
+
+    /// The value held now.
+    pub fn get(&self) -> Primitive {
+        self.lock().clone()
+    }
+
+    /// Replaces the value held, for every copy of the reference.
+    pub fn assign(&self, value: Primitive) {
+        *self.lock() = value;
+    }
+
+    /// A reference that doesn't keep the cell alive.
+    pub fn downgrade(&self) -> Weak<Mutex<Primitive>> {
+        Arc::downgrade(&self.0)
+    }
+
+    /// Whether both are copies of the same reference.
+    pub fn same(&self, other: &Ref) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// What tells the reference apart from the others alive, for the keys
+    /// of the memo.
+    pub fn address(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// The value, the lock is only held while reading or writing it.
+    fn lock(&self) -> MutexGuard<'_, Primitive> {
+        self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// The value held isn't shown, it may hold the reference itself.
+impl fmt::Debug for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ref({:#x})", self.address())
+    }
+}
+
+/// How many cells are made before the first collection, see
+/// [`Collector`].
+const FIRST_COLLECTION: usize = 1024;
+
+/// Frees the cycles of the cells, by trial deletion. It keeps the cells
+/// made by the interpreter, and once their number doubles it counts how
+/// many references to each value shared by the cells come from the cells
+/// themselves. A value with more references than that is held by the
+/// program, and what's left unreachable from these values is only held by
+/// cycles: its cells are emptied, which frees the cycles.
+///
+/// Only one thread runs the program when it collects: the threads of
+/// `par_pair` have interpreters and collectors of their own, and their
+/// cells are handed to the one that started them.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::reference::{Collector, Ref}};
+///
+/// let mut collector = Collector::default();
+/// let cell = Ref::new(Primitive::None);
+/// collector.track(&cell);
+/// cell.assign(Primitive::Ref(cell.clone()));
+/// let weak = cell.downgrade();
+/// drop(cell);
+///
+/// assert_eq!(collector.collect(), 1);
+/// assert!(weak.upgrade().is_none());
+/// ```
+#[derive(Debug)]
+pub struct Collector {
+    cells: Vec<Weak<Mutex<Primitive>>>,
+    /// How many cells are kept when the next collection runs.
+    threshold: usize,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            threshold: FIRST_COLLECTION,
+        }
+    }
+}
+
+/// A value made of others that's shared by counting the references to
+/// it, so it may be part of a cycle, see [`Collector`].
+pub(crate) enum Shared {
+    Ref(Ref),
+    Thunk(Thunk),
+    /// A scope with a frame.
+    Scope(Scope),
+    /// A list with a cell.
+    List(List),
+    Record(Record),
+}
+
+/// What a [`Shared`] holds: values, made of other shared values, or the
+/// shared values themselves.
+pub(crate) enum Traced<'a> {
+    Value(&'a Primitive),
+    Shared(Shared),
+}
+
+impl Shared {
+    /// The address of the value and how many references share it.
+    fn node(&self) -> (usize, usize) {
+        let none = "the shared scopes and lists aren't empty";
+        match self {
+            Shared::Ref(cell) => (cell.address(), Arc::strong_count(&cell.0)),
+            Shared::Thunk(thunk) => thunk.node(),
+            Shared::Scope(scope) => scope.node().expect(none),
+            Shared::List(list) => list.node().expect(none),
+            Shared::Record(record) => record.node(),
+        }
+    }
+
+    /// Calls `held` with each of the shared values this one holds, once
+    /// per reference to it.
+    fn trace(&self, held: &mut dyn FnMut(Shared)) {
+        let mut traced = |traced: Traced<'_>| match traced {
+            Traced::Value(value) => shared_in(value, held),
+            Traced::Shared(shared) => held(shared),
+        };
+        match self {
+            Shared::Ref(cell) => shared_in(&cell.lock(), held),
+            Shared::Thunk(thunk) => thunk.trace(&mut traced),
+            Shared::Scope(scope) => scope.trace(&mut traced),
+            Shared::List(list) => list.trace(&mut traced),
+            Shared::Record(record) => record.trace(&mut traced),
+        }
+    }
+}
+
+/// Calls `held` with the shared values `value` is made of, the tuples are
+/// part of it.
+fn shared_in(value: &Primitive, held: &mut dyn FnMut(Shared)) {
+    match value {
+        Primitive::Function { env, .. } if env.node().is_some() => held(Shared::Scope(env.clone())),
+        Primitive::Tuple([first, second]) => {
+            shared_in(first, held);
+            shared_in(second, held);
+        }
+        Primitive::List(list) if list.node().is_some() => held(Shared::List(list.clone())),
+        Primitive::Record(record) => held(Shared::Record(record.clone())),
+        Primitive::Ref(cell) => held(Shared::Ref(cell.clone())),
+        Primitive::Thunk(thunk) => held(Shared::Thunk(thunk.clone())),
+        _ => {}
+    }
+}
+
+impl Collector {
+    /// Keeps `cell` to look for cycles through it.
+    pub fn track(&mut self, cell: &Ref) {
+        self.cells.push(cell.downgrade());
+    }
+
+    /// Whether enough cells were made since the last collection.
+    pub fn due(&self) -> bool {
+        self.cells.len() >= self.threshold
+    }
+
+    /// Keeps the cells of `other` too.
+    pub fn adopt(&mut self, other: Collector) {
+        self.cells.extend(other.cells);
+    }
+
+    /// Empties the cells only held by cycles, returning how many.
+    pub fn collect(&mut self) -> usize {
+        // Every shared value reached from the cells, with the references
+        // to it found. The copy kept here is one more reference.
+        let mut nodes: HashMap<usize, (Shared, usize)> = HashMap::new();
+        let mut pending = Vec::new();
+        for cell in self.cells.iter().filter_map(Weak::upgrade) {
+            let address = Arc::as_ptr(&cell) as usize;
+            nodes.insert(address, (Shared::Ref(Ref(cell)), 0));
+            pending.push(address);
+        }
+        while let Some(address) = pending.pop() {
+            let mut held = Vec::new();
+            nodes[&address].0.trace(&mut |shared| held.push(shared));
+            for shared in held {
+                match nodes.entry(shared.node().0) {
+                    Entry::Occupied(mut node) => node.get_mut().1 += 1,
+                    Entry::Vacant(node) => {
+                        pending.push(*node.key());
+                        node.insert((shared, 1));
+                    }
+                }
+            }
+        }
+
+        // The values with references from elsewhere, and what they reach,
+        // are alive.
+        let mut alive = HashSet::new();
+        for (address, (shared, found)) in &nodes {
+            if shared.node().1 > found + 1 {
+                alive.insert(*address);
+                pending.push(*address);
+            }
+        }
+        while let Some(address) = pending.pop() {
+            nodes[&address].0.trace(&mut |shared| {
+                let address = shared.node().0;
+                if alive.insert(address) {
+                    pending.push(address);
+                }
+            });
+        }
+
+        let mut freed = 0;
+        for (address, (shared, _)) in &nodes {
+            if let (false, Shared::Ref(cell)) = (alive.contains(address), shared) {
+                let held = std::mem::replace(&mut *cell.lock(), Primitive::None);
+                drop(held);
+                freed += 1;
+            }
+        }
+        drop(nodes);
+
+        self.cells.retain(|cell| cell.strong_count() > 0);
+        self.threshold = FIRST_COLLECTION.max(2 * self.cells.len());
+        freed
+    }
+}
+
+/// Calls the native `name` of the references with the values of its
+/// arguments:
+///
+/// - `ref(x)` is a new reference holding `x`.
+/// - `deref(r)` is the value `r` holds.
+/// - `assign(r, x)` makes `r` hold `x`, and is `x`, like `print` is the
+///   value it prints.
+///
+/// ```
+/// use rinha::{interpreter::{ErrorKind, Primitive}, runtime::reference};
+///
+/// let counter = reference::call("ref", vec![Primitive::Int(0)]).unwrap();
+/// reference::call("assign", vec![counter.clone(), Primitive::Int(1)]).unwrap();
+/// assert_eq!(reference::call("deref", vec![counter]).unwrap(), Primitive::Int(1));
+///
+/// let error = reference::call("deref", vec![Primitive::Int(1)]).unwrap_err();
+/// assert_eq!(error, ErrorKind::NotARef("deref"));
+/// ```
+pub fn call(name: &'static str, arguments: Vec<Primitive>) -> Result<Primitive, ErrorKind> {
+    let given = arguments.len();
+    let arity = |expected: usize| ErrorKind::ArityMismatch {
+        name: name.into(),
+        expected,
+        given,
+    };
+    let mut arguments = arguments.into_iter();
+    match (name, arguments.next(), arguments.next(), arguments.next()) {
+        ("ref", Some(value), None, None) => Ok(Primitive::Ref(Ref::new(value))),
+        ("deref", Some(value), None, None) => Ok(reference(name, value)?.get()),
+        ("assign", Some(target), Some(value), None) => {
+            reference(name, target)?.assign(value.clone());
+            Ok(value)
+        }
+        ("assign", ..) => Err(arity(2)),
+        _ => Err(arity(1)),
+    }
+}
+
+/// The reference `value` is, failing for the other values.
+fn reference(name: &'static str, value: Primitive) -> Result<Ref, ErrorKind> {
+    match value {
+        Primitive::Ref(reference) => Ok(reference),
+        _ => Err(ErrorKind::NotARef(name)),
+    }
+}
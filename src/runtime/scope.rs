@@ -0,0 +1,306 @@
+//! The variables visible to the terms being evaluated. A scope is a chain
+//! of frames, the innermost first, shared with the calls made in it. Each
+//! frame is a row of slots, and the variables resolved by
+//! [`crate::resolve`] are read from the slot they're in, the others are
+//! looked up by name, frame by frame. The closures keep a frame of their
+//! own, with the variables their bodies use, so the values nothing uses
+//! anymore are freed by counting the references. Only a reference can
+//! make a value refer back to itself, when it's assigned a closure that
+//! captured it, or itself, and the collector of the references frees
+//! these cycles, see [`crate::runtime::reference::Collector`].
+//!
+//! The frames are never changed once shared, a binding goes into a new
+//! frame, or into a copy of the frame, instead, so they need no `RefCell`.
+//! They're behind an `Arc` rather than an `Rc`, the closures are sent to
+//! the threads of `par_pair`.
+
+use crate::{
+    interpreter::Primitive,
+    runtime::{
+        reference::{Shared, Traced},
+        symbol::Symbol,
+    },
+};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// How many frames a chain has at most. A longer one is flattened into a
+/// single frame, so the lookups by name stay short when the embedder
+/// chains a scope on top of another many times.
+const MAX_DEPTH: usize = 32;
+
+/// The variables in scope, bound by `let`, by the parameters of a call or
+/// by the embedder.
+///
+/// ```
+/// use rinha::interpreter::{Primitive, Scope};
+///
+/// let mut scope = Scope::new();
+/// scope.insert("x", Primitive::Int(1));
+/// let captured = scope.clone();
+/// scope.insert("x", Primitive::Int(2));
+///
+/// assert_eq!(scope.get("x"), Some(&Primitive::Int(2)));
+/// assert_eq!(captured.get("x"), Some(&Primitive::Int(1)));
+/// ```
+#[derive(Clone, Default)]
+pub struct Scope {
+    innermost: Option<Arc<Frame>>,
+}
+
+/// The variables bound together, by the same call or by the same chain of
+/// `let`.
+#[derive(Clone)]
+struct Frame {
+    /// The name of each slot, shared by the frames of the calls of the
+    /// same function.
+    names: Arc<Vec<Symbol>>,
+    /// The value of each slot, `None` until it's bound.
+    values: Vec<Option<Primitive>>,
+    parent: Option<Arc<Frame>>,
+    depth: usize,
+}
+
+impl Frame {
+    /// The bound slots, the last ones first, so the variables shadowing
+    /// others come before them.
+    fn vars(&self) -> impl Iterator<Item = (&Symbol, &Primitive)> {
+        self.names
+            .iter()
+            .zip(&self.values)
+            .rev()
+            .filter_map(|(name, value)| Some((name, value.as_ref()?)))
+    }
+}
+
+impl Scope {
+    /// The address of the innermost frame and how many references share
+    /// it, for the [`Collector`](crate::runtime::reference::Collector).
+    pub(crate) fn node(&self) -> Option<(usize, usize)> {
+        let frame = self.innermost.as_ref()?;
+        Some((Arc::as_ptr(frame) as usize, Arc::strong_count(frame)))
+    }
+
+    /// Calls `held` with what the innermost frame holds, its values and the
+    /// frames around it.
+    pub(crate) fn trace(&self, held: &mut dyn FnMut(Traced<'_>)) {
+        let Some(frame) = &self.innermost else {
+            return;
+        };
+        frame
+            .values
+            .iter()
+            .flatten()
+            .for_each(|value| held(Traced::Value(value)));
+        if let Some(parent) = &frame.parent {
+            held(Traced::Shared(Shared::Scope(Scope {
+                innermost: Some(parent.clone()),
+            })));
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A scope with an empty frame inside of this one, for the parameters
+    /// of a call.
+    pub fn child(&self) -> Scope {
+        self.with_slots(Arc::default())
+    }
+
+    /// A scope with a frame of unbound slots called `names` inside of this
+    /// one, for a function body or a top level expression resolved by
+    /// [`crate::resolve`].
+    pub(crate) fn with_slots(&self, names: Arc<Vec<Symbol>>) -> Scope {
+        let depth = self.innermost.as_ref().map_or(0, |frame| frame.depth);
+        let parent = match depth < MAX_DEPTH {
+            true => self.innermost.clone(),
+            false => {
+                let (names, values) = self
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Some(value.clone())))
+                    .unzip();
+                Some(Arc::new(Frame {
+                    names: Arc::new(names),
+                    values,
+                    parent: None,
+                    depth: 1,
+                }))
+            }
+        };
+        let depth = parent.as_ref().map_or(0, |frame| frame.depth) + 1;
+        Scope {
+            innermost: Some(Arc::new(Frame {
+                values: vec![None; names.len()],
+                names,
+                parent,
+                depth,
+            })),
+        }
+    }
+
+    /// The value of the innermost variable called `name`.
+    pub fn get(&self, name: &str) -> Option<&Primitive> {
+        let mut frame = self.innermost.as_deref();
+        while let Some(current) = frame {
+            if let Some((_, value)) = current.vars().find(|(bound, _)| *bound == name) {
+                return Some(value);
+            }
+            frame = current.parent.as_deref();
+        }
+        None
+    }
+
+    /// The value in the slot of the innermost frame, see
+    /// [`crate::resolve::Address::Local`].
+    pub(crate) fn local(&self, slot: usize) -> Option<&Primitive> {
+        self.innermost.as_ref()?.values.get(slot)?.as_ref()
+    }
+
+    /// The value in the slot of the frame the innermost one is inside of,
+    /// the variables captured by the function being run, see
+    /// [`crate::resolve::Address::Captured`].
+    pub(crate) fn captured(&self, slot: usize) -> Option<&Primitive> {
+        let parent = self.innermost.as_ref()?.parent.as_ref()?;
+        parent.values.get(slot)?.as_ref()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Binds `name` in the innermost frame. The frames other scopes share,
+    /// like the ones captured by closures, are left as they are: the
+    /// binding goes into a new frame instead.
+    pub fn insert(&mut self, name: impl Into<Symbol>, value: Primitive) {
+        let name = name.into();
+        if self.innermost.as_mut().and_then(Arc::get_mut).is_none() {
+            *self = self.child();
+        }
+        let Some(frame) = self.innermost.as_mut().and_then(Arc::get_mut) else {
+            return;
+        };
+        match frame.names.iter().rposition(|bound| *bound == name) {
+            Some(slot) => frame.values[slot] = Some(value),
+            None => {
+                Arc::make_mut(&mut frame.names).push(name);
+                frame.values.push(Some(value));
+            }
+        }
+    }
+
+    /// Binds the slot of the innermost frame. A frame other scopes share,
+    /// like the ones of the thunks of [`crate::interpreter::Interpreter::lazy`],
+    /// is copied first, so they keep seeing the slots as they were.
+    pub(crate) fn set(&mut self, slot: usize, value: Primitive) {
+        if let Some(frame) = self.innermost.as_mut() {
+            Arc::make_mut(frame).values[slot] = Some(value);
+        }
+    }
+
+    /// Binds the unbound slots of the innermost frame to the variables of
+    /// the same name in `from`.
+    pub(crate) fn fill(&mut self, from: &Scope) {
+        let Some(frame) = self.innermost.as_mut() else {
+            return;
+        };
+        if frame.values.iter().all(Option::is_some) {
+            return;
+        }
+        let frame = Arc::make_mut(frame);
+        for (name, value) in frame.names.iter().zip(&mut frame.values) {
+            if value.is_none() {
+                *value = from.get(name).cloned();
+            }
+        }
+    }
+
+    /// The variables of `inner` on top of the ones of this scope, the ones
+    /// of `inner` shadowing the others.
+    pub fn overlay(&self, inner: &Scope) -> Scope {
+        if self.same_frames(inner) {
+            return inner.clone();
+        }
+        let mut scope = self.child();
+        scope.extend(
+            inner
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        scope
+    }
+
+    /// Every variable in scope, without the ones shadowed by others.
+    pub fn iter(&self) -> impl Iterator<Item = (&Symbol, &Primitive)> {
+        let mut visible: HashMap<&Symbol, &Primitive> = HashMap::new();
+        let mut frame = self.innermost.as_deref();
+        while let Some(current) = frame {
+            for (name, value) in current.vars() {
+                visible.entry(name).or_insert(value);
+            }
+            frame = current.parent.as_deref();
+        }
+        visible.into_iter()
+    }
+
+    /// The variables bound in the innermost frame, in the order of their
+    /// slots, like the ones a closure captured.
+    pub(crate) fn locals(&self) -> impl Iterator<Item = (&Symbol, &Primitive)> {
+        self.innermost.iter().flat_map(|frame| {
+            frame
+                .names
+                .iter()
+                .zip(&frame.values)
+                .filter_map(|(name, value)| Some((name, value.as_ref()?)))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Whether both scopes are made of the very same frames.
+    fn same_frames(&self, other: &Scope) -> bool {
+        match (&self.innermost, &other.innermost) {
+            (Some(frame), Some(other)) => Arc::ptr_eq(frame, other),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<N: Into<Symbol>> Extend<(N, Primitive)> for Scope {
+    fn extend<T: IntoIterator<Item = (N, Primitive)>>(&mut self, vars: T) {
+        for (name, value) in vars {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl<N: Into<Symbol>> FromIterator<(N, Primitive)> for Scope {
+    fn from_iter<T: IntoIterator<Item = (N, Primitive)>>(vars: T) -> Self {
+        let mut scope = Scope::new();
+        scope.extend(vars);
+        scope
+    }
+}
+
+impl<S: Into<Symbol>, const N: usize> From<[(S, Primitive); N]> for Scope {
+    fn from(vars: [(S, Primitive); N]) -> Self {
+        vars.into_iter().collect()
+    }
+}
+
+/// The scopes are equal when the same variables are in scope, with equal
+/// values.
+impl PartialEq for Scope {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_frames(other) || self.iter().collect::<HashMap<_, _>>() == other.iter().collect()
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,166 @@
+//! The natives of [`crate::interpreter::Interpreter::extensions`] reading
+//! the strings and turning them into integers and back. The strings are
+//! counted and indexed by their characters, not by their bytes, so the
+//! indexes of a program never fall in the middle of a character.
+
+use crate::{
+    ast::BinaryOp,
+    interpreter::{ErrorKind, Overflow, Primitive},
+    runtime::symbol::Symbol,
+};
+
+/// The names of the natives of the strings, see [`call`].
+pub const NATIVES: [&str; 5] = [
+    "str_len",
+    "substr",
+    "char_at",
+    "int_of_string",
+    "string_of_int",
+];
+
+/// Calls the native `name` of the strings with the values of its
+/// arguments:
+///
+/// - `str_len(s)` is the number of characters of `s`.
+/// - `substr(s, start, length)` is the `length` characters of `s` from
+///   the one at `start`, counting from zero.
+/// - `char_at(s, i)` is the string of the character of `s` at `i`.
+/// - `int_of_string(s)` is the integer written in `s`, in decimal with an
+///   optional `-`. It fits in the integers of `overflow` like the
+///   arithmetic does.
+/// - `string_of_int(n)` is the decimal text of `n`.
+///
+/// The indexes past the end of the string are an error.
+///
+/// ```
+/// use rinha::{interpreter::{ErrorKind, Overflow, Primitive}, runtime::string};
+///
+/// let call = |name, arguments| string::call(name, arguments, Overflow::Trap);
+/// let text = Primitive::Str("héllo".into());
+/// assert_eq!(call("str_len", vec![text.clone()]).unwrap(), Primitive::Int(5));
+/// let middle = call("substr", vec![text.clone(), Primitive::Int(1), Primitive::Int(3)]);
+/// assert_eq!(middle.unwrap().to_string(), "éll");
+/// assert_eq!(call("int_of_string", vec![Primitive::Str("-42".into())]).unwrap(), Primitive::Int(-42));
+///
+/// let past = call("char_at", vec![text, Primitive::Int(5)]).unwrap_err();
+/// assert_eq!(past, ErrorKind::OutOfRange { name: "char_at", index: 5, len: 5 });
+/// ```
+pub fn call(
+    name: &'static str,
+    arguments: Vec<Primitive>,
+    overflow: Overflow,
+) -> Result<Primitive, ErrorKind> {
+    let given = arguments.len();
+    let arity = |expected: usize| ErrorKind::ArityMismatch {
+        name: name.into(),
+        expected,
+        given,
+    };
+    let mut arguments = arguments.into_iter();
+    let arguments = [
+        arguments.next(),
+        arguments.next(),
+        arguments.next(),
+        arguments.next(),
+    ];
+    match (name, arguments) {
+        ("str_len", [Some(text), None, None, None]) => {
+            Ok(Primitive::Int(string(name, &text)?.chars().count() as i64))
+        }
+        ("substr", [Some(text), Some(start), Some(length), None]) => {
+            let text = string(name, &text)?;
+            let len = text.chars().count();
+            let start = index(name, &start)?;
+            let end = start.saturating_add(index(name, &length)?);
+            let past = [start, end]
+                .into_iter()
+                .find(|at| !(0..=len as i64).contains(at));
+            match past {
+                Some(index) => Err(ErrorKind::OutOfRange { name, index, len }),
+                None if end < start => Err(ErrorKind::OutOfRange {
+                    name,
+                    index: end,
+                    len,
+                }),
+                None => {
+                    let taken = text
+                        .chars()
+                        .skip(start as usize)
+                        .take((end - start) as usize);
+                    Ok(Primitive::Str(Symbol::from(taken.collect::<String>())))
+                }
+            }
+        }
+        ("char_at", [Some(text), Some(at), None, None]) => {
+            let text = string(name, &text)?;
+            let at = index(name, &at)?;
+            let character = usize::try_from(at).ok().and_then(|at| text.chars().nth(at));
+            match character {
+                Some(character) => Ok(Primitive::Str(Symbol::from(character.to_string()))),
+                None => Err(ErrorKind::OutOfRange {
+                    name,
+                    index: at,
+                    len: text.chars().count(),
+                }),
+            }
+        }
+        ("int_of_string", [Some(text), None, None, None]) => {
+            int_of_string(string(name, &text)?, overflow)
+        }
+        ("string_of_int", [Some(value), None, None, None]) => match value {
+            Primitive::Int(_) | Primitive::BigInt(_) => {
+                Ok(Primitive::Str(Symbol::from(value.to_string())))
+            }
+            _ => Err(ErrorKind::NotAnInt(name)),
+        },
+        ("substr", _) => Err(arity(3)),
+        ("char_at", _) => Err(arity(2)),
+        _ => Err(arity(1)),
+    }
+}
+
+/// The text `value` is, failing for the other values.
+fn string<'v>(name: &'static str, value: &'v Primitive) -> Result<&'v str, ErrorKind> {
+    match value {
+        Primitive::Str(text) => Ok(text.as_str()),
+        _ => Err(ErrorKind::NotAStr(name)),
+    }
+}
+
+/// The index `value` is, the integers past 64 bits are past the end of
+/// any string.
+fn index(name: &'static str, value: &Primitive) -> Result<i64, ErrorKind> {
+    match value {
+        Primitive::Int(index) => Ok(*index),
+        Primitive::BigInt(index) if index.is_negative() => Ok(i64::MIN),
+        Primitive::BigInt(_) => Ok(i64::MAX),
+        _ => Err(ErrorKind::NotAnInt(name)),
+    }
+}
+
+/// The digits are added one after the other with the arithmetic of
+/// `overflow`, so the integers that don't fit fail or wrap around like the
+/// sums and the products do.
+fn int_of_string(text: &str, overflow: Overflow) -> Result<Primitive, ErrorKind> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(ErrorKind::NotAnInteger(text.into()));
+    }
+    let add = if negative {
+        BinaryOp::Sub
+    } else {
+        BinaryOp::Add
+    };
+    let mut value = Primitive::Int(0);
+    for digit in digits.bytes() {
+        let shifted = BinaryOp::Mul.apply(value, Primitive::Int(10), overflow);
+        let digit = Primitive::Int(i64::from(digit - b'0'));
+        value = shifted
+            .and_then(|shifted| add.apply(shifted, digit, overflow))
+            .map_err(|_| ErrorKind::IntegerOverflow("int_of_string"))?;
+    }
+    Ok(value)
+}
@@ -0,0 +1,163 @@
+//! The names and the strings of the programs, shared instead of copied.
+//! The names and the string literals are interned as the programs are
+//! parsed, so the same text is a single allocation, told apart from the
+//! others by its address before its text is compared. The strings the
+//! programs make as they run are shared the same way, without being
+//! interned: nothing would ever leave the table.
+
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt, hash, ops,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// An immutable string, copied by sharing it.
+///
+/// ```
+/// use rinha::runtime::symbol::Symbol;
+///
+/// let name = Symbol::intern("fib");
+/// assert!(Symbol::ptr_eq(&name, &Symbol::intern("fib")));
+/// assert_eq!(name, Symbol::from("fib".to_string()));
+/// assert_eq!(name, "fib");
+/// ```
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// The symbol of `text`, the same allocation every time for the same
+    /// text.
+    pub fn intern(text: &str) -> Symbol {
+        static TABLE: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+        let table = TABLE.get_or_init(Mutex::default);
+        let mut table = table.lock().unwrap_or_else(|error| error.into_inner());
+        if let Some(interned) = table.get(text) {
+            return Symbol(interned.clone());
+        }
+        let interned: Arc<str> = Arc::from(text);
+        table.insert(interned.clone());
+        Symbol(interned)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether both are the very same allocation, like the symbols interned
+    /// from the same text.
+    pub fn ptr_eq(this: &Symbol, other: &Symbol) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Symbol::intern("")
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol(Arc::from(text))
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol(Arc::from(text))
+    }
+}
+
+impl ops::Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Symbol::ptr_eq(self, other) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// Hashed like its text, so the maps keyed by symbols can be looked up
+/// with a `&str`.
+impl hash::Hash for Symbol {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// The symbols read from the JSON ASTs are interned, like the ones of the
+/// parser.
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(Symbol::intern(&text))
+    }
+}
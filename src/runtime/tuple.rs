@@ -0,0 +1,272 @@
+//! Every engine goes through here for the tuples, so they all build, take
+//! apart, compare and print them the same way. The values nested in the
+//! tuples, and in the lists of [`crate::runtime::list`] and the records of
+//! [`crate::runtime::record`], are walked with a stack of their own, so the
+//! deepest tuples a program can build don't overflow the native stack.
+
+use crate::interpreter::{ErrorKind, IntFormat, Primitive};
+use std::{fmt, hash};
+
+/// Why two values can't be compared by [`equal`].
+const NOT_COMPARABLE: &str =
+    "tuples, lists and records can only be compared element by element, between Int, Float, Str and Bool of the same type";
+
+/// The tuple of `first` and `second`.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::tuple};
+///
+/// let pair = tuple::new(Primitive::Int(1), Primitive::Bool(true));
+/// assert_eq!(pair.to_string(), "(1, true)");
+/// ```
+pub fn new(first: Primitive, second: Primitive) -> Primitive {
+    Primitive::Tuple([Box::new(first), Box::new(second)])
+}
+
+/// The first element of `value`, failing when it isn't a tuple.
+///
+/// ```
+/// use rinha::{
+///     interpreter::{ErrorKind, Primitive},
+///     runtime::tuple,
+/// };
+///
+/// let pair = tuple::new(Primitive::Int(1), Primitive::Int(2));
+/// assert_eq!(tuple::first(pair).unwrap().to_string(), "1");
+/// assert_eq!(tuple::first(Primitive::Int(1)).unwrap_err(), ErrorKind::NotATuple("first"));
+/// ```
+pub fn first(value: Primitive) -> Result<Primitive, ErrorKind> {
+    match value {
+        Primitive::Tuple([first, _]) => Ok(*first),
+        _ => Err(ErrorKind::NotATuple("first")),
+    }
+}
+
+/// The second element of `value`, failing when it isn't a tuple.
+pub fn second(value: Primitive) -> Result<Primitive, ErrorKind> {
+    match value {
+        Primitive::Tuple([_, second]) => Ok(*second),
+        _ => Err(ErrorKind::NotATuple("second")),
+    }
+}
+
+/// Whether the values are equal, element by element. The elements are
+/// compared like `==` compares them, so only the integers, the floats, the
+/// strings and the booleans can be, and only with values of the same type.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::tuple};
+///
+/// let pair = |a, b| tuple::new(Primitive::Int(a), Primitive::Int(b));
+/// assert_eq!(tuple::equal(&pair(1, 2), &pair(1, 2)), Ok(true));
+/// assert_eq!(tuple::equal(&pair(1, 2), &pair(2, 1)), Ok(false));
+/// assert!(tuple::equal(&pair(1, 2), &Primitive::Int(1)).is_err());
+/// ```
+pub fn equal(lhs: &Primitive, rhs: &Primitive) -> Result<bool, ErrorKind> {
+    let mut pending = vec![(lhs, rhs)];
+    let mut equal = true;
+    while let Some(pair) = pending.pop() {
+        match pair {
+            (Primitive::Tuple([lhs_0, lhs_1]), Primitive::Tuple([rhs_0, rhs_1])) => {
+                pending.push((lhs_1, rhs_1));
+                pending.push((lhs_0, rhs_0));
+            }
+            // The elements past the end of the shorter list are compared
+            // with nothing, they're unequal.
+            (Primitive::List(lhs), Primitive::List(rhs)) => {
+                equal &= lhs.len() == rhs.len();
+                pending.extend(lhs.iter().zip(rhs.iter()));
+            }
+            // The records with other fields are unequal.
+            (Primitive::Record(lhs), Primitive::Record(rhs)) => {
+                equal &= lhs
+                    .fields()
+                    .map(|(name, _)| name)
+                    .eq(rhs.fields().map(|(name, _)| name));
+                pending.extend(
+                    lhs.fields()
+                        .zip(rhs.fields())
+                        .map(|(lhs, rhs)| (lhs.1, rhs.1)),
+                );
+            }
+            (Primitive::Int(lhs), Primitive::Int(rhs)) => equal &= lhs == rhs,
+            (Primitive::BigInt(lhs), Primitive::BigInt(rhs)) => equal &= lhs == rhs,
+            // The integers that fit in 64 bits are never a `BigInt`.
+            (Primitive::Int(_), Primitive::BigInt(_))
+            | (Primitive::BigInt(_), Primitive::Int(_)) => equal = false,
+            (Primitive::Float(lhs), Primitive::Float(rhs)) => equal &= lhs == rhs,
+            (Primitive::Str(lhs), Primitive::Str(rhs)) => equal &= lhs == rhs,
+            (Primitive::Bool(lhs), Primitive::Bool(rhs)) => equal &= lhs == rhs,
+            _ => return Err(ErrorKind::TypeMismatch(NOT_COMPARABLE)),
+        }
+    }
+    Ok(equal)
+}
+
+/// Feeds `value` into `state`. The values [`equal`] says are equal hash
+/// the same.
+pub fn hash(value: &Primitive, state: &mut impl hash::Hasher) {
+    use hash::Hash;
+
+    let mut pending = vec![value];
+    while let Some(value) = pending.pop() {
+        match value {
+            Primitive::Tuple([first, second]) => {
+                state.write_u8(0);
+                pending.push(second);
+                pending.push(first);
+            }
+            Primitive::Int(value) => {
+                state.write_u8(1);
+                value.hash(state);
+            }
+            Primitive::Str(value) => {
+                state.write_u8(2);
+                value.hash(state);
+            }
+            Primitive::Bool(value) => {
+                state.write_u8(3);
+                value.hash(state);
+            }
+            Primitive::BigInt(value) => {
+                state.write_u8(6);
+                value.hash(state);
+            }
+            Primitive::Float(value) => {
+                state.write_u8(7);
+                // Both zeros are equal, so they hash the same.
+                let value = if *value == 0.0 { 0.0 } else { *value };
+                value.to_bits().hash(state);
+            }
+            Primitive::Function { name, .. } => {
+                state.write_u8(4);
+                name.hash(state);
+            }
+            Primitive::Ref(_) => state.write_u8(11),
+            Primitive::None => state.write_u8(5),
+            Primitive::Thunk(thunk) => match thunk.value() {
+                Some(value) => hash(&value, state),
+                None => state.write_u8(8),
+            },
+            Primitive::List(list) => {
+                state.write_u8(9);
+                list.len().hash(state);
+                let elements: Vec<_> = list.iter().collect();
+                pending.extend(elements.into_iter().rev());
+            }
+            Primitive::Record(record) => {
+                state.write_u8(10);
+                record.len().hash(state);
+                let fields: Vec<_> = record.fields().collect();
+                for (name, value) in fields.into_iter().rev() {
+                    pending.push(value);
+                    name.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// How deep the tuples nest in `value`, zero when it isn't a tuple. The
+/// lists and the records add no depth, the tuples inside of them count.
+///
+/// ```
+/// use rinha::{interpreter::Primitive, runtime::tuple};
+///
+/// let nested = tuple::new(Primitive::Int(1), tuple::new(Primitive::Int(2), Primitive::Int(3)));
+/// assert_eq!(tuple::depth(&nested), 2);
+/// ```
+pub fn depth(value: &Primitive) -> u64 {
+    let mut deepest = 0;
+    let mut pending = vec![(value, 0)];
+    while let Some((value, depth)) = pending.pop() {
+        match value {
+            Primitive::Tuple([first, second]) => {
+                deepest = deepest.max(depth + 1);
+                pending.push((first, depth + 1));
+                pending.push((second, depth + 1));
+            }
+            Primitive::List(list) => pending.extend(list.iter().map(|element| (element, depth))),
+            Primitive::Record(record) => {
+                pending.extend(record.fields().map(|(_, value)| (value, depth)))
+            }
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// What's left to write of a value being rendered.
+enum Piece<'a> {
+    Value(&'a Primitive),
+    Text(&'static str),
+    /// The name of a field, written before its value.
+    Name(&'a str),
+}
+
+/// Writes `value` the way `print` shows it, with the integers in `format`.
+/// It's streamed into `f`, piece by piece.
+pub fn render(f: &mut fmt::Formatter<'_>, value: &Primitive, format: IntFormat) -> fmt::Result {
+    let mut pending = vec![Piece::Value(value)];
+    while let Some(piece) = pending.pop() {
+        let value = match piece {
+            Piece::Text(text) => {
+                f.write_str(text)?;
+                continue;
+            }
+            Piece::Name(name) => {
+                write!(f, "{name}: ")?;
+                continue;
+            }
+            Piece::Value(value) => value,
+        };
+        match value {
+            Primitive::Tuple([first, second]) => {
+                pending.push(Piece::Text(")"));
+                pending.push(Piece::Value(second));
+                pending.push(Piece::Text(", "));
+                pending.push(Piece::Value(first));
+                pending.push(Piece::Text("("));
+            }
+            Primitive::List(list) => {
+                pending.push(Piece::Text("]"));
+                let elements: Vec<_> = list.iter().collect();
+                for (at, element) in elements.into_iter().enumerate().rev() {
+                    pending.push(Piece::Value(element));
+                    if at > 0 {
+                        pending.push(Piece::Text(", "));
+                    }
+                }
+                pending.push(Piece::Text("["));
+            }
+            Primitive::Record(record) => {
+                pending.push(Piece::Text("}"));
+                let fields: Vec<_> = record.fields().collect();
+                for (at, (name, value)) in fields.into_iter().enumerate().rev() {
+                    pending.push(Piece::Value(value));
+                    pending.push(Piece::Name(name));
+                    if at > 0 {
+                        pending.push(Piece::Text(", "));
+                    }
+                }
+                pending.push(Piece::Text("{"));
+            }
+            Primitive::Str(v) => write!(f, "{v}")?,
+            Primitive::Int(v) => crate::interpreter::write_int(f, *v, format)?,
+            Primitive::BigInt(v) => crate::interpreter::write_big_int(f, v, format)?,
+            // The shortest text that reads back as the same float, like
+            // `2.0`, whatever the format of the integers.
+            Primitive::Float(v) => write!(f, "{v:?}")?,
+            Primitive::Bool(v) => write!(f, "{v}")?,
+            Primitive::Function { .. } => write!(f, "<#closure>")?,
+            Primitive::Ref(_) => write!(f, "<#ref>")?,
+            Primitive::None => write!(f, "unit")?,
+            // Only the scopes hold them, the debuggers show them.
+            Primitive::Thunk(thunk) => match thunk.value() {
+                Some(value) => render(f, &value, format)?,
+                None => write!(f, "<#thunk>")?,
+            },
+        }
+    }
+    Ok(())
+}
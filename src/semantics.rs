@@ -0,0 +1,45 @@
+//! The changes to the observable behavior of the programs, read from
+//! `semantics.json` at the root of the crate. Each one names the flags
+//! that bring the old behavior back, when there are any.
+
+/// A change to what the programs do.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Change {
+    /// A short name, stable across versions.
+    pub id: String,
+    pub description: String,
+    /// The constructs of the language that behave differently.
+    pub construct: String,
+    /// The version of the crate that introduced it.
+    pub version: String,
+    /// The flags of `interpreter run` that restore the old behavior, like
+    /// `--overflow=wrap32`.
+    pub restore: Vec<String>,
+}
+
+impl Change {
+    /// Whether it was introduced after `version`, which is numbers
+    /// separated by dots.
+    pub fn is_since(&self, version: &[u64]) -> bool {
+        match numeric_version(&self.version) {
+            Some(introduced) => introduced.as_slice() > version,
+            None => true,
+        }
+    }
+}
+
+/// Every change, the oldest first.
+///
+/// ```
+/// let changes = rinha::semantics::changes();
+/// assert!(changes.iter().any(|change| change.id == "overflow-widen"));
+/// ```
+pub fn changes() -> Vec<Change> {
+    serde_json::from_str(include_str!("../semantics.json")).expect("semantics.json is valid")
+}
+
+/// The parts of a version made of numbers separated by dots, like
+/// `0.0.6`, or `None` for any other text.
+pub fn numeric_version(text: &str) -> Option<Vec<u64>> {
+    text.split('.').map(|part| part.parse().ok()).collect()
+}
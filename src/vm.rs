@@ -0,0 +1,476 @@
+//! The bytecode engine: the program is lowered into a [`Program`] once,
+//! then run by a stack machine. The calls push frames on the heap instead of
+//! recursing, so a program recurses as deep as the memory allows.
+//!
+//! The values, the errors and the output are the ones of the
+//! [`Interpreter`](crate::interpreter::Interpreter), with a few differences:
+//! the calls aren't memoized, the natives of the extensions aren't provided,
+//! and the variables are resolved where they're used, so a function bound
+//! again by `let` sees the same variables it did where it was made.
+//!
+//! With the `jit` feature and [`Vm::jit`], the functions on integers run as
+//! machine code, see `jit`.
+
+use crate::{
+    ast,
+    interpreter::{
+        drop_let_chain, write_print, ErrorKind, Frame, IntFormat, Overflow, Primitive,
+        RuntimeError, Scope, Stats,
+    },
+    names::FunctionNames,
+    runtime::{
+        record::{self, Record},
+        tuple,
+    },
+};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// The instructions, and the lowering of the terms into them.
+pub mod bytecode;
+
+/// The functions translated into machine code.
+#[cfg(feature = "jit")]
+pub mod jit;
+
+use bytecode::{Instruction, Program, Source};
+#[cfg(feature = "jit")]
+use jit::Jit;
+
+/// Without the `jit` feature there's no machine code, so there's never a
+/// `Jit` to call.
+#[cfg(not(feature = "jit"))]
+enum Jit {}
+
+#[cfg(not(feature = "jit"))]
+impl Jit {
+    fn call(
+        &mut self,
+        _: usize,
+        _: &[Primitive],
+        _: &Scope,
+        _: Option<&AtomicBool>,
+    ) -> Option<(Primitive, u64)> {
+        match *self {}
+    }
+}
+
+/// The stack machine that runs the [`Program`]s.
+pub struct Vm {
+    assert_none: bool,
+    output: Box<dyn io::Write>,
+    tees: Vec<Box<dyn io::Write>>,
+    max_output_bytes: Option<u64>,
+    interrupt: Option<Arc<AtomicBool>>,
+    names: FunctionNames,
+    int_format: IntFormat,
+    overflow: Overflow,
+    extensions: bool,
+    #[cfg(feature = "jit")]
+    jit: bool,
+    max_depth: Option<u64>,
+    max_steps: Option<u64>,
+    /// The functions being run, the innermost last.
+    frames: Vec<Frame>,
+    stats: Stats,
+}
+
+/// A function being run by the [`Vm`].
+struct Activation {
+    chunk: usize,
+    /// The next instruction.
+    pc: usize,
+    /// Where its slots start.
+    base: usize,
+    /// The function itself, `None` for the top level expression.
+    function: Option<Primitive>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    /// Creates a machine that prints to the standard output.
+    pub fn new() -> Vm {
+        Vm::with_output(io::stdout())
+    }
+    /// Creates a machine that prints into `output`.
+    ///
+    /// ```
+    /// use rinha::{parser, vm::Vm};
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "print(1 + 1)").unwrap();
+    /// let mut vm = Vm::with_output(std::io::sink());
+    /// let value = vm.run(file.expression).unwrap();
+    ///
+    /// assert_eq!(value.to_string(), "2");
+    /// assert_eq!(vm.stats().printed_bytes, 2);
+    /// ```
+    pub fn with_output(output: impl io::Write + 'static) -> Vm {
+        Vm {
+            assert_none: cfg!(debug_assertions),
+            output: Box::new(output),
+            tees: Vec::new(),
+            max_output_bytes: None,
+            interrupt: None,
+            names: FunctionNames::default(),
+            int_format: IntFormat::default(),
+            overflow: Overflow::default(),
+            extensions: false,
+            #[cfg(feature = "jit")]
+            jit: false,
+            max_depth: None,
+            max_steps: None,
+            frames: Vec::new(),
+            stats: Stats::default(),
+        }
+    }
+    /// See [`Interpreter::assert_none`](crate::interpreter::Interpreter::assert_none).
+    pub fn assert_none(mut self, assert_none: bool) -> Vm {
+        self.assert_none = assert_none;
+        self
+    }
+    /// Also writes everything printed into `sink`.
+    pub fn tee(mut self, sink: impl io::Write + 'static) -> Vm {
+        self.tees.push(Box::new(sink));
+        self
+    }
+    /// See [`Interpreter::max_output_bytes`](crate::interpreter::Interpreter::max_output_bytes).
+    pub fn max_output_bytes(mut self, limit: u64) -> Vm {
+        self.max_output_bytes = Some(limit);
+        self
+    }
+    /// Names the functions, see [`FunctionNames`].
+    pub fn function_names(mut self, names: FunctionNames) -> Vm {
+        self.names = names;
+        self
+    }
+    /// Stops at the next function call once `flag` is raised, failing with
+    /// [`ErrorKind::Interrupted`].
+    pub fn interrupt_on(mut self, flag: Arc<AtomicBool>) -> Vm {
+        self.interrupt = Some(flag);
+        self
+    }
+    /// How `print` renders the integers.
+    pub fn int_format(mut self, int_format: IntFormat) -> Vm {
+        self.int_format = int_format;
+        self
+    }
+    /// What the arithmetic does when the results don't fit.
+    pub fn overflow(mut self, overflow: Overflow) -> Vm {
+        self.overflow = overflow;
+        self
+    }
+    /// Provides the floats, see
+    /// [`Interpreter::extensions`](crate::interpreter::Interpreter::extensions).
+    pub fn extensions(mut self, extensions: bool) -> Vm {
+        self.extensions = extensions;
+        self
+    }
+    /// Runs the functions it can as machine code, see [`jit`]. They print,
+    /// fail and count their calls like the bytecode does.
+    #[cfg(feature = "jit")]
+    pub fn jit(mut self, jit: bool) -> Vm {
+        self.jit = jit;
+        self
+    }
+    /// Stops at the call that would make more than `calls` calls in
+    /// progress at once, failing with [`ErrorKind::DepthLimit`]. Like with
+    /// [`Vm::max_steps`], the functions run as bytecode.
+    pub fn max_depth(mut self, calls: u64) -> Vm {
+        self.max_depth = Some(calls);
+        self
+    }
+    /// Stops at the instruction run after the first `steps`, failing with
+    /// [`ErrorKind::StepLimit`]. The machine code counts no instructions,
+    /// so the functions run as bytecode while there's a limit.
+    pub fn max_steps(mut self, steps: u64) -> Vm {
+        self.max_steps = Some(steps);
+        self
+    }
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// The functions being run, the innermost last. It's empty once the
+    /// program finishes, and tells where it was when it didn't.
+    pub fn stack(&self) -> &[Frame] {
+        &self.frames
+    }
+    /// Lowers the term and runs it, returning the value it produces.
+    pub fn run(&mut self, term: ast::Term) -> Result<Primitive, RuntimeError> {
+        self.names.extend(FunctionNames::new(&term, None));
+        let program = Program::lower(&term, &self.names);
+        let result = self.execute(&program);
+        drop_let_chain(term);
+        result
+    }
+    /// Runs a program lowered already.
+    pub fn execute(&mut self, program: &Program) -> Result<Primitive, RuntimeError> {
+        let depth = self.frames.len();
+        #[cfg(feature = "jit")]
+        let jit = {
+            let limited = self.max_depth.is_some() || self.max_steps.is_some();
+            (self.jit && !limited).then(|| Jit::compile(program, self.overflow))
+        };
+        #[cfg(not(feature = "jit"))]
+        let jit = None;
+        let result = self.dispatch(program, jit).map_err(|mut error| {
+            error.trace = self.frames[depth..].iter().rev().cloned().collect();
+            error
+        });
+        self.frames.truncate(depth);
+        result
+    }
+    fn dispatch(
+        &mut self,
+        program: &Program,
+        mut jit: Option<Jit>,
+    ) -> Result<Primitive, RuntimeError> {
+        let mut activations = vec![Activation {
+            chunk: 0,
+            pc: 0,
+            base: 0,
+            function: None,
+        }];
+        let mut slots: Vec<Primitive> = vec![Primitive::None; program.chunks[0].slots];
+        let mut stack: Vec<Primitive> = Vec::new();
+
+        loop {
+            let activation = activations.last_mut().expect("a function is being run");
+            let chunk = &program.chunks[activation.chunk];
+            let pc = activation.pc;
+            activation.pc += 1;
+            let base = activation.base;
+            let fail = |kind| RuntimeError::new(kind, chunk.locations[pc].clone());
+            self.stats.terms += 1;
+            if let Some(max) = self.max_steps.filter(|max| self.stats.terms > *max) {
+                return Err(fail(ErrorKind::StepLimit(max)));
+            }
+
+            match &chunk.code[pc] {
+                Instruction::Int(value) => stack.push(Primitive::Int(*value)),
+                Instruction::Bool(value) => stack.push(Primitive::Bool(*value)),
+                Instruction::Float(_) if !self.extensions => {
+                    return Err(fail(ErrorKind::NeedsExtensions("floats")))
+                }
+                Instruction::Float(value) => stack.push(Primitive::Float(*value)),
+                Instruction::Str(index) => {
+                    stack.push(Primitive::Str(program.strings[*index].clone()))
+                }
+                Instruction::Unit(origin) if self.assert_none => {
+                    return Err(fail(ErrorKind::UnexpectedUnit(origin)))
+                }
+                Instruction::Unit(_) => stack.push(Primitive::None),
+                Instruction::Load(slot) => stack.push(slots[base + slot].clone()),
+                Instruction::Store(slot) => slots[base + slot] = pop(&mut stack),
+                Instruction::LoadSelf => {
+                    let function = activation.function.clone();
+                    stack.push(function.expect("only the functions call themselves"));
+                }
+                Instruction::Undefined(index) => {
+                    let name = program.variables[*index].to_string();
+                    return Err(fail(ErrorKind::UndefinedVariable(name)));
+                }
+                Instruction::Jump(to) => activation.pc = *to,
+                Instruction::JumpIfFalse(to) => match pop(&mut stack) {
+                    Primitive::Bool(true) => {}
+                    Primitive::Bool(false) => activation.pc = *to,
+                    _ => return Err(fail(ErrorKind::ConditionNotBool)),
+                },
+                Instruction::ShortCircuit(op, to) => {
+                    if op.short_circuits(stack.last().expect("the left operand was pushed")) {
+                        activation.pc = *to;
+                    }
+                }
+                Instruction::Binary(op) => {
+                    let right = pop(&mut stack);
+                    let left = pop(&mut stack);
+                    stack.push(op.apply(left, right, self.overflow).map_err(fail)?);
+                }
+                Instruction::Tuple => {
+                    let second = pop(&mut stack);
+                    let first = pop(&mut stack);
+                    stack.push(tuple::new(first, second));
+                }
+                Instruction::First => {
+                    let value = tuple::first(pop(&mut stack)).map_err(fail)?;
+                    stack.push(value);
+                }
+                Instruction::Second => {
+                    let value = tuple::second(pop(&mut stack)).map_err(fail)?;
+                    stack.push(value);
+                }
+                Instruction::Record(_) | Instruction::Access(_) if !self.extensions => {
+                    return Err(fail(ErrorKind::NeedsExtensions("records")))
+                }
+                Instruction::Record(names) => {
+                    let values = stack.split_off(stack.len() - names.len());
+                    let fields = names.iter().cloned().zip(values).collect();
+                    stack.push(Primitive::Record(Record::new(fields).map_err(fail)?));
+                }
+                Instruction::Access(field) => {
+                    let value = record::field(&pop(&mut stack), field).map_err(fail)?;
+                    stack.push(value);
+                }
+                Instruction::NoMatch => {
+                    pop(&mut stack);
+                    return Err(fail(ErrorKind::NoMatch));
+                }
+                Instruction::Print => {
+                    let value = stack.last().expect("print has a value");
+                    self.print(value, &chunk.locations[pc])?;
+                }
+                Instruction::Closure(index) => {
+                    let closure = &program.chunks[*index];
+                    let env: Scope = closure
+                        .captures
+                        .iter()
+                        .map(|capture| {
+                            let value = match capture.from {
+                                Source::Slot(slot) => slots[base + slot].clone(),
+                                Source::Itself => {
+                                    let function = activation.function.clone();
+                                    function.expect("only the functions call themselves")
+                                }
+                            };
+                            (capture.name.clone(), value)
+                        })
+                        .collect();
+                    stack.push(Primitive::Function {
+                        name: closure.name.clone(),
+                        parameters: closure.parameters.clone(),
+                        value: closure.body.clone().expect("the functions have a body"),
+                        env,
+                    });
+                }
+                Instruction::Callable => {
+                    if !matches!(stack.last(), Some(Primitive::Function { .. })) {
+                        return Err(fail(ErrorKind::NotAFunction));
+                    }
+                }
+                Instruction::Arity(given) => {
+                    let Some(Primitive::Function {
+                        name, parameters, ..
+                    }) = stack.last()
+                    else {
+                        unreachable!("`Callable` checked the function");
+                    };
+                    if parameters.len() != *given {
+                        return Err(fail(ErrorKind::ArityMismatch {
+                            name: name.to_string(),
+                            expected: parameters.len(),
+                            given: *given,
+                        }));
+                    }
+                }
+                Instruction::Call(arguments) | Instruction::TailCall(arguments) => {
+                    let tail = matches!(chunk.code[pc], Instruction::TailCall(_));
+                    if let Some(interrupt) = &self.interrupt {
+                        if interrupt.load(Ordering::Relaxed) {
+                            return Err(fail(ErrorKind::Interrupted));
+                        }
+                    }
+                    let call = chunk.locations[pc].clone();
+                    let arguments = stack.split_off(stack.len() - arguments);
+                    let function = pop(&mut stack);
+                    let Primitive::Function {
+                        name, value, env, ..
+                    } = &function
+                    else {
+                        unreachable!("`Callable` checked the function");
+                    };
+                    let index = program
+                        .chunk_of(value)
+                        .expect("the functions come from the program");
+                    let native = jit.as_mut().and_then(|jit| {
+                        jit.call(index, &arguments, env, self.interrupt.as_deref())
+                    });
+                    if let Some((value, calls)) = native {
+                        self.stats.calls += calls;
+                        if tail {
+                            let activation = activations.pop().expect("a function is being run");
+                            if activations.is_empty() {
+                                return Ok(value);
+                            }
+                            slots.truncate(activation.base);
+                            self.frames.pop();
+                        }
+                        stack.push(value);
+                        continue;
+                    }
+                    let callee = &program.chunks[index];
+                    self.stats.calls += 1;
+                    let frame = Frame {
+                        function: name.clone(),
+                        call,
+                    };
+
+                    // The function replaces the one that called it, its
+                    // slots are given back first.
+                    if tail {
+                        activations.pop();
+                        slots.truncate(base);
+                        self.frames.pop();
+                    }
+                    let base = slots.len();
+                    slots.extend(arguments);
+                    slots.resize(base + callee.slots, Primitive::None);
+                    for capture in &callee.captures {
+                        let value = env.get(&capture.name).expect("the captures are bound");
+                        slots[base + capture.slot] = value.clone();
+                    }
+                    self.frames.push(frame);
+                    self.stats.max_depth = self.stats.max_depth.max(self.frames.len() as u64);
+                    if let Some(max) = self.max_depth.filter(|max| self.frames.len() as u64 > *max)
+                    {
+                        return Err(fail(ErrorKind::DepthLimit(max)));
+                    }
+                    activations.push(Activation {
+                        chunk: index,
+                        pc: 0,
+                        base,
+                        function: Some(function),
+                    });
+                }
+                Instruction::Return => {
+                    let activation = activations.pop().expect("a function is being run");
+                    if activations.is_empty() {
+                        return Ok(pop(&mut stack));
+                    }
+                    slots.truncate(activation.base);
+                    self.frames.pop();
+                }
+            }
+        }
+    }
+    /// Writes the value through the same sink as
+    /// [`crate::interpreter::Interpreter`], counting the bytes.
+    fn print(&mut self, value: &Primitive, location: &ast::Location) -> Result<(), RuntimeError> {
+        let depth = tuple::depth(value);
+        self.stats.max_print_depth = self.stats.max_print_depth.max(depth);
+
+        write_print(
+            &mut *self.output,
+            &mut self.tees,
+            self.max_output_bytes,
+            value.display(self.int_format),
+            location,
+            &mut self.stats,
+        )?;
+        Ok(())
+    }
+}
+
+/// Pops an operand, the lowering pushes one before every instruction that
+/// takes it.
+fn pop(stack: &mut Vec<Primitive>) -> Primitive {
+    stack.pop().expect("the operand was pushed")
+}
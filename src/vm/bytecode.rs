@@ -0,0 +1,428 @@
+use crate::{
+    ast::{self, BinaryOp, Element, Term},
+    names::FunctionNames,
+    optimize,
+    runtime::symbol::Symbol,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// One instruction of a [`Chunk`]. The operands are taken from the stack
+/// of the function being run, and the results are pushed onto it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Int(i64),
+    Bool(bool),
+    /// Pushes the float, failing without the extensions.
+    Float(f64),
+    /// Pushes the string at this index of [`Program::strings`].
+    Str(usize),
+    /// Pushes the unit value, failing when asserting that it never shows
+    /// up. The text tells what produced it.
+    Unit(&'static str),
+    /// Pushes the value in the slot.
+    Load(usize),
+    /// Pops the top into the slot.
+    Store(usize),
+    /// Pushes the function being run, its body calls it by its name.
+    LoadSelf,
+    /// Fails, the variable at this index of [`Program::variables`] isn't
+    /// bound where it's used.
+    Undefined(usize),
+    /// Continues at the instruction.
+    Jump(usize),
+    /// Pops the condition, continuing at the instruction when it's false.
+    JumpIfFalse(usize),
+    /// Continues at the instruction, leaving the left operand as the result,
+    /// when it decides the result of the operation, see
+    /// [`BinaryOp::short_circuits`].
+    ShortCircuit(BinaryOp, usize),
+    /// Pops the right operand, then the left one.
+    Binary(BinaryOp),
+    /// Pops the second element, then the first one.
+    Tuple,
+    First,
+    Second,
+    /// Pops the values of the fields with these names, the last one first,
+    /// and pushes their record, failing without the extensions.
+    Record(Arc<[Symbol]>),
+    /// Pops the record, and pushes the value of its field, failing without
+    /// the extensions.
+    Access(Symbol),
+    /// Pops the value of a `match` and fails, none of its arms took it.
+    NoMatch,
+    /// Prints the top, leaving it there.
+    Print,
+    /// Pushes a function of the chunk at this index, with the variables it
+    /// captures.
+    Closure(usize),
+    /// Fails unless the top is a function, before its arguments are
+    /// evaluated.
+    Callable,
+    /// Fails unless the function under the top takes this many arguments.
+    Arity(usize),
+    /// Pops this many arguments and the function under them, and runs it.
+    Call(usize),
+    /// Like [`Instruction::Call`], but the function replaces the one being
+    /// run, whose value is the value of the call.
+    TailCall(usize),
+    /// Pops the value of the function being run, and goes back to its
+    /// caller.
+    Return,
+}
+
+/// A function lowered into instructions, or the top level expression.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// The name of the function, see [`FunctionNames`]. It's empty for the
+    /// top level expression.
+    pub name: Symbol,
+    /// Their values are in the first slots.
+    pub parameters: Arc<[Symbol]>,
+    /// The variables used by the body and bound where the function is
+    /// made.
+    pub captures: Vec<Capture>,
+    /// How many slots the parameters, the captures and the `let` of the
+    /// body take.
+    pub slots: usize,
+    pub code: Vec<Instruction>,
+    /// The location of the term of each instruction, for the errors.
+    pub locations: Vec<ast::Location>,
+    /// The body of the function, carried by its values like the ones of the
+    /// interpreter. It's `None` for the top level expression.
+    pub body: Option<Arc<Term>>,
+}
+
+/// A variable captured by a function, see [`Chunk::captures`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: Symbol,
+    /// Where the function is made, its value.
+    pub from: Source,
+    /// Where the body reads it.
+    pub slot: usize,
+}
+
+/// Where a variable is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Slot(usize),
+    /// The function being run.
+    Itself,
+}
+
+/// A program lowered into bytecode, its top level expression is the first
+/// chunk.
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub chunks: Vec<Chunk>,
+    pub strings: Vec<Symbol>,
+    /// The names of the variables that aren't bound where they're used.
+    pub variables: Vec<Symbol>,
+    /// The chunk of each function body, by the address of the body.
+    bodies: HashMap<*const Term, usize>,
+}
+
+impl Program {
+    /// Lowers `term`, resolving every variable to where its value is. The
+    /// functions are named by `names`, like in the interpreter.
+    ///
+    /// ```
+    /// use rinha::{names::FunctionNames, parser, vm::bytecode::{Instruction, Program}};
+    ///
+    /// let file = parser::parse_or_report("main.rinha", "let x = 1; x + 1").unwrap();
+    /// let program = Program::lower(&file.expression, &FunctionNames::default());
+    ///
+    /// assert_eq!(program.chunks[0].code[..2], [Instruction::Int(1), Instruction::Store(0)]);
+    /// ```
+    pub fn lower(term: &Term, names: &FunctionNames) -> Program {
+        let mut lowering = Lowering {
+            names,
+            program: Program {
+                chunks: vec![Chunk::new(Symbol::default(), Vec::new(), None)],
+                strings: Vec::new(),
+                variables: Vec::new(),
+                bodies: HashMap::new(),
+            },
+            contexts: vec![Context {
+                chunk: 0,
+                name: None,
+                locals: Vec::new(),
+            }],
+        };
+        lowering.term(term, false);
+        lowering.emit(Instruction::Return, term.location());
+        lowering.program
+    }
+
+    /// The chunk of the function whose body is `body`, when it's a function
+    /// of this program.
+    pub fn chunk_of(&self, body: &Arc<Term>) -> Option<usize> {
+        self.bodies.get(&Arc::as_ptr(body)).copied()
+    }
+}
+
+impl Chunk {
+    fn new(name: Symbol, parameters: Vec<Symbol>, body: Option<Arc<Term>>) -> Self {
+        Self {
+            name,
+            slots: parameters.len(),
+            parameters: parameters.into(),
+            captures: Vec::new(),
+            code: Vec::new(),
+            locations: Vec::new(),
+            body,
+        }
+    }
+}
+
+/// The chunk being lowered and what's bound inside of it.
+struct Context {
+    chunk: usize,
+    /// The name the body calls the function by.
+    name: Option<Symbol>,
+    /// The parameters and the `let` in scope, the innermost last.
+    locals: Vec<(Symbol, usize)>,
+}
+
+struct Lowering<'n> {
+    names: &'n FunctionNames,
+    program: Program,
+    /// The functions being lowered, the innermost last.
+    contexts: Vec<Context>,
+}
+
+impl Lowering<'_> {
+    fn chunk(&mut self) -> &mut Chunk {
+        let chunk = self
+            .contexts
+            .last()
+            .expect("a chunk is being lowered")
+            .chunk;
+        &mut self.program.chunks[chunk]
+    }
+
+    fn emit(&mut self, instruction: Instruction, location: &ast::Location) -> usize {
+        let chunk = self.chunk();
+        chunk.code.push(instruction);
+        chunk.locations.push(location.clone());
+        chunk.code.len() - 1
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let chunk = self.chunk();
+        let target = chunk.code.len();
+        match &mut chunk.code[at] {
+            Instruction::Jump(to)
+            | Instruction::JumpIfFalse(to)
+            | Instruction::ShortCircuit(_, to) => *to = target,
+            other => unreachable!("patching {other:?}, which isn't a jump"),
+        }
+    }
+
+    /// Lowers `term`, whose value is the value of the function being
+    /// lowered when it's in `tail` position.
+    fn term(&mut self, term: &Term, tail: bool) {
+        match term {
+            Term::Error(error) => {
+                self.emit(Instruction::Unit("a syntax error"), &error.location);
+            }
+            Term::Int(int) => {
+                self.emit(Instruction::Int(int.value.into()), &int.location);
+            }
+            Term::Float(float) => {
+                self.emit(Instruction::Float(float.value), &float.location);
+            }
+            Term::Bool(bool) => {
+                self.emit(Instruction::Bool(bool.value), &bool.location);
+            }
+            Term::Str(str) => {
+                self.program.strings.push(str.value.clone());
+                let index = self.program.strings.len() - 1;
+                self.emit(Instruction::Str(index), &str.location);
+            }
+            Term::Var(var) => {
+                let level = self.contexts.len() - 1;
+                let instruction = match self.resolve(level, &var.text) {
+                    Some(Source::Slot(slot)) => Instruction::Load(slot),
+                    Some(Source::Itself) => Instruction::LoadSelf,
+                    None => {
+                        self.program.variables.push(var.text.clone());
+                        Instruction::Undefined(self.program.variables.len() - 1)
+                    }
+                };
+                self.emit(instruction, &var.location);
+            }
+            Term::Let(let_) => self.let_chain(let_, tail),
+            Term::If(if_) => {
+                self.term(&if_.condition, false);
+                let otherwise = self.emit(Instruction::JumpIfFalse(0), if_.condition.location());
+                self.term(&if_.then, tail);
+                let end = self.emit(Instruction::Jump(0), &if_.location);
+                self.patch(otherwise);
+                self.term(&if_.otherwise, tail);
+                self.patch(end);
+            }
+            Term::Binary(binary) => {
+                self.term(&binary.lhs, false);
+                let short_circuit = matches!(binary.op, BinaryOp::And | BinaryOp::Or);
+                let skip = short_circuit.then(|| {
+                    let instruction = Instruction::ShortCircuit(binary.op.clone(), 0);
+                    self.emit(instruction, &binary.location)
+                });
+                self.term(&binary.rhs, false);
+                self.emit(Instruction::Binary(binary.op.clone()), &binary.location);
+                if let Some(skip) = skip {
+                    self.patch(skip);
+                }
+            }
+            Term::Tuple(tuple) => {
+                self.term(&tuple.first, false);
+                self.term(&tuple.second, false);
+                self.emit(Instruction::Tuple, &tuple.location);
+            }
+            Term::First(first) => {
+                self.term(&first.value, false);
+                self.emit(Instruction::First, &first.location);
+            }
+            Term::Second(second) => {
+                self.term(&second.value, false);
+                self.emit(Instruction::Second, &second.location);
+            }
+            Term::Record(record) => {
+                for field in &record.fields {
+                    self.term(&field.value, false);
+                }
+                let names = record.fields.iter().map(|field| field.name.text.clone());
+                self.emit(Instruction::Record(names.collect()), &record.location);
+            }
+            Term::Access(access) => {
+                self.term(&access.value, false);
+                let field = access.field.text.clone();
+                self.emit(Instruction::Access(field), &access.location);
+            }
+            // The matches are lowered into the `let` and `if` they're made
+            // of, sharing the bodies of the functions inside of them.
+            Term::Match(match_) if match_.arms.is_empty() => {
+                self.term(&match_.value, false);
+                self.emit(Instruction::NoMatch, &match_.location);
+            }
+            Term::Match(match_) => self.term(&optimize::lower_match(match_.clone()), tail),
+            Term::Print(print) => {
+                self.term(&print.value, false);
+                self.emit(Instruction::Print, &print.location);
+            }
+            Term::Call(call) => {
+                self.term(&call.callee, false);
+                self.emit(Instruction::Callable, call.callee.location());
+                let arguments = call.arguments.len();
+                self.emit(Instruction::Arity(arguments), &call.location);
+                for argument in &call.arguments {
+                    self.term(argument, false);
+                }
+                let call_instruction = match tail {
+                    true => Instruction::TailCall(arguments),
+                    false => Instruction::Call(arguments),
+                };
+                self.emit(call_instruction, &call.location);
+            }
+            Term::Function(function) => {
+                let chunk = self.function(function);
+                self.emit(Instruction::Closure(chunk), &function.location);
+            }
+        }
+    }
+
+    /// Lowers the chains of `let` in a loop, generated programs can have
+    /// hundreds of thousands of them.
+    fn let_chain(&mut self, mut let_: &ast::Let, tail: bool) {
+        let mut bound = 0;
+        loop {
+            self.term(&let_.value, false);
+            let chunk = self.chunk();
+            let slot = chunk.slots;
+            chunk.slots += 1;
+            self.emit(Instruction::Store(slot), &let_.location);
+            let context = self.contexts.last_mut().expect("a chunk is being lowered");
+            context.locals.push((let_.name.text.clone(), slot));
+            bound += 1;
+            match &*let_.next {
+                Term::Let(next) => let_ = next,
+                next => {
+                    self.term(next, tail);
+                    break;
+                }
+            }
+        }
+        let context = self.contexts.last_mut().expect("a chunk is being lowered");
+        context.locals.truncate(context.locals.len() - bound);
+    }
+
+    /// Lowers the body of `function` into a chunk of its own, returning its
+    /// index.
+    fn function(&mut self, function: &ast::Function) -> usize {
+        let name = match self.names.get(&function.location) {
+            Some(name) => Symbol::intern(name),
+            None => Symbol::from(format!(
+                "anon@{}:{}",
+                function.location.filename, function.location.start
+            )),
+        };
+        let parameters: Vec<Symbol> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.text.clone())
+            .collect();
+        let chunk = self.program.chunks.len();
+        self.program.chunks.push(Chunk::new(
+            name.clone(),
+            parameters.clone(),
+            Some(function.value.clone()),
+        ));
+        self.program
+            .bodies
+            .insert(Arc::as_ptr(&function.value), chunk);
+
+        self.contexts.push(Context {
+            chunk,
+            name: Some(name),
+            locals: parameters.into_iter().zip(0..).collect(),
+        });
+        self.term(&function.value, true);
+        self.emit(Instruction::Return, function.value.location());
+        self.contexts.pop();
+        chunk
+    }
+
+    /// Where the variable `name` is read from in the function at `level`
+    /// of the contexts, capturing it from the enclosing functions when it's
+    /// bound there. The parameters and the `let` come first, then the name
+    /// of the function, like in the scopes of the interpreter.
+    fn resolve(&mut self, level: usize, name: &Symbol) -> Option<Source> {
+        let context = &self.contexts[level];
+        if let Some((_, slot)) = context.locals.iter().rev().find(|(local, _)| local == name) {
+            return Some(Source::Slot(*slot));
+        }
+        if context.name.as_ref() == Some(name) {
+            return Some(Source::Itself);
+        }
+        let chunk = &self.program.chunks[context.chunk];
+        if let Some(capture) = chunk.captures.iter().find(|capture| capture.name == *name) {
+            return Some(Source::Slot(capture.slot));
+        }
+        if level == 0 {
+            return None;
+        }
+
+        let from = self.resolve(level - 1, name)?;
+        let chunk = &mut self.program.chunks[self.contexts[level].chunk];
+        let slot = chunk.slots;
+        chunk.slots += 1;
+        chunk.captures.push(Capture {
+            name: name.clone(),
+            from,
+            slot,
+        });
+        Some(Source::Slot(slot))
+    }
+}
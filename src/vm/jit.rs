@@ -0,0 +1,759 @@
+//! The machine code of `--backend jit`: the functions that only handle
+//! integers and booleans are translated from their bytecode into
+//! Cranelift, typing every value of their stack on the way, and the
+//! [`Vm`](super::Vm) calls them instead of running their instructions.
+//!
+//! A function is translated when it only calls itself and every one of its
+//! operations is on integers or on booleans, with its parameters and the
+//! variables it captures taken as integers. The values a closure captured
+//! are copied into an environment on the heap when it's called, which its
+//! machine code reads them from and hands to its own calls. Its arithmetic
+//! stays in 32 bits: with [`Overflow::Wrap32`] it wraps around like the
+//! other engines do, and with the other modes a result that doesn't fit is
+//! an overflow, which the bytecode goes past, or fails at. The tail calls
+//! are loops, the other calls recurse on the native stack up to
+//! [`MAX_DEPTH`].
+//!
+//! The machine code never reports an error: when anything goes wrong, an
+//! overflow, a division by zero, a deep recursion or an interrupt, the
+//! call returns `None` and the function is run on the bytecode from then
+//! on, which fails with the error where it happens. Running the call again
+//! is only sound because the functions translated print nothing.
+
+use super::bytecode::{Chunk, Instruction, Program};
+use crate::{
+    ast::BinaryOp,
+    interpreter::{Overflow, Primitive},
+    runtime::{scope::Scope, symbol::Symbol},
+};
+use cranelift_codegen::{
+    entity::EntityRef,
+    ir::{
+        condcodes::IntCC, types, AbiParam, Block, FuncRef, InstBuilder, MemFlags, Signature, Value,
+    },
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{FuncId, Linkage, Module};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// How deep the machine code recurses before it gives the call back to
+/// the bytecode, whose frames are on the heap.
+pub const MAX_DEPTH: i64 = 4096;
+
+/// The functions of a [`Program`] translated into machine code.
+pub struct Jit {
+    /// Owns the machine code, it's freed with it.
+    _module: Option<JITModule>,
+    natives: HashMap<usize, Native>,
+}
+
+/// A function translated, called through its entry.
+struct Native {
+    entry: *const u8,
+    arity: usize,
+    /// The names of the variables it captures, in the order of its
+    /// environment.
+    captures: Vec<Symbol>,
+    result: Type,
+}
+
+/// What the machine code shares with its caller, the fields are read and
+/// written at their offsets.
+#[repr(C)]
+struct Context {
+    /// Raised when the call must run on the bytecode instead.
+    failed: i64,
+    /// The calls made by the machine code, the first one included.
+    calls: i64,
+    interrupt: *const AtomicBool,
+}
+
+const FAILED: i32 = 0;
+const CALLS: i32 = 8;
+const INTERRUPT: i32 = 16;
+
+/// The entries take the context, the arguments in an array and the
+/// environment, and call the function with them.
+type Entry = unsafe extern "C" fn(*mut Context, *const i64, *const i64) -> i64;
+
+/// The types of the values of the machine code, both held in 64 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Int,
+    Bool,
+}
+
+/// A value on the stack of a function being translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Value(Value, Type),
+    /// The function itself, about to be called.
+    Itself,
+}
+
+impl Operand {
+    fn shape(self) -> Shape {
+        match self {
+            Operand::Value(_, ty) => Shape::Value(ty),
+            Operand::Itself => Shape::Itself,
+        }
+    }
+}
+
+/// What an [`Operand`] is, the blocks jumped to expect the same on the
+/// stack from every jump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Value(Type),
+    Itself,
+}
+
+/// The function isn't translated, or not with the type of result tried.
+#[derive(Debug)]
+struct Unsupported;
+
+impl Jit {
+    /// Translates the functions of `program` that can be, see the module
+    /// documentation. None are when the machine has no Cranelift backend.
+    pub fn compile(program: &Program, overflow: Overflow) -> Jit {
+        let Some(mut module) = new_module() else {
+            return Jit {
+                _module: None,
+                natives: HashMap::new(),
+            };
+        };
+
+        let mut translated = Vec::new();
+        let mut context = module.make_context();
+        // The top level expression isn't a function.
+        for (index, chunk) in program.chunks.iter().enumerate().skip(1) {
+            let signature = function_signature(&module, chunk.parameters.len());
+            let name = format!("function{index}");
+            let Ok(id) = module.declare_function(&name, Linkage::Local, &signature) else {
+                continue;
+            };
+            // The type of the result is only known once the function is
+            // translated, both are tried.
+            for result in [Type::Int, Type::Bool] {
+                context.func.signature = signature.clone();
+                let itself = module.declare_func_in_func(id, &mut context.func);
+                let mut builder = FunctionBuilderContext::new();
+                let translation = Translation::translate(
+                    FunctionBuilder::new(&mut context.func, &mut builder),
+                    &module,
+                    chunk,
+                    itself,
+                    overflow,
+                    result,
+                );
+                let translation = match translation {
+                    Ok(()) => module.define_function(id, &mut context).is_ok(),
+                    Err(_) => false,
+                };
+                module.clear_context(&mut context);
+                if translation {
+                    translated.push((index, id, result));
+                    break;
+                }
+            }
+        }
+
+        let entries: Vec<_> = translated
+            .into_iter()
+            .filter_map(|(index, id, result)| {
+                let chunk = &program.chunks[index];
+                let arity = chunk.parameters.len();
+                let entry = define_entry(&mut module, &mut context, id, arity)?;
+                let captures = chunk.captures.iter().map(|capture| capture.name.clone());
+                Some((index, entry, arity, captures.collect(), result))
+            })
+            .collect();
+        if module.finalize_definitions().is_err() {
+            return Jit {
+                _module: None,
+                natives: HashMap::new(),
+            };
+        }
+        let natives = entries
+            .into_iter()
+            .map(|(index, entry, arity, captures, result)| {
+                let entry = module.get_finalized_function(entry);
+                (
+                    index,
+                    Native {
+                        entry,
+                        arity,
+                        captures,
+                        result,
+                    },
+                )
+            })
+            .collect();
+        Jit {
+            _module: Some(module),
+            natives,
+        }
+    }
+
+    /// Whether the function of the chunk at `index` runs as machine code.
+    ///
+    /// ```
+    /// use rinha::{interpreter::Overflow, names::FunctionNames, parser};
+    /// use rinha::vm::{bytecode::Program, jit::Jit};
+    ///
+    /// let source = "let f = fn (n) => { if (n < 2) { n } else { f(n - 1) } }; f(3)";
+    /// let file = parser::parse_or_report("main.rinha", source).unwrap();
+    /// let names = FunctionNames::new(&file.expression, None);
+    /// let program = Program::lower(&file.expression, &names);
+    ///
+    /// assert!(Jit::compile(&program, Overflow::Widen).is_compiled(1));
+    /// ```
+    pub fn is_compiled(&self, index: usize) -> bool {
+        self.natives.contains_key(&index)
+    }
+
+    /// Calls the function of the chunk at `index` with `arguments` and the
+    /// variables its closure captured in `env`, returning its value and how
+    /// many calls it made, itself included. It's `None` when the function
+    /// isn't translated, when the arguments or the values captured aren't
+    /// integers of 32 bits, and when the call must run on the bytecode,
+    /// which it does from then on.
+    pub fn call(
+        &mut self,
+        index: usize,
+        arguments: &[Primitive],
+        env: &Scope,
+        interrupt: Option<&AtomicBool>,
+    ) -> Option<(Primitive, u64)> {
+        static NEVER: AtomicBool = AtomicBool::new(false);
+
+        let native = self.natives.get(&index)?;
+        if arguments.len() != native.arity {
+            return None;
+        }
+        let int = |value: Option<&Primitive>| match value {
+            Some(Primitive::Int(value)) if i32::try_from(*value).is_ok() => Some(*value),
+            _ => None,
+        };
+        let arguments = arguments
+            .iter()
+            .map(|argument| int(Some(argument)))
+            .collect::<Option<Vec<i64>>>()?;
+        let environment = native
+            .captures
+            .iter()
+            .map(|name| int(env.get(name)))
+            .collect::<Option<Vec<i64>>>()?;
+        if interrupt.is_some_and(|interrupt| interrupt.load(Ordering::Relaxed)) {
+            return None;
+        }
+
+        let mut context = Context {
+            failed: 0,
+            calls: 1,
+            interrupt: interrupt.unwrap_or(&NEVER),
+        };
+        // SAFETY: the entry was made by `define_entry` with this signature,
+        // it reads `arity` arguments, a value per capture from the
+        // environment and the fields of the context, and the module that
+        // owns it lives as long as `self`.
+        let value = unsafe {
+            let entry = std::mem::transmute::<*const u8, Entry>(native.entry);
+            entry(&mut context, arguments.as_ptr(), environment.as_ptr())
+        };
+        if context.failed != 0 {
+            self.natives.remove(&index);
+            return None;
+        }
+        let value = match native.result {
+            Type::Int => Primitive::Int(value),
+            Type::Bool => Primitive::Bool(value != 0),
+        };
+        Some((value, context.calls as u64))
+    }
+}
+
+/// The module of the machine being run on, `None` when Cranelift can't
+/// generate its code.
+fn new_module() -> Option<JITModule> {
+    let mut flags = settings::builder();
+    flags.set("use_colocated_libcalls", "false").ok()?;
+    flags.set("is_pic", "false").ok()?;
+    flags.set("opt_level", "speed").ok()?;
+    let isa = cranelift_native::builder()
+        .ok()?
+        .finish(settings::Flags::new(flags))
+        .ok()?;
+    let builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
+    Some(JITModule::new(builder))
+}
+
+/// The functions take the context, how deep they are, their environment
+/// and their arguments.
+fn function_signature(module: &JITModule, arity: usize) -> Signature {
+    let mut signature = module.make_signature();
+    let pointer = module.target_config().pointer_type();
+    signature.params.push(AbiParam::new(pointer));
+    signature.params.push(AbiParam::new(types::I64));
+    signature.params.push(AbiParam::new(pointer));
+    for _ in 0..arity {
+        signature.params.push(AbiParam::new(types::I64));
+    }
+    signature.returns.push(AbiParam::new(types::I64));
+    signature
+}
+
+/// Defines the [`Entry`] of the function `id`, the Rust side can't call it
+/// with its arguments one by one.
+fn define_entry(
+    module: &mut JITModule,
+    context: &mut cranelift_codegen::Context,
+    id: FuncId,
+    arity: usize,
+) -> Option<FuncId> {
+    let pointer = module.target_config().pointer_type();
+    let mut signature = module.make_signature();
+    signature.params.push(AbiParam::new(pointer));
+    signature.params.push(AbiParam::new(pointer));
+    signature.params.push(AbiParam::new(pointer));
+    signature.returns.push(AbiParam::new(types::I64));
+    let entry = module.declare_anonymous_function(&signature).ok()?;
+
+    context.func.signature = signature;
+    let function = module.declare_func_in_func(id, &mut context.func);
+    let mut builder = FunctionBuilderContext::new();
+    let mut builder = FunctionBuilder::new(&mut context.func, &mut builder);
+    let block = builder.create_block();
+    builder.append_block_params_for_function_params(block);
+    builder.switch_to_block(block);
+    let (shared, arguments, environment) = match builder.block_params(block) {
+        [shared, arguments, environment] => (*shared, *arguments, *environment),
+        _ => unreachable!("the entries take three parameters"),
+    };
+    let depth = builder.ins().iconst(types::I64, 0);
+    let mut values = vec![shared, depth, environment];
+    for argument in 0..arity {
+        let offset = i32::try_from(argument * 8).ok()?;
+        let value = builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), arguments, offset);
+        values.push(value);
+    }
+    let call = builder.ins().call(function, &values);
+    let value = builder.inst_results(call)[0];
+    builder.ins().return_(&[value]);
+    builder.seal_all_blocks();
+    builder.finalize();
+
+    let defined = module.define_function(entry, context).is_ok();
+    module.clear_context(context);
+    defined.then_some(entry)
+}
+
+/// A function being translated, its blocks are made as the jumps to them
+/// are found.
+struct Translation<'a> {
+    builder: FunctionBuilder<'a>,
+    chunk: &'a Chunk,
+    itself: FuncRef,
+    overflow: Overflow,
+    result: Type,
+    context: Value,
+    depth: Value,
+    /// The values captured, handed to the calls of the function itself.
+    environment: Value,
+    /// Where the tail calls jump back to.
+    body: Block,
+    /// Gives the call back to the bytecode.
+    fail: Block,
+    /// The types of the slots, once they're stored.
+    slots: Vec<Option<Type>>,
+    /// The block of every instruction jumped to, with the stack it's
+    /// reached with once a jump to it is translated.
+    targets: HashMap<usize, (Block, Option<Vec<Shape>>)>,
+    stack: Vec<Operand>,
+    /// Whether the instruction being translated can be reached.
+    reachable: bool,
+}
+
+impl<'a> Translation<'a> {
+    fn translate(
+        mut builder: FunctionBuilder<'a>,
+        module: &JITModule,
+        chunk: &'a Chunk,
+        itself: FuncRef,
+        overflow: Overflow,
+        result: Type,
+    ) -> Result<(), Unsupported> {
+        let pointer = module.target_config().pointer_type();
+        let entry = builder.create_block();
+        let body = builder.create_block();
+        let fail = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let parameters = builder.block_params(entry).to_vec();
+        let (context, depth, environment) = (parameters[0], parameters[1], parameters[2]);
+        for slot in 0..chunk.slots {
+            builder.declare_var(Variable::new(slot), types::I64);
+        }
+        for (slot, value) in parameters[3..].iter().enumerate() {
+            builder.def_var(Variable::new(slot), *value);
+        }
+        // The tail calls keep the closure, so its captures are read once.
+        for (index, capture) in chunk.captures.iter().enumerate() {
+            let offset = i32::try_from(index * 8).map_err(|_| Unsupported)?;
+            let value = builder
+                .ins()
+                .load(types::I64, MemFlags::trusted(), environment, offset);
+            builder.def_var(Variable::new(capture.slot), value);
+        }
+        let deep = builder
+            .ins()
+            .icmp_imm(IntCC::SignedGreaterThan, depth, MAX_DEPTH);
+        builder.ins().brif(deep, fail, &[], body, &[]);
+
+        // The tail calls come back here, the interrupts are checked on
+        // every call like the bytecode does.
+        builder.switch_to_block(body);
+        let interrupt = builder
+            .ins()
+            .load(pointer, MemFlags::trusted(), context, INTERRUPT);
+        let interrupted = builder
+            .ins()
+            .atomic_load(types::I8, MemFlags::trusted(), interrupt);
+        let running = builder.create_block();
+        builder.ins().brif(interrupted, fail, &[], running, &[]);
+        builder.switch_to_block(running);
+
+        let mut slots = vec![None; chunk.slots];
+        slots[..chunk.parameters.len()].fill(Some(Type::Int));
+        for capture in &chunk.captures {
+            slots[capture.slot] = Some(Type::Int);
+        }
+        let mut translation = Translation {
+            builder,
+            chunk,
+            itself,
+            overflow,
+            result,
+            context,
+            depth,
+            environment,
+            body,
+            fail,
+            slots,
+            targets: HashMap::new(),
+            stack: Vec::new(),
+            reachable: true,
+        };
+        for instruction in &chunk.code {
+            if let Instruction::Jump(to)
+            | Instruction::JumpIfFalse(to)
+            | Instruction::ShortCircuit(_, to) = instruction
+            {
+                let block = translation.builder.create_block();
+                translation.targets.entry(*to).or_insert((block, None));
+            }
+        }
+        for pc in 0..chunk.code.len() {
+            translation.arrive(pc)?;
+            if translation.reachable {
+                translation.instruction(pc)?;
+            }
+        }
+
+        let mut builder = translation.builder;
+        builder.switch_to_block(fail);
+        let one = builder.ins().iconst(types::I64, 1);
+        builder
+            .ins()
+            .store(MemFlags::trusted(), one, context, FAILED);
+        let zero = builder.ins().iconst(types::I64, 0);
+        builder.ins().return_(&[zero]);
+        builder.seal_all_blocks();
+        builder.finalize();
+        Ok(())
+    }
+
+    /// Moves on to the instruction at `pc`, into its block when it's
+    /// jumped to.
+    fn arrive(&mut self, pc: usize) -> Result<(), Unsupported> {
+        let Some((block, _)) = self.targets.get(&pc).cloned() else {
+            return Ok(());
+        };
+        if self.reachable {
+            self.jump(pc)?;
+        }
+        let (_, shape) = &self.targets[&pc];
+        let Some(shape) = shape.clone() else {
+            self.reachable = false;
+            return Ok(());
+        };
+        self.builder.switch_to_block(block);
+        let mut parameters = self.builder.block_params(block).to_vec().into_iter();
+        self.stack = shape
+            .into_iter()
+            .map(|shape| match shape {
+                Shape::Value(ty) => {
+                    Operand::Value(parameters.next().expect("a parameter per value"), ty)
+                }
+                Shape::Itself => Operand::Itself,
+            })
+            .collect();
+        self.reachable = true;
+        Ok(())
+    }
+
+    /// Jumps to the instruction at `to` with the stack, the block of the
+    /// instruction takes the values of the stack as its parameters.
+    fn jump(&mut self, to: usize) -> Result<(), Unsupported> {
+        let (block, values) = self.carry(to)?;
+        self.builder.ins().jump(block, &values);
+        Ok(())
+    }
+
+    /// The block of the instruction at `to`, and the values of the stack it
+    /// takes.
+    fn carry(&mut self, to: usize) -> Result<(Block, Vec<Value>), Unsupported> {
+        let shape: Vec<Shape> = self.stack.iter().map(|operand| operand.shape()).collect();
+        let (block, expected) = self.targets.get_mut(&to).expect("the jumps are targets");
+        match expected {
+            Some(expected) if *expected != shape => return Err(Unsupported),
+            Some(_) => {}
+            None => {
+                for shape in &shape {
+                    if let Shape::Value(_) = shape {
+                        self.builder.append_block_param(*block, types::I64);
+                    }
+                }
+                *expected = Some(shape);
+            }
+        }
+        let values = self
+            .stack
+            .iter()
+            .filter_map(|operand| match operand {
+                Operand::Value(value, _) => Some(*value),
+                Operand::Itself => None,
+            })
+            .collect();
+        Ok((*block, values))
+    }
+
+    fn pop(&mut self) -> Result<Operand, Unsupported> {
+        self.stack.pop().ok_or(Unsupported)
+    }
+
+    fn pop_value(&mut self, expected: Type) -> Result<Value, Unsupported> {
+        match self.pop()? {
+            Operand::Value(value, ty) if ty == expected => Ok(value),
+            _ => Err(Unsupported),
+        }
+    }
+
+    fn instruction(&mut self, pc: usize) -> Result<(), Unsupported> {
+        match &self.chunk.code[pc] {
+            Instruction::Int(value) => {
+                if i32::try_from(*value).is_err() {
+                    return Err(Unsupported);
+                }
+                let value = self.builder.ins().iconst(types::I64, *value);
+                self.stack.push(Operand::Value(value, Type::Int));
+            }
+            Instruction::Bool(value) => {
+                let value = self.builder.ins().iconst(types::I64, i64::from(*value));
+                self.stack.push(Operand::Value(value, Type::Bool));
+            }
+            Instruction::Load(slot) => {
+                let ty = self.slots[*slot].ok_or(Unsupported)?;
+                let value = self.builder.use_var(Variable::new(*slot));
+                self.stack.push(Operand::Value(value, ty));
+            }
+            Instruction::Store(slot) => {
+                let Operand::Value(value, ty) = self.pop()? else {
+                    return Err(Unsupported);
+                };
+                if self.slots[*slot].is_some_and(|stored| stored != ty) {
+                    return Err(Unsupported);
+                }
+                self.slots[*slot] = Some(ty);
+                self.builder.def_var(Variable::new(*slot), value);
+            }
+            Instruction::LoadSelf => self.stack.push(Operand::Itself),
+            Instruction::Jump(to) => {
+                self.jump(*to)?;
+                self.reachable = false;
+            }
+            Instruction::JumpIfFalse(to) => {
+                let condition = self.pop_value(Type::Bool)?;
+                let (otherwise, values) = self.carry(*to)?;
+                let then = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(condition, then, &[], otherwise, &values);
+                self.builder.switch_to_block(then);
+            }
+            Instruction::ShortCircuit(op, to) => {
+                let Some(Operand::Value(left, Type::Bool)) = self.stack.last().copied() else {
+                    return Err(Unsupported);
+                };
+                let (skip, values) = self.carry(*to)?;
+                let next = self.builder.create_block();
+                match op {
+                    BinaryOp::And => self.builder.ins().brif(left, next, &[], skip, &values),
+                    BinaryOp::Or => self.builder.ins().brif(left, skip, &values, next, &[]),
+                    _ => return Err(Unsupported),
+                };
+                self.builder.switch_to_block(next);
+            }
+            Instruction::Binary(op) => {
+                let right = self.pop()?;
+                let left = self.pop()?;
+                let value = match (left, right) {
+                    (Operand::Value(left, Type::Int), Operand::Value(right, Type::Int)) => {
+                        self.int(op, left, right)?
+                    }
+                    (Operand::Value(left, Type::Bool), Operand::Value(right, Type::Bool)) => {
+                        self.bool(op, left, right)?
+                    }
+                    _ => return Err(Unsupported),
+                };
+                self.stack.push(value);
+            }
+            Instruction::Callable => {
+                if self.stack.last() != Some(&Operand::Itself) {
+                    return Err(Unsupported);
+                }
+            }
+            Instruction::Arity(given) => {
+                if self.stack.last() != Some(&Operand::Itself)
+                    || *given != self.chunk.parameters.len()
+                {
+                    return Err(Unsupported);
+                }
+            }
+            Instruction::Call(arguments) | Instruction::TailCall(arguments) => {
+                let mut values = (0..*arguments)
+                    .map(|_| self.pop_value(Type::Int))
+                    .collect::<Result<Vec<_>, _>>()?;
+                values.reverse();
+                if self.pop()? != Operand::Itself {
+                    return Err(Unsupported);
+                }
+                self.count_call();
+                if let Instruction::TailCall(_) = self.chunk.code[pc] {
+                    for (slot, value) in values.into_iter().enumerate() {
+                        self.builder.def_var(Variable::new(slot), value);
+                    }
+                    self.builder.ins().jump(self.body, &[]);
+                    self.reachable = false;
+                    return Ok(());
+                }
+                let depth = self.builder.ins().iadd_imm(self.depth, 1);
+                let mut parameters = vec![self.context, depth, self.environment];
+                parameters.extend(values);
+                let call = self.builder.ins().call(self.itself, &parameters);
+                let value = self.builder.inst_results(call)[0];
+                let failed =
+                    self.builder
+                        .ins()
+                        .load(types::I64, MemFlags::trusted(), self.context, FAILED);
+                let next = self.builder.create_block();
+                self.builder.ins().brif(failed, self.fail, &[], next, &[]);
+                self.builder.switch_to_block(next);
+                self.stack.push(Operand::Value(value, self.result));
+            }
+            Instruction::Return => {
+                let value = self.pop_value(self.result)?;
+                self.builder.ins().return_(&[value]);
+                self.reachable = false;
+            }
+            _ => return Err(Unsupported),
+        }
+        Ok(())
+    }
+
+    /// Counts a call in the context.
+    fn count_call(&mut self) {
+        let calls = self
+            .builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), self.context, CALLS);
+        let calls = self.builder.ins().iadd_imm(calls, 1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), calls, self.context, CALLS);
+    }
+
+    /// Fails the call when `condition` is true.
+    fn fail_if(&mut self, condition: Value) {
+        let next = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(condition, self.fail, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    /// The operators on two integers. Their operands fit in 32 bits, so
+    /// their exact results fit in 64 bits before they're fitted.
+    fn int(&mut self, op: &BinaryOp, left: Value, right: Value) -> Result<Operand, Unsupported> {
+        let condition = match op {
+            BinaryOp::Eq => Some(IntCC::Equal),
+            BinaryOp::Neq => Some(IntCC::NotEqual),
+            BinaryOp::Lt => Some(IntCC::SignedLessThan),
+            BinaryOp::Gt => Some(IntCC::SignedGreaterThan),
+            BinaryOp::Lte => Some(IntCC::SignedLessThanOrEqual),
+            BinaryOp::Gte => Some(IntCC::SignedGreaterThanOrEqual),
+            _ => None,
+        };
+        if let Some(condition) = condition {
+            let value = self.builder.ins().icmp(condition, left, right);
+            let value = self.builder.ins().uextend(types::I64, value);
+            return Ok(Operand::Value(value, Type::Bool));
+        }
+
+        if let BinaryOp::Div | BinaryOp::Rem = op {
+            let zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+            self.fail_if(zero);
+        }
+        let exact = match op {
+            BinaryOp::Add => self.builder.ins().iadd(left, right),
+            BinaryOp::Sub => self.builder.ins().isub(left, right),
+            BinaryOp::Mul => self.builder.ins().imul(left, right),
+            BinaryOp::Div => self.builder.ins().sdiv(left, right),
+            BinaryOp::Rem => self.builder.ins().srem(left, right),
+            _ => return Err(Unsupported),
+        };
+        let narrow = self.builder.ins().ireduce(types::I32, exact);
+        let fitted = self.builder.ins().sextend(types::I64, narrow);
+        if self.overflow != Overflow::Wrap32 {
+            let overflows = self.builder.ins().icmp(IntCC::NotEqual, fitted, exact);
+            self.fail_if(overflows);
+        }
+        Ok(Operand::Value(fitted, Type::Int))
+    }
+
+    /// The operators on two booleans, `&&` and `||` have skipped their
+    /// right operand already when the left one decides.
+    fn bool(&mut self, op: &BinaryOp, left: Value, right: Value) -> Result<Operand, Unsupported> {
+        let value = match op {
+            BinaryOp::Eq | BinaryOp::Neq => {
+                let condition = match op {
+                    BinaryOp::Eq => IntCC::Equal,
+                    _ => IntCC::NotEqual,
+                };
+                let value = self.builder.ins().icmp(condition, left, right);
+                self.builder.ins().uextend(types::I64, value)
+            }
+            BinaryOp::And => self.builder.ins().band(left, right),
+            BinaryOp::Or => self.builder.ins().bor(left, right),
+            _ => return Err(Unsupported),
+        };
+        Ok(Operand::Value(value, Type::Bool))
+    }
+}
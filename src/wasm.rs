@@ -0,0 +1,755 @@
+use crate::{
+    ast::{self, BinaryOp, Element, Term},
+    format,
+    interpreter::{ErrorKind, Overflow, Primitive, Scope},
+    names::FunctionNames,
+    optimize,
+    runtime::{symbol::Symbol, tuple},
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+    sync::Arc,
+};
+
+/// The runtime of the modules, the values, the heap and the operators.
+const RUNTIME: &str = include_str!("wasm/runtime.wat");
+
+/// Where the data of the program starts, the bytes before are the scratch
+/// of the runtime.
+const DATA: u32 = 64;
+
+/// The operators in the order of the tables of the runtime, with the
+/// functions that apply them.
+const OPERATORS: [(BinaryOp, &str); 13] = [
+    (BinaryOp::Add, "$add"),
+    (BinaryOp::Sub, "$sub"),
+    (BinaryOp::Mul, "$mul"),
+    (BinaryOp::Div, "$div"),
+    (BinaryOp::Rem, "$rem"),
+    (BinaryOp::Eq, "$eq"),
+    (BinaryOp::Neq, "$neq"),
+    (BinaryOp::Lt, "$lt"),
+    (BinaryOp::Gt, "$gt"),
+    (BinaryOp::Lte, "$lte"),
+    (BinaryOp::Gte, "$gte"),
+    (BinaryOp::And, "$and"),
+    (BinaryOp::Or, "$or"),
+];
+
+/// The tags of the values in the runtime, see `src/wasm/runtime.wat`.
+const BOOL: i64 = 1;
+const STR: i64 = 2;
+const CLOSURE: i64 = 4;
+const UNIT: i64 = 5;
+
+/// How the programs are compiled.
+#[derive(Debug, Clone)]
+pub struct Options<'s> {
+    /// Only [`Overflow::Trap`] and [`Overflow::Wrap32`] can be compiled,
+    /// the values of the modules are 32 bits integers. It's `Trap` by
+    /// default.
+    pub overflow: Overflow,
+    /// The source code of the program, the errors point at lines and
+    /// columns with it, and at offsets into the file otherwise.
+    pub source: Option<&'s str>,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Self {
+            overflow: Overflow::Trap,
+            source: None,
+        }
+    }
+}
+
+/// Why a program can't be compiled.
+#[derive(miette::Diagnostic, thiserror::Error, Debug)]
+pub enum CompileError {
+    #[error("the floats can't be compiled into WebAssembly")]
+    #[diagnostic(code(rinha::wasm::float))]
+    Float {
+        #[label("here")]
+        location: ast::Location,
+    },
+
+    #[error("the records can't be compiled into WebAssembly")]
+    #[diagnostic(code(rinha::wasm::record))]
+    Record {
+        #[label("here")]
+        location: ast::Location,
+    },
+
+    #[error("only the `trap` and `wrap32` overflows can be compiled into WebAssembly")]
+    #[diagnostic(code(rinha::wasm::overflow))]
+    Overflow(Overflow),
+}
+
+/// Compiles the program into a WebAssembly module for WASI, its `_start`
+/// runs the program. What it prints goes into the standard output, and its
+/// errors into the standard error before exiting with `1`.
+///
+/// ```
+/// use rinha::{parser, wasm};
+///
+/// let file = parser::parse_or_report("main.rinha", "print(1 + 1)").unwrap();
+/// let module = wasm::compile(&file, &wasm::Options::default()).unwrap();
+///
+/// assert_eq!(module[..4], *b"\0asm");
+/// ```
+pub fn compile(file: &ast::File, options: &Options) -> Result<Vec<u8>, CompileError> {
+    let text = to_wat(file, options)?;
+    Ok(wat::parse_str(text).expect("the compiled modules are valid"))
+}
+
+/// The module of [`compile`] in the text format.
+pub fn to_wat(file: &ast::File, options: &Options) -> Result<String, CompileError> {
+    if !matches!(options.overflow, Overflow::Trap | Overflow::Wrap32) {
+        return Err(CompileError::Overflow(options.overflow));
+    }
+    let names = FunctionNames::new(&file.expression, options.source);
+    let mut compiler = Compiler {
+        names: &names,
+        source: options.source,
+        data: Data::default(),
+        functions: Vec::new(),
+        arities: BTreeSet::new(),
+        contexts: vec![Context::new(None, &[])],
+    };
+    compiler.term(&file.expression, true)?;
+    let main = compiler
+        .contexts
+        .pop()
+        .expect("the top level is being compiled");
+    let main = main.finish("(func $main (result i64)");
+    Ok(compiler.module(options.overflow, main))
+}
+
+/// The bytes at the start of the memory, the strings are deduplicated.
+#[derive(Default)]
+struct Data {
+    bytes: Vec<u8>,
+    strings: HashMap<String, u32>,
+}
+
+impl Data {
+    fn align(&mut self) {
+        while !self.bytes.len().is_multiple_of(4) {
+            self.bytes.push(0);
+        }
+    }
+
+    /// The address of the string, its length followed by its bytes.
+    fn string(&mut self, text: &str) -> u32 {
+        if let Some(address) = self.strings.get(text) {
+            return *address;
+        }
+        self.align();
+        let address = DATA + self.bytes.len() as u32;
+        self.bytes.extend((text.len() as u32).to_le_bytes());
+        self.bytes.extend(text.as_bytes());
+        self.strings.insert(text.into(), address);
+        address
+    }
+
+    /// The address of the table of `words`.
+    fn table(&mut self, words: &[u32]) -> u32 {
+        self.align();
+        let address = DATA + self.bytes.len() as u32;
+        for word in words {
+            self.bytes.extend(word.to_le_bytes());
+        }
+        address
+    }
+
+    /// The bytes as a string of the text format.
+    fn escaped(&self) -> String {
+        let mut text = String::new();
+        for byte in &self.bytes {
+            match byte {
+                b'"' | b'\\' => write!(text, "\\{:02x}", byte),
+                0x20..=0x7e => write!(text, "{}", *byte as char),
+                _ => write!(text, "\\{:02x}", byte),
+            }
+            .expect("writing into a string");
+        }
+        text
+    }
+}
+
+/// Where a variable is read from, like [`crate::vm::bytecode::Source`].
+#[derive(Debug, Clone, Copy)]
+enum Place {
+    Local(usize),
+    /// The value at this index of the captures of the closure.
+    Capture(usize),
+    /// The closure being run.
+    Itself,
+}
+
+/// A function being compiled, or the top level expression.
+struct Context {
+    /// The name the body calls the function by.
+    name: Option<Symbol>,
+    /// The parameters and the `let` in scope, the innermost last.
+    locals: Vec<(Symbol, usize)>,
+    /// The variables captured from the enclosing function, and where it
+    /// has them.
+    captures: Vec<(Symbol, Place)>,
+    parameters: usize,
+    /// The `i64` locals, the parameters first.
+    slots: usize,
+    /// The `i32` locals, and the ones of them that aren't in use.
+    temporaries: usize,
+    free: Vec<usize>,
+    code: String,
+    depth: usize,
+}
+
+impl Context {
+    fn new(name: Option<Symbol>, parameters: &[Symbol]) -> Self {
+        Self {
+            name,
+            locals: parameters.iter().cloned().zip(0..).collect(),
+            captures: Vec::new(),
+            parameters: parameters.len(),
+            slots: parameters.len(),
+            temporaries: 0,
+            free: Vec::new(),
+            code: String::new(),
+            depth: 0,
+        }
+    }
+
+    /// Writes the instruction on a line of its own, indented by the blocks
+    /// it's inside of.
+    fn line(&mut self, instruction: impl std::fmt::Display) {
+        let indent = 2 * (self.depth + 2);
+        writeln!(self.code, "{:indent$}{instruction}", "").expect("writing into a string");
+    }
+
+    fn temporary(&mut self) -> usize {
+        self.free.pop().unwrap_or_else(|| {
+            self.temporaries += 1;
+            self.temporaries - 1
+        })
+    }
+
+    /// The function, after its `header`.
+    fn finish(self, header: &str) -> String {
+        let mut function = format!("  {header}\n");
+        for slot in self.parameters..self.slots {
+            writeln!(function, "    (local $s{slot} i64)").expect("writing into a string");
+        }
+        for temporary in 0..self.temporaries {
+            writeln!(function, "    (local $t{temporary} i32)").expect("writing into a string");
+        }
+        function.push_str(&self.code);
+        function.push_str("  )\n");
+        function
+    }
+}
+
+struct Compiler<'n> {
+    names: &'n FunctionNames,
+    source: Option<&'n str>,
+    data: Data,
+    /// The functions of the table, by their index.
+    functions: Vec<String>,
+    /// The numbers of parameters of the functions, each has its type.
+    arities: BTreeSet<usize>,
+    /// The functions being compiled, the innermost last.
+    contexts: Vec<Context>,
+}
+
+impl Compiler<'_> {
+    fn context(&mut self) -> &mut Context {
+        self.contexts
+            .last_mut()
+            .expect("a function is being compiled")
+    }
+
+    fn line(&mut self, instruction: impl std::fmt::Display) {
+        self.context().line(instruction);
+    }
+
+    /// Opens a block, its instructions are indented.
+    fn open(&mut self, instruction: &str) {
+        self.line(instruction);
+        self.context().depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.context().depth -= 1;
+        self.line("end");
+    }
+
+    /// Pushes the address of the string.
+    fn string(&mut self, text: &str) {
+        let address = self.data.string(text);
+        self.line(format_args!("i32.const {address}"));
+    }
+
+    /// Pushes where the errors of `location` happen, like the positions of
+    /// the runtime errors of the interpreter.
+    fn site(&mut self, location: &ast::Location) {
+        let position = match self.source {
+            Some(source) => {
+                let (line, column) = location.line_column(source);
+                format!("{}:{line}:{column}", location.filename)
+            }
+            None => format!("{}@{}", location.filename, location.start),
+        };
+        self.string(&format!("{position}: "));
+    }
+
+    /// Pushes the value with the tag, its payload is in the high 32 bits.
+    fn value(&mut self, payload: i64, tag: i64) {
+        self.line(format_args!("i64.const {}", (payload << 32) | tag));
+    }
+
+    /// Compiles `term`, whose value is the value of the function being
+    /// compiled when it's in `tail` position.
+    fn term(&mut self, term: &Term, tail: bool) -> Result<(), CompileError> {
+        match term {
+            Term::Error(_) => self.value(0, UNIT),
+            Term::Int(int) => self.value(int.value.into(), 0),
+            Term::Float(float) => {
+                return Err(CompileError::Float {
+                    location: float.location.clone(),
+                })
+            }
+            Term::Record(ast::Record { location, .. })
+            | Term::Access(ast::Access { location, .. }) => {
+                return Err(CompileError::Record {
+                    location: location.clone(),
+                })
+            }
+            Term::Bool(bool) => self.value(bool.value.into(), BOOL),
+            Term::Str(str) => {
+                let address = self.data.string(&str.value);
+                self.value(address.into(), STR);
+            }
+            Term::Var(var) => {
+                let level = self.contexts.len() - 1;
+                match self.resolve(level, &var.text) {
+                    Some(place) => self.load(place),
+                    None => {
+                        self.site(&var.location);
+                        self.string(
+                            &ErrorKind::UndefinedVariable(var.text.to_string()).to_string(),
+                        );
+                        self.line("call $fail");
+                        self.line("unreachable");
+                    }
+                }
+            }
+            Term::Let(let_) => self.let_chain(let_, tail)?,
+            Term::If(if_) => {
+                self.term(&if_.condition, false)?;
+                self.site(if_.condition.location());
+                self.line("call $condition");
+                self.open("if (result i64)");
+                self.term(&if_.then, tail)?;
+                self.context().depth -= 1;
+                self.line("else");
+                self.context().depth += 1;
+                self.term(&if_.otherwise, tail)?;
+                self.close();
+            }
+            Term::Binary(binary) => self.binary(binary)?,
+            Term::Tuple(tuple) => {
+                self.term(&tuple.first, false)?;
+                self.term(&tuple.second, false)?;
+                self.line("call $tuple");
+            }
+            Term::First(first) => {
+                self.term(&first.value, false)?;
+                self.site(&first.location);
+                self.line("call $first");
+            }
+            Term::Second(second) => {
+                self.term(&second.value, false)?;
+                self.site(&second.location);
+                self.line("call $second");
+            }
+            Term::Print(print) => {
+                self.term(&print.value, false)?;
+                self.line("call $print");
+            }
+            // Like in the bytecode, the matches are compiled as the `let`
+            // and `if` they're made of.
+            Term::Match(match_) if match_.arms.is_empty() => {
+                self.term(&match_.value, false)?;
+                self.line("drop");
+                self.site(&match_.location);
+                self.string(&ErrorKind::NoMatch.to_string());
+                self.line("call $fail");
+                self.line("unreachable");
+            }
+            Term::Match(match_) => {
+                self.term(&optimize::lower_match(match_.clone()), tail)?;
+            }
+            Term::Call(call) => self.call(call, tail)?,
+            Term::Function(function) => self.function(function)?,
+        }
+        Ok(())
+    }
+
+    /// Compiles the chains of `let` in a loop, generated programs can have
+    /// hundreds of thousands of them.
+    fn let_chain(&mut self, mut let_: &ast::Let, tail: bool) -> Result<(), CompileError> {
+        let mut bound = 0;
+        loop {
+            self.term(&let_.value, false)?;
+            let context = self.context();
+            let slot = context.slots;
+            context.slots += 1;
+            context.locals.push((let_.name.text.clone(), slot));
+            context.line(format_args!("local.set $s{slot}"));
+            bound += 1;
+            match &*let_.next {
+                Term::Let(next) => let_ = next,
+                next => {
+                    self.term(next, tail)?;
+                    break;
+                }
+            }
+        }
+        let context = self.context();
+        context.locals.truncate(context.locals.len() - bound);
+        Ok(())
+    }
+
+    /// The operands of `&&` and `||` are evaluated like in the interpreter,
+    /// the right one only when the left one doesn't decide the result.
+    fn binary(&mut self, binary: &ast::Binary) -> Result<(), CompileError> {
+        let (_, function) = OPERATORS
+            .iter()
+            .find(|(op, _)| *op == binary.op)
+            .expect("every operator is in the table");
+        self.term(&binary.lhs, false)?;
+        let decided = match binary.op {
+            BinaryOp::And => Some(BOOL),
+            BinaryOp::Or => Some((1 << 32) | BOOL),
+            _ => None,
+        };
+        let Some(decided) = decided else {
+            self.term(&binary.rhs, false)?;
+            self.site(&binary.location);
+            self.line(format_args!("call {function}"));
+            return Ok(());
+        };
+
+        let context = self.context();
+        let slot = context.slots;
+        context.slots += 1;
+        context.line(format_args!("local.tee $s{slot}"));
+        context.line(format_args!("i64.const {decided}"));
+        context.line("i64.eq");
+        self.open("if (result i64)");
+        self.line(format_args!("local.get $s{slot}"));
+        self.context().depth -= 1;
+        self.line("else");
+        self.context().depth += 1;
+        self.line(format_args!("local.get $s{slot}"));
+        self.term(&binary.rhs, false)?;
+        self.site(&binary.location);
+        self.line(format_args!("call {function}"));
+        self.close();
+        Ok(())
+    }
+
+    /// The callee is checked before the arguments are evaluated, like in
+    /// the interpreter, then the calls in tail position replace the
+    /// function being run.
+    fn call(&mut self, call: &ast::Call, tail: bool) -> Result<(), CompileError> {
+        let given = call.arguments.len();
+        self.arities.insert(given);
+        self.term(&call.callee, false)?;
+        self.site(call.callee.location());
+        self.site(&call.location);
+        self.line(format_args!("i32.const {given}"));
+        let (_, _, after) = arity_texts(given);
+        self.string(&after);
+        self.line("call $callable");
+        let closure = self.context().temporary();
+        self.line(format_args!("local.tee $t{closure}"));
+        for argument in &call.arguments {
+            self.term(argument, false)?;
+        }
+        self.line(format_args!("local.get $t{closure}"));
+        self.line("i32.load");
+        match tail {
+            true => self.line(format_args!("return_call_indirect (type $f{given})")),
+            false => self.line(format_args!("call_indirect (type $f{given})")),
+        }
+        self.context().free.push(closure);
+        Ok(())
+    }
+
+    /// Compiles the body of `function` into a function of the table, and
+    /// makes its closure with the variables it captures.
+    fn function(&mut self, function: &ast::Function) -> Result<(), CompileError> {
+        let name = match self.names.get(&function.location) {
+            Some(name) => Symbol::intern(name),
+            None => Symbol::from(format!(
+                "anon@{}:{}",
+                function.location.filename, function.location.start
+            )),
+        };
+        let parameters: Vec<Symbol> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.text.clone())
+            .collect();
+        let index = self.functions.len();
+        self.functions.push(String::new());
+        self.arities.insert(parameters.len());
+
+        self.contexts
+            .push(Context::new(Some(name.clone()), &parameters));
+        self.term(&function.value, true)?;
+        let body = self.contexts.pop().expect("the function is being compiled");
+        let captures: Vec<Place> = body.captures.iter().map(|(_, from)| *from).collect();
+        self.functions[index] = body.finish(&format!(
+            "(func $f{index} (type $f{}) (param $env i32){} (result i64)",
+            parameters.len(),
+            (0..parameters.len())
+                .map(|slot| format!(" (param $s{slot} i64)"))
+                .collect::<String>()
+        ));
+
+        self.line(format_args!("i32.const {index}"));
+        self.line(format_args!("i32.const {}", parameters.len()));
+        self.string(&name);
+        self.line(format_args!("i32.const {}", captures.len()));
+        self.line("call $closure");
+        let closure = self.context().temporary();
+        self.line(format_args!("local.set $t{closure}"));
+        for (at, from) in captures.into_iter().enumerate() {
+            self.line(format_args!("local.get $t{closure}"));
+            self.load(from);
+            self.line(format_args!("i64.store offset={}", 16 + 8 * at));
+        }
+        self.line(format_args!("local.get $t{closure}"));
+        self.line(format_args!("i32.const {CLOSURE}"));
+        self.line("call $box");
+        self.context().free.push(closure);
+        Ok(())
+    }
+
+    /// Pushes the value of the variable.
+    fn load(&mut self, place: Place) {
+        match place {
+            Place::Local(slot) => self.line(format_args!("local.get $s{slot}")),
+            Place::Capture(at) => {
+                self.line("local.get $env");
+                self.line(format_args!("i64.load offset={}", 16 + 8 * at));
+            }
+            Place::Itself => {
+                self.line("local.get $env");
+                self.line(format_args!("i32.const {CLOSURE}"));
+                self.line("call $box");
+            }
+        }
+    }
+
+    /// Where the variable `name` is read from in the function at `level`
+    /// of the contexts, capturing it from the enclosing functions when it's
+    /// bound there, like the lowering into bytecode does.
+    fn resolve(&mut self, level: usize, name: &Symbol) -> Option<Place> {
+        let context = &self.contexts[level];
+        if let Some((_, slot)) = context.locals.iter().rev().find(|(local, _)| local == name) {
+            return Some(Place::Local(*slot));
+        }
+        if context.name.as_ref() == Some(name) {
+            return Some(Place::Itself);
+        }
+        if let Some(at) = context
+            .captures
+            .iter()
+            .position(|(capture, _)| capture == name)
+        {
+            return Some(Place::Capture(at));
+        }
+        if level == 0 {
+            return None;
+        }
+
+        let from = self.resolve(level - 1, name)?;
+        let captures = &mut self.contexts[level].captures;
+        captures.push((name.clone(), from));
+        Some(Place::Capture(captures.len() - 1))
+    }
+
+    /// The module around the compiled functions, with the data and the
+    /// tables of the runtime.
+    fn module(mut self, overflow: Overflow, main: String) -> String {
+        let mut globals = Vec::new();
+        let mut text = |data: &mut Data, global: &str, text: &str| {
+            globals.push((global.to_string(), data.string(text)));
+        };
+        let samples = samples();
+        text(&mut self.data, "$newline", "\n");
+        text(
+            &mut self.data,
+            "$true_text",
+            &Primitive::Bool(true).to_string(),
+        );
+        text(
+            &mut self.data,
+            "$false_text",
+            &Primitive::Bool(false).to_string(),
+        );
+        text(&mut self.data, "$closure_text", &samples[4].to_string());
+        text(&mut self.data, "$unit_text", &Primitive::None.to_string());
+        text(&mut self.data, "$open_text", "(");
+        text(&mut self.data, "$comma_text", ", ");
+        text(&mut self.data, "$close_text", ")");
+        let (before, middle, _) = arity_texts(0);
+        text(&mut self.data, "$arity_text", &before);
+        text(&mut self.data, "$expects_text", &middle);
+        let errors = [
+            ("$not_a_function", ErrorKind::NotAFunction),
+            ("$not_a_bool", ErrorKind::ConditionNotBool),
+            ("$not_a_tuple_first", error(tuple::first(Primitive::None))),
+            ("$not_a_tuple_second", error(tuple::second(Primitive::None))),
+            (
+                "$not_comparable",
+                error(tuple::equal(&samples[3], &Primitive::None)),
+            ),
+        ];
+        for (global, error) in errors {
+            text(&mut self.data, global, &error.to_string());
+        }
+
+        // What the operators fail with, by the tags of the operands.
+        let mut mismatches = Vec::new();
+        for (op, _) in &OPERATORS {
+            for left in &samples {
+                for right in &samples {
+                    mismatches.push(match op.apply(left.clone(), right.clone(), overflow) {
+                        Ok(_) => 0,
+                        Err(error) => self.data.string(&error.to_string()),
+                    });
+                }
+            }
+        }
+        let arithmetic = &OPERATORS[..5];
+        let overflows: Vec<u32> = arithmetic
+            .iter()
+            .map(|(op, _)| {
+                let error = ErrorKind::IntegerOverflow(format::symbol(op));
+                self.data.string(&error.to_string())
+            })
+            .collect();
+        let zeros: Vec<u32> = arithmetic
+            .iter()
+            .map(|(op, _)| {
+                let error = ErrorKind::DivisionByZero(format::symbol(op));
+                self.data.string(&error.to_string())
+            })
+            .collect();
+        globals.push(("$mismatches".into(), self.data.table(&mismatches)));
+        globals.push(("$overflows".into(), self.data.table(&overflows)));
+        globals.push(("$zeros".into(), self.data.table(&zeros)));
+        globals.push(("$wrap".into(), u32::from(overflow == Overflow::Wrap32)));
+
+        let heap = (DATA + self.data.bytes.len() as u32 + 7) & !7;
+        let mut module = String::from("(module\n");
+        for arity in &self.arities {
+            let parameters = " i64".repeat(*arity);
+            writeln!(
+                module,
+                "  (type $f{arity} (func (param i32{parameters}) (result i64)))"
+            )
+            .expect("writing into a string");
+        }
+        module.push_str(
+            "  (import \"wasi_snapshot_preview1\" \"fd_write\"\n    \
+             (func $fd_write (param i32 i32 i32 i32) (result i32)))\n  \
+             (import \"wasi_snapshot_preview1\" \"proc_exit\" (func $proc_exit (param i32)))\n",
+        );
+        writeln!(
+            module,
+            "  (memory (export \"memory\") {})",
+            heap / 65536 + 1
+        )
+        .expect("writing into a string");
+        writeln!(module, "  (global $heap (mut i32) (i32.const {heap}))")
+            .expect("writing into a string");
+        for (global, value) in globals {
+            writeln!(module, "  (global {global} i32 (i32.const {value}))")
+                .expect("writing into a string");
+        }
+        writeln!(module, "  (table {} funcref)", self.functions.len())
+            .expect("writing into a string");
+        if !self.functions.is_empty() {
+            let elements: String = (0..self.functions.len())
+                .map(|index| format!(" $f{index}"))
+                .collect();
+            writeln!(module, "  (elem (i32.const 0) func{elements})")
+                .expect("writing into a string");
+        }
+        writeln!(
+            module,
+            "  (data (i32.const {DATA}) \"{}\")",
+            self.data.escaped()
+        )
+        .expect("writing into a string");
+        module.push_str(RUNTIME);
+        for function in &self.functions {
+            module.push_str(function);
+        }
+        module.push_str(&main);
+        module.push_str("  (func (export \"_start\")\n    (drop (call $main)))\n)\n");
+        module
+    }
+}
+
+/// A value of each tag, in the order of the tags.
+fn samples() -> [Primitive; 6] {
+    let function = Primitive::Function {
+        name: Symbol::default(),
+        parameters: Arc::default(),
+        value: Arc::new(Term::Error(ast::Error {
+            message: String::new(),
+            full_text: String::new(),
+            location: ast::Location::default(),
+        })),
+        env: Scope::new(),
+    };
+    [
+        Primitive::Int(7),
+        Primitive::Bool(true),
+        Primitive::Str("b".into()),
+        tuple::new(Primitive::Int(1), Primitive::Int(2)),
+        function,
+        Primitive::None,
+    ]
+}
+
+fn error<T>(result: Result<T, ErrorKind>) -> ErrorKind {
+    match result {
+        Ok(_) => unreachable!("the samples fail"),
+        Err(error) => error,
+    }
+}
+
+/// The message of calling a function with `given` arguments, cut around
+/// the name of the function and the arguments it expects.
+fn arity_texts(given: usize) -> (String, String, String) {
+    let message = ErrorKind::ArityMismatch {
+        name: "\0".into(),
+        expected: usize::MAX,
+        given,
+    }
+    .to_string();
+    let (before, rest) = message.split_once('\0').expect("the message has the name");
+    let (middle, after) = rest
+        .split_once(&usize::MAX.to_string())
+        .expect("the message has the arity");
+    (before.into(), middle.into(), after.into())
+}
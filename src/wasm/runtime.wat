@@ -0,0 +1,470 @@
+  ;; The runtime of the modules, the compiler adds the program around it,
+  ;; see `src/wasm.rs`.
+  ;;
+  ;; A value is an `i64`, its low 32 bits are its tag and its high 32 bits
+  ;; are the integer, the boolean, or the address of what it points to:
+  ;;
+  ;; 0  Int      the integer
+  ;; 1  Bool     0 or 1
+  ;; 2  Str      its length, then its bytes
+  ;; 3  Tuple    its first value, then its second one
+  ;; 4  Closure  its function in the table, its arity, its name, then the
+  ;;             values it captures
+  ;; 5  Unit
+  ;;
+  ;; The strings of the runtime are addresses of a length and its bytes.
+  ;; The first bytes of the memory are the scratch of `$write` and of
+  ;; `$int_to_string`, the data of the program comes next, then the heap,
+  ;; which is never freed.
+
+  (func $tag (param $value i64) (result i32)
+    (i32.wrap_i64 (local.get $value)))
+
+  (func $address (param $value i64) (result i32)
+    (i32.wrap_i64 (i64.shr_u (local.get $value) (i64.const 32))))
+
+  (func $int (param $value i64) (result i64)
+    (i64.shr_s (local.get $value) (i64.const 32)))
+
+  (func $box (param $address i32) (param $tag i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $address)) (i64.const 32))
+      (i64.extend_i32_u (local.get $tag))))
+
+  (func $bool (param $value i32) (result i64)
+    (call $box (local.get $value) (i32.const 1)))
+
+  (func $alloc (param $size i32) (result i32)
+    (local $at i32) (local $end i32) (local $room i32)
+    (local.set $at (global.get $heap))
+    (local.set $end
+      (i32.and
+        (i32.add (i32.add (local.get $at) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.set $room (i32.shl (memory.size) (i32.const 16)))
+    (if (i32.gt_u (local.get $end) (local.get $room))
+      (then
+        (if (i32.eq
+              (memory.grow
+                (i32.shr_u
+                  (i32.add (i32.sub (local.get $end) (local.get $room)) (i32.const 65535))
+                  (i32.const 16)))
+              (i32.const -1))
+          (then unreachable))))
+    (global.set $heap (local.get $end))
+    (local.get $at))
+
+  ;; Writes all of the string into the file descriptor.
+  (func $write (param $fd i32) (param $string i32)
+    (local $at i32) (local $left i32)
+    (local.set $at (i32.add (local.get $string) (i32.const 4)))
+    (local.set $left (i32.load (local.get $string)))
+    (block $done
+      (loop $more
+        (br_if $done (i32.eqz (local.get $left)))
+        (i32.store (i32.const 0) (local.get $at))
+        (i32.store (i32.const 4) (local.get $left))
+        (br_if $done
+          (call $fd_write (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8)))
+        (local.set $at (i32.add (local.get $at) (i32.load (i32.const 8))))
+        (local.set $left (i32.sub (local.get $left) (i32.load (i32.const 8))))
+        (br $more))))
+
+  ;; Reports the error into the standard error, after where it happened,
+  ;; and exits with the code of the runtime errors of the interpreter.
+  (func $fail (param $site i32) (param $message i32)
+    (call $write
+      (i32.const 2)
+      (call $concat
+        (call $concat (local.get $site) (local.get $message))
+        (global.get $newline)))
+    (call $proc_exit (i32.const 1))
+    unreachable)
+
+  (func $concat (param $left i32) (param $right i32) (result i32)
+    (local $string i32)
+    (local.set $string
+      (call $alloc
+        (i32.add
+          (i32.add (i32.load (local.get $left)) (i32.load (local.get $right)))
+          (i32.const 4))))
+    (i32.store
+      (local.get $string)
+      (i32.add (i32.load (local.get $left)) (i32.load (local.get $right))))
+    (memory.copy
+      (i32.add (local.get $string) (i32.const 4))
+      (i32.add (local.get $left) (i32.const 4))
+      (i32.load (local.get $left)))
+    (memory.copy
+      (i32.add (i32.add (local.get $string) (i32.const 4)) (i32.load (local.get $left)))
+      (i32.add (local.get $right) (i32.const 4))
+      (i32.load (local.get $right)))
+    (local.get $string))
+
+  ;; The digits are written backwards, from the end of the scratch.
+  (func $int_to_string (param $value i64) (result i32)
+    (local $digits i64) (local $at i32) (local $length i32) (local $string i32)
+    (local.set $digits
+      (select
+        (i64.sub (i64.const 0) (local.get $value))
+        (local.get $value)
+        (i64.lt_s (local.get $value) (i64.const 0))))
+    (local.set $at (i32.const 48))
+    (loop $digit
+      (local.set $at (i32.sub (local.get $at) (i32.const 1)))
+      (i32.store8
+        (local.get $at)
+        (i32.add (i32.const 48) (i32.wrap_i64 (i64.rem_u (local.get $digits) (i64.const 10)))))
+      (local.set $digits (i64.div_u (local.get $digits) (i64.const 10)))
+      (br_if $digit (i64.ne (local.get $digits) (i64.const 0))))
+    (if (i64.lt_s (local.get $value) (i64.const 0))
+      (then
+        (local.set $at (i32.sub (local.get $at) (i32.const 1)))
+        (i32.store8 (local.get $at) (i32.const 45))))
+    (local.set $length (i32.sub (i32.const 48) (local.get $at)))
+    (local.set $string (call $alloc (i32.add (local.get $length) (i32.const 4))))
+    (i32.store (local.get $string) (local.get $length))
+    (memory.copy
+      (i32.add (local.get $string) (i32.const 4))
+      (local.get $at)
+      (local.get $length))
+    (local.get $string))
+
+  ;; The operands of `+` that aren't integers are strings.
+  (func $to_string (param $value i64) (result i32)
+    (if (result i32) (call $tag (local.get $value))
+      (then (call $address (local.get $value)))
+      (else (call $int_to_string (call $int (local.get $value))))))
+
+  (func $string_equal (param $left i32) (param $right i32) (result i32)
+    (local $at i32)
+    (if (i32.ne (i32.load (local.get $left)) (i32.load (local.get $right)))
+      (then (return (i32.const 0))))
+    (loop $byte
+      (if (i32.ge_u (local.get $at) (i32.load (local.get $left)))
+        (then (return (i32.const 1))))
+      (if (i32.ne
+            (i32.load8_u offset=4 (i32.add (local.get $left) (local.get $at)))
+            (i32.load8_u offset=4 (i32.add (local.get $right) (local.get $at))))
+        (then (return (i32.const 0))))
+      (local.set $at (i32.add (local.get $at) (i32.const 1)))
+      (br $byte))
+    unreachable)
+
+  ;; The text of the value, like `print` shows it.
+  (func $render (param $value i64) (result i32)
+    (local $at i32)
+    (local.set $at (call $address (local.get $value)))
+    (block $unit
+      (block $closure
+        (block $tuple
+          (block $string
+            (block $bool
+              (block $int
+                (br_table $int $bool $string $tuple $closure $unit
+                  (call $tag (local.get $value))))
+              (return (call $int_to_string (call $int (local.get $value)))))
+            (return
+              (select
+                (global.get $true_text)
+                (global.get $false_text)
+                (local.get $at))))
+          (return (local.get $at)))
+        (return
+          (call $concat
+            (call $concat
+              (call $concat
+                (call $concat
+                  (global.get $open_text)
+                  (call $render (i64.load (local.get $at))))
+                (global.get $comma_text))
+              (call $render (i64.load offset=8 (local.get $at))))
+            (global.get $close_text))))
+      (return (global.get $closure_text)))
+    (global.get $unit_text))
+
+  (func $print (param $value i64) (result i64)
+    (call $write
+      (i32.const 1)
+      (call $concat (call $render (local.get $value)) (global.get $newline)))
+    (local.get $value))
+
+  (func $tuple (param $first i64) (param $second i64) (result i64)
+    (local $at i32)
+    (local.set $at (call $alloc (i32.const 16)))
+    (i64.store (local.get $at) (local.get $first))
+    (i64.store offset=8 (local.get $at) (local.get $second))
+    (call $box (local.get $at) (i32.const 3)))
+
+  (func $first (param $value i64) (param $site i32) (result i64)
+    (if (i32.ne (call $tag (local.get $value)) (i32.const 3))
+      (then (call $fail (local.get $site) (global.get $not_a_tuple_first))))
+    (i64.load (call $address (local.get $value))))
+
+  (func $second (param $value i64) (param $site i32) (result i64)
+    (if (i32.ne (call $tag (local.get $value)) (i32.const 3))
+      (then (call $fail (local.get $site) (global.get $not_a_tuple_second))))
+    (i64.load offset=8 (call $address (local.get $value))))
+
+  (func $condition (param $value i64) (param $site i32) (result i32)
+    (if (i32.ne (call $tag (local.get $value)) (i32.const 1))
+      (then (call $fail (local.get $site) (global.get $not_a_bool))))
+    (call $address (local.get $value)))
+
+  ;; A closure without the values it captures, the caller stores them.
+  (func $closure
+    (param $function i32) (param $arity i32) (param $name i32) (param $captures i32)
+    (result i32)
+    (local $at i32)
+    (local.set $at
+      (call $alloc (i32.add (i32.const 16) (i32.shl (local.get $captures) (i32.const 3)))))
+    (i32.store (local.get $at) (local.get $function))
+    (i32.store offset=4 (local.get $at) (local.get $arity))
+    (i32.store offset=8 (local.get $at) (local.get $name))
+    (local.get $at))
+
+  ;; The closure about to be called with `given` arguments, failing like
+  ;; the interpreter does when it can't be. `given_text` ends the message of
+  ;; the wrong arities.
+  (func $callable
+    (param $value i64) (param $callee_site i32) (param $call_site i32)
+    (param $given i32) (param $given_text i32)
+    (result i32)
+    (local $at i32)
+    (if (i32.ne (call $tag (local.get $value)) (i32.const 4))
+      (then (call $fail (local.get $callee_site) (global.get $not_a_function))))
+    (local.set $at (call $address (local.get $value)))
+    (if (i32.ne (i32.load offset=4 (local.get $at)) (local.get $given))
+      (then
+        (call $fail
+          (local.get $call_site)
+          (call $concat
+            (call $concat
+              (call $concat
+                (call $concat (global.get $arity_text) (i32.load offset=8 (local.get $at)))
+                (global.get $expects_text))
+              (call $int_to_string (i64.extend_i32_u (i32.load offset=4 (local.get $at)))))
+            (local.get $given_text)))))
+    (local.get $at))
+
+  ;; Fails with what the interpreter says of the operator on the types of
+  ;; the operands, from the table of the compiler.
+  (func $mismatch (param $op i32) (param $left i64) (param $right i64) (param $site i32)
+    (call $fail
+      (local.get $site)
+      (i32.load
+        (i32.add
+          (global.get $mismatches)
+          (i32.shl
+            (i32.add
+              (i32.mul (local.get $op) (i32.const 36))
+              (i32.add
+                (i32.mul (call $tag (local.get $left)) (i32.const 6))
+                (call $tag (local.get $right))))
+            (i32.const 2))))))
+
+  ;; Fits the exact result of the operator in 32 bits, wrapping around or
+  ;; failing.
+  (func $fit (param $op i32) (param $exact i64) (param $site i32) (result i64)
+    (local $fitted i64)
+    (local.set $fitted (i64.extend_i32_s (i32.wrap_i64 (local.get $exact))))
+    (if (i32.and
+          (i32.eqz (global.get $wrap))
+          (i64.ne (local.get $fitted) (local.get $exact)))
+      (then
+        (call $fail
+          (local.get $site)
+          (i32.load
+            (i32.add (global.get $overflows) (i32.shl (local.get $op) (i32.const 2)))))))
+    (i64.shl (local.get $fitted) (i64.const 32)))
+
+  (func $ints (param $left i64) (param $right i64) (result i32)
+    (i32.eqz (i32.or (call $tag (local.get $left)) (call $tag (local.get $right)))))
+
+  (func $bools (param $left i64) (param $right i64) (result i32)
+    (i32.and
+      (i32.eq (call $tag (local.get $left)) (i32.const 1))
+      (i32.eq (call $tag (local.get $right)) (i32.const 1))))
+
+  (func $add (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then
+        (return
+          (call $fit
+            (i32.const 0)
+            (i64.add (call $int (local.get $left)) (call $int (local.get $right)))
+            (local.get $site)))))
+    ;; The strings take the integers, and the other strings.
+    (if (i32.and
+          (i32.eqz (i32.and (call $tag (local.get $left)) (i32.const -3)))
+          (i32.eqz (i32.and (call $tag (local.get $right)) (i32.const -3))))
+      (then
+        (return
+          (call $box
+            (call $concat
+              (call $to_string (local.get $left))
+              (call $to_string (local.get $right)))
+            (i32.const 2)))))
+    (call $mismatch (i32.const 0) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $sub (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then
+        (return
+          (call $fit
+            (i32.const 1)
+            (i64.sub (call $int (local.get $left)) (call $int (local.get $right)))
+            (local.get $site)))))
+    (call $mismatch (i32.const 1) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $mul (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then
+        (return
+          (call $fit
+            (i32.const 2)
+            (i64.mul (call $int (local.get $left)) (call $int (local.get $right)))
+            (local.get $site)))))
+    (call $mismatch (i32.const 2) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  ;; The divisions by zero fail, the other ones are exact in 64 bits.
+  (func $divisor (param $op i32) (param $right i64) (param $site i32) (result i64)
+    (if (i64.eqz (local.get $right))
+      (then
+        (call $fail
+          (local.get $site)
+          (i32.load
+            (i32.add (global.get $zeros) (i32.shl (local.get $op) (i32.const 2)))))))
+    (call $int (local.get $right)))
+
+  (func $div (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then
+        (return
+          (call $fit
+            (i32.const 3)
+            (i64.div_s
+              (call $int (local.get $left))
+              (call $divisor (i32.const 3) (local.get $right) (local.get $site)))
+            (local.get $site)))))
+    (call $mismatch (i32.const 3) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $rem (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then
+        (return
+          (call $fit
+            (i32.const 4)
+            (i64.rem_s
+              (call $int (local.get $left))
+              (call $divisor (i32.const 4) (local.get $right) (local.get $site)))
+            (local.get $site)))))
+    (call $mismatch (i32.const 4) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  ;; The tuples are compared element by element, the integers, the strings
+  ;; and the booleans by their contents.
+  (func $equal (param $op i32) (param $left i64) (param $right i64) (param $site i32)
+    (result i32)
+    (local $tag i32)
+    (local.set $tag (call $tag (local.get $left)))
+    (if (i32.or
+          (i32.eq (local.get $tag) (i32.const 3))
+          (i32.eq (call $tag (local.get $right)) (i32.const 3)))
+      (then
+        (return (call $tuple_equal (local.get $left) (local.get $right) (local.get $site)))))
+    (if (i32.eq (local.get $tag) (call $tag (local.get $right)))
+      (then
+        (if (i32.le_u (local.get $tag) (i32.const 1))
+          (then (return (i64.eq (local.get $left) (local.get $right)))))
+        (if (i32.eq (local.get $tag) (i32.const 2))
+          (then
+            (return
+              (call $string_equal
+                (call $address (local.get $left))
+                (call $address (local.get $right))))))))
+    (call $mismatch (local.get $op) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  ;; Every element is compared, so the ones that can't be fail even after
+  ;; a difference, like in the interpreter.
+  (func $tuple_equal (param $left i64) (param $right i64) (param $site i32) (result i32)
+    (local $tag i32) (local $first i32)
+    (local.set $tag (call $tag (local.get $left)))
+    (if (i32.ne (local.get $tag) (call $tag (local.get $right)))
+      (then (call $fail (local.get $site) (global.get $not_comparable))))
+    (if (i32.eq (local.get $tag) (i32.const 3))
+      (then
+        (local.set $first
+          (call $tuple_equal
+            (i64.load (call $address (local.get $left)))
+            (i64.load (call $address (local.get $right)))
+            (local.get $site)))
+        (return
+          (i32.and
+            (local.get $first)
+            (call $tuple_equal
+              (i64.load offset=8 (call $address (local.get $left)))
+              (i64.load offset=8 (call $address (local.get $right)))
+              (local.get $site))))))
+    (if (i32.le_u (local.get $tag) (i32.const 1))
+      (then (return (i64.eq (local.get $left) (local.get $right)))))
+    (if (i32.eq (local.get $tag) (i32.const 2))
+      (then
+        (return
+          (call $string_equal
+            (call $address (local.get $left))
+            (call $address (local.get $right))))))
+    (call $fail (local.get $site) (global.get $not_comparable))
+    unreachable)
+
+  (func $eq (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (call $bool
+      (call $equal (i32.const 5) (local.get $left) (local.get $right) (local.get $site))))
+
+  (func $neq (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (call $bool
+      (i32.eqz
+        (call $equal (i32.const 6) (local.get $left) (local.get $right) (local.get $site)))))
+
+  ;; The integers keep their order with their tag of zero under them.
+  (func $lt (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then (return (call $bool (i64.lt_s (local.get $left) (local.get $right))))))
+    (call $mismatch (i32.const 7) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $gt (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then (return (call $bool (i64.gt_s (local.get $left) (local.get $right))))))
+    (call $mismatch (i32.const 8) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $lte (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then (return (call $bool (i64.le_s (local.get $left) (local.get $right))))))
+    (call $mismatch (i32.const 9) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $gte (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $ints (local.get $left) (local.get $right))
+      (then (return (call $bool (i64.ge_s (local.get $left) (local.get $right))))))
+    (call $mismatch (i32.const 10) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  ;; The booleans keep their tag of one under either operator.
+  (func $and (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $bools (local.get $left) (local.get $right))
+      (then (return (i64.and (local.get $left) (local.get $right)))))
+    (call $mismatch (i32.const 11) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
+
+  (func $or (param $left i64) (param $right i64) (param $site i32) (result i64)
+    (if (call $bools (local.get $left) (local.get $right))
+      (then (return (i64.or (local.get $left) (local.get $right)))))
+    (call $mismatch (i32.const 12) (local.get $left) (local.get $right) (local.get $site))
+    unreachable)
@@ -0,0 +1,62 @@
+mod common;
+
+use common::Scratch;
+use std::{path::PathBuf, process::Command};
+
+fn ab_memo(program: PathBuf) -> (Option<i32>, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--ab-memo")
+        .arg(program)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn fib_is_faster_with_the_memo() {
+    let dir = Scratch::new("ab-memo");
+    let program = dir.file(
+        "fib.rinha",
+        "let fib = fn (n) => {
+  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};
+print(fib(22))",
+    );
+    let (code, stdout, stderr) = ab_memo(program);
+    assert_eq!(code, Some(0), "{stderr}");
+    assert_eq!(stdout, "17711\n", "only the first run prints");
+
+    assert!(stderr.starts_with("ab-memo:\n"), "{stderr}");
+    let calls: Vec<&str> = stderr
+        .lines()
+        .filter(|line| line.contains(" ms "))
+        .map(|line| line.split_whitespace().nth(4).unwrap())
+        .collect();
+    assert_eq!(calls, ["23", "57313"]);
+
+    let speedup = stderr
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("speedup:"))
+        .unwrap();
+    let speedup: f64 = speedup.trim().trim_end_matches('x').parse().unwrap();
+    assert!(speedup > 1.0, "{stderr}");
+}
+
+#[test]
+fn programs_that_print_in_calls_are_compared() {
+    let dir = Scratch::new("ab-memo");
+    let program = dir.file(
+        "impure.rinha",
+        "let f = fn (n) => { print(n) };\nlet _ = f(1);\nf(1)",
+    );
+    let (code, stdout, stderr) = ab_memo(program);
+    assert_eq!(code, Some(0), "{stderr}");
+    // The calls that print are never memoized, both runs print twice.
+    assert_eq!(stdout, "1\n1\n");
+    assert!(stderr.starts_with("ab-memo:\n"), "{stderr}");
+}
@@ -0,0 +1,85 @@
+//! `--arg name=value` binds the variables the programs start with.
+
+use rinha::{input::Arg, memo::SharedMemo, Backend, RunConfig};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::Arc,
+};
+
+fn args(args: &[&str]) -> Vec<Arg> {
+    args.iter().map(|arg| arg.parse().unwrap()).collect()
+}
+
+#[test]
+fn every_backend_sees_the_arguments() {
+    let source = "let double = fn (n) => { n * 2 }; (double(n), (name, verbose))";
+    for backend in [Backend::Tree, Backend::Vm, Backend::Jit] {
+        let config = RunConfig {
+            backend,
+            args: args(&["n=21", "name=fib", "verbose=true"]),
+            ..RunConfig::default()
+        };
+        let value = rinha::run_source(source, &config).unwrap().value;
+        assert_eq!(value.to_string(), "(42, (fib, true))", "{backend:?}");
+    }
+}
+
+#[test]
+fn the_program_shadows_the_arguments() {
+    let config = RunConfig {
+        args: args(&["n=1", "n=2"]),
+        ..RunConfig::default()
+    };
+    let value = rinha::run_source("n", &config).unwrap().value;
+    assert_eq!(value.to_string(), "2");
+    let value = rinha::run_source("let n = 3; n", &config).unwrap().value;
+    assert_eq!(value.to_string(), "3");
+}
+
+#[test]
+fn the_shared_memo_tells_the_arguments_apart() {
+    let shared_memo = Arc::new(SharedMemo::new(1024));
+    let source = "let f = fn (x) => { x + n }; f(1)";
+    for n in [1, 2] {
+        let config = RunConfig {
+            args: args(&[&format!("n={n}")]),
+            shared_memo: Some(shared_memo.clone()),
+            ..RunConfig::default()
+        };
+        let value = rinha::run_source(source, &config).unwrap().value;
+        assert_eq!(value.to_string(), (1 + n).to_string());
+    }
+}
+
+#[test]
+fn the_names_must_be_variables() {
+    assert!("n=".parse::<Arg>().is_ok());
+    assert!("n".parse::<Arg>().is_err());
+    assert!("=1".parse::<Arg>().is_err());
+    assert!("1n=1".parse::<Arg>().is_err());
+}
+
+#[test]
+fn the_binary_binds_the_arguments() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--arg", "n=10", "--arg", "name=sum", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let program = b"print(name + \": \" + n * 2)";
+    child.stdin.take().unwrap().write_all(program).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"sum: 20\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--arg", "n"])
+        .arg("files/sum.rinha")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`n` isn't `name=value`"), "{stderr}");
+}
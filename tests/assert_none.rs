@@ -0,0 +1,68 @@
+mod common;
+
+use common::Scratch;
+use rinha::{ast, interpreter::ErrorKind, parser, RinhaError, RunConfig};
+use std::process::Command;
+
+/// `print(0 + 1)` with the `0` turned into a syntax error node, like the
+/// ones other parsers write into their JSON ASTs.
+fn with_syntax_error(filename: &str) -> ast::File {
+    let file = parser::parse_or_report(filename, "print(0 + 1)").unwrap();
+    let mut json = serde_json::to_value(file).unwrap();
+    let lhs = &mut json["expression"]["value"]["lhs"];
+    *lhs = serde_json::json!({
+        "kind": "Error",
+        "message": "unexpected token",
+        "full_text": "0",
+        "location": lhs["location"].clone(),
+    });
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn reports_the_term_that_produced_unit() {
+    let config = RunConfig {
+        assert_none: true,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = rinha::run_ast(with_syntax_error("main.json"), &config)
+    else {
+        panic!("expected a runtime error");
+    };
+
+    assert_eq!(error.kind, ErrorKind::UnexpectedUnit("a syntax error"));
+    assert_eq!((error.location.start, error.location.end), (6, 7));
+}
+
+#[test]
+fn without_the_assertion_unit_reaches_the_operator() {
+    let config = RunConfig {
+        assert_none: false,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = rinha::run_ast(with_syntax_error("main.json"), &config)
+    else {
+        panic!("expected a runtime error");
+    };
+
+    assert!(matches!(error.kind, ErrorKind::TypeMismatch(_)));
+}
+
+#[test]
+fn the_flag_names_the_location() {
+    let dir = Scratch::new("none");
+    // The report shows the source the locations point into.
+    let source = dir.file("main.rinha", "print(0 + 1)");
+    let file = with_syntax_error(source.to_str().unwrap());
+    let json = dir.file("main.json", serde_json::to_string(&file).unwrap());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--debug-assert-none", "--color", "never"])
+        .arg(&json)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.contains("unit value produced by a syntax error"));
+    assert!(stderr.contains("1 │ print(0 + 1)"), "{stderr}");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Scratch;
+use std::{path::PathBuf, process::Command};
+
+const NOTE: &str = "note: program evaluated to (42, true); use print(...) or --print-result";
+
+fn run(flags: &[&str], program: &PathBuf) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(flags)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn bare_values_get_a_note_on_stderr() {
+    let dir = Scratch::new("bare-value");
+    let program = dir.file("bare.rinha", "(42, true)");
+
+    let (stdout, stderr) = run(&[], &program);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains(NOTE), "{stderr}");
+}
+
+#[test]
+fn quiet_and_spec_silence_the_note() {
+    let dir = Scratch::new("bare-value");
+    let program = dir.file("silenced.rinha", "(42, true)");
+
+    for flag in ["--quiet", "--spec"] {
+        assert_eq!(run(&[flag], &program), (String::new(), String::new()));
+    }
+}
+
+#[test]
+fn print_result_writes_the_value_instead() {
+    let dir = Scratch::new("bare-value");
+    let program = dir.file("result.rinha", "(42, true)");
+
+    let (stdout, stderr) = run(&["--print-result"], &program);
+    assert_eq!(stdout, "(42, true)\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn printing_programs_get_no_note() {
+    let dir = Scratch::new("bare-value");
+    let program = dir.file("printing.rinha", "print(42)");
+
+    let (stdout, stderr) = run(&[], &program);
+    assert_eq!(stdout, "42\n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn print_result_renders_like_print() {
+    let dir = Scratch::new("bare-value");
+    let values = [
+        "0 - 7",
+        "\"text\"",
+        "false",
+        "((1, \"a\"), (true, (2, 3)))",
+        "fn (x) => { x }",
+    ];
+    for (index, value) in values.iter().enumerate() {
+        let bare = dir.file(&format!("value-{index}.rinha"), value);
+        let printed = dir.file(&format!("printed-{index}.rinha"), format!("print({value})"));
+
+        let (result, _) = run(&["--print-result"], &bare);
+        let (output, _) = run(&["--quiet"], &printed);
+        assert_eq!(result, output, "{value}");
+    }
+}
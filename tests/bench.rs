@@ -0,0 +1,63 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, process::Command};
+
+#[test]
+fn sweep_writes_one_csv_row_per_configuration() {
+    let dir = Scratch::new("bench");
+    let program = dir.file(
+        "fib.rinha",
+        "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };\nprint(fib(10))",
+    );
+    let csv = program.with_extension("csv");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["bench", "--reps", "2", "--csv"])
+        .arg(&csv)
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let csv = fs::read_to_string(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("engine,memo,median_ms,stddev_ms,calls,memo_hits,printed_bytes")
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2);
+    for row in &rows {
+        assert_eq!(row.len(), 7);
+        assert!(row[2].parse::<f64>().is_ok());
+        assert!(row[3].parse::<f64>().is_ok());
+        assert_eq!(row[6], "3");
+    }
+    assert_eq!((rows[0][1], rows[1][1]), ("on", "off"));
+    assert!(rows[0][4].parse::<u64>().unwrap() < rows[1][4].parse::<u64>().unwrap());
+}
+
+#[test]
+fn refuses_programs_printing_too_much() {
+    let dir = Scratch::new("bench");
+    let program = dir.file(
+        "loud.rinha",
+        "let s = \"....................................................................................................\";\nlet loop = fn (n) => { if (n == 0) { 0 } else { let _ = print(s + s + s + s + s); loop(n - 1) } };\nloop(200)",
+    );
+
+    let refused = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["bench", "--reps", "1"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--quiet"));
+
+    let quiet = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["bench", "--reps", "1", "--quiet"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(quiet.status.success());
+}
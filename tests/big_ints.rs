@@ -0,0 +1,193 @@
+//! `--overflow widen`, or `big`, promotes the integers past 64 bits instead of failing,
+//! and they print as plain decimal digits.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::{ErrorKind, IntFormat, Overflow},
+    runtime::bigint::BigInt,
+    Backend, RinhaError, RunConfig,
+};
+use std::process::Command;
+
+fn run(source: &str) -> Result<String, RinhaError> {
+    let config = RunConfig {
+        overflow: Overflow::Widen,
+        ..RunConfig::default()
+    };
+    rinha::run_source(source, &config).map(|outcome| outcome.value.to_string())
+}
+
+fn fib(n: u32) -> String {
+    format!(
+        "let fib = fn (n) => {{
+          if (n < 2) {{ n }} else {{ fib(n - 1) + fib(n - 2) }}
+        }};
+        fib({n})"
+    )
+}
+
+#[test]
+fn fibonacci_goes_past_64_bits_memoized() {
+    assert_eq!(run(&fib(90)).unwrap(), "2880067194370816120");
+    assert_eq!(run(&fib(93)).unwrap(), "12200160415121876738");
+    assert_eq!(run(&fib(150)).unwrap(), "9969216677189303386214405760200");
+}
+
+#[test]
+fn factorials_promote_on_both_backends() {
+    let factorial = "let fact = fn (n) => { if (n == 0) { 1 } else { n * fact(n - 1) } };
+    fact(30)";
+    for backend in [Backend::Tree, Backend::Vm] {
+        let config = RunConfig {
+            overflow: Overflow::Widen,
+            backend,
+            ..RunConfig::default()
+        };
+        let outcome = rinha::run_source(factorial, &config).unwrap();
+        assert_eq!(
+            outcome.value.to_string(),
+            "265252859812191058636308480000000",
+            "{backend:?}"
+        );
+    }
+}
+
+#[test]
+fn the_32_bits_boundary_is_no_longer_an_overflow() {
+    assert_eq!(run("2147483647 + 1").unwrap(), "2147483648");
+    assert_eq!(run("(0 - 2147483647) - 2").unwrap(), "-2147483649");
+    let square = "let square = 2147483647 * 2147483647; square * square * square";
+    assert_eq!(
+        run(square).unwrap(),
+        "98079714341385330254404631364738284897724378381211926529"
+    );
+}
+
+#[test]
+fn the_remainder_keeps_the_sign_of_the_dividend() {
+    assert_eq!(run("(0 - 7) % 3").unwrap(), "-1");
+    assert_eq!(run("7 % (0 - 3)").unwrap(), "1");
+    // 2^65, the literals are 32 bits.
+    let big = "let big = 65536 * 65536 * 65536 * 65536 * 2;";
+    assert_eq!(run(&format!("{big} ((0 - big) - 5) % 7")).unwrap(), "-2");
+    assert_eq!(
+        run(&format!("{big} (0 - big) / 8")).unwrap(),
+        "-4611686018427387904"
+    );
+    assert_eq!(run(&format!("{big} big % (0 - 10)")).unwrap(), "2");
+}
+
+#[test]
+fn promoted_integers_concatenate_and_compare() {
+    // 2^63, one past the largest 64 bits integer.
+    let big = "let big = 65536 * 65536 * 65536 * 32768;";
+    assert_eq!(
+        run(&format!("{big} \"n = \" + big")).unwrap(),
+        "n = 9223372036854775808"
+    );
+    assert_eq!(
+        run(&format!("{big} big + \"!\"")).unwrap(),
+        "9223372036854775808!"
+    );
+    assert_eq!(run(&format!("{big} big > big - 1")).unwrap(), "true");
+    assert_eq!(run(&format!("{big} big == (big - 1) + 1")).unwrap(), "true");
+    assert_eq!(run(&format!("{big} big == big - 1")).unwrap(), "false");
+    assert_eq!(
+        run(&format!("{big} (big, big * 2)")).unwrap(),
+        "(9223372036854775808, 18446744073709551616)"
+    );
+    assert!(run(&format!("{big} big + true")).is_err());
+}
+
+#[test]
+fn division_by_zero_is_a_runtime_error() {
+    for source in ["1 / 0", "1 % 0", "(65536 * 65536 * 65536 * 65536) / 0"] {
+        let Err(RinhaError::Runtime(error)) = run(source) else {
+            panic!("{source} didn't fail");
+        };
+        assert!(
+            matches!(error.kind, ErrorKind::DivisionByZero(_)),
+            "{source}: {:?}",
+            error.kind
+        );
+    }
+}
+
+#[test]
+fn big_integers_follow_the_int_format() {
+    let big = BigInt::from(-(1i128 << 70));
+    let config = RunConfig {
+        overflow: Overflow::Widen,
+        ..RunConfig::default()
+    };
+    let value = rinha::run_source("0 - 1073741824 * 1073741824 * 1024", &config)
+        .unwrap()
+        .value;
+    assert_eq!(value.to_string(), big.to_string());
+    assert_eq!(
+        value.display(IntFormat::Hex).to_string(),
+        "-0x400000000000000000"
+    );
+    assert_eq!(
+        value.display(IntFormat::Grouped).to_string(),
+        "-1_180_591_620_717_411_303_424"
+    );
+    assert_eq!(
+        value.to_json(),
+        serde_json::json!("-1180591620717411303424")
+    );
+}
+
+#[test]
+fn big_int_arithmetic_matches_i128() {
+    let samples = [
+        0,
+        1,
+        -1,
+        7,
+        -7,
+        u32::MAX as i128,
+        1 << 32,
+        i64::MAX as i128,
+        i64::MIN as i128,
+        (i64::MAX as i128) * 3 + 5,
+        -(1 << 100) + 12345,
+    ];
+    for lhs in samples {
+        for rhs in samples {
+            let (big_lhs, big_rhs) = (BigInt::from(lhs), BigInt::from(rhs));
+            assert_eq!((&big_lhs + &big_rhs).to_i128(), Some(lhs + rhs));
+            assert_eq!((&big_lhs - &big_rhs).to_i128(), Some(lhs - rhs));
+            assert_eq!(big_lhs.cmp(&big_rhs), lhs.cmp(&rhs), "{lhs} {rhs}");
+            if let Some(product) = lhs.checked_mul(rhs) {
+                assert_eq!((&big_lhs * &big_rhs).to_i128(), Some(product));
+            }
+            match big_lhs.div_rem(&big_rhs) {
+                Some((quotient, remainder)) => {
+                    assert_eq!(quotient.to_i128(), Some(lhs / rhs), "{lhs} / {rhs}");
+                    assert_eq!(remainder.to_i128(), Some(lhs % rhs), "{lhs} % {rhs}");
+                }
+                None => assert_eq!(rhs, 0),
+            }
+            assert_eq!(big_lhs.to_string(), lhs.to_string());
+        }
+    }
+}
+
+#[test]
+fn the_cli_promotes_with_overflow_big() {
+    let dir = Scratch::new("big-ints");
+    let program = dir.file("main.rinha", format!("print({})", fib(100)));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--overflow")
+        .arg("big")
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"354224848179261915075\n");
+}
@@ -0,0 +1,86 @@
+//! A closed output, like when the program is piped into `head`, stops the
+//! run quietly instead of being reported as a bug of the interpreter.
+
+mod common;
+
+use common::Scratch;
+use rinha::{interpreter::ErrorKind, Backend, RinhaError, RunConfig};
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, BufReader},
+    process::{Command, Stdio},
+    rc::Rc,
+};
+
+/// Prints the numbers down from a million, far more than a pipe holds.
+const COUNTDOWN: &str = "let count = fn (n) => {
+  if (n == 0) { 0 } else { let _ = print(n); count(n - 1) }
+};
+count(1000000)";
+
+/// An output failing every write with `kind`.
+struct Failing(io::ErrorKind);
+
+impl io::Write for Failing {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(self.0.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn failure(backend: Backend, kind: io::ErrorKind) -> ErrorKind {
+    let config = RunConfig {
+        backend,
+        output: Some(Rc::new(RefCell::new(Failing(kind)))),
+        ..RunConfig::default()
+    };
+    match rinha::run_source(COUNTDOWN, &config) {
+        Err(RinhaError::Runtime(error)) => error.kind,
+        other => panic!("the run didn't fail at runtime: {other:?}"),
+    }
+}
+
+#[test]
+fn the_errors_of_the_output_are_runtime_errors() {
+    for backend in [Backend::Tree, Backend::Vm] {
+        let closed = failure(backend, io::ErrorKind::BrokenPipe);
+        assert_eq!(closed, ErrorKind::BrokenPipe, "{backend:?}");
+        let denied = failure(backend, io::ErrorKind::PermissionDenied);
+        assert!(matches!(denied, ErrorKind::Output(_)), "{backend:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn a_closed_pipe_exits_quietly() {
+    for backend in ["tree", "vm"] {
+        closed_pipe(backend);
+    }
+}
+
+fn closed_pipe(backend: &str) {
+    let dir = Scratch::new("broken-pipe");
+    let program = dir.file("main.rinha", COUNTDOWN);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--backend", backend])
+        .arg(&program)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    assert_eq!(line, "1000000\n");
+
+    // The reading end is closed once the reader is dropped.
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(141), "{backend}: {stderr}");
+    assert_eq!(stderr, "");
+}
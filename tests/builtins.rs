@@ -0,0 +1,122 @@
+//! The functions of the embedders, given to
+//! `Interpreter::register_builtin` or `RunConfig::builtins`, are called by
+//! their name like the natives, and their calls keep their order.
+
+use rinha::{
+    interpreter::{ErrorKind, Interpreter, Primitive, Scope},
+    parser, RinhaError, RunConfig,
+};
+use std::{cell::Cell, rc::Rc};
+
+/// Counts its calls, so the calls that ran again can be told apart from
+/// the memoized ones.
+fn ticks(interpreter: Interpreter) -> Interpreter {
+    let count = Cell::new(0);
+    interpreter.register_builtin("tick", move |_| {
+        count.set(count.get() + 1);
+        Ok(Primitive::Int(count.get()))
+    })
+}
+
+fn interpret(interpreter: &mut Interpreter, source: &str) -> Result<String, (ErrorKind, String)> {
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    match interpreter.interpret(file.expression, &mut Scope::new()) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => {
+            let term = &source[error.location.start as usize..error.location.end as usize];
+            Err((error.kind, term.to_string()))
+        }
+    }
+}
+
+fn value(interpreter: &mut Interpreter, source: &str) -> String {
+    interpret(interpreter, source).unwrap()
+}
+
+fn sum(arguments: &[Primitive]) -> Result<Primitive, ErrorKind> {
+    let mut total = 0;
+    for argument in arguments {
+        match argument {
+            Primitive::Int(n) => total += n,
+            _ => {
+                return Err(ErrorKind::BuiltinFailed {
+                    name: "sum".into(),
+                    message: "expects integers".into(),
+                })
+            }
+        }
+    }
+    Ok(Primitive::Int(total))
+}
+
+#[test]
+fn the_builtins_are_called_by_their_name() {
+    let mut interpreter = Interpreter::with_output(std::io::sink()).register_builtin("sum", sum);
+    assert_eq!(value(&mut interpreter, "sum(1, 2, 3)"), "6");
+    assert_eq!(
+        value(
+            &mut interpreter,
+            "let total = fn (a) => { sum(a, a) }; total(4)"
+        ),
+        "8"
+    );
+    assert_eq!(
+        value(
+            &mut interpreter,
+            "let sum = fn (a, b) => { a - b }; sum(4, 1)"
+        ),
+        "3"
+    );
+    assert_eq!(
+        interpret(&mut interpreter, "let x = 1;\nsum(x, \"a\")"),
+        Err((
+            ErrorKind::BuiltinFailed {
+                name: "sum".into(),
+                message: "expects integers".into()
+            },
+            "sum(x, \"a\")".into()
+        ))
+    );
+    assert_eq!(
+        interpret(&mut interpreter, "let f = sum; f(1)")
+            .unwrap_err()
+            .0,
+        ErrorKind::UndefinedVariable("sum".into())
+    );
+}
+
+#[test]
+fn the_calls_reaching_a_builtin_run_every_time() {
+    let mut interpreter = ticks(Interpreter::with_output(std::io::sink()));
+    let source = "let f = fn (x) => { tick() + x }; (f(0), f(0))";
+    assert_eq!(value(&mut interpreter, source), "(1, 2)");
+    assert_eq!(interpreter.stats().memo_hits, 0);
+
+    let mut interpreter = ticks(Interpreter::with_output(std::io::sink()).extensions(true));
+    let source = "let f = fn (x) => { (x, tick()) }; par_pair(f, 1, 2)";
+    assert_eq!(value(&mut interpreter, source), "((1, 1), (2, 2))");
+    assert_eq!(interpreter.stats().parallel_pairs, 0);
+}
+
+#[test]
+fn the_builtins_come_before_the_natives() {
+    let mut interpreter = Interpreter::with_output(std::io::sink())
+        .extensions(true)
+        .register_builtin("len", |_| Ok(Primitive::Str("mine".into())));
+    assert_eq!(value(&mut interpreter, "len(list(1, 2))"), "mine");
+}
+
+#[test]
+fn the_runs_take_them_too() {
+    let config = RunConfig {
+        builtins: vec![("sum".into(), Rc::new(sum))],
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source("sum(40, 2)", &config).unwrap();
+    assert_eq!(outcome.value.to_string(), "42");
+
+    let Err(RinhaError::Runtime(error)) = rinha::run_source("sum(1)", &RunConfig::default()) else {
+        panic!("the builtin is there without being given");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("sum".into()));
+}
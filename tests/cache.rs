@@ -0,0 +1,128 @@
+mod common;
+
+use common::Scratch;
+use rinha::cache::{Cache, CacheError};
+use std::{fs, process::Command, sync::Arc, thread};
+
+#[test]
+fn truncated_files_are_misses_until_stored_again() {
+    let dir = Scratch::new("cache");
+    let cache = Cache::new(&*dir);
+    cache.store("key", b"a payload long enough").unwrap();
+
+    let path = cache.path("key");
+    let contents = fs::read(&path).unwrap();
+    fs::write(&path, &contents[..contents.len() - 5]).unwrap();
+    assert!(matches!(
+        cache.load("key"),
+        Err(CacheError::Truncated {
+            found: 16,
+            expected: 21
+        })
+    ));
+
+    cache.store("key", b"again").unwrap();
+    assert_eq!(cache.load("key").unwrap(), Some(b"again".to_vec()));
+}
+
+#[test]
+fn bad_checksums_are_detected() {
+    let dir = Scratch::new("cache");
+    let cache = Cache::new(&*dir);
+    cache.store("key", b"payload").unwrap();
+
+    let path = cache.path("key");
+    let mut contents = fs::read(&path).unwrap();
+    let last = contents.len() - 1;
+    contents[last] ^= 1;
+    fs::write(&path, contents).unwrap();
+    assert!(matches!(cache.load("key"), Err(CacheError::Checksum)));
+}
+
+#[test]
+fn foreign_files_are_detected() {
+    let dir = Scratch::new("cache");
+    let cache = Cache::new(&*dir);
+    fs::create_dir_all(cache.path("")).unwrap();
+
+    fs::write(cache.path("empty"), b"").unwrap();
+    assert!(matches!(cache.load("empty"), Err(CacheError::Header)));
+
+    fs::write(cache.path("old"), b"rinha-cache 0 0.0.1 1 0\nx").unwrap();
+    assert!(matches!(cache.load("old"), Err(CacheError::Version { .. })));
+}
+
+#[test]
+fn racing_writers_leave_one_whole_file() {
+    let dir = Scratch::new("cache");
+    let cache = Arc::new(Cache::new(&*dir));
+    let payloads = [vec![b'a'; 64 * 1024], vec![b'b'; 32 * 1024]];
+
+    let writers: Vec<_> = payloads
+        .iter()
+        .cloned()
+        .map(|payload| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    cache.store("key", &payload).unwrap();
+                }
+            })
+        })
+        .collect();
+    let reader = {
+        let cache = cache.clone();
+        let payloads = payloads.clone();
+        thread::spawn(move || {
+            for _ in 0..200 {
+                if let Some(payload) = cache.load("key").unwrap() {
+                    assert!(payloads.contains(&payload));
+                }
+            }
+        })
+    };
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    assert!(payloads.contains(&cache.load("key").unwrap().unwrap()));
+    let leftovers = fs::read_dir(cache.path("")).unwrap().count();
+    assert_eq!(leftovers, 1, "the temporary files are renamed away");
+}
+
+#[test]
+fn corrupt_parse_caches_warn_once_and_run_anyway() {
+    let dir = Scratch::new("cache");
+    let program = dir.file("main.rinha", "let x = 20; print(x + 22)");
+    let cache = dir.join("cache");
+
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .arg("run")
+            .arg("--cache-dir")
+            .arg(&cache)
+            .arg(&program)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        assert_eq!(output.stdout, b"42\n");
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    assert_eq!(run(), "");
+    assert_eq!(run(), "", "the cached AST is used");
+
+    let entry = fs::read_dir(&cache)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let contents = fs::read(&entry).unwrap();
+    fs::write(&entry, &contents[..contents.len() / 2]).unwrap();
+    let stderr = run();
+    assert_eq!(stderr.matches("warning").count(), 1, "{stderr}");
+    assert!(stderr.contains("truncated"), "{stderr}");
+    assert_eq!(run(), "", "the cache was repaired");
+}
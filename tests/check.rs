@@ -0,0 +1,245 @@
+mod common;
+
+use common::Scratch;
+use rinha::{
+    check::{self, types, Diagnostics, Severity},
+    parser,
+};
+use std::{fs, process::Command};
+
+const FIVE_ISSUES: &str = "let unused = 1;
+let f = fn (a, b) => { a + b };
+let g = f(1);
+let _ = print(1 - \"a\");
+if (1) { print(missing) } else { g }";
+
+#[test]
+fn reports_every_issue_once_in_source_order() {
+    let file = parser::parse_or_report("main.rinha", FIVE_ISSUES).unwrap();
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    assert_eq!(diagnostics.max_severity(), Some(Severity::Error));
+
+    let messages: Vec<String> = diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "`unused` is never used",
+            "the function expects 2 arguments, but 1 was given",
+            "can't apply `-` to Int and Str",
+            "the condition must be a Bool, but it's Int",
+            "variable `missing` is not defined",
+        ]
+    );
+}
+
+#[test]
+fn identical_findings_are_reported_once() {
+    let file = parser::parse_or_report("main.rinha", "x").unwrap();
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    check::check(&file, &mut diagnostics);
+    assert_eq!(diagnostics.into_findings().len(), 1);
+}
+
+#[test]
+fn the_report_is_capped_with_a_trailer() {
+    let file = parser::parse_or_report("main.rinha", FIVE_ISSUES).unwrap();
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+
+    let report = diagnostics.into_report(2);
+    assert_eq!((report.errors, report.warnings), (4, 1));
+    assert_eq!(report.findings.len(), 2);
+    assert_eq!(report.more.as_deref(), Some("and 3 more"));
+}
+
+#[test]
+fn exit_code_reflects_the_highest_severity() {
+    let dir = Scratch::new("check");
+    let broken = dir.file("broken.rinha", FIVE_ISSUES);
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["check", "--error-format", "json"])
+        .arg(&broken)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let findings: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(findings.as_array().unwrap().len(), 5);
+    assert_eq!(findings[0]["severity"], "warning");
+
+    let warned = dir.file("warned.rinha", "let x = 1;\nprint(2)");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("check")
+        .arg(&warned)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`x` is never used"));
+}
+
+fn infer(source: &str) -> (Vec<String>, Vec<String>) {
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let mut diagnostics = Diagnostics::new();
+    let signatures = types::infer(&file, &mut diagnostics);
+    let messages = diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect();
+    (
+        signatures.iter().map(ToString::to_string).collect(),
+        messages,
+    )
+}
+
+#[test]
+fn the_types_are_inferred_from_the_uses() {
+    let (signatures, messages) = infer(
+        "let id = fn (x) => { x };
+let pair = (id(1), id(\"a\"));
+let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+let swap = fn (p) => { (second(p), first(p)) };
+let greet = fn (a, b) => { a + b };
+let hello = greet(\"hello, \", \"world\");
+print(swap((fib(10), hello)))",
+    );
+    assert_eq!(messages, Vec::<String>::new());
+    assert_eq!(
+        signatures,
+        [
+            "id: fn ('a) -> 'a",
+            "pair: (Int, Str)",
+            "fib: fn (Int) -> Int",
+            "swap: fn (('a, 'b)) -> ('b, 'a)",
+            "greet: fn (Str, Str) -> Str",
+            "hello: Str",
+        ]
+    );
+}
+
+#[test]
+fn the_examples_have_types() {
+    for entry in fs::read_dir("files").unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "rinha")
+        {
+            let source = fs::read_to_string(&path).unwrap();
+            let (_, messages) = infer(&source);
+            assert_eq!(messages, Vec::<String>::new(), "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn the_values_used_with_another_type_are_reported() {
+    let (_, messages) = infer(
+        "let f = fn (a, b) => { a * b };
+let _ = f(1, \"a\");
+let _ = if (true) { 1 } else { \"x\" };
+let _ = first(f);
+let g = fn (n) => { g(n, n) };
+print(1 - \"a\")",
+    );
+    assert_eq!(
+        messages,
+        [
+            "expected Int, but found Str",
+            "the branches have different types, Int and Str",
+            "`first` must be used on tuples, but it's closure",
+            "`g` is called as fn ('a, 'a) -> 'b, but it's fn ('a) -> 'b",
+            "can't apply `-` to Int and Str",
+        ]
+    );
+}
+
+#[test]
+fn the_findings_shared_with_the_other_analyses_are_reported_once() {
+    let file = parser::parse_or_report("main.rinha", "print(1 - \"a\")").unwrap();
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    types::infer(&file, &mut diagnostics);
+    assert_eq!(diagnostics.into_findings().len(), 1);
+}
+
+#[test]
+fn the_binary_prints_the_types_when_asked_for() {
+    let dir = Scratch::new("check");
+    let typed = dir.file("typed.rinha", "let x = (1, true);\nprint(x)");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["check", "--types"])
+        .arg(&typed)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "x: (Int, Bool)\n");
+
+    let branches = dir.file("branches.rinha", "print(if (true) { 1 } else { \"x\" })");
+    let run = |flags: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(flags)
+            .arg(&branches)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+    assert_eq!(run(&["check"]), Some(0), "only with --types");
+    assert_eq!(run(&["check", "--types"]), Some(1));
+}
+
+#[test]
+fn the_bound_variables_are_defined_and_never_unused() {
+    let file = parser::parse_or_report("main.rinha", "print(n + 1)").unwrap();
+    let mut diagnostics = Diagnostics::new();
+    check::check_with(&file, &["n", "verbose"], &mut diagnostics);
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn the_binary_checks_before_running_when_asked_for() {
+    let dir = Scratch::new("check");
+    let broken = dir.file(
+        "unreached.rinha",
+        "let f = fn (a, b) => { a + b };
+let _ = print(\"before\");
+if (true) { f(1) } else { missing }",
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["--check", "--error-format", "json"])
+        .arg(&broken)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"", "the program doesn't run");
+    let findings: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    let messages: Vec<_> = findings
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|finding| finding["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "the function expects 2 arguments, but 1 was given",
+            "variable `missing` is not defined",
+        ]
+    );
+
+    let bound = dir.file("bound.rinha", "let x = 1;\nprint(n + 1)");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["--check", "--quiet", "--arg", "n=41"])
+        .arg(&bound)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0), "warnings don't stop the run");
+    assert_eq!(output.stdout, b"42\n");
+}
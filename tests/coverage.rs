@@ -0,0 +1,71 @@
+mod common;
+
+use common::Scratch;
+use rinha::RunConfig;
+use std::process::Command;
+
+/// Computes `fib(n)` the given number of times.
+fn program(n: u32, times: u32) -> String {
+    format!(
+        "let fib = fn (n) => {{
+  if (n < 2) {{ n }} else {{ fib(n - 1) + fib(n - 2) }}
+}};
+let repeat = fn (n) => {{
+  if (n == 0) {{ 0 }} else {{ let _ = fib({n}); repeat(n - 1) }}
+}};
+repeat({times})"
+    )
+}
+
+#[test]
+fn memoized_bodies_run_once_per_distinct_argument() {
+    let outcome = rinha::run_source(&program(20, 50), &RunConfig::default()).unwrap();
+
+    let fib = outcome
+        .stats
+        .call_sites
+        .values()
+        .filter(|counts| counts.function == "fib");
+    let (executed, served) = fib.fold((0, 0), |(executed, served), counts| {
+        (executed + counts.executed, served + counts.served)
+    });
+    assert_eq!(executed, 21, "one per argument from 0 to 20");
+    // The first `fib(20)` reuses 18 results, the other 49 are served whole.
+    assert_eq!(served, 18 + 49);
+}
+
+#[test]
+fn without_memo_nothing_is_served() {
+    let config = RunConfig {
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(&program(10, 5), &config).unwrap();
+
+    assert!(outcome
+        .stats
+        .call_sites
+        .values()
+        .all(|counts| counts.served == 0));
+}
+
+#[test]
+fn the_report_shows_each_call_site() {
+    let dir = Scratch::new("coverage");
+    let path = dir.file("fib.rinha", program(20, 50));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--coverage", "--quiet"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let fib = stderr
+        .lines()
+        .find(|line| line.starts_with("  fib: "))
+        .unwrap();
+    assert!(fib.starts_with("  fib: 21 executed, "), "{stderr}");
+    assert!(stderr.contains(":5:36: 1 executed, 49 served"), "{stderr}");
+}
@@ -0,0 +1,122 @@
+//! The debugger hook of the interpreter, and `rinha debug` stepping
+//! through a program with the commands of the standard input.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    ast::Term,
+    interpreter::{Debugger, ErrorKind, Frame, Interpreter, Scope},
+    parser,
+};
+use std::{
+    io::Write,
+    ops::ControlFlow,
+    process::{Command, Stdio},
+};
+
+const FACT: &str = "let fact = fn (n) => {
+  if (n < 2) { 1 } else { n * fact(n - 1) }
+};
+let x = fact(3);
+print(x)
+";
+
+/// Stops the run once `depth` calls are in progress.
+struct StopAt {
+    depth: usize,
+}
+
+impl Debugger for StopAt {
+    fn before(&mut self, _: &Term, scope: &Scope, stack: &[Frame]) -> ControlFlow<()> {
+        if stack.len() < self.depth {
+            return ControlFlow::Continue(());
+        }
+        assert_eq!(
+            scope.get("n").map(ToString::to_string).as_deref(),
+            Some("2")
+        );
+        ControlFlow::Break(())
+    }
+}
+
+#[test]
+fn breaking_interrupts_the_run() {
+    let file = parser::parse_or_report("fact.rinha", FACT).unwrap();
+    let mut interpreter = Interpreter::new()
+        .memoize(false)
+        .debugger(StopAt { depth: 2 });
+    let error = interpreter
+        .interpret(file.expression, &mut Scope::new())
+        .unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Interrupted);
+}
+
+fn debug(flags: &[&str], commands: &str) -> (String, String) {
+    let dir = Scratch::new("debugger");
+    let path = dir.file("fact.rinha", FACT);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("debug")
+        .arg(&path)
+        .args(flags)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let stderr = stderr.replace(path.to_str().unwrap(), "fact.rinha");
+    (String::from_utf8(output.stdout).unwrap(), stderr)
+}
+
+#[test]
+fn the_binary_stops_at_the_first_term_and_steps() {
+    let (stdout, stderr) = debug(&[], "s\nstep\nq\n");
+    assert_eq!(stdout, "");
+    assert_eq!(
+        stderr,
+        "stopped at fact.rinha:1:1: let fact = fn (n) => {
+(rinha) stopped at fact.rinha:1:12: fn (n) => {
+(rinha) stopped at fact.rinha:4:1: let x = fact(3);
+(rinha) "
+    );
+}
+
+#[test]
+fn the_binary_stops_at_the_lines_on_every_call() {
+    let (stdout, stderr) = debug(&["--break", "2"], "c\nbt\nscope\nc\nc\n");
+    assert_eq!(stdout, "6\n");
+    assert_eq!(
+        stderr,
+        "stopped at fact.rinha:2:3: if (n < 2) {
+(rinha) stopped at fact.rinha:2:3: if (n < 2) {
+(rinha)   fact at fact.rinha:2:31
+  fact at fact.rinha:4:9
+(rinha)   fact = <#closure>
+  n = 2
+(rinha) stopped at fact.rinha:2:3: if (n < 2) {
+(rinha) "
+    );
+}
+
+#[test]
+fn the_binary_stops_at_the_let_of_a_name() {
+    let (stdout, stderr) = debug(&["--break", "x"], "b 5\nc\nv\n");
+    assert_eq!(stdout, "6\n");
+    assert_eq!(
+        stderr,
+        "stopped at fact.rinha:4:1: let x = fact(3);
+(rinha) (rinha) stopped at fact.rinha:5:1: print(x)
+(rinha)   fact = <#closure>
+  x = 6
+(rinha) \n"
+    );
+}
@@ -0,0 +1,72 @@
+//! `interpreter difftest` runs a program on two backends and reports the
+//! first place where they part ways.
+
+mod common;
+
+use common::Scratch;
+use std::process::Command;
+
+fn difftest(source: &str, args: &[&str]) -> (Option<i32>, String) {
+    let dir = Scratch::new("difftest");
+    let program = dir.file("main.rinha", source);
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("difftest")
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.code(), stdout)
+}
+
+#[test]
+fn the_backends_agree_on_the_specification() {
+    let source = "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } };
+let _ = print(sum(100));
+let _ = print((1, \"a\"));
+sum(10)";
+    for backends in ["tree,vm", "tree,jit", "vm,jit"] {
+        let (code, stdout) = difftest(source, &["--backends", backends]);
+        assert_eq!(code, Some(0), "{backends}: {stdout}");
+        assert!(
+            stdout.ends_with("agree on 2 lines of output, ending with value 55\n"),
+            "{stdout}"
+        );
+    }
+    let (code, stdout) = difftest("let _ = print(1);\n1 / 0", &[]);
+    assert_eq!(code, Some(0));
+    assert_eq!(
+        stdout,
+        "tree and vm agree on 1 line of output, ending with error: division by zero in `/` \
+         at bytes 18..23\n"
+    );
+}
+
+#[test]
+fn the_first_divergence_is_reported() {
+    // The bytecode provides none of the natives of the extensions.
+    let (code, stdout) = difftest(
+        "let _ = print(1);\nlet _ = print(len(list(1, 2)));\nprint(3)",
+        &["--extensions"],
+    );
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        stdout,
+        "the outputs diverge at line 2:\n  tree: \"2\\n\"\n  vm:   nothing\n"
+    );
+
+    let (code, stdout) = difftest(
+        "let _ = print(1);\nlen(list(1, 2))",
+        &["--extensions", "--backends", "jit,tree"],
+    );
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        stdout,
+        "the runs end differently, after 1 line of output:\n  \
+         jit:  error: variable `len` not found in the scope at bytes 18..21\n  \
+         tree: value 2\n"
+    );
+
+    let (code, _) = difftest("1", &["--backends", "tree"]);
+    assert_eq!(code, Some(2));
+}
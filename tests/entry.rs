@@ -0,0 +1,74 @@
+mod common;
+
+use common::Scratch;
+use std::process::Command;
+
+const PROGRAM: &str = "let scale = 3;
+let f = fn (n, offset) => {
+  let doubled = n * 2;
+  print(doubled * scale + offset)
+};
+f(1, 2)";
+
+/// The body of `f`.
+const BODY: &str = "expression.next.value.value";
+
+fn run(args: &[&str]) -> (Option<i32>, String, String) {
+    let dir = Scratch::new("entry");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(args)
+        .arg(dir.file("main.rinha", PROGRAM))
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn function_bodies_run_with_stubbed_parameters() {
+    let defines = [
+        "--define", "n=5", "--define", "offset=1", "--define", "scale=10",
+    ];
+    let (code, stdout, _) = run(&[&["--entry", BODY][..], &defines].concat());
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "101\n");
+}
+
+#[test]
+fn missing_bindings_are_reported_up_front() {
+    let (code, stdout, stderr) = run(&["--entry", BODY, "--define", "n=5"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("uses `scale`, `offset`"), "{stderr}");
+    assert!(stderr.contains("--define scale=VALUE"), "{stderr}");
+}
+
+#[test]
+fn paths_leading_nowhere_name_the_fields() {
+    let (code, _, stderr) = run(&["--entry", "expression.then"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("`expression` is a Let term, it has no `then`"));
+    assert!(stderr.contains("value, next"), "{stderr}");
+
+    let (code, _, stderr) = run(&["--entry", "expression.next.next.arguments.2"]);
+    assert_eq!(code, Some(2));
+    assert!(
+        stderr.contains("has 2 arguments, there's no `2`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn arguments_are_selected_by_index() {
+    let (code, stdout, _) = run(&[
+        "--entry",
+        "expression.next.next.arguments.1",
+        "--print-result",
+    ]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "2\n");
+}
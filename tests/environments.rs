@@ -0,0 +1,93 @@
+//! The closures see the variables of where they were made, shared with the
+//! scope they were made in: the bindings made after them, and the ones made
+//! by the calls, don't change what they see.
+
+use rinha::RunConfig;
+use std::{cell::RefCell, rc::Rc};
+
+/// What the program printed, the same with and without the memo.
+fn run(source: &str) -> String {
+    let printed = [true, false].map(|memoize| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            memoize,
+            ..RunConfig::default()
+        };
+        rinha::run_source(source, &config).unwrap();
+        String::from_utf8(output.take()).unwrap()
+    });
+    assert_eq!(printed[0], printed[1], "the memo changed the output");
+    printed[0].clone()
+}
+
+#[test]
+fn a_captured_variable_keeps_its_value_when_shadowed() {
+    let program = "let x = 1;
+let f = fn () => { x };
+let x = 2;
+let _ = print(f());
+print(x)";
+    assert_eq!(run(program), "1\n2\n");
+}
+
+#[test]
+fn parameters_shadow_the_captured_variables_only_inside_the_call() {
+    let program = "let x = 1;
+let f = fn (x) => { x + 10 };
+let _ = print(f(5));
+print(x)";
+    assert_eq!(run(program), "15\n1\n");
+}
+
+#[test]
+fn the_bindings_of_a_body_dont_leak_out_of_the_call() {
+    let program = "let n = 5;
+let f = fn (m) => { let n = m * 2; n };
+let _ = print(f(1));
+print(n)";
+    assert_eq!(run(program), "2\n5\n");
+}
+
+#[test]
+fn closures_made_by_the_same_call_site_keep_their_own_arguments() {
+    let program = "let make = fn (n) => { fn (x) => { x + n } };
+let one = make(1);
+let two = make(2);
+let _ = print(one(10));
+let _ = print(two(10));
+print(one(20))";
+    assert_eq!(run(program), "11\n12\n21\n");
+}
+
+#[test]
+fn nested_closures_see_every_enclosing_call() {
+    let program = "let outer = fn (a) => { fn (b) => { fn (c) => { a + b + c } } };
+let middle = outer(1);
+let inner = middle(20);
+let a = 1000;
+print(inner(300))";
+    assert_eq!(run(program), "321\n");
+}
+
+#[test]
+fn a_recursive_function_sees_itself_after_its_name_is_shadowed() {
+    let program = "let count = fn (n) => { if (n == 0) { 0 } else { 1 + count(n - 1) } };
+let counted = count;
+let count = fn (n) => { 100 };
+let _ = print(counted(3));
+print(count(3))";
+    assert_eq!(run(program), "3\n100\n");
+}
+
+#[test]
+fn long_chains_of_closures_keep_every_binding() {
+    let mut program = String::new();
+    for i in 0..100 {
+        program.push_str(&format!(
+            "let v{i} = {i};\nlet f{i} = fn () => {{ v{i} }};\n"
+        ));
+    }
+    program.push_str("let v0 = 1000;\nprint(f0() + f99() + v0)");
+    assert_eq!(run(&program), "1099\n");
+}
@@ -0,0 +1,83 @@
+//! `estimate` samples a run with a budget of calls, and tells whether the
+//! whole run likely fits the limits.
+
+mod common;
+
+use common::Scratch;
+use std::{path::PathBuf, process::Command};
+
+/// The report of `estimate`, which always exits with 0 once it ran.
+fn estimate(args: &[&str], program: PathBuf) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("estimate")
+        .args(args)
+        .arg(program)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(0), "{stderr}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn verdict(report: &str) -> &str {
+    report
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("verdict:"))
+        .unwrap()
+        .trim()
+}
+
+#[test]
+fn a_linear_program_is_likely_within_the_limits() {
+    let dir = Scratch::new("estimate");
+    let sum = dir.file(
+        "sum.rinha",
+        "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } };\nprint(sum(1000))",
+    );
+    let report = estimate(&["--fuel", "20000"], sum);
+    assert!(report.starts_with("estimate, a heuristic"), "{report}");
+    assert!(report.contains("1001 calls"), "{report}");
+    assert!(report.contains("finished"), "{report}");
+    assert_eq!(verdict(&report), "likely within the limits");
+}
+
+#[test]
+fn fib_without_the_memo_is_likely_over_the_limits() {
+    let dir = Scratch::new("estimate");
+    let fib = dir.file(
+        "fib.rinha",
+        "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };\nprint(fib(40))",
+    );
+    let report = estimate(&["--fuel", "20000", "--no-memo"], fib.clone());
+    assert!(report.contains("ran out of fuel"), "{report}");
+    assert_eq!(verdict(&report), "likely over the limits");
+    assert!(report.contains("the memo would serve them"), "{report}");
+
+    // The memo makes it linear, so it finishes within the sample.
+    let report = estimate(&["--fuel", "20000"], fib);
+    assert_eq!(verdict(&report), "likely within the limits");
+}
+
+#[test]
+fn a_long_loop_is_not_mistaken_for_repeated_work() {
+    let dir = Scratch::new("estimate");
+    let count = dir.file(
+        "count.rinha",
+        "let count = fn (n, acc) => { if (n == 0) { acc } else { count(n - 1, acc + 1) } };\nprint(count(1000000, 0))",
+    );
+    let report = estimate(&["--fuel", "20000"], count);
+    assert!(report.contains("grow as executed^1.00"), "{report}");
+    assert_eq!(verdict(&report), "unknown, the sample is too short to tell");
+}
+
+#[test]
+fn the_output_limit_is_checked() {
+    let dir = Scratch::new("estimate");
+    let chatty = dir.file(
+        "chatty.rinha",
+        "let _ = print(\"hello\");\nprint(\"world\")",
+    );
+    let report = estimate(&["--max-output-bytes", "6"], chatty);
+    assert!(report.contains("12 bytes"), "{report}");
+    assert_eq!(verdict(&report), "likely over the limits");
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::Scratch;
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+fn interpreter(program: &PathBuf) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_interpreter"));
+    command
+        .arg("run")
+        .arg("--events")
+        .arg("ndjson")
+        .arg("--events-interval")
+        .arg("10")
+        .arg(program);
+    command
+}
+
+/// The events among the lines of stderr, every one of them versioned.
+fn events(stderr: &[u8]) -> Vec<serde_json::Value> {
+    let stderr = String::from_utf8(stderr.to_vec()).unwrap();
+    let events: Vec<serde_json::Value> = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(events.iter().all(|event| event["schema"] == 1), "{stderr}");
+    events
+}
+
+fn names(events: &[serde_json::Value]) -> Vec<&str> {
+    events
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .filter(|name| *name != "progress")
+        .collect()
+}
+
+#[test]
+fn successful_runs_start_and_finish() {
+    let dir = Scratch::new("events");
+    let program = dir.file("ok.rinha", "print(1 + 1)");
+    let output = interpreter(&program).output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        output.stdout, b"2\n",
+        "the output of the program is untouched"
+    );
+
+    let events = events(&output.stderr);
+    assert_eq!(names(&events), ["start", "limits", "done"]);
+    assert_eq!(events[0]["file"], program.to_str().unwrap());
+    assert_eq!(events[0]["hash"].as_str().unwrap().len(), 16);
+    assert_eq!(events[1]["overflow"], "widen");
+    assert_eq!(events[2]["status"], "ok");
+    assert_eq!(events[2]["exit_code"], 0);
+}
+
+#[test]
+fn runtime_errors_finish_with_an_error() {
+    let dir = Scratch::new("events");
+    let program = dir.file("error.rinha", "1 + true");
+    let output = interpreter(&program).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let events = events(&output.stderr);
+    assert_eq!(names(&events), ["start", "limits", "done"]);
+    assert_eq!(events[2]["status"], "error");
+    assert_eq!(events[2]["exit_code"], 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn stopped_runs_report_their_progress() {
+    let dir = Scratch::new("events");
+    let program = dir.file(
+        "spinning.rinha",
+        "let spin = fn (n, k) => {
+  if (n == 0) { 1 } else { spin(n - 1, k * 2) + spin(n - 1, k * 2 + 1) }
+};
+spin(28, 1)",
+    );
+    let mut child = interpreter(&program)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Waits for a progress event past the first one, reported on the first call.
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut lines = vec![];
+    loop {
+        let mut line = String::new();
+        let read = stderr.read_line(&mut line).unwrap();
+        assert!(read > 0, "the run ended early: {lines:?}");
+        let running = line.contains("\"progress\"") && !line.contains("\"ops\":1,");
+        lines.push(line);
+        if running {
+            break;
+        }
+    }
+    let killed = Command::new("kill")
+        .arg("-INT")
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let mut rest = String::new();
+    std::io::Read::read_to_string(&mut stderr, &mut rest).unwrap();
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+
+    let events = events((lines.concat() + &rest).as_bytes());
+    let progress = events.iter().find(|event| event["event"] == "progress");
+    assert!(progress.unwrap()["elapsed_ms"].is_u64());
+    assert!(progress.unwrap()["ops"].as_u64().unwrap() >= 1);
+    assert_eq!(names(&events), ["start", "limits", "done"]);
+    let done = events.last().unwrap();
+    assert_eq!(done["status"], "interrupted");
+    assert_eq!(done["exit_code"], 130);
+}
@@ -0,0 +1,83 @@
+use std::process::Command;
+
+fn rejected(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn color_lists_the_valid_values() {
+    let stderr = rejected(&["--color", "sometimes", "files/easy.json"]);
+    assert!(
+        stderr.contains("[possible values: auto, always, never]"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn error_format_lists_the_valid_values() {
+    let stderr = rejected(&["run", "--error-format", "xml", "files/easy.json"]);
+    assert!(
+        stderr.contains("[possible values: human, json]"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn bench_reps_must_be_positive() {
+    let stderr = rejected(&["bench", "--reps", "0", "files/easy.json"]);
+    assert!(stderr.contains("must be at least 1"), "{stderr}");
+}
+
+#[test]
+fn memo_capacity_must_be_positive() {
+    let stderr = rejected(&["run", "--memo-capacity", "0", "files/easy.json"]);
+    assert!(stderr.contains("0 is not in 1.."), "{stderr}");
+}
+
+#[test]
+fn help_lists_the_possible_values() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("--help")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("Possible values:").count(), 8, "{stdout}");
+    for value in [
+        "auto", "always", "never", "human", "json", "plain", "grouped", "hex", "wrap32", "wrap64",
+        "trap", "widen", "ndjson", "tree", "vm", "source", "0", "1", "2",
+    ] {
+        assert!(stdout.contains(&format!("- {value}:")), "{stdout}");
+    }
+}
+
+#[test]
+fn the_help_of_the_backends_is_plain_text() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--help"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for backend in ["- tree:", "- vm:", "- jit:"] {
+        assert!(stdout.contains(backend), "{stdout}");
+    }
+    assert!(!stdout.contains("[`"), "{stdout}");
+}
+
+#[test]
+fn events_interval_must_be_positive() {
+    let stderr = rejected(&[
+        "run",
+        "--events",
+        "ndjson",
+        "--events-interval",
+        "0",
+        "files/easy.json",
+    ]);
+    assert!(stderr.contains("0 is not in 1.."), "{stderr}");
+}
@@ -0,0 +1,159 @@
+//! The floats of `--extensions`: the integers they meet are turned into
+//! floats, they print so they read back the same, and they're an error
+//! without the extensions.
+
+use rinha::{
+    interpreter::{ErrorKind, Primitive},
+    parser, Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, process::Command, rc::Rc};
+
+/// What the program printed, the same with and without the memo.
+fn printed(source: &str) -> String {
+    let printed = [true, false].map(|memoize| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            extensions: true,
+            memoize,
+            ..RunConfig::default()
+        };
+        rinha::run_source(source, &config).unwrap();
+        String::from_utf8(output.take()).unwrap()
+    });
+    assert_eq!(printed[0], printed[1], "the memo changed the output");
+    printed[0].clone()
+}
+
+fn error(source: &str, extensions: bool) -> ErrorKind {
+    let config = RunConfig {
+        extensions,
+        ..RunConfig::default()
+    };
+    match rinha::run_source(source, &config) {
+        Err(RinhaError::Runtime(error)) => error.kind,
+        other => panic!("expected a runtime error, got {other:?}"),
+    }
+}
+
+#[test]
+fn the_integers_are_promoted() {
+    let program = "let _ = print(1 + 0.5);
+let _ = print(3 / 2.0);
+let _ = print(3 / 2);
+let _ = print(2 * 0.25 < 1);
+print(1 == 1.0)";
+    assert_eq!(printed(program), "1.5\n1.5\n1\ntrue\ntrue\n");
+}
+
+#[test]
+fn whole_floats_print_with_their_fraction() {
+    let program = "let _ = print(2.0);
+let _ = print(1.5 * 2);
+let _ = print((0.5, 1.0 - 1));
+print(\"x\" + 3.0)";
+    assert_eq!(printed(program), "2.0\n3.0\n(0.5, 0.0)\nx3.0\n");
+}
+
+#[test]
+fn nan_is_unequal_to_itself() {
+    let program = "let inf = 1.0 / 0.0;
+let nan = inf - inf;
+let _ = print(inf);
+let _ = print(nan);
+let _ = print(nan == nan);
+let _ = print(nan != nan);
+let _ = print((nan < 1.0) || (nan >= 1.0));
+print(0.0 == (0.0 * (0.0 - 1.0)))";
+    assert_eq!(printed(program), "inf\nNaN\nfalse\ntrue\nfalse\ntrue\n");
+}
+
+#[test]
+fn the_memo_tells_the_floats_from_the_integers() {
+    let program = "let double = fn (x) => { x * 2 };
+let _ = print(double(1));
+let _ = print(double(1.0));
+let _ = print(double(0.0));
+print(double(0.0 * (0.0 - 1.0)))";
+    assert_eq!(printed(program), "2\n2.0\n0.0\n-0.0\n");
+}
+
+#[test]
+fn the_bytecode_runs_them_the_same() {
+    let program = "let area = fn (r) => { 3.14159 * r * r };
+let _ = print(area(2));
+let _ = print((1.0 / 0.0, 7 / 2.0));
+print(area(1) > 3)";
+    let expected = printed(program);
+    assert_eq!(expected, "12.56636\n(inf, 3.5)\ntrue\n");
+    for backend in [Backend::Vm, Backend::Jit] {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            extensions: true,
+            backend,
+            ..RunConfig::default()
+        };
+        rinha::run_source(program, &config).unwrap();
+        assert_eq!(
+            String::from_utf8(output.take()).unwrap(),
+            expected,
+            "{backend:?}"
+        );
+    }
+}
+
+#[test]
+fn the_remainder_is_only_for_the_integers() {
+    assert!(matches!(error("5.0 % 2", true), ErrorKind::TypeMismatch(_)));
+    assert!(matches!(error("5 % 2.0", true), ErrorKind::TypeMismatch(_)));
+}
+
+#[test]
+fn floats_need_the_extensions() {
+    assert_eq!(
+        error("print(1.5)", false),
+        ErrorKind::NeedsExtensions("floats")
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--spec", "--extensions", "files/easy.rinha"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+}
+
+#[test]
+fn json_asts_carry_floats_as_numbers_or_strings() {
+    let term = |value: &str| {
+        format!(
+            r#"{{"name": "main.rinha", "location": {{"start": 0, "end": 0, "filename": "main.rinha"}},
+  "expression": {{"kind": "Float", "value": {value},
+    "location": {{"start": 0, "end": 0, "filename": "main.rinha"}}}}}}"#
+        )
+    };
+    let config = RunConfig {
+        extensions: true,
+        ..RunConfig::default()
+    };
+    for (value, expected) in [("1.5", 1.5), ("\"1.5\"", 1.5), ("2", 2.0)] {
+        let file: rinha::ast::File = serde_json::from_str(&term(value)).unwrap();
+        let outcome = rinha::run_ast(file, &config).unwrap();
+        assert_eq!(outcome.value, Primitive::Float(expected), "{value}");
+    }
+
+    let file: rinha::ast::File = serde_json::from_str(&term("\"NaN\"")).unwrap();
+    let outcome = rinha::run_ast(file.clone(), &config).unwrap();
+    assert_eq!(outcome.value, Primitive::Float(f64::NAN));
+    let json = serde_json::to_string(&file).unwrap();
+    assert!(json.contains(r#""value":"NaN""#), "{json}");
+}
+
+#[test]
+fn the_formatter_writes_floats_that_read_back() {
+    let source = "print((1.5 - 3, 10.0))";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let formatted = rinha::format::to_source(&file.expression);
+    assert_eq!(printed(&formatted), printed(source));
+    assert_eq!(printed(source), "(-1.5, 10.0)\n");
+}
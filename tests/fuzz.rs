@@ -0,0 +1,73 @@
+//! The programs of `rinha::fuzz` are well formed, and the engines neither
+//! panic nor disagree on them. The fuzz target of `fuzz/` checks the
+//! programs of the inputs of the fuzzer, here the bytes are pseudo-random.
+
+use arbitrary::Unstructured;
+use rinha::{
+    format,
+    fuzz::{self, Finding},
+    interpreter::ErrorKind,
+    parser, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, io, rc::Rc};
+
+/// The bytes of the `seed`th program, from a xorshift generator so every
+/// run checks the same programs.
+fn bytes(seed: u64) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..512)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn the_engines_agree_on_the_generated_programs() {
+    let config = RunConfig {
+        output: Some(Rc::new(RefCell::new(io::sink()))),
+        assert_none: false,
+        max_depth: Some(64),
+        max_steps: Some(100_000),
+        ..RunConfig::default()
+    };
+    let mut values = 0;
+    for seed in 0..500 {
+        let file = fuzz::file(&mut Unstructured::new(&bytes(seed))).unwrap();
+        let source = format::to_source(&file.expression);
+        match fuzz::check(&file) {
+            Ok(()) => {}
+            Err(Finding::Panicked(backend, error)) => {
+                panic!("{backend:?} panicked on\n{source}\n{error:?}")
+            }
+            Err(Finding::Diverged { tree, vm }) => {
+                panic!("the engines disagree on\n{source}\ntree: {tree:?}\nvm: {vm:?}")
+            }
+        }
+        match rinha::run_ast(file, &config) {
+            Ok(_) => values += 1,
+            Err(RinhaError::Runtime(error)) => assert!(
+                !matches!(error.kind, ErrorKind::UndefinedVariable(_)),
+                "{source}"
+            ),
+            Err(error) => panic!("{error:?}"),
+        }
+        parser::parse_or_report("main.rinha", &source).unwrap();
+    }
+    // Most of them aren't well typed, but many still end with a value.
+    assert!(values > 100, "{values} of the programs ended with a value");
+}
+
+#[test]
+fn the_programs_are_made_of_the_bytes() {
+    let source = |bytes: &[u8]| {
+        let file = fuzz::file(&mut Unstructured::new(bytes)).unwrap();
+        format::to_source(&file.expression)
+    };
+    assert_eq!(source(&bytes(7)), source(&bytes(7)));
+    assert_ne!(source(&bytes(7)), source(&bytes(8)));
+    assert_eq!(source(&[]), "0");
+}
@@ -0,0 +1,61 @@
+//! The example programs of `files/`, run from their source and from their
+//! AST after a trip through JSON. The tests are built with the `debug-invariants` feature, so
+//! the interpreter checks its invariants along the way.
+
+use rinha::{parser, RunConfig};
+use std::{cell::RefCell, fs, rc::Rc, thread};
+
+/// The recursion of the examples doesn't fit the default stack of the
+/// test threads in debug builds.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+const GOLDEN: &[(&str, &str)] = &[
+    ("combination", "45\n"),
+    ("easy", "11\n"),
+    ("fib", "fib(46) = 1836311903\n"),
+    ("sum", "500500\n"),
+    ("sum2", "4\n"),
+    ("sum3", "7\n"),
+    ("tuple", "1\n2\n3\n"),
+];
+
+fn printed(run: impl FnOnce(&RunConfig)) -> String {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        ..RunConfig::default()
+    };
+    run(&config);
+    let printed = output.borrow();
+    String::from_utf8(printed.clone()).unwrap()
+}
+
+fn check(name: &'static str, expected: &'static str) {
+    let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+    let from_source = printed(|config| {
+        rinha::run_source(&source, config).unwrap();
+    });
+    assert_eq!(from_source, expected, "{name}.rinha");
+
+    // The JSON files next to the sources are stale, so the AST is made
+    // from the source.
+    let parsed = parser::parse_or_report("main.rinha", &source).unwrap();
+    let json = serde_json::to_string(&parsed).unwrap();
+    let file: rinha::ast::File = serde_json::from_str(&json).unwrap();
+    let from_ast = printed(|config| {
+        rinha::run_ast(file, config).unwrap();
+    });
+    assert_eq!(from_ast, expected, "{name} through JSON");
+}
+
+#[test]
+fn examples_print_the_expected_output() {
+    for &(name, expected) in GOLDEN {
+        thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || check(name, expected))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::Scratch;
+use std::process::Command;
+
+/// `fib` with a debugging print inside.
+const PRINTING_FIB: &str = "let fib = fn (n) => {
+  let _ = print(n);
+  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};
+let log = fn (x) => { print(x) };
+let _ = log(1);
+let _ = log(1);
+fib(10)";
+
+fn run(args: &[&str]) -> String {
+    let dir = Scratch::new("impure-recursion");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(args)
+        .arg(dir.file("fib.rinha", PRINTING_FIB))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn a_print_inside_fib_is_hinted_once() {
+    let stderr = run(&["--print-result"]);
+    assert_eq!(stderr.matches("hint:").count(), 1, "{stderr}");
+    assert!(
+        stderr.contains("`fib` calls itself and prints at"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("fib.rinha:2:11"), "{stderr}");
+}
+
+#[test]
+fn there_are_no_hints_with_spec() {
+    assert_eq!(run(&["--spec"]), "");
+}
+
+#[test]
+fn there_are_no_hints_without_the_memo() {
+    assert_eq!(run(&["--no-memo"]), "");
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, path::Path, process::Command};
+
+fn interpret(path: &Path) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg(path)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    (output.status.code().unwrap(), stderr)
+}
+
+#[test]
+fn directory_suggests_the_single_candidate() {
+    let dir = Scratch::new("input");
+    fs::write(dir.join("program.json"), "{}").unwrap();
+
+    let (code, stderr) = interpret(&dir);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("is a directory"), "{stderr}");
+    assert!(stderr.contains(&dir.display().to_string()), "{stderr}");
+    assert!(stderr.contains("program.json"), "{stderr}");
+}
+
+#[test]
+fn nonexistent_path() {
+    let dir = Scratch::new("input");
+    let path = dir.join("missing.json");
+
+    let (code, stderr) = interpret(&path);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("does not exist"), "{stderr}");
+    assert!(stderr.contains("missing.json"), "{stderr}");
+}
+
+#[test]
+fn invalid_utf8_file() {
+    let dir = Scratch::new("input");
+    let path = dir.file("program.json", [0xff, 0xfe, 0xfd]);
+
+    let (code, stderr) = interpret(&path);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("not valid UTF-8"), "{stderr}");
+    assert!(stderr.contains("program.json"), "{stderr}");
+}
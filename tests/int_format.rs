@@ -0,0 +1,69 @@
+mod common;
+
+use common::Scratch;
+use rinha::interpreter::{IntFormat, Primitive};
+use std::{path::PathBuf, process::Command};
+
+fn rendered(value: i64, format: IntFormat) -> String {
+    Primitive::Int(value).display(format).to_string()
+}
+
+#[test]
+fn plain_is_the_default_rendering() {
+    for value in [0, 1234567, -1234567] {
+        assert_eq!(rendered(value, IntFormat::Plain), value.to_string());
+        assert_eq!(Primitive::Int(value).to_string(), value.to_string());
+    }
+}
+
+#[test]
+fn grouped_inserts_underscores_every_three_digits() {
+    assert_eq!(rendered(0, IntFormat::Grouped), "0");
+    assert_eq!(rendered(999, IntFormat::Grouped), "999");
+    assert_eq!(rendered(1000, IntFormat::Grouped), "1_000");
+    assert_eq!(rendered(1836311903, IntFormat::Grouped), "1_836_311_903");
+    assert_eq!(rendered(-1234567, IntFormat::Grouped), "-1_234_567");
+    assert_eq!(
+        rendered(i32::MIN.into(), IntFormat::Grouped),
+        "-2_147_483_648"
+    );
+}
+
+#[test]
+fn hex_prefixes_the_digits() {
+    assert_eq!(rendered(0, IntFormat::Hex), "0x0");
+    assert_eq!(rendered(255, IntFormat::Hex), "0xff");
+    assert_eq!(rendered(-255, IntFormat::Hex), "-0xff");
+}
+
+fn stdout(flags: &[&str], program: &PathBuf) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(flags)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_flag_applies_to_print_and_the_result() {
+    let dir = Scratch::new("int-format");
+    let program = dir.file("tuple.rinha", "print((1000000, 0 - 4096))");
+
+    let grouped = stdout(&["--int-format", "grouped", "--print-result"], &program);
+    assert_eq!(grouped, "(1_000_000, -4_096)\n(1_000_000, -4_096)\n");
+
+    let hex = stdout(&["--int-format", "hex"], &program);
+    assert_eq!(hex, "(0xf4240, -0x1000)\n");
+}
+
+#[test]
+fn spec_overrides_the_flag() {
+    let dir = Scratch::new("int-format");
+    let program = dir.file("spec.rinha", "print(1000000)");
+
+    let spec = stdout(&["--int-format", "grouped", "--spec"], &program);
+    assert_eq!(spec, "1000000\n");
+}
@@ -0,0 +1,226 @@
+//! The interpreter used as a library: the terms are built in Rust or read
+//! from the JSON ASTs, and the tests look at the value they produce and at
+//! what they printed into a buffer.
+
+use rinha::{
+    ast::{self, BinaryOp, Term},
+    interpreter::{ErrorKind, Interpreter, Primitive, Scope, Value},
+    parser::Var,
+    runtime::tuple,
+};
+use std::{cell::RefCell, fs, io, rc::Rc, sync::Arc};
+
+/// A sink the test keeps a handle to.
+#[derive(Clone, Default)]
+struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Evaluates `term`, returning its value and what it printed.
+fn eval(term: Term) -> (Result<Primitive, ErrorKind>, String) {
+    let buffer = Buffer::default();
+    let mut interpreter = Interpreter::with_output(buffer.clone());
+    let value = interpreter.interpret(term, &mut Scope::new());
+    let printed = String::from_utf8(buffer.0.take()).unwrap();
+    (value.map_err(|error| error.kind), printed)
+}
+
+/// Builds the terms, each at a location of its own, like the parser does.
+#[derive(Default)]
+struct Terms {
+    offset: usize,
+}
+
+impl Terms {
+    fn at(&mut self) -> ast::Location {
+        self.offset += 1;
+        ast::Location::new(self.offset, self.offset + 1, "built.rinha")
+    }
+
+    fn var(&mut self, text: &str) -> Var {
+        Var {
+            text: text.into(),
+            location: self.at(),
+            address: Default::default(),
+        }
+    }
+
+    fn int(&mut self, value: i32) -> Term {
+        Term::Int(ast::Int {
+            value,
+            location: self.at(),
+        })
+    }
+
+    fn str(&mut self, value: &str) -> Term {
+        Term::Str(ast::Str {
+            value: value.into(),
+            location: self.at(),
+        })
+    }
+
+    fn get(&mut self, text: &str) -> Term {
+        Term::Var(self.var(text))
+    }
+
+    fn binary(&mut self, lhs: Term, op: BinaryOp, rhs: Term) -> Term {
+        Term::Binary(ast::Binary {
+            lhs: Box::new(lhs),
+            op,
+            rhs: Box::new(rhs),
+            location: self.at(),
+        })
+    }
+
+    fn print(&mut self, value: Term) -> Term {
+        Term::Print(ast::Print {
+            value: Box::new(value),
+            location: self.at(),
+        })
+    }
+
+    fn tuple(&mut self, first: Term, second: Term) -> Term {
+        Term::Tuple(ast::Tuple {
+            first: Box::new(first),
+            second: Box::new(second),
+            location: self.at(),
+        })
+    }
+
+    fn function(&mut self, parameters: &[&str], value: Term) -> Term {
+        let parameters = parameters.iter().map(|name| self.var(name)).collect();
+        Term::Function(ast::Function {
+            parameters,
+            value: Arc::new(value),
+            location: self.at(),
+        })
+    }
+
+    fn call(&mut self, callee: Term, arguments: Vec<Term>) -> Term {
+        Term::Call(ast::Call {
+            callee: Box::new(callee),
+            arguments,
+            location: self.at(),
+        })
+    }
+
+    fn let_(&mut self, name: &str, value: Term, next: Term) -> Term {
+        Term::Let(ast::Let {
+            name: self.var(name),
+            value: Box::new(value),
+            next: Box::new(next),
+            location: self.at(),
+        })
+    }
+}
+
+#[test]
+fn prints_go_into_the_buffer_and_the_value_is_returned() {
+    let mut t = Terms::default();
+    let (lhs, rhs) = (t.int(40), t.int(2));
+    let sum = t.binary(lhs, BinaryOp::Add, rhs);
+    let term = t.print(sum);
+
+    let (value, printed) = eval(term);
+    assert_eq!(value, Ok(Primitive::Int(42)));
+    assert_eq!(printed, "42\n");
+}
+
+#[test]
+fn calls_bind_their_arguments() {
+    // let greet = fn (name) => { print("hi " + name) }; greet("there")
+    let mut t = Terms::default();
+    let hi = t.str("hi ");
+    let name = t.get("name");
+    let greeting = t.binary(hi, BinaryOp::Add, name);
+    let body = t.print(greeting);
+    let greet = t.function(&["name"], body);
+    let callee = t.get("greet");
+    let there = t.str("there");
+    let call = t.call(callee, vec![there]);
+    let term = t.let_("greet", greet, call);
+
+    let (value, printed) = eval(term);
+    assert_eq!(value, Ok(Primitive::Str("hi there".into())));
+    assert_eq!(printed, "hi there\n");
+}
+
+#[test]
+fn tuples_are_compared_by_their_elements() {
+    let mut t = Terms::default();
+    let (one, two) = (t.int(1), t.str("two"));
+    let inner = t.tuple(one, two);
+    let three = t.int(3);
+    let term = t.tuple(inner, three);
+
+    let (value, printed) = eval(term);
+    let expected = tuple::new(
+        tuple::new(Primitive::Int(1), Primitive::Str("two".into())),
+        Primitive::Int(3),
+    );
+    assert_eq!(value, Ok(expected));
+    assert_eq!(printed, "");
+}
+
+#[test]
+fn errors_come_back_with_what_was_printed_before_them() {
+    let mut t = Terms::default();
+    let one = t.int(1);
+    let printed = t.print(one);
+    let missing = t.get("missing");
+    let term = t.let_("_", printed, missing);
+
+    let (value, printed) = eval(term);
+    assert_eq!(value, Err(ErrorKind::UndefinedVariable("missing".into())));
+    assert_eq!(printed, "1\n");
+}
+
+#[test]
+fn the_json_examples() {
+    for (name, value, printed) in [
+        ("easy", Primitive::Int(11), "11\n"),
+        ("combination", Primitive::Int(45), "45\n"),
+        ("tuple", Primitive::Int(3), "1\n2\n3\n"),
+    ] {
+        let json = fs::read_to_string(format!("files/{name}.json")).unwrap();
+        let file: ast::File = serde_json::from_str(&json).unwrap();
+        assert_eq!(eval(file.expression), (Ok(value), printed.into()), "{name}");
+    }
+}
+
+#[test]
+fn each_program_is_evaluated_in_a_scope_of_its_own() {
+    let mut interpreter = Interpreter::with_output(io::sink());
+    let first = rinha::parser::parse_or_report("first.rinha", "let x = 1; x").unwrap();
+    let second = rinha::parser::parse_or_report("second.rinha", "x").unwrap();
+
+    assert_eq!(interpreter.eval(first), Ok(Value::Int(1)));
+    let error = interpreter.eval(second).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("x".into()));
+}
+
+#[test]
+fn functions_are_equal_when_they_come_from_the_same_definition() {
+    let source = "let make = fn (n) => { fn (x) => { x + n } };\n(make(1), (make(1), make(2)))";
+    let file = rinha::parser::parse_or_report("main.rinha", source).unwrap();
+    let (value, _) = eval(file.expression);
+    let value = value.unwrap();
+
+    let first = tuple::first(value.clone()).unwrap();
+    let rest = tuple::second(value).unwrap();
+    let (same, other) = (
+        tuple::first(rest.clone()).unwrap(),
+        tuple::second(rest).unwrap(),
+    );
+    assert_eq!(first, same);
+    assert_ne!(first, other);
+}
@@ -0,0 +1,34 @@
+//! The tests are built with the `debug-invariants` feature, these corrupt
+//! the interpreter state on purpose to see the checks fire.
+
+use rinha::{
+    interpreter::{Interpreter, Scope},
+    parser,
+};
+use std::io;
+
+const PROGRAM: &str = "let f = fn (n) => { n + 1 };\nf(1)";
+
+fn run(interpreter: &mut Interpreter) {
+    let file = parser::parse_or_report("main.rinha", PROGRAM).unwrap();
+    let _ = interpreter.interpret(file.expression, &mut Scope::new());
+}
+
+#[test]
+fn the_checks_pass_on_valid_state() {
+    run(&mut Interpreter::with_output(io::sink()));
+}
+
+#[test]
+#[should_panic(expected = "invariant violated: memo entry `ghost@:0()` belongs to no function")]
+fn memo_entries_must_belong_to_a_function() {
+    let mut interpreter = Interpreter::with_output(io::sink());
+    interpreter.corrupt_memo_for_tests("ghost");
+    run(&mut interpreter);
+}
+
+#[test]
+#[should_panic(expected = "invariant violated: a frame was popped twice")]
+fn frames_are_popped_once() {
+    Interpreter::with_output(io::sink()).pop_frame_for_tests();
+}
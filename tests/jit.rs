@@ -0,0 +1,201 @@
+//! `--backend jit` runs the functions on integers as machine code, and the
+//! programs run like they do on the bytecode: they print the same, end with
+//! the same value, count the same calls and fail with the same errors.
+
+use rinha::{
+    interpreter::{ErrorKind, Overflow, Primitive},
+    names::FunctionNames,
+    parser,
+    runtime::scope::Scope,
+    vm::{bytecode::Program, jit::Jit},
+    Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, fs, process::Command, rc::Rc, thread};
+
+const EXAMPLES: &[&str] = &["combination", "easy", "sum", "sum2", "sum3", "tuple"];
+
+/// What the program printed, its value and how many calls it made.
+fn run(source: &str, backend: Backend, overflow: Overflow) -> (String, String, u64) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        backend,
+        overflow,
+        output: Some(output.clone()),
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(source, &config).unwrap();
+    (
+        String::from_utf8(output.take()).unwrap(),
+        outcome.value.to_string(),
+        outcome.stats.calls,
+    )
+}
+
+fn same_as_the_bytecode(source: &str, overflow: Overflow) -> (String, String, u64) {
+    let ran = run(source, Backend::Jit, overflow);
+    assert_eq!(ran, run(source, Backend::Vm, overflow), "{source}");
+    ran
+}
+
+/// The error of the program, with the start and the end of where it points.
+fn error(source: &str, backend: Backend) -> (ErrorKind, u64, u64) {
+    let config = RunConfig {
+        backend,
+        overflow: Overflow::Trap,
+        ..RunConfig::default()
+    };
+    match rinha::run_source(source, &config) {
+        Err(RinhaError::Runtime(error)) => (error.kind, error.location.start, error.location.end),
+        other => panic!("expected a runtime error, got {other:?}"),
+    }
+}
+
+/// Which functions of the program are translated, by their name.
+fn compiled(source: &str) -> Vec<String> {
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let names = FunctionNames::new(&file.expression, None);
+    let program = Program::lower(&file.expression, &names);
+    let jit = Jit::compile(&program, Overflow::Trap);
+    (1..program.chunks.len())
+        .filter(|index| jit.is_compiled(*index))
+        .map(|index| program.chunks[index].name.to_string())
+        .collect()
+}
+
+#[test]
+fn the_examples_run_the_same() {
+    for name in EXAMPLES {
+        let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+        same_as_the_bytecode(&source, Overflow::Trap);
+    }
+}
+
+#[test]
+fn only_the_functions_on_integers_are_translated() {
+    let program = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+let even = fn (n) => { if (n == 0) { true } else { (n != 1) && even(n - 2) } };
+let loud = fn (n) => { print(n) };
+let greet = fn (n) => { \"hi \" + n };
+let twice = fn (n) => { fib(n) * 2 };
+let pair = fn (n) => { (n, n) };
+let adder = fn (x) => { fn (y) => { x + y } };
+fib(1)";
+    assert_eq!(compiled(program), ["fib", "even", "anon#8@main.rinha"]);
+}
+
+#[test]
+fn the_closures_run_as_machine_code_with_what_they_captured() {
+    let program = "let scaled = fn (k) => {
+  let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, acc + k * n) } };
+  loop
+};
+scaled(3)(100, 0)";
+    let (_, value, _) = same_as_the_bytecode(program, Overflow::Trap);
+    assert_eq!(value, "15150");
+    assert_eq!(compiled(program), ["loop"]);
+
+    let file = parser::parse_or_report("main.rinha", program).unwrap();
+    let names = FunctionNames::new(&file.expression, None);
+    let program = Program::lower(&file.expression, &names);
+    let index = (1..program.chunks.len())
+        .find(|index| &*program.chunks[*index].name == "loop")
+        .unwrap();
+    let mut jit = Jit::compile(&program, Overflow::Trap);
+    let arguments = [Primitive::Int(100), Primitive::Int(0)];
+    let mut env = Scope::new();
+    env.insert("k", Primitive::Int(3));
+    let called = jit.call(index, &arguments, &env, None);
+    assert_eq!(called, Some((Primitive::Int(15150), 101)));
+
+    // A value the machine code can't take runs the closure on the bytecode.
+    env.insert("k", Primitive::Bool(true));
+    assert_eq!(jit.call(index, &arguments, &env, None), None);
+    assert!(jit.is_compiled(index));
+}
+
+#[test]
+fn recursion_and_loops_run_the_same() {
+    let program = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+let even = fn (n) => { if (n == 0) { true } else { (n != 1) && even(n - 2) } };
+let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, (acc + n) % 1000) } };
+let divide = fn (a, b) => { (a / b, a % b) };
+let _ = print(fib(15));
+let _ = print(even(1001));
+let _ = print(loop(10000, 0));
+let _ = print(divide(0 - 7, 2));
+fib(22)";
+    let (printed, value, _) = same_as_the_bytecode(program, Overflow::Trap);
+    assert_eq!(printed, "610\nfalse\n0\n(-3, -1)\n");
+    assert_eq!(value, "17711");
+}
+
+#[test]
+fn the_results_wrap_around_like_the_reference() {
+    let program = "let fact = fn (n) => { if (n == 0) { 1 } else { n * fact(n - 1) } };
+let power = fn (n) => { if (n == 0) { 1 } else { 2 * power(n - 1) } };
+let _ = print(power(31));
+fact(13)";
+    let (printed, value, _) = same_as_the_bytecode(program, Overflow::Wrap32);
+    assert_eq!(printed, "-2147483648\n");
+    assert_eq!(value, "1932053504");
+}
+
+#[test]
+fn the_results_past_32_bits_are_the_ones_of_the_bytecode() {
+    let program = "let power = fn (n) => { if (n == 0) { 1 } else { 2 * power(n - 1) } };
+power(70)";
+    let (_, value, _) = same_as_the_bytecode(program, Overflow::Widen);
+    assert_eq!(value, "1180591620717411303424");
+    assert_eq!(compiled(program), ["power"]);
+}
+
+#[test]
+fn the_errors_are_the_ones_of_the_bytecode() {
+    let programs = [
+        "let power = fn (n) => { if (n == 0) { 1 } else { 2 * power(n - 1) } };\npower(40)",
+        "let down = fn (n) => { if (n == 0) { 1 / n } else { down(n - 1) } };\ndown(10)",
+        "let mod = fn (a, b) => { a % b };\nmod(3, 0)",
+    ];
+    for program in programs {
+        let jit = error(program, Backend::Jit);
+        assert_eq!(jit, error(program, Backend::Vm), "{program}");
+        assert!(
+            matches!(
+                jit.0,
+                ErrorKind::IntegerOverflow(_) | ErrorKind::DivisionByZero(_)
+            ),
+            "{program}"
+        );
+    }
+}
+
+#[test]
+fn deep_recursion_goes_back_to_the_bytecode() {
+    let depth = "let depth = fn (n) => { if (n == 0) { 0 } else { 1 + depth(n - 1) } };
+depth(100000)";
+    let (_, value, calls) = thread::spawn(move || run(depth, Backend::Jit, Overflow::Trap))
+        .join()
+        .unwrap();
+    assert_eq!(value, "100000");
+    assert_eq!(calls, 100001);
+}
+
+#[test]
+fn the_binary_selects_the_machine_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args([
+            "run",
+            "--backend",
+            "jit",
+            "--print-result",
+            "files/sum.rinha",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "500500\n500500\n"
+    );
+}
@@ -0,0 +1,86 @@
+//! `--lazy` evaluates the values of the `let` and the arguments of the
+//! calls once their variable is used, and once.
+
+use rinha::{RinhaError, RunConfig};
+use std::{cell::RefCell, process::Command, rc::Rc, thread};
+
+/// Runs the program, returning what it printed and its value.
+fn run(source: &str, lazy: bool) -> (String, Result<String, RinhaError>) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        lazy,
+        output: Some(output.clone()),
+        ..RunConfig::default()
+    };
+    let value = rinha::run_source(source, &config).map(|outcome| outcome.value.to_string());
+    let printed = String::from_utf8(output.borrow().clone()).unwrap();
+    (printed, value)
+}
+
+#[test]
+fn the_unused_values_are_never_evaluated() {
+    let source = "let loop = fn (n) => { loop(n) };
+let pick = fn (a, b) => { a };
+let unused = 1 / 0;
+pick(42, loop(0))";
+    let (_, value) = run(source, true);
+    assert_eq!(value.unwrap(), "42");
+}
+
+#[test]
+fn the_values_are_evaluated_once_when_used() {
+    let source = "let x = print(1);
+let _ = print(2);
+let twice = fn (n) => { n + n };
+twice(x) + x";
+    assert_eq!(run(source, false).0, "1\n2\n");
+    let (printed, value) = run(source, true);
+    assert_eq!(printed, "1\n");
+    assert_eq!(value.unwrap(), "3");
+}
+
+#[test]
+fn the_programs_that_end_end_with_the_same_value() {
+    let sources = [
+        "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+        "let sum = fn (n, acc) => { if (n == 0) { acc } else { sum(n - 1, acc + n) } }; sum(1000, 0)",
+        "let pair = (1 + 1, \"a\" + 1); let swap = fn (p) => { (second(p), first(p)) }; swap(pair)",
+        "let add = fn (a) => { fn (b) => { a + b } }; let inc = add(1); inc(inc(1))",
+    ];
+    for source in sources {
+        // The accumulator of `sum` is a chain of a thousand thunks, each
+        // one forcing the one before it.
+        let (strict, lazy) = thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || (run(source, false).1.unwrap(), run(source, true).1.unwrap()))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(lazy, strict, "{source}");
+    }
+}
+
+#[test]
+fn the_errors_happen_where_the_value_is_used() {
+    let source = "let x = 1 / 0; let y = 2; y + x";
+    let Err(RinhaError::Runtime(error)) = run(source, true).1 else {
+        panic!("the division by zero doesn't fail");
+    };
+    assert_eq!(error.location.start, 8);
+}
+
+#[test]
+fn the_binary_runs_lazily_on_the_tree_walking_interpreter() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["--lazy", "--quiet", "files/sum.rinha"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"500500\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["--lazy", "--backend", "vm", "files/sum.rinha"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
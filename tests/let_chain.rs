@@ -0,0 +1,100 @@
+//! Long chains of `let` are evaluated in a loop, not by recursion.
+//!
+//! The chains are built as trees, parsing half a million lines of source
+//! would take most of the time of the test.
+
+use rinha::{
+    ast::{Binary, BinaryOp, Bool, File, Int, Let, Location, Print, Term},
+    parser::Var,
+    RinhaError, RunConfig,
+};
+use std::{cell::RefCell, rc::Rc, thread};
+
+const LINKS: i32 = 500_000;
+
+fn int(value: i32) -> Box<Term> {
+    Box::new(Term::Int(Int {
+        value,
+        location: Location::default(),
+    }))
+}
+
+/// A chain of `let _ = print(index);` ending with `last`, built from the
+/// last link up so building it doesn't recurse either.
+fn chain(first: Option<Term>, last: Term) -> File {
+    let mut expression = last;
+    for index in (0..LINKS).rev() {
+        let print = Term::Print(Print {
+            value: int(index),
+            location: Location::default(),
+        });
+        expression = link(print, expression);
+    }
+    if let Some(first) = first {
+        expression = link(first, expression);
+    }
+    File {
+        name: "chain.rinha".into(),
+        expression,
+        location: Location::default(),
+        metadata: None,
+    }
+}
+
+fn link(value: Term, next: Term) -> Term {
+    Term::Let(Let {
+        name: Var {
+            text: "_".into(),
+            location: Location::default(),
+            address: Default::default(),
+        },
+        value: value.into(),
+        next: next.into(),
+        location: Location::default(),
+    })
+}
+
+/// Runs on a thread with the default stack size, smaller than the main
+/// thread's.
+fn run(file: File) -> (Result<String, RinhaError>, String) {
+    thread::spawn(move || {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            ..RunConfig::default()
+        };
+        let result = rinha::run_ast(file, &config).map(|outcome| outcome.value.to_string());
+        let printed = String::from_utf8(output.take()).unwrap();
+        (result, printed)
+    })
+    .join()
+    .unwrap()
+}
+
+#[test]
+fn long_let_chains_run_in_order_on_the_default_stack() {
+    let (result, printed) = run(chain(None, *int(42)));
+    assert_eq!(result.unwrap(), "42");
+
+    let mut lines = printed.lines();
+    for index in 0..LINKS {
+        assert_eq!(lines.next(), Some(index.to_string().as_str()));
+    }
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn failing_in_the_middle_of_a_long_chain_drops_the_rest() {
+    let failing = Term::Binary(Binary {
+        lhs: int(1),
+        op: BinaryOp::Add,
+        rhs: Box::new(Term::Bool(Bool {
+            value: true,
+            location: Location::default(),
+        })),
+        location: Location::default(),
+    });
+    let (result, printed) = run(chain(Some(failing), *int(0)));
+    assert!(matches!(result, Err(RinhaError::Runtime(_))));
+    assert_eq!(printed, "");
+}
@@ -0,0 +1,150 @@
+//! `--max-depth` and `--max-steps` stop the runaway recursions and the
+//! loops that never end with an error, on every backend.
+
+mod common;
+
+use common::Scratch;
+use rinha::{interpreter::ErrorKind, Backend, RinhaError, RunConfig};
+use std::{fs, process::Command};
+
+const DEEP: &str = "let deep = fn (n) => { if (n == 0) { 0 } else { 1 + deep(n - 1) } };
+deep(200)";
+
+const LOOP: &str = "let loop = fn (n) => { loop(n + 1) };
+loop(0)";
+
+/// Recurses until something stops it, each call nested in the one before.
+const RUNAWAY: &str = "let runaway = fn (n) => { 1 + runaway(n + 1) };
+runaway(0)";
+
+const BACKENDS: [Backend; 3] = [Backend::Tree, Backend::Vm, Backend::Jit];
+
+fn run(source: &str, config: RunConfig) -> Result<String, (ErrorKind, String)> {
+    match rinha::run_source(source, &config) {
+        Ok(outcome) => Ok(outcome.value.to_string()),
+        Err(RinhaError::Runtime(error)) => {
+            let term = &source[error.location.start as usize..error.location.end as usize];
+            Err((error.kind, term.to_string()))
+        }
+        Err(other) => panic!("{source} failed with {other:?}"),
+    }
+}
+
+#[test]
+fn the_recursion_stops_at_the_depth() {
+    for backend in BACKENDS {
+        let config = |max_depth| RunConfig {
+            backend,
+            max_depth: Some(max_depth),
+            ..RunConfig::default()
+        };
+        assert_eq!(
+            run(DEEP, config(100)),
+            Err((ErrorKind::DepthLimit(100), "deep(n - 1)".into())),
+            "{backend:?}"
+        );
+        assert_eq!(run(DEEP, config(201)).unwrap(), "200", "{backend:?}");
+        let counted = "let count = fn (n) => { if (n == 0) { 0 } else { count(n - 1) } };
+count(10000)";
+        assert_eq!(run(counted, config(1)).unwrap(), "0", "{backend:?}");
+    }
+}
+
+#[test]
+fn the_loops_stop_at_the_steps() {
+    for backend in BACKENDS {
+        let config = RunConfig {
+            backend,
+            max_steps: Some(10_000),
+            ..RunConfig::default()
+        };
+        let (kind, _) = run(LOOP, config).unwrap_err();
+        assert_eq!(kind, ErrorKind::StepLimit(10_000), "{backend:?}");
+    }
+    let config = |max_steps| RunConfig {
+        max_steps: Some(max_steps),
+        ..RunConfig::default()
+    };
+    let program = "let x = 1; let y = 2; x + y";
+    assert_eq!(
+        run(program, config(5)).unwrap_err().0,
+        ErrorKind::StepLimit(5)
+    );
+    assert_eq!(run(program, config(100)).unwrap(), "3");
+}
+
+#[test]
+fn the_halves_of_par_pair_count_with_the_rest() {
+    let source = format!("{DEEP}; par_pair(deep, 10, 200)").replace("deep(200);", "");
+    let config = RunConfig {
+        extensions: true,
+        max_depth: Some(100),
+        ..RunConfig::default()
+    };
+    let (kind, term) = run(&source, config).unwrap_err();
+    assert_eq!(
+        (kind, term.as_str()),
+        (ErrorKind::DepthLimit(100), "deep(n - 1)")
+    );
+}
+
+#[test]
+fn the_run_exits_with_the_code_of_the_limits() {
+    let dir = Scratch::new("limits");
+    let program = dir.file("main.rinha", LOOP);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args([
+            "run",
+            "--max-steps",
+            "1000",
+            "--max-depth",
+            "50",
+            "--summary",
+        ])
+        .arg(dir.join("summary.json"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("resource limit exceeded: the run took more than 1000 steps"),
+        "{stderr}"
+    );
+    let summary = fs::read_to_string(dir.join("summary.json")).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    assert_eq!(summary["exit_code"], 3);
+    assert_eq!(summary["max_steps"], 1000);
+    assert_eq!(summary["max_depth"], 50);
+}
+
+#[test]
+fn the_recursion_stops_before_the_native_stack_is_used_up() {
+    let config = RunConfig {
+        max_stack: Some(256 * 1024),
+        ..RunConfig::default()
+    };
+    let (kind, term) = run(RUNAWAY, config).unwrap_err();
+    assert!(matches!(kind, ErrorKind::DepthLimit(_)), "{kind:?}");
+    assert_eq!(term, "runaway(n + 1)");
+}
+
+#[test]
+fn the_runaway_recursions_exit_with_the_code_of_the_limits() {
+    let dir = Scratch::new("limits");
+    let program = dir.file("main.rinha", RUNAWAY);
+
+    // Without flags, on the stack the binary gives its thread.
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("resource limit exceeded: the calls nested deeper than"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("overflow"), "{stderr}");
+}
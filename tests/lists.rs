@@ -0,0 +1,134 @@
+//! The lists of `--extensions`: `list`, `cons`, `head`, `tail` and `len`
+//! build and take them apart, sharing the rest of the lists they're made
+//! from.
+
+use rinha::{
+    interpreter::{ErrorKind, Primitive, Stats},
+    RinhaError, RunConfig,
+};
+use std::process::{Command, Stdio};
+
+const LOOPS: &str = "let range = fn (n, acc) => {
+  if (n == 0) { acc } else { range(n - 1, cons(n, acc)) }
+};
+let map = fn (f, l) => {
+  if (len(l) == 0) { list() } else { cons(f(head(l)), map(f, tail(l))) }
+};
+let sum = fn (l, acc) => {
+  if (len(l) == 0) { acc } else { sum(tail(l), acc + head(l)) }
+};";
+
+fn run(source: &str, extensions: bool) -> Result<(Primitive, Stats), RinhaError> {
+    let config = RunConfig {
+        extensions,
+        ..RunConfig::default()
+    };
+    rinha::run_source(source, &config).map(|outcome| (outcome.value, outcome.stats))
+}
+
+fn value(source: &str) -> String {
+    run(source, true).unwrap().0.to_string()
+}
+
+fn error(source: &str) -> (ErrorKind, String) {
+    let Err(RinhaError::Runtime(error)) = run(source, true) else {
+        panic!("{source} didn't fail at runtime");
+    };
+    let term = &source[error.location.start as usize..error.location.end as usize];
+    (error.kind, term.to_string())
+}
+
+#[test]
+fn lists_are_built_and_taken_apart() {
+    assert_eq!(value("list(1, \"a\", (2, true))"), "[1, a, (2, true)]");
+    assert_eq!(value("list()"), "[]");
+    assert_eq!(value("cons(0, list(1, 2))"), "[0, 1, 2]");
+    assert_eq!(
+        value("let l = list(1, 2, 3); (head(l), tail(l))"),
+        "(1, [2, 3])"
+    );
+    assert_eq!(value("len(cons(list(), list(1)))"), "2");
+    assert_eq!(
+        value(&format!(
+            "{LOOPS} map(fn (x) => {{ x * x }}, range(4, list()))"
+        )),
+        "[1, 4, 9, 16]"
+    );
+}
+
+#[test]
+fn lists_compare_element_wise() {
+    assert_eq!(value("list(1, (2, 3)) == cons(1, list((2, 3)))"), "true");
+    assert_eq!(value("list(1, 2) == list(1)"), "false");
+    assert_eq!(value("list() != list()"), "false");
+    let (kind, _) = error("list(1) == list(\"1\")");
+    assert!(matches!(kind, ErrorKind::TypeMismatch(_)), "{kind:?}");
+}
+
+#[test]
+fn the_natives_fail_where_they_are_called() {
+    assert_eq!(
+        error("let l = list();\nhead(l)"),
+        (ErrorKind::EmptyList("head"), "head(l)".into())
+    );
+    assert_eq!(
+        error("let l = list();\ntail(l)"),
+        (ErrorKind::EmptyList("tail"), "tail(l)".into())
+    );
+    assert_eq!(
+        error("let t = (1, 2);\nlen(t)"),
+        (ErrorKind::NotAList("len"), "len(t)".into())
+    );
+    assert_eq!(
+        error("cons(1, 2, 3)"),
+        (
+            ErrorKind::ArityMismatch {
+                name: "cons".into(),
+                expected: 2,
+                given: 3
+            },
+            "cons(1, 2, 3)".into()
+        )
+    );
+}
+
+#[test]
+fn the_programs_can_bind_the_names_of_the_natives() {
+    assert_eq!(value("let head = fn (x) => { x + 1 }; head(1)"), "2");
+    let Err(RinhaError::Runtime(error)) = run("list(1)", false) else {
+        panic!("the lists work without the extensions");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("list".into()));
+}
+
+#[test]
+fn the_long_lists_are_walked_without_the_memo() {
+    let source = format!("{LOOPS} let l = range(50000, list()); (len(l), sum(l, 0))");
+    let (value, stats) = run(&source, true).unwrap();
+    assert_eq!(value.to_string(), "(50000, 1250025000)");
+    assert_eq!(stats.memo_hits, 0);
+
+    let json = run("list(1, (2, 3), list())", true).unwrap().0.to_json();
+    assert_eq!(json, serde_json::json!([1, [2, 3], []]));
+}
+
+#[test]
+fn the_binary_checks_the_programs_using_them() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--extensions", "--check", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(b"print(len(tail(list(1, 2))))")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"1\n");
+}
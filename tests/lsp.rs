@@ -0,0 +1,177 @@
+//! `rinha-lsp` answers the editors through the standard input and output,
+//! with the findings of the analyses, the types and the definitions.
+
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+const URI: &str = "file:///main.rinha";
+
+const PROGRAM: &str = "let double = fn (n) => { n * 2 };
+let _ = print(double(\"é\"));
+double(21)";
+
+struct Server {
+    child: Child,
+    output: BufReader<ChildStdout>,
+    id: u64,
+}
+
+impl Server {
+    fn start() -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rinha-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+        let mut server = Server {
+            child,
+            output,
+            id: 0,
+        };
+        let capabilities = server.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(capabilities["capabilities"]["hoverProvider"], true);
+        server.notify("initialized", json!({}));
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        let input = self.child.stdin.as_mut().unwrap();
+        write!(input, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        input.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.output.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.output.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Sends the request and waits for its response, skipping the
+    /// notifications of the server.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        let id = self.id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Waits for the diagnostics of the document.
+    fn diagnostics(&mut self) -> Vec<Value> {
+        loop {
+            let message = self.receive();
+            if message["method"] == "textDocument/publishDiagnostics" {
+                assert_eq!(message["params"]["uri"], URI);
+                return message["params"]["diagnostics"].as_array().unwrap().clone();
+            }
+        }
+    }
+
+    fn open(&mut self, text: &str) -> Vec<Value> {
+        let document = json!({ "uri": URI, "languageId": "rinha", "version": 1, "text": text });
+        self.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        self.diagnostics()
+    }
+
+    fn at(&mut self, method: &str, line: u32, character: u32) -> Value {
+        let params = json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        });
+        self.request(method, params)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn the_findings_are_published_when_the_document_changes() {
+    let mut server = Server::start();
+    let diagnostics = server.open(PROGRAM);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0]["message"], "expected Int, but found Str");
+    // After the `"é"`, in UTF-16 code units.
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 1, "character": 21 }, "end": { "line": 1, "character": 24 } })
+    );
+
+    let change = json!({
+        "textDocument": { "uri": URI, "version": 2 },
+        "contentChanges": [{ "text": "let x = 1;\nprint(" }],
+    });
+    server.notify("textDocument/didChange", change);
+    let diagnostics = server.diagnostics();
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0]["severity"], 1);
+
+    let change = json!({
+        "textDocument": { "uri": URI, "version": 3 },
+        "contentChanges": [{ "text": "print(1)" }],
+    });
+    server.notify("textDocument/didChange", change);
+    assert_eq!(server.diagnostics(), Vec::<Value>::new());
+}
+
+#[test]
+fn the_hover_shows_the_inferred_types() {
+    let mut server = Server::start();
+    server.open(PROGRAM);
+
+    let hover = server.at("textDocument/hover", 0, 6);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```rinha\ndouble: fn (Int) -> Int\n```"
+    );
+    let hover = server.at("textDocument/hover", 0, 25);
+    assert_eq!(hover["contents"]["value"], "```rinha\nn: Int\n```");
+    assert_eq!(server.at("textDocument/hover", 2, 8), Value::Null);
+}
+
+#[test]
+fn the_definitions_are_where_the_names_are_bound() {
+    let mut server = Server::start();
+    server.open(PROGRAM);
+
+    let definition = server.at("textDocument/definition", 2, 2);
+    assert_eq!(definition["uri"], URI);
+    assert_eq!(
+        definition["range"],
+        json!({ "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 10 } })
+    );
+    let definition = server.at("textDocument/definition", 0, 25);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 0, "character": 17 })
+    );
+}
@@ -0,0 +1,210 @@
+//! `match` takes the first arm whose pattern fits the value, the tuples,
+//! the literals and `_` with their guards. The bytecode and WebAssembly run
+//! the `let` and `if` the optimizer lowers it into, which print and fail
+//! like the interpreter.
+
+use rinha::{
+    check::{self, types, Diagnostics},
+    interpreter::ErrorKind,
+    optimize::{self, Pass},
+    parser, path, Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// What the program printed and how it ended, the same on every backend
+/// and once its matches are lowered.
+fn ran(source: &str) -> (String, Result<String, (ErrorKind, String)>) {
+    let run = |backend: Backend, lowered: bool| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            backend,
+            ..RunConfig::default()
+        };
+        let mut file = parser::parse_or_report("main.rinha", source).unwrap();
+        if lowered {
+            file.expression = optimize::run(Pass::LowerMatches, file.expression, config.overflow);
+        }
+        let value = match rinha::run_ast(file, &config) {
+            Ok(outcome) => Ok(outcome.value.to_string()),
+            Err(RinhaError::Runtime(error)) => {
+                let location = &error.location;
+                let term = &source[location.start as usize..location.end as usize];
+                Err((error.kind, term.to_string()))
+            }
+            Err(other) => panic!("{source} failed with {other:?}"),
+        };
+        (String::from_utf8(output.take()).unwrap(), value)
+    };
+    let tree = run(Backend::Tree, false);
+    assert_eq!(tree, run(Backend::Tree, true), "once lowered: {source}");
+    assert_eq!(tree, run(Backend::Vm, false), "in the bytecode: {source}");
+    assert_eq!(tree, run(Backend::Jit, false), "in machine code: {source}");
+    tree
+}
+
+fn value(source: &str) -> String {
+    ran(source).1.unwrap()
+}
+
+fn error(source: &str) -> (ErrorKind, String) {
+    ran(source).1.unwrap_err()
+}
+
+#[test]
+fn the_first_arm_that_fits_is_taken() {
+    let program = r#"let describe = fn (p) => {
+  match (p) {
+    (0, 0) => "origin",
+    (0, y) => "on the y axis at " + y,
+    (x, 0) if x > 0 => "right of the origin",
+    (x, _) if x < 0 => "left",
+    _ => "elsewhere",
+  }
+};
+let _ = print(describe((0, 0)));
+let _ = print(describe((0, 5)));
+let _ = print(describe((3, 0)));
+let _ = print(match (((1, 2), 3)) { ((a, b), _) => "pair of " + (a + b) });
+let _ = print(describe((0 - 1, 2)));
+let _ = print(describe((1, 2)));
+let _ = print(match ("b") { "a" => 1, "b" => 2, _ => 3 });
+match (true) { false => "no", true => "yes" }"#;
+    let (printed, value) = ran(program);
+    assert_eq!(
+        printed,
+        "origin\non the y axis at 5\nright of the origin\npair of 3\nleft\nelsewhere\n2\n"
+    );
+    assert_eq!(value.unwrap(), "yes");
+}
+
+#[test]
+fn the_bodies_are_in_tail_position() {
+    let program = "let count = fn (n) => { match (n) { 0 => \"done\", _ => count(n - 1) } };
+count(100000)";
+    assert_eq!(value(program), "done");
+    let fib = "let fib = fn (n) => { match (n) { 0 => 0, 1 => 1, n => fib(n - 1) + fib(n - 2) } };
+fib(20)";
+    assert_eq!(value(fib), "6765");
+}
+
+#[test]
+fn the_variables_of_the_patterns_are_scoped_to_their_arm() {
+    assert_eq!(
+        value("let x = 1; let y = match (2) { x => x * 10 }; x + y"),
+        "21"
+    );
+    assert_eq!(
+        value("let f = match ((1, 2)) { (a, b) => fn (c) => { a + b + c } }; f(3)"),
+        "6"
+    );
+    assert_eq!(
+        value("let x = 5; match ((1, 2)) { (x, 0) => x, (x, y) if x > y => x, _ => x }"),
+        "5"
+    );
+}
+
+/// The literals are compared before the variables are bound, like the
+/// `if` of the lowering do.
+#[test]
+fn the_failures_are_the_ones_of_the_steps_taken() {
+    assert_eq!(
+        error("let b = true;\nmatch (b) { false => 1 }"),
+        (ErrorKind::NoMatch, "match (b) { false => 1 }".into())
+    );
+    assert_eq!(
+        error("match (5) { (x, _) => x, _ => 0 }"),
+        (ErrorKind::NotATuple("first"), "(x, _)".into())
+    );
+    assert_eq!(
+        error("match ((1, 5)) { (_, (a, b)) => a, _ => 0 }"),
+        (ErrorKind::NotATuple("first"), "(a, b)".into())
+    );
+    assert_eq!(value("match ((5, 2)) { ((a, b), 1) => a, _ => 0 }"), "0");
+    assert_eq!(
+        error("match (5) { (_, _) => 1, _ => 2 }"),
+        (ErrorKind::NotATuple("first"), "(_, _)".into())
+    );
+    let (kind, term) = error("match (1) { \"a\" => 1, _ => 2 }");
+    assert!(matches!(kind, ErrorKind::TypeMismatch(_)), "{kind:?}");
+    assert_eq!(term, "\"a\"");
+    assert_eq!(
+        error("match (1) { x if x => 1 }"),
+        (ErrorKind::ConditionNotBool, "x".into())
+    );
+    assert_eq!(
+        error("match ((1, 2)) { (1, x) if x + 1 => 1 }"),
+        (ErrorKind::ConditionNotBool, "x + 1".into())
+    );
+}
+
+#[test]
+fn the_matches_are_formatted_and_read_back() {
+    let source = "let f = fn (p) => { match (p) { (0, _) => { let z = 1; z }, (x, y) if x > y => x, _ => 0 } }; match (f((3, 2))) {}";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let formatted = rinha::format::to_source(&file.expression);
+    assert!(
+        formatted.contains("  match (p) {\n    (0, _) => {\n      let z = 1;\n      z\n    },\n    (x, y) if x > y => x,\n    _ => 0,\n  }"),
+        "{formatted}"
+    );
+    assert!(formatted.ends_with("match (f((3, 2))) {}"), "{formatted}");
+    assert_eq!(ran(&formatted), ran(source));
+
+    let json = serde_json::to_string(&file).unwrap();
+    let read: rinha::ast::File = serde_json::from_str(&json).unwrap();
+    assert_eq!(rinha::format::to_source(&read.expression), formatted);
+
+    let guard = path::select(&file, "expression.value.value.arms.1.guard").unwrap();
+    assert_eq!(rinha::format::to_source(guard), "x > y");
+    let error = path::select(&file, "expression.value.value.arms.3.body").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`expression.value.value` has 3 arms, there's no `3`"
+    );
+}
+
+#[test]
+fn the_analyses_know_the_patterns() {
+    let source = "let f = fn (p) => { match (p) { (x, 0) => x + 1, (_, y) => y } };
+let g = match (1) { \"a\" => 1, n if n => 2, _ => \"c\" };
+let _ = match (3) { (a, b) => 1, unused => 2 };
+f((1, 2))";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let mut diagnostics = Diagnostics::new();
+    let signatures = types::infer(&file, &mut diagnostics);
+    assert_eq!(signatures[0].to_string(), "f: fn ((Int, Int)) -> Int");
+    let messages: Vec<String> = diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "expected Int, but found Str",
+            "the guard must be a Bool, but it's Int",
+            "the arms have different types, Int and Str",
+            "expected Int, but found ('a, 'b)",
+        ]
+    );
+
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    let messages: Vec<String> = diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "`g` is never used",
+            "the pattern is Str, but the value is Int",
+            "the guard must be a Bool, but it's Int",
+            "the pattern is Tuple, but the value is Int",
+            "`a` is never used",
+            "`b` is never used",
+            "`unused` is never used",
+        ]
+    );
+}
@@ -0,0 +1,84 @@
+//! The memo keeps at most `--memo-capacity` entries, dropping the ones used
+//! the longest ago, so the runs making many distinct calls use bounded
+//! memory and still get the same values.
+
+mod common;
+
+use common::Scratch;
+use rinha::{memo::Lru, RunConfig};
+use std::{fs, process::Command};
+
+const FIB: &str = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+fib(25)";
+
+#[test]
+fn the_oldest_entries_make_room() {
+    let mut memo = Lru::new(3);
+    for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+        assert_eq!(memo.insert(key, value), None);
+    }
+    // Reading an entry, or writing it again, makes it the newest.
+    assert_eq!(memo.get("a"), Some(&1));
+    assert_eq!(memo.insert("b", 20), None);
+    assert_eq!(memo.insert("d", 4), Some(("c", 3)));
+    assert_eq!(memo.insert("e", 5), Some(("a", 1)));
+    assert_eq!(memo.get("b"), Some(&20));
+    assert_eq!(memo.len(), 3);
+    let mut keys: Vec<_> = memo.keys().copied().collect();
+    keys.sort();
+    assert_eq!(keys, ["b", "d", "e"]);
+
+    let mut nothing = Lru::new(0);
+    assert_eq!(nothing.insert("a", 1), None);
+    assert!(nothing.is_empty());
+}
+
+#[test]
+fn the_values_stay_the_same_with_fewer_entries() {
+    let run = |memo_capacity| {
+        let config = RunConfig {
+            memo_capacity,
+            ..RunConfig::default()
+        };
+        rinha::run_source(FIB, &config).unwrap()
+    };
+    let unbounded = run(usize::MAX);
+    assert_eq!(unbounded.value.to_string(), "75025");
+    assert_eq!(unbounded.stats.memo_evictions, 0);
+
+    // `fib(n - 1)` reads `fib(n - 3)` after writing `fib(n - 2)`, the
+    // three entries used last are all `fib(n)` needs.
+    let three = run(3);
+    assert_eq!(three.value.to_string(), "75025");
+    assert_eq!(three.stats.calls, unbounded.stats.calls);
+    assert_eq!(three.stats.memo_hits, unbounded.stats.memo_hits);
+    assert!(three.stats.memo_evictions > 0);
+
+    let two = run(2);
+    assert_eq!(two.value.to_string(), "75025");
+    assert!(two.stats.calls > three.stats.calls);
+
+    let none = run(0);
+    assert_eq!(none.value.to_string(), "75025");
+    assert_eq!(none.stats.memo_hits, 0);
+    assert_eq!(none.stats.calls, 242785);
+    // Nothing is kept, so nothing is evicted either.
+    assert_eq!(none.stats.memo_evictions, 0);
+}
+
+#[test]
+fn the_summary_counts_the_evictions() {
+    let dir = Scratch::new("memo-capacity");
+    let program = dir.file("main.rinha", FIB);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--memo-capacity", "1", "--summary"])
+        .arg(dir.join("summary.json"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let summary = fs::read_to_string(dir.join("summary.json")).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    assert!(summary["memo_evictions"].as_u64().unwrap() > 0, "{summary}");
+}
@@ -0,0 +1,217 @@
+//! The memo only serves a call the result it would have computed, and
+//! never skips what a call prints.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    ast::Location,
+    interpreter::{Primitive, Stats},
+    memo::{FunctionId, MemoKey},
+    RunConfig,
+};
+use std::{
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    process::Command,
+    rc::Rc,
+};
+
+/// What the program printed and its counters.
+fn run(source: &str, memoize: bool) -> (String, Stats) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        memoize,
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(source, &config).unwrap();
+    (String::from_utf8(output.take()).unwrap(), outcome.stats)
+}
+
+#[test]
+fn closures_passed_as_arguments_are_told_apart() {
+    let program = "let apply = fn (f, x) => { f(x) };
+let double = fn (x) => { x * 2 };
+let square = fn (x) => { x * x };
+let _ = print(apply(double, 3));
+print(apply(square, 3))";
+    assert_eq!(run(program, true).0, "6\n9\n");
+}
+
+#[test]
+fn closures_of_one_definition_are_told_apart_by_what_they_captured() {
+    let program = "let add = fn (n) => { fn (x) => { x + n } };
+let apply = fn (f, x) => { f(x) };
+let _ = print(apply(add(1), 10));
+print(apply(add(2), 10))";
+    assert_eq!(run(program, true).0, "11\n12\n");
+
+    let anonymous = "let apply = fn (f, x) => { f(x) };
+let _ = print(apply(fn (x) => { x + 1 }, 10));
+print(apply(fn (x) => { x + 2 }, 10))";
+    assert_eq!(run(anonymous, true).0, "11\n12\n");
+}
+
+#[test]
+fn functions_that_share_a_name_are_told_apart() {
+    let program = "let f = fn (x) => { x + 1 };
+let _ = print(f(1));
+let f = fn (x) => { x * 10 };
+print(f(1))";
+    assert_eq!(run(program, true).0, "2\n10\n");
+}
+
+#[test]
+fn tuples_and_values_of_other_types_are_told_apart() {
+    let program = "let show = fn (x) => { x };
+let _ = print(show((1, 2)));
+let _ = print(show((1, (2, 3))));
+let _ = print(show(\"1\"));
+print(show(1) + 1)";
+    assert_eq!(run(program, true).0, "(1, 2)\n(1, (2, 3))\n1\n2\n");
+}
+
+fn key(name: &str, start: usize, arguments: Vec<Primitive>) -> MemoKey {
+    MemoKey {
+        function: FunctionId {
+            name: name.into(),
+            location: Location::new(start, start + 1, "main.rinha"),
+        },
+        captured: Box::new([]),
+        arguments: arguments.into(),
+    }
+}
+
+fn hash(key: &MemoKey) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn memo_keys_are_equal_when_their_values_are() {
+    let tuple = || {
+        Primitive::Tuple([
+            Box::new(Primitive::Int(1)),
+            Box::new(Primitive::Str("a".into())),
+        ])
+    };
+    let one = key("f", 0, vec![tuple(), Primitive::Float(0.5)]);
+    let other = key("f", 0, vec![tuple(), Primitive::Float(0.5)]);
+    assert_eq!(one, other);
+    assert_eq!(hash(&one), hash(&other));
+    assert_eq!(one.digest(), other.digest());
+    assert_eq!(one.to_string(), "f@main.rinha:0((1, a), 0.5)");
+}
+
+#[test]
+fn memo_keys_tell_apart_what_strings_would_not() {
+    // The functions with no name are told apart by where they are.
+    assert_ne!(key("", 0, vec![]), key("", 5, vec![]));
+    // The values of other types, and the floats `==` can't tell apart.
+    assert_ne!(
+        key("f", 0, vec![Primitive::Int(1)]),
+        key("f", 0, vec![Primitive::Float(1.0)])
+    );
+    assert_ne!(
+        key("f", 0, vec![Primitive::Int(1)]),
+        key("f", 0, vec![Primitive::Str("1".into())])
+    );
+    assert_ne!(
+        key("f", 0, vec![Primitive::Float(0.0)]),
+        key("f", 0, vec![Primitive::Float(-0.0)])
+    );
+    // The arguments aren't run together.
+    assert_ne!(
+        key("f", 0, vec![Primitive::Str("a,b".into())]),
+        key(
+            "f",
+            0,
+            vec![Primitive::Str("a".into()), Primitive::Str("b".into())]
+        )
+    );
+}
+
+#[test]
+fn floats_and_integers_have_their_own_entries() {
+    let program = "let half = fn (x) => { x / 2 };
+let _ = print(half(1));
+print(half(1.0))";
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        extensions: true,
+        ..RunConfig::default()
+    };
+    rinha::run_source(program, &config).unwrap();
+    assert_eq!(String::from_utf8(output.take()).unwrap(), "0\n0.5\n");
+}
+
+#[test]
+fn calls_that_print_print_every_time() {
+    let program = "let log = fn (x) => { print(x) };
+let _ = log(1);
+log(1)";
+    let (printed, stats) = run(program, true);
+    assert_eq!(printed, "1\n1\n");
+    assert_eq!(stats.memo_hits, 0);
+
+    // Nor when the print is in a call they make.
+    let nested = "let log = fn (x) => { print(x) };
+let twice = fn (x) => { log(x) + 1 };
+let _ = twice(1);
+twice(1)";
+    assert_eq!(run(nested, true).0, "1\n1\n");
+}
+
+#[test]
+fn the_calls_of_a_printing_function_that_didnt_print_are_memoized() {
+    // Purity is decided by each call, a function that only prints for some
+    // arguments is memoized for the others.
+    let program = "let show = fn (x) => { if (x == 0) { print(x) } else { x } };
+let _ = show(0);
+let _ = show(0);
+let _ = show(1);
+show(1)";
+    let (printed, stats) = run(program, true);
+    assert_eq!(printed, "0\n0\n");
+    assert_eq!(stats.memo_hits, 1);
+}
+
+#[test]
+fn fib_is_still_memoized() {
+    let program = "let fib = fn (n) => {
+  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};
+print(fib(32))";
+    let (printed, stats) = run(program, true);
+    assert_eq!(printed, "2178309\n");
+    // One call runs for each `n`, the others are served by the memo.
+    assert_eq!(stats.calls - stats.memo_hits, 33);
+    assert_eq!(stats.memo_hits, 30);
+}
+
+#[test]
+fn no_memo_runs_every_call() {
+    let dir = Scratch::new("memo-soundness");
+    let program = dir.file(
+        "fib.rinha",
+        "let fib = fn (n) => {\n  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }\n};\nprint(fib(15))",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--no-memo", "--coverage"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(output.stdout, b"610\n");
+    // fib(15) makes 1973 calls when none is served by the memo.
+    assert!(
+        stderr.contains("fib: 1973 executed, 0 served by the memo"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,70 @@
+//! The closures and the tuples a program drops are freed as it runs: the
+//! scopes are shared by reference and without references nothing the
+//! programs make can point back at itself, so counting the references
+//! frees them all. Every allocation of this test is counted, so it's the
+//! only test of its file.
+
+use rinha::{interpreter::Value, RunConfig};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Makes a closure and a tuple holding it at each of `n` iterations, the
+/// ones of the iteration before are dropped.
+fn program(n: u32) -> String {
+    format!(
+        "let loop = fn (n, acc) => {{
+  if (n == 0) {{ first(acc) }} else {{
+    let add = fn (x) => {{ x + 1 }};
+    loop(n - 1, (add(first(acc)), add))
+  }}
+}};
+loop({n}, (0, 0))"
+    )
+}
+
+/// The most bytes the run had allocated at once.
+fn peak(n: u32) -> usize {
+    let config = RunConfig {
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let source = program(n);
+    let before = LIVE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let value = rinha::run_source(&source, &config).unwrap().value;
+    assert_eq!(value, Value::Int(n.into()));
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn the_dropped_closures_and_tuples_are_freed() {
+    let short = peak(1_000);
+    let long = peak(100_000);
+    assert!(
+        long < short * 2,
+        "{short} bytes for 1000 iterations, {long} for 100000"
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::Scratch;
+use rinha::{
+    ast::Metadata,
+    input::{self, InputError},
+    parser,
+};
+use std::{fs, process::Command};
+
+fn parsed_json() -> serde_json::Value {
+    let file = parser::parse_or_report("main.rinha", "print(1)").unwrap();
+    serde_json::to_value(file).unwrap()
+}
+
+/// The AST of `print(1)`, claiming to be written by `version`.
+fn written_by(version: &str) -> String {
+    let mut json = parsed_json();
+    json["metadata"]["parser_version"] = version.into();
+    json.to_string()
+}
+
+#[test]
+fn parsing_records_the_parser_version() {
+    let json = parsed_json();
+    assert_eq!(
+        json["metadata"]["parser_version"],
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[test]
+fn the_metadata_is_read_back() {
+    let json = parsed_json().to_string();
+    let file = input::parse_ast("main.json", &json, |_| panic!("it's not newer")).unwrap();
+    assert_eq!(file.metadata, Some(Metadata::current()));
+}
+
+#[test]
+fn asts_without_metadata_are_read() {
+    let json = fs::read_to_string("files/fib.json").unwrap();
+    let file = input::parse_ast("fib.json", &json, |_| panic!("it's not newer")).unwrap();
+    assert_eq!(file.metadata, None);
+
+    let reserialized = serde_json::to_value(&file).unwrap();
+    assert!(reserialized.get("metadata").is_none());
+}
+
+#[test]
+fn only_later_versions_are_newer() {
+    let newer = |version: &str| {
+        Metadata {
+            parser_version: version.into(),
+        }
+        .is_newer()
+    };
+    assert!(newer("99.0.0"));
+    assert!(newer(&format!("{}.1", env!("CARGO_PKG_VERSION"))));
+    assert!(!newer(env!("CARGO_PKG_VERSION")));
+    assert!(!newer("0.0.1"));
+    assert!(!newer("next"));
+}
+
+#[test]
+fn newer_asts_run_with_a_warning() {
+    let dir = Scratch::new("metadata");
+    let program = dir.file("newer.json", written_by("99.0.0"));
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"1\n");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let warning = format!(
+        "written by the parser of rinha 99.0.0, newer than this rinha {}",
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(stderr.contains(&warning), "{stderr}");
+}
+
+#[test]
+fn newer_asts_that_cant_be_read_name_both_versions() {
+    let mut json: serde_json::Value = serde_json::from_str(&written_by("99.0.0")).unwrap();
+    json["expression"]["kind"] = "Loop".into();
+    let json = json.to_string();
+
+    let error = input::parse_ast("newer.json", &json, |_| {}).unwrap_err();
+    let InputError::InvalidAst { help, .. } = &error else {
+        panic!("{error:?}");
+    };
+    assert!(help.as_ref().unwrap().contains("rinha 99.0.0"));
+
+    let dir = Scratch::new("metadata");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg(dir.file("unreadable.json", &json))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("not a valid JSON AST"), "{stderr}");
+}
@@ -0,0 +1,50 @@
+use rinha::{
+    interpreter::{ErrorKind, Primitive},
+    RinhaError, RunConfig,
+};
+
+const ANONYMOUS: &str = "let apply = fn (f) => { f(1, 2) };\napply(fn (x) => { x })";
+
+#[test]
+fn arity_errors_name_anonymous_functions() {
+    let result = rinha::run_source(ANONYMOUS, &RunConfig::default());
+    let Err(RinhaError::Runtime(error)) = result else {
+        panic!("expected a runtime error");
+    };
+
+    assert_eq!(
+        error.kind,
+        ErrorKind::ArityMismatch {
+            name: "anon#2@main.rinha:2:7".into(),
+            expected: 1,
+            given: 2,
+        }
+    );
+}
+
+#[test]
+fn asts_without_source_only_get_the_ordinal() {
+    let file = rinha::parser::parse_or_report("main.rinha", ANONYMOUS).unwrap();
+    let Err(RinhaError::Runtime(error)) = rinha::run_ast(file, &RunConfig::default()) else {
+        panic!("expected a runtime error");
+    };
+
+    let ErrorKind::ArityMismatch { name, .. } = error.kind else {
+        panic!("expected an arity mismatch");
+    };
+    assert_eq!(name, "anon#2@main.rinha");
+}
+
+#[test]
+fn let_bound_functions_keep_their_binding_name() {
+    let outcome = rinha::run_source(
+        "let add = fn (a, b) => { a + b };\nlet plus = add;\nplus",
+        &RunConfig::default(),
+    )
+    .unwrap();
+
+    let Primitive::Function { name, .. } = outcome.value else {
+        panic!("expected a function");
+    };
+    assert_eq!(name, "add");
+}
@@ -0,0 +1,63 @@
+//! The notebook of `examples/notebook.rs`, built on the library API alone.
+
+mod common;
+
+use common::Scratch;
+use std::{env, path::PathBuf, process::Command};
+
+/// The example, built next to the tests. `cargo test` builds it too, but
+/// not when only some tests are asked for.
+fn notebook() -> PathBuf {
+    let mut build = Command::new(env!("CARGO"));
+    build.args(["build", "--quiet", "--example", "notebook"]);
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    let built = build.status().unwrap();
+    assert!(built.success());
+    let deps = env::current_exe().unwrap();
+    let target = deps.parent().unwrap().parent().unwrap();
+    target.join("examples").join("notebook")
+}
+
+#[test]
+fn the_cells_share_their_bindings_and_a_failing_one_doesnt_stop_the_rest() {
+    let output = Command::new(notebook())
+        .arg("files/notebook.txt")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let transcript = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        transcript,
+        "[1]
+squares are ready
+=> 16
+[2]
+Int can only be sum with Int and Str
+    Diagnostic severity: error
+Begin snippet for files/notebook.txt#2 starting at line 1, column 1
+
+snippet line 1: square(2) + true
+    label at line 1, columns 1 to 16: here
+diagnostic code: rinha::runtime_error
+[3]
+25
+=> 25
+"
+    );
+}
+
+#[test]
+fn the_functions_of_later_cells_are_named_by_their_binding() {
+    let dir = Scratch::new("notebook");
+    let cells = dir.file(
+        "cells.txt",
+        "let inc = fn (x) => { x + 1 };\ninc(1)\n%%cell\nlet add = fn (a, b) => { a + b };\nadd(inc(1))\n",
+    );
+
+    let output = Command::new(notebook()).arg(&cells).output().unwrap();
+    let transcript = String::from_utf8(output.stdout).unwrap();
+    assert!(transcript.starts_with("[1]\n=> 2\n[2]\n"), "{transcript}");
+    assert!(transcript.contains("`add`"), "{transcript}");
+}
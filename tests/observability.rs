@@ -0,0 +1,120 @@
+//! Observing a run never changes it: with any of the flags and hooks that
+//! report on the run, the example programs print the same, end with the
+//! same value and count the same calls as without them.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::{PrintThresholds, Stats},
+    RunConfig,
+};
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    thread,
+    time::Duration,
+};
+
+/// The recursion of the examples doesn't fit the default stack of the
+/// test threads in debug builds.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+const EXAMPLES: &[&str] = &["combination", "easy", "fib", "sum", "sum2", "sum3", "tuple"];
+
+/// The flags that report on the run, each of them alone.
+const FLAGS: &[&[&str]] = &[
+    &["--coverage"],
+    &["--events", "ndjson", "--events-interval", "1"],
+    &["--warn-print-depth", "0", "--warn-print-bytes", "0"],
+    &["--tee", "TEE"],
+    &["--ab-memo"],
+    &["--time", "--stats"],
+];
+
+/// Runs the second time without the memo, `fib(46)` would take ages.
+const SLOW_WITHOUT_MEMO: &[&str] = &["fib"];
+
+/// What the program printed, its value and its counters.
+fn observed(source: &str, config: RunConfig) -> (String, String, Stats) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        ..config
+    };
+    let outcome = rinha::run_source(source, &config).unwrap();
+    let printed = String::from_utf8(output.take()).unwrap();
+    (printed, outcome.value.to_string(), outcome.stats)
+}
+
+#[test]
+fn the_hooks_dont_change_the_runs() {
+    for name in EXAMPLES {
+        thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+                let plain = observed(&source, RunConfig::default());
+                let observing = RunConfig {
+                    tees: vec![Rc::new(RefCell::new(io::sink()))],
+                    print_thresholds: PrintThresholds {
+                        depth: Some(0),
+                        bytes: Some(0),
+                    },
+                    on_print_warning: Some(Rc::new(|_| {})),
+                    progress_interval: Duration::ZERO,
+                    on_progress: Some(Rc::new(|_| {})),
+                    on_sequential_pair: Some(Rc::new(|_| {})),
+                    ..RunConfig::default()
+                };
+                assert_eq!(observed(&source, observing), plain, "{name}");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
+
+/// The standard output of the run, with the value printed at the end, and
+/// the counters of its summary.
+fn run(program: &Path, flags: &[&str], dir: &Path) -> (String, serde_json::Value) {
+    let summary = dir.join("summary.json");
+    let tee = dir.join("tee.txt");
+    let flags = flags.iter().map(|flag| match *flag {
+        "TEE" => tee.as_os_str(),
+        flag => flag.as_ref(),
+    });
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--print-result", "--summary"])
+        .arg(&summary)
+        .args(flags)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let mut summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary).unwrap()).unwrap();
+    // The flags asked for are recorded, the other fields must match.
+    summary["semantic_flags"].take();
+    (String::from_utf8(output.stdout).unwrap(), summary)
+}
+
+#[test]
+fn the_flags_dont_change_the_runs() {
+    let dir = Scratch::new("observability");
+
+    for name in EXAMPLES {
+        let program = PathBuf::from(format!("files/{name}.rinha"));
+        let plain = run(&program, &[], &dir);
+        for flags in FLAGS {
+            if flags == &["--ab-memo"] && SLOW_WITHOUT_MEMO.contains(name) {
+                continue;
+            }
+            assert_eq!(run(&program, flags, &dir), plain, "{name} with {flags:?}");
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! The conformance table of the binary operators.
+//!
+//! Every operator is applied to every pair of operand types, through the
+//! same [`BinaryOp::apply`] the interpreter uses, under every [`Overflow`]
+//! mode, and the outcomes are compared with `tests/snapshots/operators.tsv`.
+//! The last rows are the arithmetic that overflows. The table documents the
+//! semantics, and it's what other engines must match. When a change is
+//! intended, bless the new table with `RINHA_BLESS=1 cargo test`.
+
+use rinha::{
+    ast::BinaryOp,
+    interpreter::{Overflow, Primitive},
+    runtime::{record::Record, reference::Ref},
+    RunConfig,
+};
+use std::fs;
+
+const SNAPSHOT: &str = "tests/snapshots/operators.tsv";
+
+/// The columns of the outcomes, the default mode first.
+const MODES: [(&str, Overflow); 4] = [
+    ("widen", Overflow::Widen),
+    ("trap", Overflow::Trap),
+    ("wrap32", Overflow::Wrap32),
+    ("wrap64", Overflow::Wrap64),
+];
+
+/// Operands whose results don't fit in 32 or in 64 bits, then the
+/// divisions by zero.
+const OVERFLOWING: [(usize, i64, i64); 11] = [
+    (0, i32::MAX as i64, 1),
+    (1, i32::MIN as i64, 1),
+    (2, i32::MAX as i64, 2),
+    (3, i32::MIN as i64, -1),
+    (0, i64::MAX, 1),
+    (1, i64::MIN, 1),
+    (2, i64::MAX, 2),
+    (3, i64::MIN, -1),
+    (4, -7, 3),
+    (3, 1, 0),
+    (4, 1, 0),
+];
+
+const OPERATORS: [(&str, BinaryOp); 13] = [
+    ("+", BinaryOp::Add),
+    ("-", BinaryOp::Sub),
+    ("*", BinaryOp::Mul),
+    ("/", BinaryOp::Div),
+    ("%", BinaryOp::Rem),
+    ("==", BinaryOp::Eq),
+    ("!=", BinaryOp::Neq),
+    ("<", BinaryOp::Lt),
+    (">", BinaryOp::Gt),
+    ("<=", BinaryOp::Lte),
+    (">=", BinaryOp::Gte),
+    ("&&", BinaryOp::And),
+    ("||", BinaryOp::Or),
+];
+
+/// One value of each type, the left and the right operands differ so the
+/// table shows which side is which. The floats, the lists, the records and
+/// the references are extensions, the table has them anyway. The integer past 64 bits is
+/// only made by [`Overflow::Widen`], the other modes fit it like any exact
+/// result.
+fn samples(right: bool) -> Vec<(&'static str, Primitive)> {
+    let closure = rinha::run_source("fn (x) => { x }", &RunConfig::default())
+        .unwrap()
+        .value;
+    let (int, float, str, bool) = match right {
+        false => (7, 7.5, "a", true),
+        true => (2, 2.0, "b", false),
+    };
+    vec![
+        ("Int", Primitive::Int(int)),
+        ("BigInt", big(int)),
+        ("Float", Primitive::Float(float)),
+        ("Str", Primitive::Str(str.into())),
+        ("Bool", Primitive::Bool(bool)),
+        (
+            "Tuple",
+            Primitive::Tuple([Box::new(Primitive::Int(1)), Box::new(Primitive::Int(2))]),
+        ),
+        (
+            "List",
+            Primitive::List([Primitive::Int(1), Primitive::Int(2)].into_iter().collect()),
+        ),
+        (
+            "Record",
+            Primitive::Record(Record::new(vec![("x".into(), Primitive::Int(1))]).unwrap()),
+        ),
+        ("Ref", Primitive::Ref(Ref::new(Primitive::Int(1)))),
+        ("Function", closure),
+        ("Unit", Primitive::None),
+    ]
+}
+
+/// `2^64 + n`, past 64 bits.
+fn big(n: i64) -> Primitive {
+    let two_to_the_64 = Primitive::Int(1 << 62);
+    let big = BinaryOp::Mul.apply(two_to_the_64, Primitive::Int(4), Overflow::Widen);
+    BinaryOp::Add
+        .apply(big.unwrap(), Primitive::Int(n), Overflow::Widen)
+        .unwrap()
+}
+
+fn outcome(result: Result<Primitive, rinha::interpreter::ErrorKind>) -> String {
+    match result {
+        Ok(value) => {
+            let kind = match value {
+                Primitive::Int(_) => "Int",
+                Primitive::BigInt(_) => "BigInt",
+                Primitive::Float(_) => "Float",
+                Primitive::Str(_) => "Str",
+                Primitive::Bool(_) => "Bool",
+                Primitive::Tuple(_) => "Tuple",
+                Primitive::List(_) => "List",
+                Primitive::Record(_) => "Record",
+                Primitive::Ref(_) => "Ref",
+                Primitive::Function { .. } => "Function",
+                Primitive::None => "Unit",
+                Primitive::Thunk(_) => "Thunk",
+            };
+            format!("{kind} {value}")
+        }
+        Err(error) => format!("error: {error}"),
+    }
+}
+
+fn row(symbol: &str, lhs: &str, rhs: &str, apply: impl Fn(Overflow) -> String) -> String {
+    let outcomes: Vec<String> = MODES.iter().map(|(_, mode)| apply(*mode)).collect();
+    format!("{symbol}\t{lhs}\t{rhs}\t{}\n", outcomes.join("\t"))
+}
+
+fn table() -> String {
+    let modes: Vec<&str> = MODES.iter().map(|(name, _)| *name).collect();
+    let mut table = format!("op\tlhs\trhs\t{}\n", modes.join("\t"));
+
+    let (lefts, rights) = (samples(false), samples(true));
+    for (symbol, op) in &OPERATORS {
+        for (lhs_type, lhs) in &lefts {
+            for (rhs_type, rhs) in &rights {
+                table.push_str(&row(symbol, lhs_type, rhs_type, |mode| {
+                    outcome(op.apply(lhs.clone(), rhs.clone(), mode))
+                }));
+            }
+        }
+    }
+
+    for (index, lhs, rhs) in OVERFLOWING {
+        let (symbol, op) = &OPERATORS[index];
+        table.push_str(&row(symbol, &lhs.to_string(), &rhs.to_string(), |mode| {
+            outcome(op.apply(Primitive::Int(lhs), Primitive::Int(rhs), mode))
+        }));
+    }
+    table
+}
+
+#[test]
+fn operators_match_the_conformance_table() {
+    let current = table();
+    if std::env::var_os("RINHA_BLESS").is_some() {
+        fs::write(SNAPSHOT, &current).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(SNAPSHOT).unwrap_or_default();
+    let drifted: Vec<String> = current
+        .lines()
+        .zip(snapshot.lines())
+        .filter(|(current, snapshot)| current != snapshot)
+        .map(|(current, snapshot)| format!("- {snapshot}\n+ {current}"))
+        .collect();
+    assert!(
+        drifted.is_empty() && current.lines().count() == snapshot.lines().count(),
+        "the semantics of the operators changed, bless them with `RINHA_BLESS=1 cargo test` if it's intended:\n{}",
+        drifted.join("\n")
+    );
+}
@@ -0,0 +1,140 @@
+//! The optimizer rewrites the programs into simpler ones that print the
+//! same, fail the same way and end with the same value.
+
+use rinha::{
+    ast::Term,
+    format,
+    interpreter::Overflow,
+    optimize::{self, OptLevel},
+    parser, Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, fs, process::Command, rc::Rc};
+
+const EXAMPLES: &[&str] = &["combination", "easy", "sum", "sum2", "sum3", "tuple"];
+
+const LEVELS: [OptLevel; 3] = [OptLevel::O0, OptLevel::O1, OptLevel::O2];
+
+fn optimized(source: &str, level: OptLevel, overflow: Overflow) -> String {
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    format::to_source(&optimize::optimize(file.expression, level, overflow))
+}
+
+/// What the program printed and how it ended, with the terms it evaluated.
+fn run(source: &str, opt_level: OptLevel) -> (String, Result<String, String>, u64) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        backend: Backend::Vm,
+        output: Some(output.clone()),
+        opt_level,
+        ..RunConfig::default()
+    };
+    let (ended, terms) = match rinha::run_source(source, &config) {
+        Ok(outcome) => (Ok(outcome.value.to_string()), outcome.stats.terms),
+        Err(RinhaError::Runtime(error)) => (Err(error.to_string()), 0),
+        Err(other) => panic!("{other:?}"),
+    };
+    (String::from_utf8(output.take()).unwrap(), ended, terms)
+}
+
+#[test]
+fn the_operators_on_constants_are_folded() {
+    let fold = |source| optimized(source, OptLevel::O1, Overflow::Trap);
+    assert_eq!(fold("print(1 + 2 * 3)"), "print(7)");
+    assert_eq!(fold("\"a\" + (1 + 1) + \"b\""), "\"a2b\"");
+    assert_eq!(fold("(1 < 2) && (\"a\" == \"a\")"), "true");
+    assert_eq!(fold("false && f(1)"), "false");
+    assert_eq!(fold("let x = 2; x * (3 - 1)"), "let x = 2;\nx * 2");
+    // Failing when run, or past what a literal holds.
+    assert_eq!(fold("1 / 0"), "1 / 0");
+    assert_eq!(fold("2147483647 + 1"), "2147483647 + 1");
+    assert_eq!(fold("true + 1"), "true + 1");
+
+    let file = parser::parse_or_report("main.rinha", "2147483647 + 1").unwrap();
+    match optimize::optimize(file.expression, OptLevel::O1, Overflow::Wrap32) {
+        Term::Int(int) => assert_eq!(int.value, i32::MIN),
+        other => panic!("expected the sum to wrap around, got {other:?}"),
+    }
+}
+
+#[test]
+fn the_branches_not_taken_are_pruned() {
+    let prune = |source| optimized(source, OptLevel::O1, Overflow::Trap);
+    assert_eq!(
+        prune("if (1 < 2) { print(1) } else { print(2) }"),
+        "print(1)"
+    );
+    assert_eq!(prune("if (false) { 1 } else { 2 + 3 }"), "5");
+    assert_eq!(
+        prune("if (1) { 2 } else { 3 }"),
+        "if (1) {\n  2\n} else {\n  3\n}"
+    );
+}
+
+#[test]
+fn the_unused_lets_without_effects_are_dropped() {
+    let drop = |source| optimized(source, OptLevel::O2, Overflow::Trap);
+    assert_eq!(drop("let x = 1; let y = (2, \"a\"); 3"), "3");
+    // Used only by itself, or by a function that's used.
+    assert_eq!(drop("let f = fn (n) => { f(n) }; 1"), "1");
+    assert_eq!(
+        drop("let a = 1; let f = fn () => { a }; f()"),
+        "let a = 1;\nlet f = fn () => {\n  a\n};\nf()"
+    );
+    // Shadowed by a parameter, or by another `let`.
+    assert_eq!(
+        drop("let x = 1; let g = fn (x) => { x }; g(2)"),
+        "let g = fn (x) => {\n  x\n};\ng(2)"
+    );
+    assert_eq!(drop("let x = 1; let x = 2; x"), "let x = 2;\nx");
+    // The values that print, fail or call stay.
+    assert_eq!(drop("let _ = print(1); 2"), "let _ = print(1);\n2");
+    assert_eq!(drop("let y = 1 / 0; 2"), "let y = 1 / 0;\n2");
+    assert_eq!(drop("let z = missing; 2"), "let z = missing;\n2");
+    // Folded into a constant first.
+    assert_eq!(drop("let w = 1 + 1; 2"), "2");
+}
+
+#[test]
+fn every_level_runs_the_examples_the_same() {
+    for name in EXAMPLES {
+        let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+        let (printed, ended, _) = run(&source, OptLevel::O0);
+        for level in LEVELS {
+            let (optimized_printed, optimized_ended, _) = run(&source, level);
+            assert_eq!(optimized_printed, printed, "{name} at {level:?}");
+            assert_eq!(optimized_ended, ended, "{name} at {level:?}");
+        }
+    }
+}
+
+#[test]
+fn the_optimized_programs_evaluate_fewer_terms() {
+    let source = "let unused = (1, 2);
+let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, acc + 60 * 60 * 24) } };
+if (1 + 1 == 2) { print(loop(100, 0)) } else { 0 }";
+    let terms: Vec<u64> = LEVELS
+        .iter()
+        .map(|level| {
+            let (printed, _, terms) = run(source, *level);
+            assert_eq!(printed, "8640000\n");
+            terms
+        })
+        .collect();
+    assert!(terms[0] > terms[1] && terms[1] > terms[2], "{terms:?}");
+}
+
+#[test]
+fn the_binary_optimizes_when_asked_for() {
+    let run = |level: &str| {
+        Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(["--opt-level", level, "files/combination.rinha"])
+            .output()
+            .unwrap()
+    };
+    for level in ["0", "1", "2"] {
+        let output = run(level);
+        assert!(output.status.success(), "{level}");
+        assert_eq!(output.stdout, b"45\n");
+    }
+    assert!(!run("3").status.success());
+}
@@ -0,0 +1,71 @@
+//! `--max-output-bytes` stops the programs that print forever before they
+//! fill the disk.
+
+mod common;
+
+use common::Scratch;
+use rinha::{interpreter::ErrorKind, RinhaError, RunConfig};
+use std::{cell::RefCell, fs, process::Command, rc::Rc};
+
+const LIMIT: u64 = 64 * 1024;
+
+/// Prints lines of a thousand bytes, newline included, forever, few enough calls deep to fit
+/// the stack of debug builds before the limit.
+fn spamming() -> String {
+    let line = "x".repeat(999);
+    format!("let spam = fn (n) => {{\n  let _ = print(\"{line}\");\n  spam(n + 1)\n}};\nspam(0)")
+}
+
+#[test]
+fn the_output_and_the_tees_stop_at_the_limit() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let tee = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        tees: vec![tee.clone()],
+        max_output_bytes: Some(LIMIT),
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = rinha::run_source(&spamming(), &config) else {
+        panic!("the program wasn't stopped");
+    };
+    assert_eq!(error.kind, ErrorKind::OutputLimit(LIMIT));
+    assert_eq!(output.borrow().len() as u64, LIMIT);
+    assert_eq!(*output.borrow(), *tee.borrow());
+}
+
+#[test]
+fn the_run_exits_with_its_own_code_and_summarizes_the_limit() {
+    let dir = Scratch::new("output-limit");
+    let program = dir.file("main.rinha", spamming());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--max-output-bytes")
+        .arg(LIMIT.to_string())
+        .arg("--tee")
+        .arg(dir.join("tee.txt"))
+        .arg("--summary")
+        .arg(dir.join("summary.json"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    // A print writes a thousand bytes, with its newline.
+    let printed = output.stdout.len() as u64;
+    assert!((LIMIT - 1000..=LIMIT).contains(&printed), "{printed}");
+    let tee = fs::read(dir.join("tee.txt")).unwrap();
+    assert_eq!(tee, output.stdout);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("the output reached its limit of 65536 bytes"),
+        "{stderr}"
+    );
+
+    let summary = fs::read_to_string(dir.join("summary.json")).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    assert_eq!(summary["status"], "failed");
+    assert_eq!(summary["exit_code"], 3);
+    assert_eq!(summary["max_output_bytes"], LIMIT);
+}
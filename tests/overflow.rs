@@ -0,0 +1,99 @@
+mod common;
+
+use common::Scratch;
+use rinha::{interpreter::Overflow, RunConfig};
+use std::{cell::RefCell, process::Command, rc::Rc};
+
+/// Squares the largest 32 bits integer, then goes past 64 bits.
+const PROGRAM: &str = "let square = 2147483647 * 2147483647;
+let _ = print(square);
+square * 4";
+
+/// What the program prints and evaluates to, or the error it fails with.
+fn run(overflow: Overflow) -> (String, Result<String, String>) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        overflow,
+        ..RunConfig::default()
+    };
+    let result = match rinha::run_source(PROGRAM, &config) {
+        Ok(outcome) => Ok(outcome.value.to_string()),
+        Err(error) => Err(error.to_string()),
+    };
+    let printed = String::from_utf8(output.take()).unwrap();
+    (printed, result)
+}
+
+#[test]
+fn trap_fails_at_the_first_overflow() {
+    assert_eq!(
+        run(Overflow::Trap),
+        (String::new(), Err("integer overflow in `*`".into()))
+    );
+}
+
+#[test]
+fn wrap32_wraps_every_result_into_32_bits() {
+    assert_eq!(run(Overflow::Wrap32), ("1\n".into(), Ok("4".into())));
+}
+
+#[test]
+fn wrap64_goes_past_32_bits_and_wraps_in_64() {
+    assert_eq!(
+        run(Overflow::Wrap64),
+        ("4611686014132420609\n".into(), Ok("-17179869180".into()))
+    );
+}
+
+#[test]
+fn widen_goes_past_32_bits_and_past_64() {
+    let widened = (
+        "4611686014132420609\n".into(),
+        Ok("18446744056529682436".into()),
+    );
+    assert_eq!(run(Overflow::Widen), widened);
+    assert_eq!(run(Overflow::default()), widened);
+}
+
+#[test]
+fn fib_goes_past_32_bits_without_flags() {
+    let dir = Scratch::new("overflow");
+    let program = dir.file(
+        "main.rinha",
+        "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };\nprint(fib(100))",
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"354224848179261915075\n");
+}
+
+#[test]
+fn spec_wraps_in_32_bits_unless_told_otherwise() {
+    let dir = Scratch::new("overflow");
+    let program = dir.file("main.rinha", "print(2147483647 + 1)");
+
+    let run = |flags: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .arg("run")
+            .args(flags)
+            .arg(&program)
+            .output()
+            .unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stdout).unwrap(),
+        )
+    };
+    assert_eq!(run(&["--spec"]), (Some(0), "-2147483648\n".into()));
+    assert_eq!(run(&[]), (Some(0), "2147483648\n".into()));
+    assert_eq!(run(&["--overflow", "trap"]), (Some(1), String::new()));
+    assert_eq!(
+        run(&["--spec", "--overflow", "widen"]),
+        (Some(0), "2147483648\n".into())
+    );
+}
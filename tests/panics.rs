@@ -0,0 +1,76 @@
+//! The panics of the interpreter are reported as bugs of the interpreter,
+//! after everything the program printed.
+
+mod common;
+
+use common::Scratch;
+use rinha::{run::InternalError, RinhaError, RunConfig};
+use std::{cell::RefCell, fs, process::Command, rc::Rc};
+
+const PROGRAM: &str = "let f = fn (x) => { print(x) };
+let _ = f(1);
+let _ = f(2);
+f(3)";
+
+#[test]
+fn panics_are_internal_errors_with_the_stack() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        panic_after_prints: Some(2),
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Internal(error)) = rinha::run_source(PROGRAM, &config) else {
+        panic!("the panic wasn't caught");
+    };
+    let InternalError {
+        message,
+        panicked_at,
+        node,
+        stack,
+        version,
+        ..
+    } = *error;
+
+    assert_eq!(output.borrow().as_slice(), b"1\n2\n");
+    assert_eq!(message, "deliberate panic, asked for by the tests");
+    assert!(panicked_at.unwrap().starts_with("src/interpreter.rs:"));
+    assert_eq!(node.map(|node| node.offset()), PROGRAM.find("f(3)"));
+    assert_eq!(stack, ["f at main.rinha:4:1"]);
+    assert_eq!(version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn the_report_comes_after_what_was_printed() {
+    let dir = Scratch::new("panics");
+    let program = dir.file("main.rinha", PROGRAM);
+
+    // Both streams go into the same file, so it tells their order.
+    let combined = fs::File::create(dir.join("combined.txt")).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--color=never")
+        .arg("--panic-after-prints-for-tests=2")
+        .arg(&program)
+        .stdout(combined.try_clone().unwrap())
+        .stderr(combined)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(101));
+
+    let combined = fs::read_to_string(dir.join("combined.txt")).unwrap();
+    assert!(combined.starts_with("1\n2\n"), "{combined}");
+    let report = combined.replace('│', " ");
+    let report = report.split_whitespace().collect::<Vec<_>>().join(" ");
+    for expected in [
+        "internal interpreter error: deliberate panic, asked for by the tests",
+        "while evaluating this call",
+        "this is a bug of the interpreter, not of the program",
+        "https://github.com/FabioSeixas/rinha-de-compiler/issues",
+        concat!("version: rinha ", env!("CARGO_PKG_VERSION")),
+        "panicked at: src/interpreter.rs:",
+        "stack, innermost first: f at",
+    ] {
+        assert!(report.contains(expected), "{expected:?} in {report}");
+    }
+}
@@ -0,0 +1,210 @@
+//! `par_pair(f, a, b)`, provided with `--extensions`, calls `f` on two
+//! threads when nothing it can reach prints.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::{ErrorKind, SequentialPair, Stats},
+    RinhaError, RunConfig,
+};
+use std::{
+    cell::RefCell,
+    process::Command,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const FIB: &str = "let fib = fn (n) => {
+  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};";
+
+/// What the program prints, its value and its counters, and the
+/// `par_pair` calls that ran sequentially.
+struct Run {
+    printed: String,
+    result: Result<(String, Stats), RinhaError>,
+    sequential: Vec<SequentialPair>,
+}
+
+fn run(source: &str, config: RunConfig) -> Run {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let sequential = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        extensions: true,
+        on_sequential_pair: Some(Rc::new({
+            let sequential = sequential.clone();
+            move |pair: &SequentialPair| sequential.borrow_mut().push(pair.clone())
+        })),
+        ..config
+    };
+    let result = rinha::run_source(source, &config);
+    Run {
+        printed: String::from_utf8(output.take()).unwrap(),
+        result: result.map(|outcome| (outcome.value.to_string(), outcome.stats)),
+        sequential: sequential.take(),
+    }
+}
+
+#[test]
+fn pure_functions_run_on_two_threads() {
+    let run = run(
+        &format!("{FIB} par_pair(fib, 20, 21)"),
+        RunConfig::default(),
+    );
+    let (value, stats) = run.result.unwrap();
+    assert_eq!(value, "(6765, 10946)");
+    assert_eq!(stats.parallel_pairs, 1);
+    // The calls of the threads are counted too.
+    assert!(stats.calls > 20 + 21, "{}", stats.calls);
+    assert!(run.sequential.is_empty());
+}
+
+#[test]
+fn pure_functions_run_faster_on_two_threads() {
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+    if cores < 2 {
+        eprintln!("skipped, a single core can't show the speedup");
+        return;
+    }
+    let config = || RunConfig {
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let timed = |source: String| {
+        let time = Instant::now();
+        run(&source, config()).result.unwrap();
+        time.elapsed()
+    };
+    let sequential = timed(format!("{FIB} (fib(19), fib(19))"));
+    let parallel = timed(format!("{FIB} par_pair(fib, 19, 19)"));
+    assert!(
+        parallel.as_secs_f64() < sequential.as_secs_f64() * 0.8,
+        "{parallel:?} in parallel, {sequential:?} sequentially"
+    );
+}
+
+#[test]
+fn functions_that_print_run_sequentially_in_order() {
+    let source = "let log = fn (n) => { print(n) };
+    let twice = fn (n) => { log(n) + log(n + 10) };
+    par_pair(twice, 1, 2)";
+    let run = run(source, RunConfig::default());
+    assert_eq!(run.printed, "1\n11\n2\n12\n");
+    let (value, stats) = run.result.unwrap();
+    assert_eq!(value, "(12, 14)");
+    assert_eq!(stats.parallel_pairs, 0);
+
+    assert_eq!(run.sequential.len(), 1);
+    assert_eq!(run.sequential[0].function, "twice");
+    let start = source.find("par_pair").unwrap() as u64;
+    assert_eq!(run.sequential[0].call.start, start);
+}
+
+#[test]
+fn functions_given_as_arguments_are_looked_at_too() {
+    let source = "let log = fn (n) => { print(n) };
+    let call = fn (f) => { f(7) };
+    par_pair(call, log, log)";
+    let config = RunConfig {
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let run = run(source, config);
+    assert_eq!(run.printed, "7\n7\n");
+    assert_eq!(run.sequential.len(), 1);
+}
+
+#[test]
+fn raising_the_interrupt_stops_both_threads() {
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let raiser = {
+        let interrupt = interrupt.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            interrupt.store(true, Ordering::Relaxed);
+        })
+    };
+    let source = "let spin = fn (n) => { spin(n + 1) }; par_pair(spin, 0, 1)";
+    let time = Instant::now();
+    let run = run(
+        source,
+        RunConfig {
+            interrupt: Some(interrupt),
+            memoize: false,
+            ..RunConfig::default()
+        },
+    );
+    raiser.join().unwrap();
+    let Err(RinhaError::Runtime(error)) = run.result else {
+        panic!("the spinning didn't stop");
+    };
+    assert_eq!(error.kind, ErrorKind::Interrupted);
+    assert!(time.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn errors_of_a_half_keep_their_location() {
+    let source = "let f = fn (n) => { if (n == 1) { n + true } else { n } }; par_pair(f, 0, 1)";
+    let Err(RinhaError::Runtime(error)) = run(source, RunConfig::default()).result else {
+        panic!("the half didn't fail");
+    };
+    assert!(matches!(error.kind, ErrorKind::TypeMismatch(_)));
+    assert_eq!(
+        error.location.start,
+        source.find("n + true").unwrap() as u64
+    );
+}
+
+#[test]
+fn it_is_only_provided_with_the_extensions_and_when_not_bound() {
+    let source = format!("{FIB} par_pair(fib, 1, 2)");
+    let result = rinha::run_source(&source, &RunConfig::default());
+    let Err(RinhaError::Runtime(error)) = result else {
+        panic!("par_pair was provided without the extensions");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("par_pair".into()));
+
+    let own = "let par_pair = fn (f, a, b) => { f(a) + f(b) };
+    let double = fn (n) => { n * 2 };
+    par_pair(double, 1, 2)";
+    let (value, stats) = run(own, RunConfig::default()).result.unwrap();
+    assert_eq!(value, "6");
+    assert_eq!(stats.parallel_pairs, 0);
+
+    let Err(RinhaError::Runtime(error)) = run("par_pair(1, 2)", RunConfig::default()).result else {
+        panic!("the wrong arity was accepted");
+    };
+    assert!(matches!(
+        error.kind,
+        ErrorKind::ArityMismatch { given: 2, .. }
+    ));
+}
+
+#[test]
+fn the_cli_warns_about_the_sequential_pairs() {
+    let dir = Scratch::new("par-pair");
+    let program = dir.file(
+        "main.rinha",
+        "let log = fn (n) => { print(n) };\npar_pair(log, 1, 2)",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--extensions"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"1\n2\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("main.rinha:2:1 calls `log` on one thread after the other"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::Scratch;
+use std::{path::PathBuf, process::Command};
+
+/// Runs the pipeline, returning the exit code, stdout and the stage names
+/// with their status from the summary lines.
+fn pipeline(flags: &[&str], program: &PathBuf) -> (Option<i32>, String, Vec<String>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("pipeline")
+        .args(flags)
+        .arg(program)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let summaries = stderr
+        .lines()
+        .filter(|line| line.contains(" in ") && line.ends_with("ms"))
+        .map(|line| line.rsplit_once(" in ").unwrap().0.to_string())
+        .collect();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        summaries,
+    )
+}
+
+#[test]
+fn runs_every_stage_in_order() {
+    let dir = Scratch::new("pipeline");
+    let program = dir.file("fine.rinha", "let x = 1;\nprint(x + 1)");
+
+    let (code, stdout, summaries) = pipeline(&[], &program);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "2\n");
+    assert_eq!(summaries, ["parse    ok", "check    ok", "execute  ok"]);
+}
+
+#[test]
+fn warnings_are_not_fatal() {
+    let dir = Scratch::new("pipeline");
+    let program = dir.file("warned.rinha", "let x = 1;\nprint(2)");
+
+    let (code, stdout, summaries) = pipeline(&[], &program);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "2\n");
+    assert_eq!(
+        summaries,
+        ["parse    ok", "check    ok, 1 warning(s)", "execute  ok"]
+    );
+}
+
+#[test]
+fn stops_at_the_first_fatal_stage() {
+    let dir = Scratch::new("pipeline");
+    let program = dir.file("broken.rinha", "print(missing)");
+    let (code, stdout, summaries) = pipeline(&[], &program);
+    assert_eq!(code, Some(1));
+    assert_eq!(stdout, "");
+    assert_eq!(
+        summaries,
+        [
+            "parse    ok",
+            "check    failed, 1 error(s) and 0 warning(s)"
+        ]
+    );
+
+    let program = dir.file("unparsable.rinha", "let x = ;");
+    let (code, _, summaries) = pipeline(&[], &program);
+    assert_eq!(code, Some(2));
+    assert_eq!(summaries, ["parse    failed"]);
+}
+
+#[test]
+fn until_stops_early() {
+    let dir = Scratch::new("pipeline");
+    let program = dir.file("early.rinha", "print(1)");
+
+    let (code, stdout, summaries) = pipeline(&["--until", "check"], &program);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "");
+    assert_eq!(summaries, ["parse    ok", "check    ok"]);
+}
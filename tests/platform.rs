@@ -0,0 +1,144 @@
+//! What the interpreter writes is the same on every platform, whatever the
+//! pointer width, the endianness or the seeds of the hash maps.
+//!
+//! The tests run on a single platform, so they check the properties that
+//! make the outputs portable: the serialized types use fixed-width
+//! integers, the canonical ASTs match digests taken once, and two
+//! processes, which seed their hash maps differently, write the same
+//! bytes. Bless new digests with `RINHA_BLESS=1 cargo test`.
+
+mod common;
+
+use common::Scratch;
+use rinha::{cache, parser};
+use std::{fs, process::Command};
+
+const DIGESTS: &str = "tests/snapshots/ast-digests.tsv";
+
+const GOLDEN: [&str; 7] = ["combination", "easy", "fib", "sum", "sum2", "sum3", "tuple"];
+
+/// The declarations deriving `Serialize` in the sources, with their bodies.
+fn serialized_declarations(source: &str) -> Vec<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut declarations = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        if !(line.starts_with("#[derive(") && line.contains("Serialize")) {
+            continue;
+        }
+        let body: Vec<&str> = lines[index..]
+            .iter()
+            .take_while(|line| **line != "}")
+            .copied()
+            .collect();
+        declarations.push(body.join("\n"));
+    }
+    declarations
+}
+
+#[test]
+fn serialized_types_have_no_platform_sized_integers() {
+    let mut checked = 0;
+    for entry in fs::read_dir("src").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = fs::read_to_string(&path).unwrap();
+            for declaration in serialized_declarations(&source) {
+                checked += 1;
+                assert!(
+                    !declaration.contains("usize") && !declaration.contains("isize"),
+                    "{} serializes a platform sized integer:\n{declaration}",
+                    path.display()
+                );
+            }
+        }
+    }
+    assert!(checked > 10, "only {checked} serialized types were found");
+}
+
+/// The JSON AST of each golden program, without the metadata that changes
+/// with every release.
+fn canonical_ast(name: &str) -> String {
+    let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+    let mut file = parser::parse_or_report(&format!("{name}.rinha"), &source).unwrap();
+    file.metadata = None;
+    serde_json::to_string(&file).unwrap()
+}
+
+#[test]
+fn canonical_asts_match_their_digests() {
+    let mut current = String::from("program\tbytes\tfnv1a\n");
+    for name in GOLDEN {
+        let json = canonical_ast(name);
+        current.push_str(&format!(
+            "{name}\t{}\t{:016x}\n",
+            json.len(),
+            cache::fnv1a(json.as_bytes())
+        ));
+    }
+    if std::env::var_os("RINHA_BLESS").is_some() {
+        fs::write(DIGESTS, &current).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(DIGESTS).unwrap_or_default();
+    assert_eq!(
+        current, snapshot,
+        "the ASTs changed, bless them with `RINHA_BLESS=1 cargo test` if it's intended"
+    );
+}
+
+/// Runs the program in a process of its own, returning its stdout, its
+/// stderr and the summary.
+fn run(name: &str) -> (Vec<u8>, Vec<u8>, String) {
+    let dir = Scratch::new("platform");
+    let summary = dir.join(format!("{name}.json"));
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--coverage")
+        .arg("--summary")
+        .arg(&summary)
+        .arg(format!("files/{name}.rinha"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let summary = fs::read_to_string(summary).unwrap();
+    (output.stdout, output.stderr, summary)
+}
+
+#[test]
+fn separate_processes_write_the_same_bytes() {
+    for name in GOLDEN {
+        assert_eq!(run(name), run(name), "{name}");
+    }
+}
+
+#[test]
+fn summaries_hold_only_integers_and_names() {
+    let (_, _, summary) = run("fib");
+    let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    let fields = summary.as_object().unwrap();
+
+    let keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        [
+            "calls",
+            "exit_code",
+            "max_print_bytes",
+            "max_print_depth",
+            "memo_evictions",
+            "memo_hits",
+            "printed_bytes",
+            "semantic_flags",
+            "status"
+        ]
+    );
+    for (key, value) in fields {
+        let names = |items: &Vec<serde_json::Value>| items.iter().all(|item| item.is_string());
+        let portable = value.is_string()
+            || value.is_i64()
+            || value.is_u64()
+            || value.as_array().is_some_and(names);
+        assert!(portable, "`{key}` is {value}");
+    }
+}
@@ -0,0 +1,95 @@
+use rinha::{
+    interpreter::{IntFormat, PrettyOptions, Primitive},
+    RunConfig,
+};
+
+/// A tuple nested four levels deep.
+const NESTED: &str = "((\"first\", (1, (true, (2, 3)))), (\"second\", (1000000, false)))";
+
+fn value(source: &str) -> Primitive {
+    rinha::run_source(source, &RunConfig::default())
+        .unwrap()
+        .value
+}
+
+fn narrow() -> PrettyOptions {
+    PrettyOptions {
+        width: 28,
+        ..PrettyOptions::default()
+    }
+}
+
+#[test]
+fn print_keeps_the_compact_form() {
+    assert_eq!(
+        value(NESTED).to_string(),
+        "((first, (1, (true, (2, 3)))), (second, (1000000, false)))"
+    );
+}
+
+#[test]
+fn wide_enough_tuples_stay_on_one_line() {
+    assert_eq!(
+        value(NESTED).pretty(&PrettyOptions::default()),
+        "((first, (1, (true, (2, 3)))), (second, (1000000, false)))"
+    );
+}
+
+#[test]
+fn narrow_layouts_break_one_element_per_line() {
+    let expected = "\
+(
+  (
+    first,
+    (1, (true, (2, 3)))
+  ),
+  (second, (1000000, false))
+)";
+    assert_eq!(value(NESTED).pretty(&narrow()), expected);
+}
+
+#[test]
+fn indentation_and_int_format_are_configurable() {
+    let options = PrettyOptions {
+        indent: 4,
+        int_format: IntFormat::Grouped,
+        ..narrow()
+    };
+    let expected = "\
+(
+    (
+        first,
+        (1, (true, (2, 3)))
+    ),
+    (
+        second,
+        (1_000_000, false)
+    )
+)";
+    assert_eq!(value(NESTED).pretty(&options), expected);
+}
+
+#[test]
+fn deep_tuples_are_elided() {
+    let options = PrettyOptions {
+        max_depth: Some(2),
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        value(NESTED).pretty(&options),
+        "((first, (...)), (second, (...)))"
+    );
+}
+
+#[test]
+fn repeated_tuples_are_shown_once() {
+    let options = PrettyOptions {
+        share: true,
+        ..PrettyOptions::default()
+    };
+    let value = value("let p = (1, (2, 3)); ((p, p), (p, (2, 3)))");
+    assert_eq!(
+        value.pretty(&options),
+        "((#1 (1, #2 (2, 3)), &ref#1), (&ref#1, &ref#2))"
+    );
+}
@@ -0,0 +1,65 @@
+use rinha::RunConfig;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::RefCell,
+    io,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Tracks the peak of the allocated bytes.
+struct Tracking;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(current, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Builds a string of 2^`doublings` bytes by doubling a single character.
+fn program(doublings: usize, print: bool) -> String {
+    let mut source = String::from("let s0 = \"x\";\n");
+    for i in 1..=doublings {
+        source.push_str(&format!("let s{i} = s{} + s{};\n", i - 1, i - 1));
+    }
+    if print {
+        source.push_str(&format!("print(s{doublings})"));
+    } else {
+        source.push_str(&format!("s{doublings}"));
+    }
+    source
+}
+
+fn peak(source: &str) -> usize {
+    let config = RunConfig {
+        output: Some(Rc::new(RefCell::new(io::sink()))),
+        ..RunConfig::default()
+    };
+    PEAK.store(CURRENT.load(Ordering::SeqCst), Ordering::SeqCst);
+    rinha::run_source(source, &config).unwrap();
+    PEAK.load(Ordering::SeqCst)
+}
+
+#[test]
+fn printing_a_long_line_does_not_copy_it() {
+    let size = 1 << 23;
+    let building = peak(&program(23, false));
+    let printing = peak(&program(23, true));
+
+    // Printing is streamed into the output, so it doesn't allocate another
+    // copy of the ~8MB string on top of what building it needs.
+    assert!(printing < building + size / 2, "{printing} vs {building}");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::{Interpreter, Scope},
+    parser,
+};
+use std::{cell::RefCell, io, process::Command, rc::Rc};
+
+const PROGRAM: &str = "let say = fn (x) => { print(x) };
+let _ = say(1);
+let _ = say((2, \"two\"));
+print(3)";
+
+/// A sink the test keeps a handle to.
+#[derive(Clone, Default)]
+struct Recording(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Recording {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Recording {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+#[test]
+fn every_observer_sees_each_print_exactly_once() {
+    let (output, first_tee, second_tee) = (
+        Recording::default(),
+        Recording::default(),
+        Recording::default(),
+    );
+    let hooked = Rc::new(RefCell::new(vec![]));
+    let hook = hooked.clone();
+
+    let file = parser::parse_or_report("main.rinha", PROGRAM).unwrap();
+    let mut interpreter = Interpreter::with_output(output.clone())
+        .tee(first_tee.clone())
+        .tee(second_tee.clone())
+        .on_print(move |value, _| hook.borrow_mut().push(value.to_string()));
+    interpreter
+        .interpret(file.expression, &mut Scope::new())
+        .unwrap();
+
+    let expected = "1\n(2, two)\n3\n";
+    assert_eq!(output.text(), expected);
+    assert_eq!(first_tee.text(), expected);
+    assert_eq!(second_tee.text(), expected);
+    assert_eq!(*hooked.borrow(), ["1", "(2, two)", "3"]);
+
+    let stats = interpreter.stats();
+    assert_eq!(stats.printed_bytes, expected.len() as u64);
+    let mut sites: Vec<(u64, u64)> = stats
+        .prints_by_site
+        .iter()
+        .map(|(location, count)| (location.start, *count))
+        .collect();
+    sites.sort();
+    assert_eq!(sites, [(22, 2), (75, 1)]);
+}
+
+#[test]
+fn the_tee_file_matches_stdout() {
+    let dir = Scratch::new("tee");
+    let program = dir.file("main.rinha", PROGRAM);
+    let tee = dir.join("printed.txt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--tee")
+        .arg(&tee)
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, std::fs::read(&tee).unwrap());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::Scratch;
+use rinha::RunConfig;
+use std::{cell::RefCell, io, path::PathBuf, process::Command, rc::Rc};
+
+/// Prints a tuple nesting 50 deep twice, then a short value.
+const NESTED: &str = "let nest = fn (n) => {
+  if (n == 0) { 0 } else { (n, nest(n - 1)) }
+};
+let _ = print(nest(50));
+let _ = print(nest(50));
+print(1)";
+
+/// Prints a string of 2^21 bytes, past the default of `--warn-print-bytes`.
+const LONG: &str = "let double = fn (s, n) => {
+  if (n == 0) { s } else { double(s + s, n - 1) }
+};
+let _ = print(double(\"x\", 21));
+0";
+
+fn stderr(args: &[&str], program: PathBuf) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(args)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn the_stats_keep_the_deepest_and_longest_prints() {
+    let config = RunConfig {
+        output: Some(Rc::new(RefCell::new(io::sink()))),
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(NESTED, &config).unwrap();
+    assert_eq!(outcome.stats.max_print_depth, 50);
+    // `(50, (49, ... (1, 0)...))` and its newline.
+    let longest = (1..=50).map(|n| format!("({n}, ")).collect::<String>() + "0" + &")".repeat(50);
+    assert_eq!(outcome.stats.max_print_bytes, longest.len() as u64 + 1);
+}
+
+#[test]
+fn each_threshold_warns_once_with_the_location() {
+    let dir = Scratch::new("print-warnings");
+    let program = dir.file("nested.rinha", NESTED);
+    let stderr = stderr(
+        &["--warn-print-depth", "10", "--warn-print-bytes", "100"],
+        program,
+    );
+    let warnings: Vec<&str> = stderr
+        .lines()
+        .filter(|line| line.starts_with("warning"))
+        .collect();
+    assert_eq!(warnings.len(), 2, "{stderr}");
+    assert!(warnings[0].contains("nested.rinha:4:9"), "{stderr}");
+    assert!(
+        warnings[0].contains("nests tuples 50 deep, more than the 10"),
+        "{stderr}"
+    );
+    assert!(warnings[1].contains("nested.rinha:4:9"), "{stderr}");
+    assert!(
+        warnings[1].contains("more than the 100 of --warn-print-bytes"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn the_default_thresholds_are_off_with_spec() {
+    let dir = Scratch::new("print-warnings");
+    let program = dir.file("long.rinha", LONG);
+    let warned = stderr(&["--quiet"], program.clone());
+    assert!(warned.contains("wrote 2097153 bytes"), "{warned}");
+
+    assert_eq!(stderr(&["--spec"], program.clone()), "");
+    let explicit = stderr(&["--spec", "--warn-print-bytes", "10"], program);
+    assert!(explicit.contains("--warn-print-bytes"), "{explicit}");
+}
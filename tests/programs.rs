@@ -0,0 +1,72 @@
+//! `interpreter test` runs the programs of `programs/` and compares what
+//! they print with their `.expected` files, on every backend. Run with
+//! `RINHA_BLESS=1` to write the files again.
+
+mod common;
+
+use common::Scratch;
+use std::{fs, path::Path, process::Command};
+
+fn test(dir: &Path, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("test")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.code(), stdout)
+}
+
+#[test]
+fn the_programs_print_their_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("programs");
+    if std::env::var_os("RINHA_BLESS").is_some() {
+        let (code, stdout) = test(&dir, &["--bless"]);
+        assert_eq!(code, Some(0), "{stdout}");
+    }
+    for backend in ["tree", "vm", "jit"] {
+        let (code, stdout) = test(&dir, &["--backend", backend]);
+        assert_eq!(code, Some(0), "{backend}: {stdout}");
+        assert!(stdout.ends_with(" 0 blessed, 0 failed\n"), "{stdout}");
+    }
+}
+
+#[test]
+fn the_differences_fail_until_blessed() {
+    let dir = Scratch::new("programs");
+    dir.file("a.rinha", "let _ = print(1);\nprint(2)");
+    dir.file("b.rinha", "let _ = print(\"b\");\n1 / 0");
+    dir.file("notes.txt", "not a program");
+
+    let (code, stdout) = test(&dir, &[]);
+    assert_eq!(code, Some(1));
+    assert!(stdout.contains("a.rinha: no `.expected` file"), "{stdout}");
+    assert!(
+        stdout.ends_with("0 passed, 0 blessed, 2 failed\n"),
+        "{stdout}"
+    );
+
+    let (code, stdout) = test(&dir, &["--bless"]);
+    assert_eq!(code, Some(0));
+    assert!(
+        stdout.ends_with("0 passed, 2 blessed, 0 failed\n"),
+        "{stdout}"
+    );
+    let expected = |name| fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(expected("a.rinha.expected"), "1\n2\n");
+    assert_eq!(
+        expected("b.rinha.expected"),
+        "b\nerror: division by zero in `/`\n"
+    );
+
+    fs::write(dir.join("a.rinha"), "let _ = print(1);\nprint(3)").unwrap();
+    let (code, stdout) = test(&dir, &[]);
+    assert_eq!(code, Some(1));
+    assert!(stdout.contains("a.rinha: line 2 differs"), "{stdout}");
+    assert!(stdout.contains("expected: \"2\\n\""), "{stdout}");
+    assert!(stdout.contains("ok      "), "{stdout}");
+
+    let (code, _) = test(&dir.join("missing"), &[]);
+    assert_eq!(code, Some(2));
+}
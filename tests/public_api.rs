@@ -0,0 +1,164 @@
+//! Snapshot of the public surface of the library.
+//!
+//! The exported items are collected from the sources of every `pub mod`,
+//! and compared with `tests/snapshots/public-api.txt`. When the change is
+//! intended, bless the new snapshot with `RINHA_BLESS=1 cargo test`.
+
+use std::{collections::HashSet, fs, path::Path};
+
+const SNAPSHOT: &str = "tests/snapshots/public-api.txt";
+
+/// Joins the lines of a declaration until its body or its end, collapsing
+/// the whitespace.
+fn signature(lines: &[&str]) -> String {
+    let mut signature = String::new();
+    for line in lines {
+        let end = if line.trim_start().starts_with("pub use") {
+            line.find(';')
+        } else {
+            line.find(['{', ';'])
+        };
+        signature.push(' ');
+        signature.push_str(&line[..end.unwrap_or(line.len())]);
+        if end.is_some() {
+            break;
+        }
+    }
+    signature.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A field or a variant, they're always on a single line.
+fn member(line: &str) -> String {
+    let line = line.split("//").next().unwrap_or_default();
+    let line = line.split('{').next().unwrap_or_default();
+    line.trim().trim_end_matches(',').to_string()
+}
+
+fn module_file(parent: &Path, name: &str) -> Option<String> {
+    [
+        parent.join(format!("{name}.rs")),
+        parent.join(name).join("mod.rs"),
+    ]
+    .iter()
+    .find_map(|path| fs::read_to_string(path).ok())
+}
+
+/// The name declared by a `pub struct`, `pub enum`, `pub type` or `pub trait`.
+fn declared_name(item: &str) -> Option<&str> {
+    let rest = ["pub struct ", "pub enum ", "pub type ", "pub trait "]
+        .iter()
+        .find_map(|prefix| item.strip_prefix(prefix))?;
+    rest.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+}
+
+/// Collects the public items of a module, recursing into its public
+/// submodules. Trait impls are kept apart, since only the ones involving
+/// public types are part of the surface.
+fn collect(
+    module: &str,
+    dir: &Path,
+    source: &str,
+    items: &mut Vec<String>,
+    impls: &mut Vec<(String, String)>,
+) {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut owner: Option<String> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if !line.is_empty() && !line.starts_with(' ') && !line.starts_with('}') {
+            owner = None;
+        }
+
+        if line.starts_with("pub ") {
+            let item = signature(&lines[index..]);
+            items.push(format!("{module}: {item}"));
+
+            let is_block = line.trim_end().ends_with('{');
+            if item.starts_with("pub struct") || item.starts_with("pub enum") {
+                owner = is_block.then(|| item.clone());
+            }
+
+            if let Some(name) = item.strip_prefix("pub mod ") {
+                if let Some(source) = module_file(dir, name) {
+                    let path = format!("{module}::{name}");
+                    collect(&path, &dir.join(name), &source, items, impls);
+                }
+            }
+        } else if line.starts_with("impl") {
+            let item = signature(&lines[index..]);
+            owner = Some(item.clone());
+            if item.contains(" for ") {
+                impls.push((module.to_string(), item));
+            }
+        } else if let Some(owner) = &owner {
+            let Some(member_line) = line.strip_prefix("    ") else {
+                continue;
+            };
+            let variant = owner.starts_with("pub enum")
+                && member_line.starts_with(|c: char| c.is_ascii_uppercase());
+            let field = owner.starts_with("pub struct") && member_line.starts_with("pub ");
+            let method = owner.starts_with("impl")
+                && !owner.contains(" for ")
+                && member_line.starts_with("pub ");
+
+            if variant || field {
+                items.push(format!("{module}: {owner} => {}", member(member_line)));
+            } else if method {
+                let method = signature(&lines[index..])
+                    .replace("( ", "(")
+                    .replace(", )", ")");
+                items.push(format!("{module}: {owner} => {method}"));
+            }
+        }
+    }
+}
+
+fn public_api() -> String {
+    let lib = fs::read_to_string("src/lib.rs").unwrap();
+    let mut items = vec![];
+    let mut impls = vec![];
+    collect("rinha", Path::new("src"), &lib, &mut items, &mut impls);
+
+    let public: HashSet<&str> = items
+        .iter()
+        .filter_map(|item| declared_name(item.split(": ").nth(1)?))
+        .collect();
+    let impls: Vec<String> = impls
+        .iter()
+        .filter(|(_, item)| {
+            item.split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|word| public.contains(word))
+        })
+        .map(|(module, item)| format!("{module}: {item}"))
+        .collect();
+
+    items.extend(impls);
+    items.sort();
+    items.dedup();
+    items.join("\n") + "\n"
+}
+
+#[test]
+fn public_api_matches_the_snapshot() {
+    let current = public_api();
+    if std::env::var_os("RINHA_BLESS").is_some() {
+        fs::create_dir_all("tests/snapshots").unwrap();
+        fs::write(SNAPSHOT, &current).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(SNAPSHOT).unwrap_or_default();
+    if snapshot != current {
+        let added = current.lines().filter(|line| !snapshot.contains(line));
+        let removed = snapshot.lines().filter(|line| !current.contains(line));
+        let diff: Vec<String> = added
+            .map(|line| format!("+ {line}"))
+            .chain(removed.map(|line| format!("- {line}")))
+            .collect();
+        panic!(
+            "the public API changed, bless it with `RINHA_BLESS=1 cargo test` if it's intended:\n{}",
+            diff.join("\n")
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Nothing is written unless a flag asks for it, so the interpreter runs in
+//! read-only containers.
+
+mod common;
+
+use common::Scratch;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+/// Runs with every place a program could write to implicitly, the home,
+/// the caches, the temporary files and the working directory, in `dir`.
+fn interpreter(dir: &Path, args: &[&str]) -> Output {
+    let manifest = env!("CARGO_MANIFEST_DIR");
+    Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CACHE_HOME", dir)
+        .env("XDG_DATA_HOME", dir)
+        .env("XDG_STATE_HOME", dir)
+        .env("TMPDIR", dir)
+        .arg(format!("{manifest}/files/fib.rinha"))
+        .output()
+        .unwrap()
+}
+
+#[cfg(unix)]
+fn make_read_only(dir: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o555)).unwrap();
+}
+
+#[cfg(unix)]
+fn make_writable(dir: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn plain_runs_write_nothing() {
+    let dir = Scratch::new("read-only");
+    make_read_only(&dir);
+    // Root ignores the permissions, so the directory is also checked to be
+    // left empty.
+    let output = interpreter(&dir, &[]);
+    make_writable(&dir);
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"fib(46) = 1836311903\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+/// A path that can't be created, whatever the permissions: its parent is
+/// a file.
+fn unwritable(dir: &Path) -> String {
+    fs::write(dir.join("file"), "").unwrap();
+    dir.join("file").join("out").to_string_lossy().into_owned()
+}
+
+#[test]
+fn the_flags_that_write_are_named_when_they_cant() {
+    for flag in ["--tee", "--summary", "--cache-dir"] {
+        let dir = Scratch::new("read-only");
+        let path = unwritable(&dir);
+        let output = interpreter(&dir, &[flag, &path]);
+        assert_eq!(output.status.code(), Some(2), "{output:?}");
+        assert_eq!(output.stdout, b"", "{flag} fails before running");
+
+        // The report is wrapped to the width of a terminal.
+        let stderr = String::from_utf8_lossy(&output.stderr).replace('│', " ");
+        let stderr = stderr.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(stderr.contains(&path), "{stderr}");
+        assert!(stderr.contains(&format!("asked for by {flag}")), "{stderr}");
+    }
+}
@@ -0,0 +1,175 @@
+//! The records of `--extensions`: `{ x: 1, y: 2 }` builds one, `point.x`
+//! reads its field, and they're the same whatever the order their fields
+//! are written in.
+
+use miette::Diagnostic;
+use rinha::{
+    check::{self, types, Diagnostics},
+    interpreter::ErrorKind,
+    parser, wasm, Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// What the program printed, the same on every backend.
+fn printed(source: &str) -> String {
+    let printed = [Backend::Tree, Backend::Vm, Backend::Jit].map(|backend| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            extensions: true,
+            backend,
+            ..RunConfig::default()
+        };
+        rinha::run_source(source, &config).unwrap();
+        String::from_utf8(output.take()).unwrap()
+    });
+    assert_eq!(printed[0], printed[1], "the bytecode printed otherwise");
+    assert_eq!(printed[0], printed[2], "the machine code printed otherwise");
+    printed[0].clone()
+}
+
+/// The error of the run and the text of the term it points at.
+fn error(source: &str, extensions: bool) -> (ErrorKind, String) {
+    let config = RunConfig {
+        extensions,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = rinha::run_source(source, &config) else {
+        panic!("{source} didn't fail at runtime");
+    };
+    let term = &source[error.location.start as usize..error.location.end as usize];
+    (error.kind, term.to_string())
+}
+
+#[test]
+fn records_are_built_and_read() {
+    let program = "let origin = { y: 0, x: 0 };
+let move = fn (p, dx) => { { x: p.x + dx, y: p.y } };
+let _ = print(move(origin, 3));
+let _ = print(move(origin, 3).x);
+let line = { from: origin, to: { x: 1, y: 2 }, name: \"a\" };
+let _ = print(line.to.y);
+let _ = print({});
+print(line)";
+    assert_eq!(
+        printed(program),
+        "{x: 3, y: 0}\n3\n2\n{}\n{from: {x: 0, y: 0}, name: a, to: {x: 1, y: 2}}\n"
+    );
+}
+
+#[test]
+fn records_compare_field_by_field() {
+    let program = "let _ = print({ x: 1, y: (2, 3) } == { y: (2, 3), x: 1 });
+let _ = print({ x: 1 } == { x: 2 });
+let _ = print({ x: 1 } == { y: 1 });
+print({ x: 1 } != { x: 1, y: 2 })";
+    assert_eq!(printed(program), "true\nfalse\nfalse\ntrue\n");
+    let (kind, _) = error("{ x: 1 } == (1, 2)", true);
+    assert!(matches!(kind, ErrorKind::TypeMismatch(_)), "{kind:?}");
+}
+
+#[test]
+fn the_reads_fail_where_they_are_written() {
+    assert_eq!(
+        error("let p = { x: 1 };\np.y + 1", true),
+        (ErrorKind::NoSuchField("y".into()), "p.y".into())
+    );
+    assert_eq!(
+        error("let t = (1, 2);\nt.x", true),
+        (ErrorKind::NotARecord("x".into()), "t.x".into())
+    );
+    assert_eq!(
+        error("let p = 1;\n{ x: p }", false),
+        (ErrorKind::NeedsExtensions("records"), "{ x: p }".into())
+    );
+}
+
+#[test]
+fn a_field_is_given_once() {
+    let Err(error) = parser::parse_or_report("main.rinha", "{ x: 1, y: 2, x: 3 }") else {
+        panic!("the field given twice was parsed");
+    };
+    let messages: Vec<String> = error.related().unwrap().map(|e| e.to_string()).collect();
+    assert_eq!(messages, ["the field `x` is given twice"]);
+
+    // Only the JSON ASTs can have them.
+    let file = parser::parse_or_report("main.rinha", "{ x: 1, y: 2 }").unwrap();
+    let json = serde_json::to_string(&file)
+        .unwrap()
+        .replace("\"y\"", "\"x\"");
+    let config = RunConfig {
+        extensions: true,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) =
+        rinha::run_ast(serde_json::from_str(&json).unwrap(), &config)
+    else {
+        panic!("the field given twice was run");
+    };
+    assert_eq!(error.kind, ErrorKind::DuplicateField("x".into()));
+}
+
+#[test]
+fn the_analyses_know_the_fields() {
+    let source = "let p = { x: 1, name: \"a\" };
+let _ = p.name + 1;
+let _ = p.z;
+let n = 1;
+n.x";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let mut diagnostics = Diagnostics::new();
+    let signatures = types::infer(&file, &mut diagnostics);
+    assert_eq!(signatures[0].to_string(), "p: {name: Str, x: Int}");
+    let messages: Vec<String> = diagnostics
+        .into_findings()
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "the record has no field `z`, it's {name: Str, x: Int}",
+            "`.x` must be used on records, but it's Int",
+        ]
+    );
+
+    let mut diagnostics = Diagnostics::new();
+    check::check(&file, &mut diagnostics);
+    let findings = diagnostics.into_findings();
+    assert_eq!(
+        findings.last().unwrap().message,
+        "`.x` must be used on records, but it's Int"
+    );
+}
+
+#[test]
+fn the_records_are_formatted_and_read_back() {
+    let source = "let p = { x: (1, 2), y: fn (a) => { a } }; print(first(p.x) + p.y(1))";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let formatted = rinha::format::to_source(&file.expression);
+    assert!(
+        formatted.contains("{ x: (1, 2), y: fn (a) => {"),
+        "{formatted}"
+    );
+    assert_eq!(printed(&formatted), printed(source));
+    assert_eq!(printed(source), "2\n");
+
+    let config = RunConfig {
+        extensions: true,
+        ..RunConfig::default()
+    };
+    let value = rinha::run_source("{ a: (1, { b: true }) }", &config)
+        .unwrap()
+        .value;
+    assert_eq!(value.to_json(), serde_json::json!({"a": [1, {"b": true}]}));
+}
+
+#[test]
+fn webassembly_has_no_records() {
+    let file = parser::parse_or_report("main.rinha", "print({ x: 1 }.x)").unwrap();
+    let error = wasm::compile(&file, &wasm::Options::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "the records can't be compiled into WebAssembly"
+    );
+}
@@ -0,0 +1,79 @@
+//! A reference assigned a closure that captured it is a cycle that
+//! counting the references doesn't free, the collector of the interpreter
+//! does, see [`rinha::runtime::reference::Collector`]. Every allocation of
+//! this test is counted, so it's the only test of its file.
+
+use rinha::{interpreter::Value, RunConfig};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Makes a reference holding a closure that reads it at each of `n`
+/// iterations, the ones of the iteration before are dropped.
+fn program(n: u32) -> String {
+    format!(
+        "let loop = fn (n) => {{
+  if (n == 0) {{ 0 }} else {{
+    let r = ref(0);
+    let f = fn (x) => {{ deref(r) }};
+    let _ = assign(r, f);
+    loop(n - 1)
+  }}
+}};
+loop({n})"
+    )
+}
+
+/// The most bytes the run had allocated at once, and the bytes still
+/// allocated once it's over.
+fn run(n: u32) -> (usize, usize) {
+    let config = RunConfig {
+        memoize: false,
+        extensions: true,
+        ..RunConfig::default()
+    };
+    let source = program(n);
+    let before = LIVE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let value = rinha::run_source(&source, &config).unwrap().value;
+    assert_eq!(value, Value::Int(0));
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    (peak, LIVE.load(Ordering::Relaxed).saturating_sub(before))
+}
+
+#[test]
+fn the_cycles_of_references_are_freed() {
+    // The first run interns the names, which stay allocated.
+    run(1);
+
+    let (short, left) = run(10_000);
+    assert_eq!(left, 0, "{left} bytes left after 10000 cycles");
+    let (long, left) = run(100_000);
+    assert_eq!(left, 0, "{left} bytes left after 100000 cycles");
+    assert!(
+        long < short * 2,
+        "{short} bytes for 10000 cycles, {long} for 100000"
+    );
+}
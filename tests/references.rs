@@ -0,0 +1,174 @@
+//! The references of `--extensions`: `ref` makes a cell, `deref` reads it
+//! and `assign` writes it, for every copy of the reference. Their reads and
+//! writes keep their order, the memo and `par_pair` leave them alone.
+
+use rinha::{
+    interpreter::{ErrorKind, Primitive, Stats},
+    RinhaError, RunConfig,
+};
+use std::process::{Command, Stdio};
+
+fn run(source: &str, extensions: bool) -> Result<(Primitive, Stats), RinhaError> {
+    let config = RunConfig {
+        extensions,
+        ..RunConfig::default()
+    };
+    rinha::run_source(source, &config).map(|outcome| (outcome.value, outcome.stats))
+}
+
+fn value(source: &str) -> String {
+    run(source, true).unwrap().0.to_string()
+}
+
+fn error(source: &str) -> (ErrorKind, String) {
+    let Err(RinhaError::Runtime(error)) = run(source, true) else {
+        panic!("{source} didn't fail at runtime");
+    };
+    let term = &source[error.location.start as usize..error.location.end as usize];
+    (error.kind, term.to_string())
+}
+
+#[test]
+fn references_are_made_read_and_written() {
+    assert_eq!(
+        value("let r = ref(1); let _ = assign(r, deref(r) + 1); deref(r)"),
+        "2"
+    );
+    assert_eq!(value("let r = ref(\"a\"); assign(r, (1, 2))"), "(1, 2)");
+    assert_eq!(value("(ref(1), list(ref(2)))"), "(<#ref>, [<#ref>])");
+    assert_eq!(
+        value("let r = ref(0); let _ = assign(r, r); deref(r)"),
+        "<#ref>"
+    );
+    let json = run("(1, ref(2))", true).unwrap().0.to_json();
+    assert_eq!(json, serde_json::json!([1, "<#ref>"]));
+}
+
+#[test]
+fn the_copies_share_the_cell_and_the_calls_touching_it_are_not_memoized() {
+    let counter = "let count = ref(0);
+let bump = fn () => { assign(count, deref(count) + 1) };
+let twice = fn (f) => { let _ = f(); f() };
+let _ = bump();
+let _ = twice(bump);
+let _ = twice(bump);
+deref(count)";
+    let (count, stats) = run(counter, true).unwrap();
+    assert_eq!(count.to_string(), "5");
+    assert_eq!(stats.memo_hits, 0);
+
+    let fresh =
+        "let make = fn () => { ref(0) }; let a = make(); let _ = assign(a, 1); deref(make())";
+    assert_eq!(value(fresh), "0");
+}
+
+#[test]
+fn a_memo_table_can_be_written_in_rinha() {
+    let fib = "let table = ref(list());
+let calls = ref(0);
+let find = fn (n, entries) => {
+  if (len(entries) == 0) { (false, 0) }
+  else { if (first(head(entries)) == n) { (true, second(head(entries))) } else { find(n, tail(entries)) } }
+};
+let fib = fn (n) => {
+  let found = find(n, deref(table));
+  if (first(found)) { second(found) } else {
+    let _ = assign(calls, deref(calls) + 1);
+    let value = if (n < 2) { n } else { fib(n - 1) + fib(n - 2) };
+    let _ = assign(table, cons((n, value), deref(table)));
+    value
+  }
+};
+let value = fib(40);
+(value, deref(calls))";
+    let config = RunConfig {
+        extensions: true,
+        memoize: false,
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(fib, &config).unwrap();
+    assert_eq!(outcome.value.to_string(), "(102334155, 41)");
+}
+
+#[test]
+fn the_natives_fail_where_they_are_called() {
+    assert_eq!(
+        error("let n = 1;\nderef(n)"),
+        (ErrorKind::NotARef("deref"), "deref(n)".into())
+    );
+    assert_eq!(
+        error("assign(list(), 1)"),
+        (ErrorKind::NotARef("assign"), "assign(list(), 1)".into())
+    );
+    assert_eq!(
+        error("let r = ref(1);\nassign(r)"),
+        (
+            ErrorKind::ArityMismatch {
+                name: "assign".into(),
+                expected: 2,
+                given: 1
+            },
+            "assign(r)".into()
+        )
+    );
+    let (kind, term) = error("let r = ref(1);\nr == r");
+    assert!(matches!(kind, ErrorKind::TypeMismatch(_)), "{kind:?}");
+    assert_eq!(term, "r == r");
+}
+
+#[test]
+fn the_pairs_reaching_a_reference_run_one_after_the_other() {
+    let source = "let seen = ref(list());
+let visit = fn (n) => { assign(seen, cons(n, deref(seen))) };
+let _ = par_pair(visit, 1, 2);
+deref(seen)";
+    let (value, stats) = run(source, true).unwrap();
+    assert_eq!(value.to_string(), "[2, 1]");
+    assert_eq!(stats.parallel_pairs, 0);
+}
+
+#[test]
+fn the_cycles_still_used_are_kept_by_the_collections() {
+    // A counter held by a cycle, then enough cells made to collect a few
+    // times before reading it.
+    let program = "let counter = ref(0);
+let bump = fn (n) => {
+  let held = deref(counter);
+  assign(counter, (first(held) + n, second(held)))
+};
+let _ = assign(counter, (41, bump));
+let self_ = ref(0);
+let _ = assign(self_, fn (x) => { deref(self_) });
+let loop = fn (n) => { if (n == 0) { 0 } else { let _ = ref(n); loop(n - 1) } };
+let _ = loop(5000);
+let _ = second(deref(counter))(1);
+(first(deref(counter)), deref(self_)(0))";
+    assert_eq!(value(program), "(42, <#closure>)");
+}
+
+#[test]
+fn the_references_need_the_extensions() {
+    assert_eq!(value("let ref = fn (x) => { x + 1 }; ref(1)"), "2");
+    let Err(RinhaError::Runtime(error)) = run("ref(1)", false) else {
+        panic!("the references work without the extensions");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("ref".into()));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--extensions", "--check", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(b"let r = ref(1); let _ = assign(r, 2); print(deref(r))")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"2\n");
+}
@@ -0,0 +1,77 @@
+//! The variables resolved to the slots of the frames, and the programs
+//! reading them from there the way they read them by name.
+
+use rinha::{
+    interpreter::{Interpreter, Primitive, Scope},
+    names::FunctionNames,
+    parser,
+    resolve::{self, Address},
+    RunConfig,
+};
+use std::io;
+
+const ADDER: &str = "let x = 1;
+let add = fn (a) => { fn (b) => { a + b + x } };
+let x = 2;
+let inc = add(x);
+inc(3)";
+
+#[test]
+fn the_captured_variables_come_from_the_frame_of_the_maker() {
+    let mut file = parser::parse_or_report("main.rinha", ADDER).unwrap();
+    let names = FunctionNames::new(&file.expression, None);
+    let resolution = resolve::resolve(&mut file.expression, &names);
+
+    assert_eq!(*resolution.top.slots, ["x", "add", "x", "inc"]);
+    assert!(resolution.top.captures.is_empty());
+    let [(_, inner), (_, outer)] = &resolution.functions[..] else {
+        panic!("two functions were resolved");
+    };
+    assert_eq!(*outer.slots, ["add", "a"]);
+    assert_eq!(outer.captures, [("x".into(), Address::Local(0))]);
+    assert_eq!(inner.slots[1..], ["b"]);
+    assert_eq!(
+        inner.captures,
+        [
+            ("a".into(), Address::Local(1)),
+            ("x".into(), Address::Captured(0)),
+        ]
+    );
+}
+
+#[test]
+fn shadowing_keeps_its_meaning() {
+    let programs = [
+        (ADDER, "6"),
+        (
+            "let f = fn (n) => { let n = n + 1; let g = fn () => { n }; g() }; f(1)",
+            "2",
+        ),
+        ("let f = fn (n) => { let f = n * 2; f }; f(4)", "8"),
+        ("let f = fn (f) => { f + 1 }; f(1)", "2"),
+        (
+            "let fact = fn (n) => { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(5)",
+            "120",
+        ),
+    ];
+    for (source, expected) in programs {
+        let value = rinha::run_source(source, &RunConfig::default())
+            .unwrap()
+            .value;
+        assert_eq!(value.to_string(), expected, "{source}");
+    }
+}
+
+#[test]
+fn the_bindings_of_a_term_are_seen_by_the_next_ones() {
+    let mut interpreter = Interpreter::with_output(io::sink());
+    let mut scope = Scope::from([("x".to_string(), Primitive::Int(20))]);
+    let first = parser::parse_or_report("first.rinha", "let f = fn (n) => { n + x }; 0").unwrap();
+    interpreter.interpret(first.expression, &mut scope).unwrap();
+
+    let second = parser::parse_or_report("second.rinha", "f(22)").unwrap();
+    let value = interpreter
+        .interpret(second.expression, &mut scope)
+        .unwrap();
+    assert_eq!(value, Primitive::Int(42));
+}
@@ -0,0 +1,382 @@
+//! Formats the ASTs back into source, parses them again, and checks that
+//! both ASTs print and evaluate the same. It catches the formatter dropping
+//! parentheses that change the evaluation.
+
+use rinha::{
+    ast::{self, BinaryOp, Term},
+    format, parser, RunConfig,
+};
+use std::{cell::RefCell, fs, panic, process::Command, rc::Rc, sync::Arc, thread};
+
+/// The recursion of the examples doesn't fit the default stack of the
+/// test threads in debug builds.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+const GENERATED: u64 = 300;
+
+/// What a run printed and how it ended: the value, or the error, or the
+/// panic of an integer overflow.
+#[derive(Debug, PartialEq)]
+struct Observed {
+    printed: String,
+    ended: Result<String, String>,
+}
+
+fn observe(file: ast::File) -> Observed {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        ..RunConfig::default()
+    };
+    let ended = panic::catch_unwind(panic::AssertUnwindSafe(|| rinha::run_ast(file, &config)));
+    let ended = match ended {
+        Ok(Ok(outcome)) => Ok(outcome.value.to_string()),
+        Ok(Err(rinha::RinhaError::Runtime(error))) => Err(error.kind.to_string()),
+        Ok(Err(error)) => Err(error.to_string()),
+        Err(_) => Err("panicked".into()),
+    };
+    let printed = String::from_utf8_lossy(&output.borrow()).into_owned();
+    Observed { printed, ended }
+}
+
+fn round_trip(name: &str, file: ast::File) {
+    let source = format::to_source(&file.expression);
+    let reparsed = match parser::parse_or_report("formatted.rinha", &source) {
+        Ok(reparsed) => reparsed,
+        Err(_) => fail(name, &source, "the formatted source doesn't parse"),
+    };
+
+    let original = observe(file);
+    let formatted = observe(reparsed);
+    if original != formatted {
+        let reason = format!("original {original:?}, formatted {formatted:?}");
+        fail(name, &source, &reason);
+    }
+}
+
+/// Saves the source that failed, so it can be run by hand.
+fn fail(name: &str, source: &str, reason: &str) -> ! {
+    let path = std::env::temp_dir().join(format!("rinha-round-trip-{name}.rinha"));
+    fs::write(&path, source).unwrap();
+    panic!(
+        "{name}: {reason}\nthe source was saved into {}:\n{source}",
+        path.display()
+    );
+}
+
+fn on_big_stack(run: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn examples_survive_the_round_trip() {
+    let examples = fs::read_dir("files").unwrap();
+    for entry in examples {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "rinha")
+        {
+            let source = fs::read_to_string(&path).unwrap();
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            on_big_stack(move || {
+                let file = parser::parse_or_report(&name, &source).unwrap();
+                round_trip(&name, file);
+            });
+        }
+    }
+}
+
+#[test]
+fn generated_programs_survive_the_round_trip() {
+    // The integer overflows panic on both sides, they'd flood the output.
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| {
+        for seed in 0..GENERATED {
+            let mut generator = Generator::new(seed);
+            let expression = generator.program();
+            let file = ast::File {
+                name: "generated.rinha".into(),
+                expression,
+                location: loc(),
+                metadata: None,
+            };
+            round_trip(&format!("generated-{seed}"), file);
+        }
+    });
+    let _ = panic::take_hook();
+    if let Err(error) = result {
+        let message = error.downcast_ref::<String>().cloned().unwrap_or_default();
+        panic!("{message}");
+    }
+}
+
+#[test]
+fn the_binary_prints_the_json_asts_as_source() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["fmt", "files/combination.json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json = fs::read_to_string("files/combination.json").unwrap();
+    let file: ast::File = serde_json::from_str(&json).unwrap();
+    let printed = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(printed, format::to_source(&file.expression) + "\n");
+    assert!(printed.contains("\n  if (a || b) {\n    1\n  } else {\n"));
+    round_trip("combination.json", file);
+}
+
+fn loc() -> ast::Location {
+    ast::Location::new(0, 0, "generated.rinha")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Int,
+    Bool,
+    Str,
+    Tuple,
+}
+
+/// Generates well typed programs, so they mostly run to completion and
+/// exercise the evaluation instead of the errors.
+struct Generator {
+    state: u64,
+    variables: Vec<(String, Type)>,
+    fresh: usize,
+}
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        Self {
+            state: seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407),
+            variables: vec![],
+            fresh: 0,
+        }
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) % bound
+    }
+
+    fn name(&mut self, prefix: &str) -> String {
+        self.fresh += 1;
+        format!("{prefix}{}", self.fresh)
+    }
+
+    fn var(text: &str) -> rinha::parser::Var {
+        rinha::parser::Var {
+            text: text.into(),
+            location: loc(),
+            address: Default::default(),
+        }
+    }
+
+    fn program(&mut self) -> Term {
+        let ty = self.ty();
+        self.term(ty, 4)
+    }
+
+    fn ty(&mut self) -> Type {
+        [Type::Int, Type::Bool, Type::Str, Type::Tuple][self.below(4) as usize]
+    }
+
+    fn binary(lhs: Term, op: BinaryOp, rhs: Term) -> Term {
+        Term::Binary(ast::Binary {
+            lhs: Box::new(lhs),
+            op,
+            rhs: Box::new(rhs),
+            location: loc(),
+        })
+    }
+
+    fn int(value: i32) -> Term {
+        Term::Int(ast::Int {
+            value,
+            location: loc(),
+        })
+    }
+
+    fn term(&mut self, ty: Type, depth: u32) -> Term {
+        if depth == 0 {
+            return self.leaf(ty);
+        }
+
+        match self.below(8) {
+            0 => self.leaf(ty),
+            1 => self.let_(ty, depth),
+            2 => Term::If(ast::If {
+                condition: Box::new(self.term(Type::Bool, depth - 1)),
+                then: Box::new(self.term(ty, depth - 1)),
+                otherwise: Box::new(self.term(ty, depth - 1)),
+                location: loc(),
+            }),
+            3 => Term::Print(ast::Print {
+                value: Box::new(self.term(ty, depth - 1)),
+                location: loc(),
+            }),
+            4 => self.function(ty, depth),
+            _ => self.operation(ty, depth),
+        }
+    }
+
+    fn leaf(&mut self, ty: Type) -> Term {
+        let candidates: Vec<String> = self
+            .variables
+            .iter()
+            .filter(|(_, variable)| *variable == ty)
+            .map(|(name, _)| name.clone())
+            .collect();
+        if !candidates.is_empty() && self.below(2) == 0 {
+            let index = self.below(candidates.len() as u64) as usize;
+            return Term::Var(Self::var(&candidates[index]));
+        }
+
+        match ty {
+            Type::Int => Self::int(self.below(200) as i32 - 100),
+            Type::Bool => Term::Bool(ast::Bool {
+                value: self.below(2) == 0,
+                location: loc(),
+            }),
+            Type::Str => Term::Str(ast::Str {
+                value: ["", "a", "rinha", "x y"][self.below(4) as usize].into(),
+                location: loc(),
+            }),
+            Type::Tuple => self.tuple(0),
+        }
+    }
+
+    fn tuple(&mut self, depth: u32) -> Term {
+        let first = self.ty();
+        let second = self.ty();
+        let first = if first == Type::Tuple {
+            Type::Int
+        } else {
+            first
+        };
+        Term::Tuple(ast::Tuple {
+            first: Box::new(self.term(first, depth)),
+            second: Box::new(self.term(second, depth.saturating_sub(1))),
+            location: loc(),
+        })
+    }
+
+    fn let_(&mut self, ty: Type, depth: u32) -> Term {
+        let name = self.name("v");
+        let value_type = self.ty();
+        let value = self.term(value_type, depth - 1);
+        self.variables.push((name.clone(), value_type));
+        let next = self.term(ty, depth - 1);
+        self.variables.pop();
+        Term::Let(ast::Let {
+            name: Self::var(&name),
+            value: Box::new(value),
+            next: Box::new(next),
+            location: loc(),
+        })
+    }
+
+    /// A function over integers, bound by `let` and called right away.
+    fn function(&mut self, ty: Type, depth: u32) -> Term {
+        let name = self.name("f");
+        let arity = 1 + self.below(2) as usize;
+        let parameters: Vec<String> = (0..arity).map(|_| self.name("p")).collect();
+
+        let outer = std::mem::take(&mut self.variables);
+        self.variables = parameters.iter().map(|p| (p.clone(), Type::Int)).collect();
+        let body = self.term(Type::Int, depth - 1);
+        self.variables = outer;
+
+        let arguments = (0..arity)
+            .map(|_| self.term(Type::Int, depth - 1))
+            .collect();
+        let call = Term::Call(ast::Call {
+            callee: Box::new(Term::Var(Self::var(&name))),
+            arguments,
+            location: loc(),
+        });
+        let next = match ty {
+            Type::Int => call,
+            _ => Term::Tuple(ast::Tuple {
+                first: Box::new(call),
+                second: Box::new(self.term(ty, depth - 1)),
+                location: loc(),
+            }),
+        };
+
+        Term::Let(ast::Let {
+            name: Self::var(&name),
+            value: Box::new(Term::Function(ast::Function {
+                parameters: parameters.iter().map(|p| Self::var(p)).collect(),
+                value: Arc::new(body),
+                location: loc(),
+            })),
+            next: Box::new(next),
+            location: loc(),
+        })
+    }
+
+    fn operation(&mut self, ty: Type, depth: u32) -> Term {
+        let depth = depth - 1;
+        match ty {
+            Type::Int => {
+                let op = [
+                    BinaryOp::Add,
+                    BinaryOp::Sub,
+                    BinaryOp::Mul,
+                    BinaryOp::Div,
+                    BinaryOp::Rem,
+                ][self.below(5) as usize]
+                    .clone();
+                let lhs = self.term(Type::Int, depth);
+                let rhs = match op {
+                    // Literals keep the divisions away from zero.
+                    BinaryOp::Div | BinaryOp::Rem => Self::int(1 + self.below(9) as i32),
+                    _ => self.term(Type::Int, depth),
+                };
+                Self::binary(lhs, op, rhs)
+            }
+            Type::Bool => match self.below(3) {
+                0 => {
+                    let op = [BinaryOp::And, BinaryOp::Or][self.below(2) as usize].clone();
+                    let lhs = self.term(Type::Bool, depth);
+                    let rhs = self.term(Type::Bool, depth);
+                    Self::binary(lhs, op, rhs)
+                }
+                1 => {
+                    let op = [BinaryOp::Lt, BinaryOp::Gt, BinaryOp::Lte, BinaryOp::Gte]
+                        [self.below(4) as usize]
+                        .clone();
+                    let lhs = self.term(Type::Int, depth);
+                    let rhs = self.term(Type::Int, depth);
+                    Self::binary(lhs, op, rhs)
+                }
+                _ => {
+                    let op = [BinaryOp::Eq, BinaryOp::Neq][self.below(2) as usize].clone();
+                    let operands = [Type::Int, Type::Bool, Type::Str][self.below(3) as usize];
+                    let lhs = self.term(operands, depth);
+                    let rhs = self.term(operands, depth);
+                    Self::binary(lhs, op, rhs)
+                }
+            },
+            Type::Str => {
+                let lhs = self.term(Type::Str, depth);
+                let rhs_type = [Type::Str, Type::Int][self.below(2) as usize];
+                let rhs = self.term(rhs_type, depth);
+                Self::binary(lhs, BinaryOp::Add, rhs)
+            }
+            Type::Tuple => self.tuple(depth),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use rinha::{
+    interpreter::{ErrorKind, Primitive},
+    RinhaError, RunConfig,
+};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn runs_a_valid_program() {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        output: Some(output.clone()),
+        ..RunConfig::default()
+    };
+
+    let outcome = rinha::run_source("let x = 40;\nprint(x + 2)", &config).unwrap();
+    assert!(matches!(outcome.value, Primitive::Int(42)));
+    assert_eq!(String::from_utf8_lossy(&output.borrow()), "42\n");
+}
+
+#[test]
+fn reports_syntax_errors() {
+    let result = rinha::run_source("let x = ;", &RunConfig::default());
+    assert!(matches!(result, Err(RinhaError::Parse(_))));
+}
+
+#[test]
+fn reports_runtime_errors_with_their_location() {
+    let result = rinha::run_source("let x = 1;\nx + y", &RunConfig::default());
+    let Err(RinhaError::Runtime(error)) = result else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("y".into()));
+    assert_eq!((error.location.start, error.location.end), (15, 16));
+    assert_eq!(error.location.filename, "main.rinha");
+}
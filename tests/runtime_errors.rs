@@ -0,0 +1,261 @@
+//! The runtime errors point at the term that failed, and the binary
+//! reports them against the source with a failing exit code.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::{ErrorKind, Overflow},
+    optimize::OptLevel,
+    Backend, RinhaError, RunConfig,
+};
+use std::process::Command;
+
+/// A failing program, what it fails with and the text of the term it
+/// points at.
+struct Failing {
+    name: &'static str,
+    source: &'static str,
+    kind: ErrorKind,
+    term: &'static str,
+}
+
+fn failing() -> Vec<Failing> {
+    vec![
+        Failing {
+            name: "undefined",
+            source: "let x = 1;\nprint(x + y)",
+            kind: ErrorKind::UndefinedVariable("y".into()),
+            term: "y",
+        },
+        Failing {
+            name: "not-a-function",
+            source: "let x = 1;\nprint(x(2) + 1)",
+            kind: ErrorKind::NotAFunction,
+            term: "x",
+        },
+        Failing {
+            name: "arity",
+            source: "let add = fn (a, b) => { a + b };\nprint(add(1))",
+            kind: ErrorKind::ArityMismatch {
+                name: "add".into(),
+                expected: 2,
+                given: 1,
+            },
+            term: "add(1)",
+        },
+        Failing {
+            name: "type-mismatch",
+            source: "let x = true;\nprint(1 - x)",
+            kind: ErrorKind::TypeMismatch("You can only subtract Int by another Int"),
+            term: "1 - x",
+        },
+        Failing {
+            name: "division-by-zero",
+            source: "let zero = 0;\nprint(7 % zero)",
+            kind: ErrorKind::DivisionByZero("%"),
+            term: "7 % zero",
+        },
+        Failing {
+            name: "condition",
+            source: "let n = 1;\nif (n) { 1 } else { 2 }",
+            kind: ErrorKind::ConditionNotBool,
+            term: "n",
+        },
+        Failing {
+            name: "not-a-tuple",
+            source: "let t = 1;\nprint(first(t))",
+            kind: ErrorKind::NotATuple("first"),
+            term: "first(t)",
+        },
+        Failing {
+            name: "overflow",
+            source: "let big = 2147483647;\nprint(big * 2)",
+            kind: ErrorKind::IntegerOverflow("*"),
+            term: "big * 2",
+        },
+        Failing {
+            name: "sum-mismatch",
+            source: "let b = true;\nprint(b + 1)",
+            kind: ErrorKind::TypeMismatch("Sum operation can only be done between Int and Str"),
+            term: "b + 1",
+        },
+        Failing {
+            name: "called-result",
+            source: "let f = fn (a) => { a };\nprint(f(1)(2))",
+            kind: ErrorKind::NotAFunction,
+            term: "f(1)",
+        },
+    ]
+}
+
+/// The ways of running the programs, the errors point at the same terms in
+/// all of them.
+fn configs() -> Vec<(String, RunConfig)> {
+    let mut configs: Vec<_> = [Backend::Tree, Backend::Vm, Backend::Jit]
+        .into_iter()
+        .map(|backend| {
+            let config = RunConfig {
+                backend,
+                overflow: Overflow::Trap,
+                ..RunConfig::default()
+            };
+            (format!("{backend:?}"), config)
+        })
+        .collect();
+    configs.push((
+        "O2".into(),
+        RunConfig {
+            opt_level: OptLevel::O2,
+            overflow: Overflow::Trap,
+            ..RunConfig::default()
+        },
+    ));
+    configs.push((
+        "lazy".into(),
+        RunConfig {
+            lazy: true,
+            overflow: Overflow::Trap,
+            ..RunConfig::default()
+        },
+    ));
+    configs
+}
+
+#[test]
+fn errors_point_at_the_offending_term() {
+    for case in failing() {
+        for (how, config) in configs() {
+            let result = rinha::run_source(case.source, &config);
+            let Err(RinhaError::Runtime(error)) = result else {
+                panic!("{} didn't fail at runtime with {how}", case.name);
+            };
+            assert_eq!(error.kind, case.kind, "{} with {how}", case.name);
+
+            let start = case.source.rfind(case.term).unwrap() as u64;
+            let end = start + case.term.len() as u64;
+            assert_eq!(
+                (error.location.start, error.location.end),
+                (start, end),
+                "{} with {how}",
+                case.name
+            );
+        }
+    }
+}
+
+#[test]
+fn the_arity_errors_agree_with_the_counts() {
+    let arity = |expected, given| {
+        ErrorKind::ArityMismatch {
+            name: "f".to_string(),
+            expected,
+            given,
+        }
+        .to_string()
+    };
+    assert_eq!(
+        arity(1, 2),
+        "function `f` expects 1 argument, but 2 were given"
+    );
+    assert_eq!(
+        arity(2, 1),
+        "function `f` expects 2 arguments, but 1 was given"
+    );
+    assert_eq!(
+        arity(0, 1),
+        "function `f` expects 0 arguments, but 1 was given"
+    );
+}
+
+#[test]
+fn the_binary_reports_them_against_the_source() {
+    let dir = Scratch::new("runtime-errors");
+    for case in failing() {
+        let program = dir.file(&format!("{}.rinha", case.name), case.source);
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(["run", "--color", "never", "--overflow", "trap"])
+            .arg(&program)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{}", case.name);
+        assert!(output.stdout.is_empty(), "{}", case.name);
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let line = case.source.lines().last().unwrap();
+        assert!(stderr.contains(&format!("2 │ {line}")), "{stderr}");
+        let stderr = stderr.replace('│', " ");
+        let stderr = stderr.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(stderr.contains(&case.kind.to_string()), "{stderr}");
+        assert!(!stderr.contains("panicked"), "{stderr}");
+    }
+}
+
+#[test]
+fn the_errors_carry_the_calls_they_went_through() {
+    let source = "let fact = fn (n) => { if (n == 0) { 1 / n } else { n * fact(n - 1) } };
+let go = fn (x) => { fact(x) + 1 };
+print(go(3))";
+    for backend in [Backend::Tree, Backend::Vm, Backend::Jit] {
+        let config = RunConfig {
+            backend,
+            ..RunConfig::default()
+        };
+        let Err(RinhaError::Runtime(error)) = rinha::run_source(source, &config) else {
+            panic!("the program didn't fail at runtime with {backend:?}");
+        };
+        let calls: Vec<(&str, &str)> = error
+            .trace
+            .iter()
+            .map(|frame| {
+                let call = &source[frame.call.start as usize..frame.call.end as usize];
+                (frame.function.as_str(), call)
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("fact", "fact(n - 1)"),
+                ("fact", "fact(n - 1)"),
+                ("fact", "fact(n - 1)"),
+                ("fact", "fact(x)"),
+                ("go", "go(3)"),
+            ],
+            "{backend:?}"
+        );
+    }
+}
+
+#[test]
+fn the_binary_shows_the_calls_after_the_error() {
+    let dir = Scratch::new("backtrace");
+    let run = |name: &str, source: &str| {
+        let program = dir.file(name, source);
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(["run", "--color", "never"])
+            .arg(&program)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{output:?}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let (_, trace) = stderr.split_once("stack, innermost first:\n").unwrap();
+        trace.replace(&format!("{}/", dir.display()), "")
+    };
+
+    let fact = "let fact = fn (n) => {\n  if (n == 0) { 1 / n } else { n * fact(n - 1) }\n};
+let go = fn (x) => { fact(x) + 1 };\nprint(go(30))";
+    assert_eq!(
+        run("fact.rinha", fact),
+        "  fact at fact.rinha:2:36, 30 times in a row\n  fact at fact.rinha:4:22\n  go at fact.rinha:5:7\n"
+    );
+
+    // The calls alternate between two sites, there's nothing to fold.
+    let zigzag = "let zigzag = fn (n) => {
+  if (n == 0) { 1 / n } else { if (n % 2 == 0) { zigzag(n - 1) + 1 } else { zigzag(n - 1) + 2 } }
+};\nprint(zigzag(100))";
+    let trace = run("zigzag.rinha", zigzag);
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(lines.len(), 16 + 1 + 4, "{trace}");
+    assert_eq!(lines[16], "  ... 81 more calls");
+    assert_eq!(lines[20], "  zigzag at zigzag.rinha:4:7");
+}
@@ -0,0 +1,136 @@
+//! `semantics.json` lists the changes to what the programs do, and the
+//! flags it names to restore the old behaviors must be real.
+
+mod common;
+
+use common::Scratch;
+use rinha::semantics::{self, numeric_version};
+use std::{fs, path::Path, process::Command};
+
+/// Runs the program with `flags`, returning the exit code and the
+/// semantic flags of the summary.
+fn run(program: &Path, flags: &[String]) -> (Option<i32>, Vec<String>) {
+    let summary = program.with_file_name("summary.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .args(flags)
+        .arg("--summary")
+        .arg(&summary)
+        .arg(program)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{flags:?}: {stderr}");
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(summary).unwrap()).unwrap();
+    let flags = summary["semantic_flags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|flag| flag.as_str().unwrap().to_string())
+        .collect();
+    (output.status.code(), flags)
+}
+
+#[test]
+fn the_changes_are_well_formed() {
+    let changes = semantics::changes();
+    let current = numeric_version(env!("CARGO_PKG_VERSION")).unwrap();
+    for (index, change) in changes.iter().enumerate() {
+        assert!(
+            changes[..index].iter().all(|other| other.id != change.id),
+            "{} is listed twice",
+            change.id
+        );
+        let version = numeric_version(&change.version);
+        assert!(
+            version.is_some_and(|version| version <= current),
+            "{} was introduced in {}, past this version",
+            change.id,
+            change.version
+        );
+    }
+}
+
+#[test]
+fn every_restore_flag_exists_and_is_reported_by_the_run() {
+    let dir = Scratch::new("semantics");
+    let program = dir.file("main.rinha", "print(1)");
+    for change in semantics::changes() {
+        if change.restore.is_empty() {
+            continue;
+        }
+        // Unknown flags and values are rejected by the command line.
+        let (_, in_effect) = run(&program, &change.restore);
+        for flag in &change.restore {
+            assert!(
+                in_effect.contains(flag),
+                "{}: {flag} isn't among the semantic flags of the run, {in_effect:?}",
+                change.id
+            );
+        }
+    }
+}
+
+#[test]
+fn the_summary_lists_the_semantic_flags_in_effect() {
+    let dir = Scratch::new("semantics");
+    let program = dir.file("main.rinha", "print(1)");
+    let (_, defaults) = run(&program, &[]);
+    assert!(
+        defaults.contains(&"--overflow=widen".to_string()),
+        "{defaults:?}"
+    );
+    assert!(
+        defaults.contains(&"--int-format=plain".to_string()),
+        "{defaults:?}"
+    );
+    assert!(!defaults.contains(&"--spec".to_string()), "{defaults:?}");
+
+    let (_, spec) = run(&program, &["--spec".to_string()]);
+    assert!(spec.contains(&"--spec".to_string()), "{spec:?}");
+    assert!(spec.contains(&"--overflow=wrap32".to_string()), "{spec:?}");
+}
+
+#[test]
+fn since_lists_only_the_later_changes() {
+    let list = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .arg("semantics")
+            .arg("--json")
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let changes: Vec<semantics::Change> = serde_json::from_slice(&output.stdout).unwrap();
+        changes
+    };
+    assert_eq!(list(&[]), semantics::changes());
+    assert_eq!(list(&["--since", "0.0.0"]), semantics::changes());
+    assert_eq!(list(&["--since", "99"]), vec![]);
+
+    let since = list(&["--since", "0.0.5"]);
+    assert!(since.iter().all(|change| change.is_since(&[0, 0, 5])));
+    assert!(!since.is_empty());
+
+    let rejected = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["semantics", "--since", "latest"])
+        .output()
+        .unwrap();
+    assert_eq!(rejected.status.code(), Some(2));
+}
+
+#[test]
+fn the_listing_names_the_restore_flags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("semantics")
+        .output()
+        .unwrap();
+    let listing = String::from_utf8(output.stdout).unwrap();
+    for change in semantics::changes() {
+        assert!(listing.contains(&change.id), "{listing}");
+        for flag in change.restore {
+            assert!(listing.contains(&flag), "{listing}");
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use rinha::{
+    interpreter::{Overflow, Primitive},
+    memo::SharedMemo,
+    RunConfig, RunOutcome,
+};
+use std::{cell::RefCell, rc::Rc, sync::Arc, thread};
+
+const PROGRAM: &str = "
+let fib = fn (n) => {
+  if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+};
+let shout = fn (n) => { print(n) };
+let _ = shout(7);
+print(fib(20))
+";
+
+/// Runs the program on its own thread, like a request of a server would.
+fn request(memo: &Arc<SharedMemo>) -> (RunOutcome, String) {
+    let memo = memo.clone();
+    thread::spawn(move || {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            output: Some(output.clone()),
+            shared_memo: Some(memo),
+            ..RunConfig::default()
+        };
+        let outcome = rinha::run_source(PROGRAM, &config).unwrap();
+        let printed = String::from_utf8(output.take()).unwrap();
+        (outcome, printed)
+    })
+    .join()
+    .unwrap()
+}
+
+#[test]
+fn later_requests_are_served_by_the_shared_memo() {
+    let memo = Arc::new(SharedMemo::new(1024));
+
+    let (first, first_output) = request(&memo);
+    assert_eq!(first.stats.shared_hits, 0);
+    assert_eq!(first_output, "7\n6765\n");
+
+    for _ in 0..2 {
+        let (later, output) = request(&memo);
+        assert_eq!(output, first_output);
+        // Only the impure `shout` runs again, `fib(20)` is served at once.
+        assert_eq!(later.stats.calls, 2);
+        assert_eq!(later.stats.shared_hits, 1);
+    }
+}
+
+#[test]
+fn impure_functions_are_never_shared() {
+    let memo = Arc::new(SharedMemo::new(1024));
+    request(&memo);
+
+    let program = RunConfig::default().program_key(PROGRAM);
+    let snapshot = memo.snapshot(program);
+    let has_call = |name: &str, argument: i64| {
+        snapshot
+            .keys()
+            .any(|key| key.function.name == name && key.arguments[..] == [Primitive::Int(argument)])
+    };
+    assert!(has_call("fib", 20));
+    assert!(!has_call("shout", 7));
+    assert_eq!(snapshot.version(), 1);
+}
+
+#[test]
+fn the_snapshots_are_capped() {
+    let memo = Arc::new(SharedMemo::new(5));
+    request(&memo);
+    request(&memo);
+
+    let snapshot = memo.snapshot(RunConfig::default().program_key(PROGRAM));
+    assert_eq!(snapshot.len(), 5);
+}
+
+#[test]
+fn other_programs_get_their_own_snapshot() {
+    let memo = Arc::new(SharedMemo::new(1024));
+    request(&memo);
+
+    let config = RunConfig {
+        output: Some(Rc::new(RefCell::new(std::io::sink()))),
+        shared_memo: Some(memo),
+        ..RunConfig::default()
+    };
+    let other = format!("{PROGRAM}\n");
+    let outcome = rinha::run_source(&other, &config).unwrap();
+    assert_eq!(outcome.stats.shared_hits, 0);
+}
+
+#[test]
+fn other_settings_get_their_own_snapshot() {
+    let memo = Arc::new(SharedMemo::new(1024));
+    let source = "let big = fn (n) => { n * 100000 * 100000 }; big(1)";
+    let run = |overflow| {
+        let config = RunConfig {
+            overflow,
+            shared_memo: Some(memo.clone()),
+            ..RunConfig::default()
+        };
+        rinha::run_source(source, &config).unwrap()
+    };
+
+    let wrapped = run(Overflow::Wrap32);
+    assert_eq!(wrapped.value.to_string(), "1410065408");
+    let widened = run(Overflow::Widen);
+    assert_eq!(widened.value.to_string(), "10000000000");
+    assert_eq!(widened.stats.shared_hits, 0);
+}
@@ -0,0 +1,55 @@
+//! The values of the functions share their body and their parameters with
+//! the copies the calls make of them, and the locations share their
+//! filename, so a call copies none of them.
+
+use rinha::{
+    interpreter::{Interpreter, Primitive, Scope},
+    parser,
+    runtime::symbol::Symbol,
+    RinhaError, RunConfig,
+};
+use std::{io, sync::Arc};
+
+#[test]
+fn the_function_a_call_binds_to_itself_is_shared() {
+    let source = "let f = fn (a, b) => { f }; (f, f(1, 2))";
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let value = Interpreter::with_output(io::sink())
+        .interpret(file.expression, &mut Scope::new())
+        .unwrap();
+    let Primitive::Tuple([made, itself]) = value else {
+        panic!("the program makes a tuple");
+    };
+    let (
+        Primitive::Function {
+            value, parameters, ..
+        },
+        Primitive::Function {
+            value: its_value,
+            parameters: its_parameters,
+            ..
+        },
+    ) = (*made, *itself)
+    else {
+        panic!("the tuple is of functions");
+    };
+    assert!(Arc::ptr_eq(&value, &its_value));
+    assert!(Arc::ptr_eq(&parameters, &its_parameters));
+}
+
+#[test]
+fn the_locations_share_their_filename() {
+    let config = RunConfig {
+        filename: "shared.rinha".into(),
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) =
+        rinha::run_source("let f = fn (n) => { n / 0 }; f(1)", &config)
+    else {
+        panic!("the division by zero doesn't fail");
+    };
+    assert!(Symbol::ptr_eq(
+        &error.location.filename,
+        &Symbol::intern("shared.rinha")
+    ));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::Scratch;
+use std::{path::PathBuf, process::Command};
+
+fn run(path: &PathBuf) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg(path)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn shebang_program_runs_like_the_plain_one() {
+    let dir = Scratch::new("shebang");
+    let program = "let x = 40;\nprint(x + 2)\n";
+    let plain = dir.file("plain.rinha", program);
+    let script = dir.file(
+        "script.rinha",
+        format!("#!/usr/bin/env interpreter\n{program}"),
+    );
+
+    let plain = run(&plain);
+    let script = run(&script);
+    assert!(script.status.success());
+    assert_eq!(plain.stdout, script.stdout);
+    assert_eq!(String::from_utf8_lossy(&script.stdout), "42\n");
+}
+
+#[test]
+fn shebang_keeps_error_lines() {
+    let dir = Scratch::new("shebang");
+    let path = dir.file(
+        "broken.rinha",
+        "#!/usr/bin/env interpreter\nlet x = 1;\nprint(x +)\n",
+    );
+
+    let output = run(&path);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("3 │ print(x +)"), "{stderr}");
+}
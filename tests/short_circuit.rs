@@ -0,0 +1,56 @@
+//! `&&` and `||` only evaluate their right operand when the left one
+//! doesn't decide the result, on both backends.
+
+use rinha::{interpreter::ErrorKind, Backend, RinhaError, RunConfig};
+use std::{cell::RefCell, rc::Rc};
+
+/// What the program printed and its value, the same on both backends.
+fn run(source: &str) -> Result<(String, String), ErrorKind> {
+    let [tree, vm] = [Backend::Tree, Backend::Vm].map(|backend| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let config = RunConfig {
+            backend,
+            output: Some(output.clone()),
+            ..RunConfig::default()
+        };
+        match rinha::run_source(source, &config) {
+            Ok(outcome) => Ok((
+                String::from_utf8(output.take()).unwrap(),
+                outcome.value.to_string(),
+            )),
+            Err(RinhaError::Runtime(error)) => Err(error.kind),
+            Err(error) => panic!("{source} failed with {error:?}"),
+        }
+    });
+    assert_eq!(tree, vm, "{source}");
+    tree
+}
+
+const EXPLODE: &str = "let explode = fn () => { let _ = print(\"boom\"); 1 / 0 == 0 };\n";
+
+#[test]
+fn the_right_operand_is_skipped_when_the_left_one_decides() {
+    let program = format!("{EXPLODE}let _ = print(false && explode()); true || explode()");
+    assert_eq!(
+        run(&program),
+        Ok(("false\n".to_string(), "true".to_string()))
+    );
+}
+
+#[test]
+fn the_right_operand_runs_otherwise() {
+    let program = format!("{EXPLODE}true && explode()");
+    assert_eq!(run(&program), Err(ErrorKind::DivisionByZero("/")));
+    let program = "let yes = fn () => { print(true) }; (false || yes(), true && yes())";
+    assert_eq!(
+        run(program),
+        Ok(("true\ntrue\n".to_string(), "(true, true)".to_string()))
+    );
+}
+
+#[test]
+fn operands_that_arent_booleans_are_still_errors() {
+    assert!(matches!(run("1 && false"), Err(ErrorKind::TypeMismatch(_))));
+    assert!(run("true || 1").is_ok());
+    assert!(matches!(run("false || 1"), Err(ErrorKind::TypeMismatch(_))));
+}
@@ -0,0 +1,74 @@
+//! `--time` and `--stats` report on the run into stderr, after it ends.
+
+use rinha::{Backend, RunConfig};
+use std::process::Command;
+
+/// Counts down from `n` in tail position, then from `n` in a recursion
+/// that keeps `n` calls in progress.
+fn program(n: u32) -> String {
+    format!(
+        "let down = fn (n) => {{ if (n == 0) {{ 0 }} else {{ down(n - 1) }} }};
+let deep = fn (n) => {{ if (n == 0) {{ 0 }} else {{ 1 + deep(n - 1) }} }};
+down({n}) + deep({n})"
+    )
+}
+
+#[test]
+fn every_backend_counts_the_calls_and_their_depth() {
+    for backend in [Backend::Tree, Backend::Vm, Backend::Jit] {
+        let config = RunConfig {
+            backend,
+            memoize: false,
+            ..RunConfig::default()
+        };
+        let stats = rinha::run_source(&program(50), &config).unwrap().stats;
+        assert_eq!(stats.calls, 2 * 51, "{backend:?}");
+        assert!(stats.terms > 0, "{backend:?}");
+        if backend != Backend::Jit {
+            // The tail calls replace their caller, `deep` nests its calls.
+            assert_eq!(stats.max_depth, 51, "{backend:?}");
+        }
+    }
+}
+
+#[test]
+fn the_terms_are_counted_once_each() {
+    let stats = rinha::run_source("1 + 2", &RunConfig::default())
+        .unwrap()
+        .stats;
+    assert_eq!(stats.terms, 3);
+    assert_eq!(stats.max_depth, 0);
+}
+
+#[test]
+fn the_binary_reports_the_time_and_the_counters() {
+    let run = |flags: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(flags)
+            .args(["--quiet", "files/sum.rinha"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    assert_eq!(run(&[]), "");
+
+    let time = run(&["--time"]);
+    let millis = time
+        .strip_prefix("time: ")
+        .and_then(|time| time.strip_suffix(" ms\n"))
+        .unwrap_or_else(|| panic!("unexpected report: {time:?}"));
+    assert!(millis.parse::<f64>().unwrap() >= 0.0);
+
+    let stats = run(&["--stats"]);
+    let lines: Vec<&str> = stats.lines().collect();
+    assert_eq!(lines[0], "stats:");
+    assert!(lines[1].starts_with("  terms:"), "{stats}");
+    assert!(lines[2].starts_with("  calls:"), "{stats}");
+    assert!(lines[3].starts_with("  memo:      "), "{stats}");
+    assert!(lines[3].ends_with(" misses"), "{stats}");
+    assert!(lines[4].starts_with("  max depth:"), "{stats}");
+
+    let unmemoized = run(&["--stats", "--no-memo", "--backend", "vm"]);
+    assert!(unmemoized.contains("  memo:      off\n"), "{unmemoized}");
+}
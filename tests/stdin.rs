@@ -0,0 +1,102 @@
+//! The same program runs the same from its source, from its JSON AST and
+//! from the standard input, whichever of them it's given as, and without a
+//! program it's read from the standard input. `--from`
+//! says which one it is when the extension would mislead.
+
+mod common;
+
+use common::Scratch;
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const PROGRAM: &str = "let double = fn (n) => { n * 2 };
+let _ = print(double(21));
+print((1, \"two\"))";
+
+const PRINTED: &str = "42\n(1, two)\n";
+
+fn interpreter(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(output.status.success(), "{output:?}");
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn source_ast_and_stdin_print_the_same() {
+    let dir = Scratch::new("stdin");
+    let source = dir.file("program.rinha", PROGRAM);
+    let source = source.to_str().unwrap();
+    let ast = interpreter(&["--emit-ast", source], "");
+    let json = dir.file("program.json", stdout(&ast));
+
+    assert_eq!(stdout(&interpreter(&[source], "")), PRINTED);
+    assert_eq!(stdout(&interpreter(&[json.to_str().unwrap()], "")), PRINTED);
+    assert_eq!(stdout(&interpreter(&["-"], PROGRAM)), PRINTED);
+    assert_eq!(stdout(&interpreter(&["-"], stdout(&ast))), PRINTED);
+}
+
+#[test]
+fn without_a_program_it_reads_the_standard_input() {
+    assert_eq!(stdout(&interpreter(&[], PROGRAM)), PRINTED);
+    assert_eq!(stdout(&interpreter(&["--quiet"], PROGRAM)), PRINTED);
+    assert_eq!(stdout(&interpreter(&["run"], PROGRAM)), PRINTED);
+}
+
+#[test]
+fn emit_ast_prints_the_ast_without_running() {
+    let output = interpreter(&["run", "--emit-ast", "-"], PROGRAM);
+
+    let file: rinha::ast::File = serde_json::from_str(stdout(&output)).unwrap();
+    assert!(matches!(file.expression, rinha::ast::Term::Let(_)));
+}
+
+#[test]
+fn malformed_input_is_reported_with_the_source() {
+    let output = interpreter(&["-"], "let x = ;\nprint(x)");
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("let x = ;"), "{stderr}");
+
+    let output = interpreter(&["-"], "{\"name\": ");
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("not a valid JSON AST"), "{stderr}");
+}
+
+#[test]
+fn from_overrides_what_the_extension_tells() {
+    let dir = Scratch::new("stdin");
+    let source = dir.file("from.rinha", PROGRAM);
+    let ast = interpreter(&["--emit-ast", source.to_str().unwrap()], "");
+    let json = dir.file("ast.rinha", stdout(&ast));
+    let json = json.to_str().unwrap();
+
+    assert_eq!(
+        stdout(&interpreter(&["run", "--from", "json", json], "")),
+        PRINTED
+    );
+    let output = interpreter(&["run", json], "");
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let output = interpreter(&["run", "--from", "source", "-"], stdout(&ast));
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+}
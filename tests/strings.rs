@@ -0,0 +1,144 @@
+//! The natives of `--extensions` reading the strings: `str_len`, `substr`
+//! and `char_at` count their characters, `int_of_string` and
+//! `string_of_int` turn them into integers and back.
+
+use rinha::{
+    interpreter::{ErrorKind, Overflow},
+    RinhaError, RunConfig,
+};
+
+fn run(source: &str, config: &RunConfig) -> Result<String, RinhaError> {
+    rinha::run_source(source, config).map(|outcome| outcome.value.to_string())
+}
+
+fn value(source: &str) -> String {
+    let config = RunConfig {
+        extensions: true,
+        ..RunConfig::default()
+    };
+    run(source, &config).unwrap()
+}
+
+fn error(source: &str) -> (ErrorKind, String) {
+    let config = RunConfig {
+        extensions: true,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = run(source, &config) else {
+        panic!("{source} didn't fail at runtime");
+    };
+    let term = &source[error.location.start as usize..error.location.end as usize];
+    (error.kind, term.to_string())
+}
+
+#[test]
+fn the_strings_are_read_by_their_characters() {
+    assert_eq!(value("str_len(\"rinha\")"), "5");
+    assert_eq!(value("str_len(\"\")"), "0");
+    assert_eq!(value("str_len(\"ação\")"), "4");
+    assert_eq!(value("substr(\"compiler\", 3, 3)"), "pil");
+    assert_eq!(value("substr(\"ação\", 1, 3)"), "ção");
+    assert_eq!(value("str_len(substr(\"abc\", 3, 0))"), "0");
+    assert_eq!(value("char_at(\"ação\", 1)"), "ç");
+
+    let reverse = "let reverse = fn (s, i, acc) => {
+  if (i == str_len(s)) { acc } else { reverse(s, i + 1, char_at(s, i) + acc) }
+};
+reverse(\"rinha\", 0, \"\")";
+    assert_eq!(value(reverse), "ahnir");
+}
+
+#[test]
+fn the_integers_are_read_and_written() {
+    assert_eq!(value("int_of_string(\"42\") + 1"), "43");
+    assert_eq!(value("int_of_string(\"-2147483648\")"), "-2147483648");
+    assert_eq!(value("string_of_int(0 - 12) + \"!\""), "-12!");
+    assert_eq!(
+        value("let n = 1234; int_of_string(string_of_int(n)) == n"),
+        "true"
+    );
+
+    let big = RunConfig {
+        extensions: true,
+        overflow: Overflow::Widen,
+        ..RunConfig::default()
+    };
+    let digits = "123456789012345678901234567890";
+    let source = format!("string_of_int(int_of_string(\"{digits}\") + 1)");
+    assert_eq!(
+        run(&source, &big).unwrap(),
+        "123456789012345678901234567891"
+    );
+}
+
+#[test]
+fn the_natives_fail_where_they_are_called() {
+    assert_eq!(
+        error("let s = \"abc\";\nchar_at(s, 3)"),
+        (
+            ErrorKind::OutOfRange {
+                name: "char_at",
+                index: 3,
+                len: 3
+            },
+            "char_at(s, 3)".into()
+        )
+    );
+    assert_eq!(
+        error("substr(\"abc\", 2, 2)").0,
+        ErrorKind::OutOfRange {
+            name: "substr",
+            index: 4,
+            len: 3
+        }
+    );
+    assert_eq!(
+        error("substr(\"abc\", 0 - 1, 2)").0,
+        ErrorKind::OutOfRange {
+            name: "substr",
+            index: -1,
+            len: 3
+        }
+    );
+    assert_eq!(
+        error("int_of_string(\"4 2\")"),
+        (
+            ErrorKind::NotAnInteger("4 2".into()),
+            "int_of_string(\"4 2\")".into()
+        )
+    );
+    let trap = RunConfig {
+        extensions: true,
+        overflow: Overflow::Trap,
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(overflow)) = run("int_of_string(\"2147483648\")", &trap) else {
+        panic!("int_of_string didn't overflow");
+    };
+    assert_eq!(overflow.kind, ErrorKind::IntegerOverflow("int_of_string"));
+    assert_eq!(error("str_len(1)").0, ErrorKind::NotAStr("str_len"));
+    assert_eq!(
+        error("string_of_int(\"1\")").0,
+        ErrorKind::NotAnInt("string_of_int")
+    );
+    assert_eq!(
+        error("substr(\"abc\", 1)").0,
+        ErrorKind::ArityMismatch {
+            name: "substr".into(),
+            expected: 3,
+            given: 2
+        }
+    );
+}
+
+#[test]
+fn the_natives_need_the_extensions() {
+    assert_eq!(
+        value("let str_len = fn (s) => { 0 }; str_len(\"abc\")"),
+        "0"
+    );
+    let Err(RinhaError::Runtime(error)) = run("str_len(\"a\")", &RunConfig::default()) else {
+        panic!("the natives of the strings work without the extensions");
+    };
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("str_len".into()));
+}
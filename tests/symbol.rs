@@ -0,0 +1,63 @@
+//! The names and the string literals are interned, the same text is the
+//! same allocation wherever it's written.
+
+use rinha::{
+    ast::{self, Term},
+    interpreter::{Interpreter, Primitive, Scope},
+    parser,
+    runtime::symbol::Symbol,
+};
+use std::io;
+
+#[test]
+fn the_same_names_and_literals_share_their_text() {
+    let source = r#"let greet = fn (name) => { "hi " + name }; greet("hi ")"#;
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let Term::Let(let_) = &file.expression else {
+        panic!("the program starts with a `let`");
+    };
+    let Term::Call(call) = &*let_.next else {
+        panic!("the `let` is followed by the call");
+    };
+    let Term::Var(callee) = &*call.callee else {
+        panic!("the call is of a variable");
+    };
+    assert!(Symbol::ptr_eq(&let_.name.text, &callee.text));
+
+    let Term::Str(argument) = &call.arguments[0] else {
+        panic!("the argument is a literal");
+    };
+    let Term::Function(function) = &*let_.value else {
+        panic!("the `let` binds a function");
+    };
+    let Term::Binary(ast::Binary { lhs, .. }) = &*function.value else {
+        panic!("the body is a concatenation");
+    };
+    let Term::Str(literal) = &**lhs else {
+        panic!("the concatenation starts with a literal");
+    };
+    assert!(Symbol::ptr_eq(&argument.value, &literal.value));
+
+    let json = serde_json::to_string(&file).unwrap();
+    let read: ast::File = serde_json::from_str(&json).unwrap();
+    let Term::Let(read) = &read.expression else {
+        panic!("the program starts with a `let`");
+    };
+    assert!(Symbol::ptr_eq(&read.name.text, &let_.name.text));
+}
+
+#[test]
+fn the_values_of_the_literals_are_the_literals() {
+    let file = parser::parse_or_report("main.rinha", r#"("a", "a")"#).unwrap();
+    let value = Interpreter::with_output(io::sink())
+        .interpret(file.expression, &mut Scope::new())
+        .unwrap();
+    let Primitive::Tuple([first, second]) = value else {
+        panic!("the program makes a tuple");
+    };
+    let (Primitive::Str(first), Primitive::Str(second)) = (&*first, &*second) else {
+        panic!("the tuple is of strings");
+    };
+    assert!(Symbol::ptr_eq(first, second));
+    assert!(Symbol::ptr_eq(first, &Symbol::intern("a")));
+}
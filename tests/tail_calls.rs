@@ -0,0 +1,77 @@
+//! The calls in tail position don't grow the native stack, so the loops
+//! written as tail recursion run for as long as they need.
+
+use rinha::{RinhaError, RunConfig};
+use std::thread;
+
+/// Runs on a thread with the default stack size, far too small for this
+/// many nested calls.
+fn run(source: &'static str, memoize: bool) -> Result<String, RinhaError> {
+    thread::spawn(move || {
+        let config = RunConfig {
+            memoize,
+            ..RunConfig::default()
+        };
+        rinha::run_source(source, &config).map(|outcome| outcome.value.to_string())
+    })
+    .join()
+    .unwrap()
+}
+
+#[test]
+fn a_counting_loop_of_a_million_iterations() {
+    let counting = "let count = fn (n, acc) => {
+      if (n == 0) { acc } else { count(n - 1, acc + 1) }
+    };
+    count(1000000, 0)";
+    assert_eq!(run(counting, false).unwrap(), "1000000");
+}
+
+#[test]
+fn mutually_recursive_tail_calls() {
+    // The functions only see what's bound before them, so `even` is given
+    // `odd` to call.
+    let parity = "let even = fn (n, odd) => {
+      if (n == 0) { true } else { odd(n - 1) }
+    };
+    let odd = fn (n) => {
+      if (n == 0) { false } else { even(n - 1, odd) }
+    };
+    (even(100000, odd), odd(99999))";
+    assert_eq!(run(parity, false).unwrap(), "(true, true)");
+}
+
+#[test]
+fn fib_with_accumulators() {
+    let fib = "let fib = fn (n, a, b) => {
+      if (n == 0) { a } else { fib(n - 1, b, (a + b) % 1000000007) }
+    };
+    fib(100000, 0, 1)";
+    assert_eq!(run(fib, false).unwrap(), "911435502");
+}
+
+#[test]
+fn tail_calls_through_let_and_memoized() {
+    // By Wilson's theorem, (p - 1)! is p - 1 modulo a prime p.
+    let factorial = "let factorial = fn (n, acc) => {
+      let next = (acc * n) % 10007;
+      if (n <= 1) { acc } else { factorial(n - 1, next) }
+    };
+    factorial(10006, 1)";
+    assert_eq!(run(factorial, true).unwrap(), "10006");
+}
+
+#[test]
+fn errors_in_tail_calls_keep_their_location() {
+    let failing = "let down = fn (n) => {
+      if (n == 0) { n + true } else { down(n - 1) }
+    };
+    down(100000)";
+    let Err(RinhaError::Runtime(error)) = run(failing, true) else {
+        panic!("the loop didn't fail");
+    };
+    assert_eq!(
+        error.location.start,
+        failing.find("n + true").unwrap() as u64
+    );
+}
@@ -0,0 +1,180 @@
+//! The files written by a run are closed the same way however it ends.
+
+mod common;
+
+use common::Scratch;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Prints, then makes calls that are all different for much longer than
+/// any test waits. The leaves count one each, so the sum never overflows.
+const SPINNING: &str = "let _ = print(\"started\");
+let spin = fn (n, k) => {
+  if (n == 0) { 1 } else { spin(n - 1, k * 2) + spin(n - 1, k * 2 + 1) }
+};
+spin(28, 1)";
+
+fn interpreter(program: &Path) -> Command {
+    let dir = program.parent().unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_interpreter"));
+    command
+        .arg("run")
+        .arg("--tee")
+        .arg(dir.join("tee.txt"))
+        .arg("--summary")
+        .arg(dir.join("summary.json"))
+        .arg(program);
+    command
+}
+
+fn summary(program: &Path) -> serde_json::Value {
+    let text = fs::read_to_string(program.with_file_name("summary.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_flushes_what_was_printed_and_marks_the_tee_partial() {
+    let dir = Scratch::new("teardown");
+    let program = dir.file("main.rinha", SPINNING);
+    let mut child = interpreter(&program)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The first line proves the program is past its print and spinning.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "started\n");
+
+    let killed = Command::new("kill")
+        .arg("-INT")
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(130));
+    assert_eq!(rest, "");
+
+    let summary = summary(&program);
+    assert_eq!(summary["status"], "interrupted");
+    assert_eq!(summary["exit_code"], 130);
+
+    let partial = fs::read_to_string(program.with_file_name("tee.txt.partial")).unwrap();
+    assert_eq!(partial, "started\n");
+    assert!(!program.with_file_name("tee.txt").exists());
+}
+
+#[test]
+fn runtime_errors_complete_the_files() {
+    let dir = Scratch::new("teardown");
+    let program = dir.file(
+        "main.rinha",
+        "let _ = print(1);\nlet _ = print(2);\n1 + true",
+    );
+    let output = interpreter(&program).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"1\n2\n");
+
+    let tee = fs::read_to_string(program.with_file_name("tee.txt")).unwrap();
+    assert_eq!(tee, "1\n2\n");
+    assert!(!program.with_file_name("tee.txt.partial").exists());
+    assert_eq!(summary(&program)["status"], "failed");
+}
+
+#[test]
+fn completed_runs_summarize_their_counters() {
+    let dir = Scratch::new("teardown");
+    let program = dir.file("main.rinha", "let f = fn (x) => { print(x) };\nf(7)");
+    let output = interpreter(&program).output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let summary = summary(&program);
+    assert_eq!(summary["status"], "completed");
+    assert_eq!(summary["exit_code"], 0);
+    assert_eq!(summary["calls"], 1);
+    assert_eq!(summary["printed_bytes"], 2);
+    assert!(!program.with_file_name("summary.json.partial").exists());
+}
+
+#[test]
+fn panics_still_flush_and_summarize() {
+    let dir = Scratch::new("teardown");
+    let program = dir.file("main.rinha", "let _ = print(1);\nprint(2)");
+    let output = interpreter(&program)
+        .arg("--panic-after-prints-for-tests=1")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(output.stdout, b"1\n");
+    assert_eq!(summary(&program)["status"], "panicked");
+
+    let partial = fs::read_to_string(program.with_file_name("tee.txt.partial")).unwrap();
+    assert_eq!(partial, "1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn tees_that_are_not_regular_files_are_written_in_place() {
+    let dir = Scratch::new("teardown");
+    use std::os::unix::fs::FileTypeExt;
+
+    let program = dir.file("main.rinha", "let _ = print(1);\nprint(2)");
+    let fifo = program.with_file_name("tee.fifo");
+    let _ = fs::remove_file(&fifo);
+    let made = Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(made.success());
+
+    // Opening the pipe for writing waits for its reader.
+    let reader = {
+        let fifo = fifo.clone();
+        std::thread::spawn(move || fs::read_to_string(fifo).unwrap())
+    };
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("run")
+        .arg("--tee")
+        .arg(&fifo)
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(reader.join().unwrap(), "1\n2\n");
+
+    assert!(fs::metadata(&fifo).unwrap().file_type().is_fifo());
+    assert!(!program.with_file_name("tee.fifo.partial").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tees_that_cannot_be_written_fail_the_run() {
+    let dir = Scratch::new("teardown");
+    use std::os::unix::fs::FileTypeExt;
+
+    let program = dir.file("main.rinha", "print(1)");
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--tee", "/dev/full"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(2), "{stderr}");
+    assert_eq!(output.stdout, b"1\n");
+    assert!(
+        stderr.contains("could not write `/dev/full`, asked for by --tee"),
+        "{stderr}"
+    );
+    assert!(fs::metadata("/dev/full")
+        .unwrap()
+        .file_type()
+        .is_char_device());
+}
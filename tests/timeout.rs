@@ -0,0 +1,90 @@
+//! `--timeout` and `RunConfig::timeout` stop the programs that run for
+//! too long at their next call, on every backend, and the runs stopped
+//! still tell how far they got.
+
+mod common;
+
+use common::Scratch;
+use rinha::{interpreter::ErrorKind, Backend, RinhaError, RunConfig};
+use std::{
+    fs,
+    process::Command,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+const LOOP: &str = "let loop = fn (n) => { loop(n + 1) };
+loop(0)";
+
+const BACKENDS: [Backend; 3] = [Backend::Tree, Backend::Vm, Backend::Jit];
+
+#[test]
+fn the_loops_stop_at_the_timeout() {
+    for backend in BACKENDS {
+        let timeout = Duration::from_millis(100);
+        let config = RunConfig {
+            backend,
+            timeout: Some(timeout),
+            ..RunConfig::default()
+        };
+        let started = Instant::now();
+        let Err(RinhaError::Runtime(error)) = rinha::run_source(LOOP, &config) else {
+            panic!("the loop ended on {backend:?}");
+        };
+        assert!(started.elapsed() < Duration::from_secs(10), "{backend:?}");
+        assert_eq!(error.kind, ErrorKind::Timeout(timeout), "{backend:?}");
+        let stats = error.stats.expect("the stats of the run");
+        assert!(stats.calls > 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn the_runs_within_the_timeout_end_as_usual() {
+    let config = RunConfig {
+        timeout: Some(Duration::from_secs(60)),
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source("let f = fn (n) => { n * 2 }; f(21)", &config).unwrap();
+    assert_eq!(outcome.value.to_string(), "42");
+
+    // The flag of the embedder still stops the run, as an interruption.
+    let config = RunConfig {
+        timeout: Some(Duration::from_secs(60)),
+        interrupt: Some(Arc::new(AtomicBool::new(true))),
+        ..RunConfig::default()
+    };
+    let Err(RinhaError::Runtime(error)) = rinha::run_source(LOOP, &config) else {
+        panic!("the interrupted loop ended");
+    };
+    assert_eq!(error.kind, ErrorKind::Interrupted);
+}
+
+#[test]
+fn the_run_exits_with_the_code_of_the_limits() {
+    let dir = Scratch::new("timeout");
+    let program = dir.file("main.rinha", LOOP);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--timeout", "200ms", "--summary"])
+        .arg(dir.join("summary.json"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("resource limit exceeded: the run took longer than its timeout of 200ms"),
+        "{stderr}"
+    );
+    let summary = fs::read_to_string(dir.join("summary.json")).unwrap();
+    let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    assert_eq!(summary["exit_code"], 3);
+    assert_eq!(summary["timeout_ms"], 200);
+    assert!(summary["calls"].as_u64().unwrap() > 0, "{summary}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--timeout", "5 seconds", "-"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
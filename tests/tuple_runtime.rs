@@ -0,0 +1,156 @@
+//! The tuple runtime shared by the engines, over every kind of value.
+
+use rinha::{
+    interpreter::{ErrorKind, IntFormat, Primitive},
+    runtime::tuple,
+    RunConfig,
+};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    thread,
+};
+
+/// How deep the nested tuples go, far deeper than the default stack of a
+/// thread fits when it's walked recursively.
+const DEEP: usize = 1_000_000;
+
+/// One value of each kind, the tuple included.
+fn values() -> Vec<Primitive> {
+    let closure = rinha::run_source("fn (x) => { x }", &RunConfig::default())
+        .unwrap()
+        .value;
+    vec![
+        Primitive::Int(7),
+        Primitive::Str("a".into()),
+        Primitive::Bool(true),
+        tuple::new(Primitive::Int(1), Primitive::Int(2)),
+        closure,
+        Primitive::None,
+    ]
+}
+
+/// `(0, (1, (2, ...)))`, built from the innermost tuple out.
+fn nested(depth: usize, last: i64) -> Primitive {
+    let mut value = Primitive::Int(last);
+    for index in (0..depth).rev() {
+        value = tuple::new(Primitive::Int(index as i64), value);
+    }
+    value
+}
+
+/// Takes the nested value apart one level at a time, dropping it whole
+/// would recurse once per level.
+fn dismantle(mut value: Primitive) {
+    while let Primitive::Tuple([_, second]) = value {
+        value = *second;
+    }
+}
+
+fn hashed(value: &Primitive) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tuple::hash(value, &mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn projections_take_apart_any_tuple_and_only_tuples() {
+    for first in values() {
+        for second in values() {
+            let (first_text, second_text) = (first.to_string(), second.to_string());
+            let pair = tuple::new(first.clone(), second.clone());
+            assert_eq!(pair.to_string(), format!("({first_text}, {second_text})"));
+            assert_eq!(tuple::first(pair.clone()).unwrap().to_string(), first_text);
+            assert_eq!(tuple::second(pair).unwrap().to_string(), second_text);
+        }
+    }
+    for value in values() {
+        if matches!(value, Primitive::Tuple(_)) {
+            continue;
+        }
+        let first = tuple::first(value.clone());
+        assert_eq!(first.unwrap_err(), ErrorKind::NotATuple("first"));
+        let second = tuple::second(value);
+        assert_eq!(second.unwrap_err(), ErrorKind::NotATuple("second"));
+    }
+}
+
+#[test]
+fn equality_compares_the_elements_like_the_operators() {
+    let comparable = |value: &Primitive| {
+        matches!(
+            value,
+            Primitive::Int(_) | Primitive::Str(_) | Primitive::Bool(_) | Primitive::Tuple(_)
+        )
+    };
+    for (left, lhs) in values().iter().enumerate() {
+        for (right, rhs) in values().iter().enumerate() {
+            let equal = tuple::equal(lhs, rhs);
+            match left == right && comparable(lhs) {
+                true => assert_eq!(equal, Ok(true), "{lhs} == {rhs}"),
+                false => assert!(equal.is_err(), "{lhs} == {rhs}"),
+            }
+        }
+    }
+
+    let pair = |a, b| tuple::new(Primitive::Int(a), Primitive::Str(b));
+    assert_eq!(
+        tuple::equal(&pair(1, "a".into()), &pair(1, "b".into())),
+        Ok(false)
+    );
+    assert_eq!(
+        tuple::equal(&pair(2, "a".into()), &pair(1, "a".into())),
+        Ok(false)
+    );
+    let shapes = tuple::equal(&nested(2, 0), &nested(3, 0));
+    assert!(shapes.is_err(), "tuples of different shapes");
+}
+
+#[test]
+fn equal_values_hash_the_same() {
+    for value in values() {
+        assert_eq!(hashed(&value), hashed(&value.clone()), "{value}");
+    }
+    assert_eq!(hashed(&nested(10, 1)), hashed(&nested(10, 1)));
+    assert_ne!(hashed(&nested(10, 1)), hashed(&nested(10, 2)));
+
+    // The tags keep the values of different kinds apart.
+    let int = Primitive::Int(1);
+    let bool = Primitive::Bool(true);
+    assert_ne!(hashed(&int), hashed(&bool));
+    let mut plain = DefaultHasher::new();
+    1i64.hash(&mut plain);
+    assert_ne!(hashed(&int), plain.finish());
+}
+
+#[test]
+fn rendering_uses_the_int_format_inside_tuples() {
+    let value = tuple::new(
+        Primitive::Int(255),
+        tuple::new(Primitive::Int(-1), Primitive::None),
+    );
+    assert_eq!(
+        value.display(IntFormat::Hex).to_string(),
+        "(0xff, (-0x1, unit))"
+    );
+    assert_eq!(tuple::depth(&value), 2);
+    assert_eq!(tuple::depth(&Primitive::Int(1)), 0);
+}
+
+#[test]
+fn deep_tuples_are_walked_without_recursion() {
+    thread::spawn(|| {
+        let (lhs, rhs) = (nested(DEEP, 1), nested(DEEP, 1));
+        assert_eq!(tuple::depth(&lhs), DEEP as u64);
+        assert_eq!(tuple::equal(&lhs, &rhs), Ok(true));
+        assert_eq!(hashed(&lhs), hashed(&rhs));
+
+        let rendered = lhs.to_string();
+        assert!(rendered.starts_with("(0, (1, (2, "), "{}", &rendered[..20]);
+        assert!(rendered.ends_with(&format!("{}, 1{}", DEEP - 1, ")".repeat(DEEP))));
+        dismantle(lhs);
+        dismantle(rhs);
+    })
+    .join()
+    .unwrap();
+}
@@ -0,0 +1,96 @@
+//! `first` and `second` take apart any tuple value, not only the tuples
+//! written in place. The programs go through a JSON AST, like the ones the
+//! competition runs, and run the same on both backends.
+
+use rinha::{
+    interpreter::{ErrorKind, Primitive},
+    parser, Backend, RinhaError, RunConfig,
+};
+
+fn run(source: &str) -> Result<Primitive, RinhaError> {
+    let parsed = parser::parse_or_report("main.rinha", source).unwrap();
+    let json = serde_json::to_string(&parsed).unwrap();
+    let file: rinha::ast::File = serde_json::from_str(&json).unwrap();
+    let [tree, vm] = [Backend::Tree, Backend::Vm].map(|backend| {
+        let config = RunConfig {
+            backend,
+            ..RunConfig::default()
+        };
+        rinha::run_ast(file.clone(), &config).map(|outcome| outcome.value)
+    });
+    match (&tree, vm) {
+        (Ok(tree), Ok(vm)) => assert_eq!(*tree, vm, "{source}"),
+        (Err(RinhaError::Runtime(tree)), Err(RinhaError::Runtime(vm))) => {
+            assert_eq!(*tree, vm, "{source}")
+        }
+        (tree, vm) => panic!("{source} ran as {tree:?} and as {vm:?}"),
+    }
+    tree
+}
+
+fn value(source: &str) -> String {
+    run(source).unwrap().to_string()
+}
+
+#[test]
+fn tuples_bound_by_let() {
+    assert_eq!(value("let t = (1, 2); first(t)"), "1");
+    assert_eq!(value("let t = (1, 2); second(t)"), "2");
+}
+
+#[test]
+fn tuples_passed_to_and_returned_from_functions() {
+    assert_eq!(value("let f = fn (t) => { second(t) }; f((1, 2))"), "2");
+    let pairs = "let fib = fn (n) => {
+      if (n < 2) { (n, n) } else { (n, second(fib(n - 1)) + second(fib(n - 2))) }
+    };
+    second(fib(10))";
+    assert_eq!(value(pairs), "55");
+}
+
+#[test]
+fn nested_projections_and_tuples_from_if() {
+    assert_eq!(value("let x = (1, (2, 3)); first(second(x))"), "2");
+    assert_eq!(value("second(second((1, (2, 3))))"), "3");
+    assert_eq!(value("first(if (true) { (4, 5) } else { (6, 7) })"), "4");
+}
+
+#[test]
+fn values_that_are_not_tuples_are_errors() {
+    for (source, name) in [
+        ("let t = 1; first(t)", "first"),
+        ("second(\"a\")", "second"),
+    ] {
+        let Err(RinhaError::Runtime(error)) = run(source) else {
+            panic!("{source} isn't an error");
+        };
+        assert_eq!(error.kind, ErrorKind::NotATuple(name));
+        assert_eq!(
+            error.kind.to_string(),
+            format!("`{name}` must be used on tuples")
+        );
+    }
+}
+
+#[test]
+fn tuples_compare_element_wise() {
+    assert_eq!(value("(1, (2, \"a\")) == (1, (2, \"a\"))"), "true");
+    assert_eq!(value("let t = (1, (2, 3)); t != (1, (2, 4))"), "true");
+    assert_eq!(
+        value("let f = fn () => { (true, 1) }; f() == (true, 1)"),
+        "true"
+    );
+}
+
+#[test]
+fn closures_are_an_error_to_compare() {
+    for source in [
+        "let f = fn (x) => { x }; f == f",
+        "let f = fn (x) => { x }; (1, f) != (1, f)",
+    ] {
+        let Err(RinhaError::Runtime(error)) = run(source) else {
+            panic!("{source} isn't an error");
+        };
+        assert!(matches!(error.kind, ErrorKind::TypeMismatch(_)), "{source}");
+    }
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::Scratch;
+use rinha::interpreter::Primitive;
+use std::process::Command;
+
+#[test]
+fn display_renders_unit() {
+    assert_eq!(Primitive::None.to_string(), "unit");
+}
+
+#[test]
+fn tuple_rendering_keeps_unit() {
+    let tuple = Primitive::Tuple([Box::new(Primitive::Int(1)), Box::new(Primitive::None)]);
+    assert_eq!(tuple.to_string(), "(1, unit)");
+}
+
+#[test]
+fn json_conversion_uses_null() {
+    let tuple = Primitive::Tuple([Box::new(Primitive::None), Box::new(Primitive::Int(2))]);
+    assert_eq!(Primitive::None.to_json(), serde_json::Value::Null);
+    assert_eq!(tuple.to_json(), serde_json::json!([null, 2]));
+}
+
+/// Turns every `0` into a syntax error node, they evaluate to unit.
+fn zeros_to_errors(json: &mut serde_json::Value) {
+    if json["kind"] == "Int" && json["value"] == 0 {
+        *json = serde_json::json!({
+            "kind": "Error",
+            "message": "unexpected token",
+            "full_text": "0",
+            "location": json["location"].clone(),
+        });
+    } else if let Some(object) = json.as_object_mut() {
+        object.values_mut().for_each(zeros_to_errors);
+    }
+}
+
+#[test]
+fn printing_unit_writes_a_line() {
+    let file = rinha::parser::parse_or_report("main.rinha", "let _ = print(0);\nprint((1, 0))");
+    let mut json = serde_json::to_value(file.unwrap()).unwrap();
+    zeros_to_errors(&mut json);
+    let dir = Scratch::new("unit");
+    let path = dir.file("main.json", json.to_string());
+
+    // Debug builds report the unit values by default.
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("--debug-assert-none=false")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "unit\n(1, unit)\n");
+}
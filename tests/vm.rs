@@ -0,0 +1,97 @@
+//! The bytecode machine of `--backend vm` runs the programs like the
+//! tree-walking interpreter: they print the same and end with the same
+//! value, and its calls don't grow the native stack.
+
+use rinha::{Backend, RunConfig};
+use std::{cell::RefCell, fs, process::Command, rc::Rc, thread};
+
+/// `fib(46)` takes ages without the memo, which the machine doesn't have.
+const EXAMPLES: &[&str] = &["combination", "easy", "sum", "sum2", "sum3", "tuple"];
+
+/// What the program printed and its value.
+fn run(source: &str, backend: Backend) -> (String, String) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        backend,
+        output: Some(output.clone()),
+        memoize: false,
+        extensions: true,
+        ..RunConfig::default()
+    };
+    let outcome = rinha::run_source(source, &config).unwrap();
+    (
+        String::from_utf8(output.take()).unwrap(),
+        outcome.value.to_string(),
+    )
+}
+
+fn same_as_the_interpreter(source: &str) -> (String, String) {
+    let ran = run(source, Backend::Vm);
+    assert_eq!(ran, run(source, Backend::Tree), "{source}");
+    ran
+}
+
+#[test]
+fn the_examples_run_the_same() {
+    for name in EXAMPLES {
+        let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+        thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || same_as_the_interpreter(&source))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
+
+#[test]
+fn closures_capture_what_they_use() {
+    let program = "let adder = fn (x) => { fn (y) => { x + y } };
+let add2 = adder(2);
+let x = 100;
+let twice = fn (f, x) => { f(f(x)) };
+let _ = print(twice(add2, 1));
+let counter = fn (n) => {
+  let next = fn () => { counter(n + 1) };
+  if (n == 3) { n } else { next() }
+};
+let _ = print(counter(0));
+let shadow = fn (shadow) => { shadow + 1 };
+let _ = print(shadow(1));
+print((add2, (1.5 * 2, \"x\" + x)))";
+    let (printed, value) = same_as_the_interpreter(program);
+    assert_eq!(printed, "5\n3\n2\n(<#closure>, (3.0, x100))\n");
+    assert_eq!(value, "(<#closure>, (3.0, x100))");
+}
+
+#[test]
+fn deep_recursion_runs_on_the_heap() {
+    // Not in tail position, the interpreter would need a stack far larger
+    // than the one of this thread.
+    let depth = "let depth = fn (n) => { if (n == 0) { 0 } else { 1 + depth(n - 1) } };
+depth(100000)";
+    let value = thread::spawn(move || run(depth, Backend::Vm).1)
+        .join()
+        .unwrap();
+    assert_eq!(value, "100000");
+}
+
+#[test]
+fn the_binary_selects_the_machine() {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args([
+            "run",
+            "--backend",
+            "vm",
+            "--print-result",
+            "files/sum.rinha",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let tree = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["run", "--print-result", "files/sum.rinha"])
+        .output()
+        .unwrap();
+    assert_eq!(output.stdout, tree.stdout);
+}
@@ -0,0 +1,286 @@
+//! `compile --target wasm32` turns the programs into WebAssembly modules
+//! for WASI, which print and fail like the interpreter does.
+
+mod common;
+
+use common::Scratch;
+use rinha::{
+    interpreter::Overflow,
+    parser,
+    wasm::{self, CompileError},
+    Backend, RinhaError, RunConfig,
+};
+use std::{cell::RefCell, fs, process::Command, rc::Rc};
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store};
+
+const EXAMPLES: &[&str] = &["combination", "easy", "sum", "sum2", "sum3", "tuple"];
+
+/// What the module wrote into the standard output and error, and the code
+/// it exited with.
+#[derive(Debug, Default, PartialEq)]
+struct Ran {
+    stdout: String,
+    stderr: String,
+    code: i32,
+}
+
+fn compile(source: &str, overflow: Overflow) -> Result<Vec<u8>, CompileError> {
+    let file = parser::parse_or_report("main.rinha", source).unwrap();
+    let options = wasm::Options {
+        overflow,
+        source: Some(source),
+    };
+    wasm::compile(&file, &options)
+}
+
+/// Runs the module with the two functions of WASI it imports.
+fn run_module(module: &[u8]) -> Ran {
+    let mut config = Config::default();
+    config.wasm_tail_call(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, module).unwrap();
+    let mut store = Store::new(&engine, Ran::default());
+    let mut linker = Linker::<Ran>::new(&engine);
+    linker
+        .func_wrap(
+            "wasi_snapshot_preview1",
+            "fd_write",
+            |mut caller: Caller<'_, Ran>, fd: i32, iovs: i32, count: i32, written: i32| -> i32 {
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(Extern::into_memory)
+                    .unwrap();
+                let data = memory.data(&caller);
+                let word = |at: i32| {
+                    let at = at as usize;
+                    u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as usize
+                };
+                let mut bytes = Vec::new();
+                for iov in 0..count {
+                    let (start, length) = (word(iovs + 8 * iov), word(iovs + 8 * iov + 4));
+                    bytes.extend_from_slice(&data[start..start + length]);
+                }
+                let total = bytes.len() as u32;
+                memory
+                    .write(&mut caller, written as usize, &total.to_le_bytes())
+                    .unwrap();
+                let text = String::from_utf8(bytes).unwrap();
+                match fd {
+                    1 => caller.data_mut().stdout.push_str(&text),
+                    2 => caller.data_mut().stderr.push_str(&text),
+                    _ => return 8,
+                }
+                0
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "wasi_snapshot_preview1",
+            "proc_exit",
+            |code: i32| -> Result<(), wasmi::Error> { Err(wasmi::Error::i32_exit(code)) },
+        )
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let start = instance.get_typed_func::<(), ()>(&store, "_start").unwrap();
+    if let Err(error) = start.call(&mut store, ()) {
+        store.data_mut().code = error.i32_exit_status().expect("the module exits");
+    }
+    store.into_data()
+}
+
+fn run(source: &str, overflow: Overflow) -> Ran {
+    run_module(&compile(source, overflow).unwrap())
+}
+
+/// What the bytecode prints, and its error as the module reports it.
+fn interpret(source: &str, overflow: Overflow) -> Ran {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let config = RunConfig {
+        filename: "main.rinha".into(),
+        backend: Backend::Vm,
+        overflow,
+        output: Some(output.clone()),
+        ..RunConfig::default()
+    };
+    let (stderr, code) = match rinha::run_source(source, &config) {
+        Ok(_) => (String::new(), 0),
+        Err(RinhaError::Runtime(error)) => {
+            let (line, column) = error.location.line_column(source);
+            (format!("main.rinha:{line}:{column}: {}\n", error.kind), 1)
+        }
+        Err(other) => panic!("expected a runtime error, got {other:?}"),
+    };
+    Ran {
+        stdout: String::from_utf8(output.take()).unwrap(),
+        stderr,
+        code,
+    }
+}
+
+fn same_as_the_interpreter(source: &str, overflow: Overflow) -> Ran {
+    let ran = run(source, overflow);
+    assert_eq!(ran, interpret(source, overflow), "{source}");
+    ran
+}
+
+#[test]
+fn the_examples_print_the_same() {
+    for name in EXAMPLES {
+        let source = fs::read_to_string(format!("files/{name}.rinha")).unwrap();
+        same_as_the_interpreter(&source, Overflow::Trap);
+    }
+}
+
+#[test]
+fn closures_tuples_and_strings_work() {
+    let program = r#"let adder = fn (x) => { fn (y) => { x + y } };
+let add2 = adder(2);
+let compose = fn (f, g) => { fn (x) => { f(g(x)) } };
+let twice = compose(add2, add2);
+let swap = fn (pair) => { (second(pair), first(pair)) };
+let greet = fn (name) => { "hello, " + name + "!" };
+let _ = print(twice(10));
+let _ = print(swap((1, ("a", true))));
+let _ = print(greet("rinha") + 42);
+let _ = print((1, 2) == (1, 2));
+let _ = print("ab" != "a" + "b");
+let _ = print(adder);
+let _ = print(false || (1 < 2) && true);
+print(print(7) * 6)"#;
+    let ran = same_as_the_interpreter(program, Overflow::Trap);
+    assert_eq!(
+        ran.stdout,
+        "14\n((a, true), 1)\nhello, rinha!42\ntrue\nfalse\n<#closure>\ntrue\n7\n42\n"
+    );
+}
+
+#[test]
+fn the_loops_in_tail_position_run_in_constant_space() {
+    let program =
+        "let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, (acc + n) % 1000) } };
+print(loop(100000, 0))";
+    assert_eq!(run(program, Overflow::Trap).stdout, "0\n");
+}
+
+#[test]
+fn the_errors_point_where_the_interpreter_does() {
+    let programs = [
+        "let f = fn (a, b) => { a };\nprint(f(1))",
+        "let x = 1;\nprint(x(2))",
+        "print(if (1) { 2 } else { 3 })",
+        "let _ = print(1);\nfirst(2)",
+        "second(\"a\")",
+        "print(y + 1)",
+        "let p = fn (n) => { if (n == 0) { 1 } else { 2 * p(n - 1) } };\np(40)",
+        "let d = fn (a, b) => { a / b };\nd(1, 0)",
+        "(1, fn () => { 1 }) == (2, fn () => { 1 })",
+        "1 + true",
+        "true && 1",
+    ];
+    for program in programs {
+        let ran = same_as_the_interpreter(program, Overflow::Trap);
+        assert_eq!(ran.code, 1, "{program}");
+    }
+}
+
+#[test]
+fn the_matches_run_like_in_the_bytecode() {
+    let program = r#"let sign = fn (p) => {
+  match (p) {
+    (0, _) => "zero",
+    (x, "a") if x > 0 => "positive a",
+    (x, _) => x,
+  }
+};
+let _ = print(sign((0, "b")));
+let _ = print(sign((1, "a")));
+let _ = print(sign((2, "b")));
+match (sign((0, "a"))) { "one" => 1 }"#;
+    let ran = same_as_the_interpreter(program, Overflow::Trap);
+    assert_eq!(ran.stdout, "zero\npositive a\n2\n");
+    assert_eq!(
+        ran.stderr,
+        "main.rinha:11:1: no arm of the match takes the value\n"
+    );
+    same_as_the_interpreter("match (5) { (a, _) => a }", Overflow::Trap);
+}
+
+#[test]
+fn every_operator_fails_like_the_interpreter() {
+    let operands = ["7", "0", "true", "\"b\"", "(1, 2)", "fn () => { 1 }"];
+    let operators = [
+        "+", "-", "*", "/", "%", "==", "!=", "<", ">", "<=", ">=", "&&", "||",
+    ];
+    for op in operators {
+        for left in operands {
+            for right in operands {
+                let program = format!("let l = {left};\nlet r = {right};\nprint(l {op} r)");
+                same_as_the_interpreter(&program, Overflow::Trap);
+            }
+        }
+    }
+}
+
+#[test]
+fn the_integers_wrap_around_like_the_reference() {
+    let program = "let fact = fn (n) => { if (n == 0) { 1 } else { n * fact(n - 1) } };
+let _ = print(((0 - 2147483647) - 1) - 1);
+let _ = print(((0 - 2147483647) - 1) / (0 - 1));
+fact(13)";
+    let ran = same_as_the_interpreter(program, Overflow::Wrap32);
+    assert_eq!(ran.stdout, "2147483647\n-2147483648\n");
+}
+
+#[test]
+fn what_the_modules_cannot_hold_is_rejected() {
+    assert!(matches!(
+        compile("print(1 + 2)", Overflow::Widen),
+        Err(CompileError::Overflow(Overflow::Widen))
+    ));
+    let file = parser::parse_or_report("main.rinha", "print(1.5)").unwrap();
+    match wasm::compile(&file, &wasm::Options::default()) {
+        Err(CompileError::Float { location }) => assert_eq!(location.start, 6),
+        other => panic!("expected the float to be rejected, got {other:?}"),
+    }
+}
+
+#[test]
+fn the_binary_writes_the_module() {
+    let directory = Scratch::new("wasm");
+    let output = directory.join("sum.wasm");
+    let status = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(["compile", "--target", "wasm32", "files/sum.json", "-o"])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let module = fs::read(&output).unwrap();
+    assert_eq!(module[..4], *b"\0asm");
+    assert_eq!(run_module(&module).stdout, "500500\n");
+}
+
+#[test]
+fn from_tells_what_the_program_is_written_as() {
+    let directory = Scratch::new("wasm");
+    // A JSON AST the extension would take for source code.
+    let program = directory.file("sum.rinha", fs::read("files/sum.json").unwrap());
+    let output = directory.join("sum.wasm");
+    let compile = |from: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_interpreter"))
+            .args(["compile", "--target", "wasm32"])
+            .args(from)
+            .arg(&program)
+            .arg("-o")
+            .arg(&output)
+            .status()
+            .unwrap()
+    };
+    assert_eq!(compile(&[]).code(), Some(2));
+    assert!(compile(&["--from", "json"]).success());
+    assert_eq!(run_module(&fs::read(&output).unwrap()).stdout, "500500\n");
+}